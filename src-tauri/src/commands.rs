@@ -1,27 +1,68 @@
+use serde::Serialize;
 use sqlx::any::AnyConnectOptions;
 use sqlx::{AnyConnection, Connection};
 use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
-use tauri::{AppHandle, Manager, Runtime, State};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, Runtime, State};
 use tokio::task::AbortHandle;
-use urlencoding::encode;
 use uuid::Uuid;
 
-use crate::drivers::{mysql, postgres, sqlite};
+use crate::drivers::common::{
+    build_url, column_stats_from_row, column_stats_sql, connection_string_endpoint,
+    describe_statement_timeout, driver_for, ensure_query_allowed, ensure_select_only,
+    ensure_writable, is_explicit_transaction, is_text_type, prepare_bind_params, search_table_sql,
+    simple_select_table, single_primary_key, split_statements, validate_explainable,
+    RowBatchCallback,
+};
+use crate::drivers::duckdb;
 use crate::keychain_utils;
 use crate::models::{
-    ConnectionParams, ForeignKey, Index, QueryResult, SavedConnection, SshConnection, SshConnectionInput, SshTestParams,
-    TableColumn, TableInfo,
+    BatchEditResult, ColumnDefinition, ColumnRef, ColumnStats, ConnectionImport, ConnectionParams,
+    ConnectionTestInfo, ExplainResult, ForeignKey, Index, ProcedureResult, QueryParams,
+    QueryResult, RecordEdit, RoutineInfo, SavedConnection, ScriptResult, SshConnection,
+    SshConnectionInput, SshTestParams, SshTunnelInfo, StatementPreview, TableColumn, TableInfo,
+    TableRowCount, Trigger, ViewInfo,
+};
+use crate::persistence;
+use crate::pool_manager;
+use crate::query_history::{self, QueryHistoryEntry};
+use crate::ssh_tunnel::{
+    self, get_tunnels, SshEndpoint, SshTunnel, TunnelOptions, DEFAULT_KEEPALIVE_INTERVAL,
 };
-use crate::ssh_tunnel::{get_tunnels, SshTunnel};
 
+/// Running queries by query id, so cancelling one leaves the others alone
 pub struct QueryCancellationState {
     pub handles: Arc<Mutex<HashMap<String, AbortHandle>>>,
 }
 
+impl QueryCancellationState {
+    /// Tracks a query's task. A query still running under the same id (e.g. the
+    /// previous run of a tab) is superseded and cancelled.
+    fn register(&self, query_id: &str, handle: AbortHandle) {
+        let previous = self
+            .handles
+            .lock()
+            .unwrap()
+            .insert(query_id.to_string(), handle);
+        if let Some(previous) = previous {
+            previous.abort();
+        }
+    }
+
+    /// Stops tracking a finished query, unless a newer run took over its id
+    fn finish(&self, query_id: &str, task_id: tokio::task::Id) {
+        let mut handles = self.handles.lock().unwrap();
+        if handles.get(query_id).map(AbortHandle::id) == Some(task_id) {
+            handles.remove(query_id);
+        }
+    }
+}
+
 impl Default for QueryCancellationState {
     fn default() -> Self {
         Self {
@@ -30,6 +71,32 @@ impl Default for QueryCancellationState {
     }
 }
 
+/// Interval between `query://running` heartbeats while a query is in flight
+const QUERY_HEARTBEAT_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Serialize)]
+struct QueryRunningEvent {
+    connection_id: String,
+    elapsed_ms: u64,
+}
+
+#[derive(Clone, Serialize)]
+struct QueryDoneEvent {
+    connection_id: String,
+    row_count: usize,
+    duration_ms: u64,
+    success: bool,
+}
+
+/// A chunk of rows streamed from a running query, emitted as `query-batch`
+#[derive(Clone, Serialize)]
+struct QueryBatchEvent {
+    connection_id: String,
+    columns: Vec<String>,
+    rows: Vec<Vec<serde_json::Value>>,
+    rows_fetched: usize,
+}
+
 // --- Persistence Helpers ---
 
 pub async fn expand_ssh_connection_params<R: Runtime>(
@@ -54,44 +121,108 @@ pub async fn expand_ssh_connection_params<R: Runtime>(
             expanded_params.ssh_password = ssh_conn.password.clone();
             expanded_params.ssh_key_file = ssh_conn.key_file.clone();
             expanded_params.ssh_key_passphrase = ssh_conn.key_passphrase.clone();
+            expanded_params.ssh_jump_hosts = ssh_conn.jump_hosts.clone();
+            expanded_params.ssh_verify_host_key = ssh_conn.verify_host_key;
         }
     }
 
     Ok(expanded_params)
 }
 
+/// The database server an SSH tunnel forwards to: the connection string's host if
+/// there is one, otherwise the host and port fields
+fn tunnel_remote(params: &ConnectionParams) -> Result<(String, u16), String> {
+    Ok(connection_string_endpoint(params)?.unwrap_or_else(|| {
+        (
+            params
+                .host
+                .clone()
+                .unwrap_or_else(|| "localhost".to_string()),
+            params.port.unwrap_or(3306),
+        )
+    }))
+}
+
+/// Identifies the SSH tunnel a connection goes through
+fn tunnel_key(params: &ConnectionParams) -> Result<String, String> {
+    let ssh_host = params.ssh_host.as_deref().ok_or("Missing SSH Host")?;
+    let ssh_port = params.ssh_port.unwrap_or(22);
+    let ssh_user = params.ssh_user.as_deref().ok_or("Missing SSH User")?;
+    let (remote_host, remote_port) = tunnel_remote(params)?;
+    let key = format!(
+        "{}@{}:{}:{}->{}",
+        ssh_user, ssh_host, ssh_port, remote_host, remote_port
+    );
+    // The same server reached through other jump hosts is a different tunnel
+    let key = match params.ssh_jump_hosts.as_deref() {
+        Some(jumps) if !jumps.is_empty() => format!("{} via {}", key, jumps.join(",")),
+        _ => key,
+    };
+    // An unverified tunnel must not be reused once verification is turned on
+    if params.ssh_verify_host_key.unwrap_or(false) {
+        Ok(format!(
+            "{} verified by {}",
+            key,
+            ssh_tunnel::known_hosts_path().display()
+        ))
+    } else {
+        Ok(key)
+    }
+}
+
+/// The SSH server and login a connection's tunnel goes through
+fn ssh_endpoint(params: &ConnectionParams) -> Result<SshEndpoint, String> {
+    Ok(SshEndpoint {
+        host: params.ssh_host.clone().ok_or("Missing SSH Host")?,
+        port: params.ssh_port.unwrap_or(22),
+        user: params.ssh_user.clone().ok_or("Missing SSH User")?,
+        password: params.ssh_password.clone(),
+        key_file: params.ssh_key_file.clone(),
+        key_passphrase: params.ssh_key_passphrase.clone(),
+        jump_hosts: params.ssh_jump_hosts.clone().unwrap_or_default(),
+    })
+}
+
 pub fn resolve_connection_params(params: &ConnectionParams) -> Result<ConnectionParams, String> {
     if params.ssh_enabled.unwrap_or(false) {
-        let ssh_host = params.ssh_host.as_deref().ok_or("Missing SSH Host")?;
-        let ssh_port = params.ssh_port.unwrap_or(22);
-        let ssh_user = params.ssh_user.as_deref().ok_or("Missing SSH User")?;
-        let remote_host = params.host.as_deref().unwrap_or("localhost");
-        let remote_port = params.port.unwrap_or(3306);
-
-        let map_key = format!(
-            "{}@{}:{}:{}->{}",
-            ssh_user, ssh_host, ssh_port, remote_host, remote_port
-        );
+        let ssh = ssh_endpoint(params)?;
+        let (remote_host, remote_port) = tunnel_remote(params)?;
+        let map_key = tunnel_key(params)?;
 
         {
-            let tunnels = get_tunnels().lock().unwrap();
+            let mut tunnels = get_tunnels().lock().unwrap();
             if let Some(tunnel) = tunnels.get(&map_key) {
-                let mut new_params = params.clone();
-                new_params.host = Some("127.0.0.1".to_string());
-                new_params.port = Some(tunnel.local_port);
-                return Ok(new_params);
+                if tunnel.is_healthy() {
+                    let mut new_params = params.clone();
+                    new_params.host = Some("127.0.0.1".to_string());
+                    new_params.port = Some(tunnel.local_port);
+                    return Ok(new_params);
+                }
+                // The tunnel is down; replace it with a fresh one below
+                println!(
+                    "[Connection] SSH tunnel {} is down ({}), reopening",
+                    map_key,
+                    tunnel.failure().unwrap_or_default()
+                );
+                if let Some(tunnel) = tunnels.remove(&map_key) {
+                    tunnel.stop();
+                }
             }
         }
 
         let tunnel = SshTunnel::new(
-            ssh_host,
-            ssh_port,
-            ssh_user,
-            params.ssh_password.as_deref(),
-            params.ssh_key_file.as_deref(),
-            params.ssh_key_passphrase.as_deref(),
-            remote_host,
+            &ssh,
+            &remote_host,
             remote_port,
+            &TunnelOptions {
+                preferred_local_port: params.ssh_local_port,
+                allow_port_fallback: params.ssh_local_port_fallback.unwrap_or(false),
+                keepalive_interval: params
+                    .ssh_keepalive_secs
+                    .map(Duration::from_secs)
+                    .unwrap_or(DEFAULT_KEEPALIVE_INTERVAL),
+                verify_host_key: params.ssh_verify_host_key.unwrap_or(false),
+            },
         )
         .map_err(|e| {
             eprintln!("[Connection Error] SSH Tunnel setup failed: {}", e);
@@ -114,6 +245,68 @@ pub fn resolve_connection_params(params: &ConnectionParams) -> Result<Connection
     }
 }
 
+/// Closes the pools built from a connection's settings, so the next query connects
+/// with whatever they are now. Never opens an SSH tunnel: without an open tunnel no
+/// pool can be using it.
+async fn close_connection_pool<R: Runtime>(app: &AppHandle<R>, params: &ConnectionParams) {
+    let Ok(mut pool_params) = expand_ssh_connection_params(app, params).await else {
+        return;
+    };
+    if pool_params.ssh_enabled.unwrap_or(false) {
+        let Ok(map_key) = tunnel_key(&pool_params) else {
+            return;
+        };
+        let local_port = match get_tunnels().lock().unwrap().get(&map_key) {
+            Some(tunnel) => tunnel.local_port,
+            None => return,
+        };
+        pool_params.host = Some("127.0.0.1".to_string());
+        pool_params.port = Some(local_port);
+    }
+    pool_manager::close_connection_pools(&pool_params).await;
+}
+
+/// Runs a driver operation and, if it failed because the pooled connection was dropped
+/// (e.g. after a server restart), evicts the dead pool so later calls get a fresh one.
+/// Read-only operations (`retry = true`) are then run once more. Anything else is
+/// only retried when the error shows the statement never reached the server, as
+/// it may otherwise have been applied already. The pool of an in-memory SQLite
+/// database is never rebuilt, as that would wipe it. Errors from the statement
+/// timeout are reported as such.
+async fn with_reconnect<T, F, Fut>(
+    params: &ConnectionParams,
+    retry: bool,
+    op: F,
+) -> Result<T, String>
+where
+    F: Fn() -> Fut,
+    Fut: std::future::Future<Output = Result<T, String>>,
+{
+    let result = match op().await {
+        Err(e)
+            if pool_manager::is_connection_closed_error(&e)
+                && !pool_manager::is_memory_database(params) =>
+        {
+            pool_manager::close_pool(params).await;
+            if retry || pool_manager::is_unsent_statement_error(&e) {
+                eprintln!(
+                    "[Reconnect] Connection to {} lost ({}), rebuilding pool and retrying",
+                    params.database, e
+                );
+                op().await
+            } else {
+                eprintln!(
+                    "[Reconnect] Connection to {} lost ({}), rebuilding pool; the statement may have run, so it is not retried",
+                    params.database, e
+                );
+                Err(e)
+            }
+        }
+        result => result,
+    };
+    result.map_err(|e| describe_statement_timeout(params, e))
+}
+
 pub fn get_config_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
     let config_dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
     if !config_dir.exists() {
@@ -122,6 +315,20 @@ pub fn get_config_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String
     Ok(config_dir.join("connections.json"))
 }
 
+/// Connections as stored on disk, without secrets from the keychain. A missing file
+/// reads as empty and an interrupted write is recovered first. An unparsable file
+/// is an error, so the next save can't overwrite it with an empty list.
+fn read_saved_connections(path: &Path) -> Result<Vec<SavedConnection>, String> {
+    persistence::read_json_recovering(path)
+        .map(Option::unwrap_or_default)
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+fn write_saved_connections(path: &Path, connections: &[SavedConnection]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(connections).map_err(|e| e.to_string())?;
+    persistence::write_atomic(path, json.as_bytes())
+}
+
 pub fn get_ssh_config_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
     let config_dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
     if !config_dir.exists() {
@@ -135,32 +342,35 @@ pub fn find_connection_by_id<R: Runtime>(
     id: &str,
 ) -> Result<SavedConnection, String> {
     let path = get_config_path(app)?;
-    if !path.exists() {
-        return Err("Connection not found".into());
-    }
-    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
-    let connections: Vec<SavedConnection> = serde_json::from_str(&content).unwrap_or_default();
+    let connections = read_saved_connections(&path)?;
     let mut conn = connections
         .into_iter()
         .find(|c| c.id == id)
         .ok_or_else(|| "Connection not found".to_string())?;
+    if conn.params.app_name.is_none() {
+        conn.params.app_name = Some(conn.name.clone());
+    }
 
     if conn.params.save_in_keychain.unwrap_or(false) {
-        match keychain_utils::get_db_password(&conn.id) {
-            Ok(pwd) => conn.params.password = Some(pwd),
-            Err(e) => eprintln!(
-                "[Warning] Failed to retrieve DB password for connection '{}' ({}): {}",
-                conn.name, conn.id, e
-            ),
+        // A missing secret is fine (e.g. an empty password), but an unreachable
+        // store is reported instead of failing later with an auth error
+        let password = keychain_utils::get_db_password(&conn.id).map_err(|e| {
+            format!(
+                "Failed to retrieve DB password for connection '{}': {}",
+                conn.name, e
+            )
+        })?;
+        if password.is_some() {
+            conn.params.password = password;
         }
 
         if conn.params.ssh_enabled.unwrap_or(false) {
-            if let Ok(ssh_pwd) = keychain_utils::get_ssh_password(&conn.id) {
+            if let Some(ssh_pwd) = keychain_utils::get_ssh_password(&conn.id)? {
                 if !ssh_pwd.trim().is_empty() {
                     conn.params.ssh_password = Some(ssh_pwd);
                 }
             }
-            if let Ok(ssh_passphrase) = keychain_utils::get_ssh_key_passphrase(&conn.id) {
+            if let Some(ssh_passphrase) = keychain_utils::get_ssh_key_passphrase(&conn.id)? {
                 if !ssh_passphrase.trim().is_empty() {
                     conn.params.ssh_key_passphrase = Some(ssh_passphrase);
                 }
@@ -181,60 +391,26 @@ pub async fn get_schema_snapshot<R: Runtime>(
     let saved_conn = find_connection_by_id(&app, &connection_id)?;
     let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
     let params = resolve_connection_params(&expanded_params)?;
-    let driver = saved_conn.params.driver.clone();
+    let driver = driver_for(&saved_conn.params)?;
 
     // 1. Get Tables
-    let tables = match driver.as_str() {
-        "mysql" => mysql::get_tables(&params).await,
-        "postgres" => postgres::get_tables(&params).await,
-        "sqlite" => sqlite::get_tables(&params).await,
-        _ => Err("Unsupported driver".into()),
-    }?;
+    let tables = driver.get_tables(&params).await?;
 
     // 2. Fetch ALL columns and foreign keys in batch (2 queries instead of N*2)
-    let schema = match driver.as_str() {
-        "mysql" => {
-            let mut columns_map = mysql::get_all_columns_batch(&params).await?;
-            let mut fks_map = mysql::get_all_foreign_keys_batch(&params).await?;
-
-            tables
-                .into_iter()
-                .map(|table| crate::models::TableSchema {
-                    name: table.name.clone(),
-                    columns: columns_map.remove(&table.name).unwrap_or_default(),
-                    foreign_keys: fks_map.remove(&table.name).unwrap_or_default(),
-                })
-                .collect()
-        }
-        "postgres" => {
-            let mut columns_map = postgres::get_all_columns_batch(&params).await?;
-            let mut fks_map = postgres::get_all_foreign_keys_batch(&params).await?;
-
-            tables
-                .into_iter()
-                .map(|table| crate::models::TableSchema {
-                    name: table.name.clone(),
-                    columns: columns_map.remove(&table.name).unwrap_or_default(),
-                    foreign_keys: fks_map.remove(&table.name).unwrap_or_default(),
-                })
-                .collect()
-        }
-        "sqlite" => {
-            let table_names: Vec<String> = tables.iter().map(|t| t.name.clone()).collect();
-            let mut columns_map = sqlite::get_all_columns_batch(&params, &table_names).await?;
-            let mut fks_map = sqlite::get_all_foreign_keys_batch(&params, &table_names).await?;
-
-            tables
-                .into_iter()
-                .map(|table| crate::models::TableSchema {
-                    name: table.name.clone(),
-                    columns: columns_map.remove(&table.name).unwrap_or_default(),
-                    foreign_keys: fks_map.remove(&table.name).unwrap_or_default(),
-                })
-                .collect()
-        }
-        _ => return Err("Unsupported driver".into()),
-    };
+    let table_names: Vec<String> = tables.iter().map(|t| t.name.clone()).collect();
+    let mut columns_map = driver.get_all_columns_batch(&params, &table_names).await?;
+    let mut fks_map = driver
+        .get_all_foreign_keys_batch(&params, &table_names)
+        .await?;
+
+    let schema = tables
+        .into_iter()
+        .map(|table| crate::models::TableSchema {
+            name: table.name.clone(),
+            columns: columns_map.remove(&table.name).unwrap_or_default(),
+            foreign_keys: fks_map.remove(&table.name).unwrap_or_default(),
+        })
+        .collect();
 
     Ok(schema)
 }
@@ -244,14 +420,11 @@ pub async fn save_connection<R: Runtime>(
     app: AppHandle<R>,
     name: String,
     params: ConnectionParams,
+    group: Option<String>,
 ) -> Result<SavedConnection, String> {
     let path = get_config_path(&app)?;
-    let mut connections: Vec<SavedConnection> = if path.exists() {
-        let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
-        serde_json::from_str(&content).unwrap_or_default()
-    } else {
-        Vec::new()
-    };
+    let _lock = persistence::lock_file_async(&path).await?;
+    let mut connections = read_saved_connections(&path)?;
 
     let id = Uuid::new_v4().to_string();
     let mut params_to_save = params.clone();
@@ -279,10 +452,10 @@ pub async fn save_connection<R: Runtime>(
         id: id.clone(),
         name,
         params: params_to_save,
+        group: persistence::normalize_group(group.as_deref()),
     };
     connections.push(new_conn.clone());
-    let json = serde_json::to_string_pretty(&connections).map_err(|e| e.to_string())?;
-    fs::write(path, json).map_err(|e| e.to_string())?;
+    write_saved_connections(&path, &connections)?;
 
     let mut returned_conn = new_conn;
     returned_conn.params = params; // Return with password for frontend state
@@ -292,13 +465,12 @@ pub async fn save_connection<R: Runtime>(
 #[tauri::command]
 pub async fn delete_connection<R: Runtime>(app: AppHandle<R>, id: String) -> Result<(), String> {
     let path = get_config_path(&app)?;
-    if !path.exists() {
-        return Ok(());
-    }
-
-    let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
-    let mut connections: Vec<SavedConnection> = serde_json::from_str(&content).unwrap_or_default();
+    let _lock = persistence::lock_file_async(&path).await?;
+    let mut connections = read_saved_connections(&path)?;
 
+    if let Some(conn) = connections.iter().find(|c| c.id == id) {
+        close_connection_pool(&app, &conn.params).await;
+    }
     connections.retain(|c| c.id != id);
 
     // Attempt to remove passwords from keychain (ignore if not found)
@@ -306,8 +478,7 @@ pub async fn delete_connection<R: Runtime>(app: AppHandle<R>, id: String) -> Res
     keychain_utils::delete_ssh_password(&id).ok();
     keychain_utils::delete_ssh_key_passphrase(&id).ok();
 
-    let json = serde_json::to_string_pretty(&connections).map_err(|e| e.to_string())?;
-    fs::write(path, json).map_err(|e| e.to_string())?;
+    write_saved_connections(&path, &connections)?;
     Ok(())
 }
 
@@ -317,10 +488,11 @@ pub async fn update_connection<R: Runtime>(
     id: String,
     name: String,
     params: ConnectionParams,
+    group: Option<String>,
 ) -> Result<SavedConnection, String> {
     let path = get_config_path(&app)?;
-    let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
-    let mut connections: Vec<SavedConnection> = serde_json::from_str(&content).unwrap_or_default();
+    let _lock = persistence::lock_file_async(&path).await?;
+    let mut connections = read_saved_connections(&path)?;
 
     let conn_idx = connections
         .iter()
@@ -355,16 +527,26 @@ pub async fn update_connection<R: Runtime>(
         keychain_utils::delete_ssh_key_passphrase(&id).ok();
     }
 
+    // Without a group the connection stays where it is; `move_connections` ungroups
+    let group = match group {
+        Some(group) => persistence::normalize_group(Some(&group)),
+        None => connections[conn_idx].group.clone(),
+    };
     let updated = SavedConnection {
         id: id.clone(),
         name,
         params: params_to_save,
+        group,
     };
 
-    connections[conn_idx] = updated.clone();
+    let previous = std::mem::replace(&mut connections[conn_idx], updated.clone());
 
-    let json = serde_json::to_string_pretty(&connections).map_err(|e| e.to_string())?;
-    fs::write(path, json).map_err(|e| e.to_string())?;
+    write_saved_connections(&path, &connections)?;
+    // The old pool may be keyed on a host, database or option that just changed.
+    // An in-memory database is kept open, as closing its pool would wipe it.
+    if !pool_manager::is_memory_database(&previous.params) {
+        close_connection_pool(&app, &previous.params).await;
+    }
 
     let mut returned_conn = updated;
     returned_conn.params = params;
@@ -377,8 +559,8 @@ pub async fn duplicate_connection<R: Runtime>(
     id: String,
 ) -> Result<SavedConnection, String> {
     let path = get_config_path(&app)?;
-    let content = fs::read_to_string(&path).map_err(|e| e.to_string())?;
-    let mut connections: Vec<SavedConnection> = serde_json::from_str(&content).unwrap_or_default();
+    let _lock = persistence::lock_file_async(&path).await?;
+    let mut connections = read_saved_connections(&path)?;
 
     let original_idx = connections
         .iter()
@@ -388,16 +570,16 @@ pub async fn duplicate_connection<R: Runtime>(
 
     // Recover passwords if in keychain
     if original.params.save_in_keychain.unwrap_or(false) {
-        if let Ok(pwd) = keychain_utils::get_db_password(&original.id) {
+        if let Ok(Some(pwd)) = keychain_utils::get_db_password(&original.id) {
             original.params.password = Some(pwd);
         }
         if original.params.ssh_enabled.unwrap_or(false) {
-            if let Ok(ssh_pwd) = keychain_utils::get_ssh_password(&original.id) {
+            if let Ok(Some(ssh_pwd)) = keychain_utils::get_ssh_password(&original.id) {
                 if !ssh_pwd.trim().is_empty() {
                     original.params.ssh_password = Some(ssh_pwd);
                 }
             }
-            if let Ok(ssh_passphrase) = keychain_utils::get_ssh_key_passphrase(&original.id) {
+            if let Ok(Some(ssh_passphrase)) = keychain_utils::get_ssh_key_passphrase(&original.id) {
                 if !ssh_passphrase.trim().is_empty() {
                     original.params.ssh_key_passphrase = Some(ssh_passphrase);
                 }
@@ -432,12 +614,12 @@ pub async fn duplicate_connection<R: Runtime>(
         id: new_id,
         name: format!("{} (Copy)", original.name),
         params: new_params,
+        group: original.group.clone(),
     };
 
     connections.push(new_conn.clone());
 
-    let json = serde_json::to_string_pretty(&connections).map_err(|e| e.to_string())?;
-    fs::write(path, json).map_err(|e| e.to_string())?;
+    write_saved_connections(&path, &connections)?;
 
     let mut returned_conn = new_conn;
     // Return with passwords for frontend consistency
@@ -452,6 +634,90 @@ pub async fn duplicate_connection<R: Runtime>(
     Ok(returned_conn)
 }
 
+/// Puts the given connections into `group` (`None` moves them to the top level)
+#[tauri::command]
+pub async fn move_connections<R: Runtime>(
+    app: AppHandle<R>,
+    ids: Vec<String>,
+    group: Option<String>,
+) -> Result<(), String> {
+    let path = get_config_path(&app)?;
+    let _lock = persistence::lock_file_async(&path).await?;
+    let mut connections = read_saved_connections(&path)?;
+
+    let group = persistence::normalize_group(group.as_deref());
+    for conn in connections.iter_mut().filter(|c| ids.contains(&c.id)) {
+        conn.group = group.clone();
+    }
+
+    write_saved_connections(&path, &connections)
+}
+
+/// Renames a group, or moves it with its subgroups under another one. Returns the
+/// number of connections that moved.
+#[tauri::command]
+pub async fn rename_connection_group<R: Runtime>(
+    app: AppHandle<R>,
+    from: String,
+    to: Option<String>,
+) -> Result<usize, String> {
+    let path = get_config_path(&app)?;
+    let _lock = persistence::lock_file_async(&path).await?;
+    let mut connections = read_saved_connections(&path)?;
+
+    let moved = persistence::rename_group(&mut connections, &from, to.as_deref());
+
+    write_saved_connections(&path, &connections)?;
+    Ok(moved)
+}
+
+#[tauri::command]
+pub async fn export_connections<R: Runtime>(
+    app: AppHandle<R>,
+    path: String,
+) -> Result<usize, String> {
+    let config_path = get_config_path(&app)?;
+    let connections = read_saved_connections(&config_path)?;
+
+    persistence::export_connections(Path::new(&path), &connections)?;
+    Ok(connections.len())
+}
+
+#[tauri::command]
+pub async fn import_connections<R: Runtime>(
+    app: AppHandle<R>,
+    path: String,
+) -> Result<ConnectionImport, String> {
+    let config_path = get_config_path(&app)?;
+    let _lock = persistence::lock_file_async(&config_path).await?;
+    let mut connections = read_saved_connections(&config_path)?;
+
+    let imported = persistence::import_connections(Path::new(&path), &connections)?;
+    connections.extend(imported.iter().cloned());
+
+    write_saved_connections(&config_path, &connections)?;
+
+    // SQLite and DuckDB files need no credentials; everything else was exported without them
+    let password_required = imported
+        .iter()
+        .filter(|c| {
+            !matches!(c.params.driver.as_str(), "sqlite" | "duckdb")
+                || c.params.ssh_enabled.unwrap_or(false)
+        })
+        .map(|c| c.id.clone())
+        .collect();
+
+    println!(
+        "[Connections] Imported {} connections from {}",
+        imported.len(),
+        path
+    );
+    Ok(ConnectionImport {
+        imported,
+        password_required,
+    })
+}
+
 #[tauri::command]
 pub async fn get_connections<R: Runtime>(
     app: AppHandle<R>,
@@ -460,29 +726,26 @@ pub async fn get_connections<R: Runtime>(
     migrate_ssh_connections(&app).await.ok();
 
     let path = get_config_path(&app)?;
-    if !path.exists() {
-        return Ok(Vec::new());
-    }
-    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
-    let mut connections: Vec<SavedConnection> = serde_json::from_str(&content).unwrap_or_default();
+    let mut connections = read_saved_connections(&path)?;
 
     // Populate passwords from keychain if needed
     for conn in &mut connections {
         if conn.params.save_in_keychain.unwrap_or(false) {
             match keychain_utils::get_db_password(&conn.id) {
-                Ok(pwd) => conn.params.password = Some(pwd),
+                Ok(Some(pwd)) => conn.params.password = Some(pwd),
+                Ok(None) => {}
                 Err(e) => eprintln!(
                     "[Keyring Error] Failed to get DB password for {}: {}",
                     conn.id, e
                 ),
             }
             if conn.params.ssh_enabled.unwrap_or(false) {
-                if let Ok(ssh_pwd) = keychain_utils::get_ssh_password(&conn.id) {
+                if let Ok(Some(ssh_pwd)) = keychain_utils::get_ssh_password(&conn.id) {
                     if !ssh_pwd.trim().is_empty() {
                         conn.params.ssh_password = Some(ssh_pwd);
                     }
                 }
-                if let Ok(ssh_passphrase) = keychain_utils::get_ssh_key_passphrase(&conn.id) {
+                if let Ok(Some(ssh_passphrase)) = keychain_utils::get_ssh_key_passphrase(&conn.id) {
                     if !ssh_passphrase.trim().is_empty() {
                         conn.params.ssh_key_passphrase = Some(ssh_passphrase);
                     }
@@ -499,12 +762,8 @@ pub async fn get_connections<R: Runtime>(
 /// Migrates old embedded SSH connections to separate SSH connection entries
 async fn migrate_ssh_connections<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
     let conn_path = get_config_path(app)?;
-    if !conn_path.exists() {
-        return Ok(()); // Nothing to migrate
-    }
-
-    let content = fs::read_to_string(&conn_path).map_err(|e| e.to_string())?;
-    let connections: Vec<SavedConnection> = serde_json::from_str(&content).unwrap_or_default();
+    let _lock = persistence::lock_file_async(&conn_path).await?;
+    let connections = read_saved_connections(&conn_path)?;
 
     // Check if any connections have old embedded SSH params
     let needs_migration = connections
@@ -548,12 +807,12 @@ async fn migrate_ssh_connections<R: Runtime>(app: &AppHandle<R>) -> Result<(), S
 
                     // Migrate credentials from connection keychain to SSH keychain
                     if conn.params.save_in_keychain.unwrap_or(false) {
-                        if let Ok(ssh_pwd) = keychain_utils::get_ssh_password(&conn.id) {
+                        if let Ok(Some(ssh_pwd)) = keychain_utils::get_ssh_password(&conn.id) {
                             if !ssh_pwd.trim().is_empty() {
                                 keychain_utils::set_ssh_password(&new_ssh_id, &ssh_pwd).ok();
                             }
                         }
-                        if let Ok(ssh_pass) = keychain_utils::get_ssh_key_passphrase(&conn.id) {
+                        if let Ok(Some(ssh_pass)) = keychain_utils::get_ssh_key_passphrase(&conn.id) {
                             if !ssh_pass.trim().is_empty() {
                                 keychain_utils::set_ssh_key_passphrase(&new_ssh_id, &ssh_pass).ok();
                             }
@@ -580,6 +839,8 @@ async fn migrate_ssh_connections<R: Runtime>(app: &AppHandle<R>) -> Result<(), S
                             Some(key_file.clone())
                         },
                         key_passphrase: None,
+                        jump_hosts: conn.params.ssh_jump_hosts.clone(),
+                        verify_host_key: conn.params.ssh_verify_host_key,
                         save_in_keychain: conn.params.save_in_keychain,
                     };
 
@@ -597,6 +858,8 @@ async fn migrate_ssh_connections<R: Runtime>(app: &AppHandle<R>) -> Result<(), S
                 conn.params.ssh_password = None;
                 conn.params.ssh_key_file = None;
                 conn.params.ssh_key_passphrase = None;
+                conn.params.ssh_jump_hosts = None;
+                conn.params.ssh_verify_host_key = None;
             }
         }
 
@@ -608,8 +871,7 @@ async fn migrate_ssh_connections<R: Runtime>(app: &AppHandle<R>) -> Result<(), S
     fs::write(ssh_path, ssh_json).map_err(|e| e.to_string())?;
 
     // Save migrated connections
-    let conn_json = serde_json::to_string_pretty(&migrated_connections).map_err(|e| e.to_string())?;
-    fs::write(conn_path, conn_json).map_err(|e| e.to_string())?;
+    write_saved_connections(&conn_path, &migrated_connections)?;
 
     println!(
         "[Migration] Successfully migrated {} SSH connections",
@@ -644,12 +906,12 @@ pub async fn get_ssh_connections<R: Runtime>(
         }
 
         if ssh.save_in_keychain.unwrap_or(false) {
-            if let Ok(pwd) = keychain_utils::get_ssh_password(&ssh.id) {
+            if let Ok(Some(pwd)) = keychain_utils::get_ssh_password(&ssh.id) {
                 if !pwd.trim().is_empty() {
                     ssh.password = Some(pwd);
                 }
             }
-            if let Ok(passphrase) = keychain_utils::get_ssh_key_passphrase(&ssh.id) {
+            if let Ok(Some(passphrase)) = keychain_utils::get_ssh_key_passphrase(&ssh.id) {
                 if !passphrase.trim().is_empty() {
                     ssh.key_passphrase = Some(passphrase);
                 }
@@ -701,6 +963,8 @@ pub async fn save_ssh_connection<R: Runtime>(
         } else {
             ssh.key_passphrase.clone()
         },
+        jump_hosts: ssh.jump_hosts.clone(),
+        verify_host_key: ssh.verify_host_key,
         save_in_keychain: ssh.save_in_keychain,
     };
 
@@ -763,6 +1027,8 @@ pub async fn update_ssh_connection<R: Runtime>(
         } else {
             ssh.key_passphrase.clone()
         },
+        jump_hosts: ssh.jump_hosts.clone(),
+        verify_host_key: ssh.verify_host_key,
         save_in_keychain: ssh.save_in_keychain,
     };
 
@@ -804,8 +1070,6 @@ pub async fn delete_ssh_connection<R: Runtime>(
 
 #[tauri::command]
 pub async fn test_ssh_connection(ssh: SshTestParams) -> Result<String, String> {
-    use crate::ssh_tunnel;
-
     ssh_tunnel::test_ssh_connection(
         &ssh.host,
         ssh.port,
@@ -813,14 +1077,119 @@ pub async fn test_ssh_connection(ssh: SshTestParams) -> Result<String, String> {
         ssh.password.as_deref(),
         ssh.key_file.as_deref(),
         ssh.key_passphrase.as_deref(),
+        ssh.verify_host_key.unwrap_or(false),
     )
 }
 
+/// Opens an SSH tunnel with the connection's SSH settings, checks it and closes it
+/// again, so the SSH side can be tested without valid database credentials. With
+/// `probe`, a connection is also forwarded to the database host.
 #[tauri::command]
-pub async fn test_connection<R: Runtime>(
+pub async fn test_ssh_tunnel<R: Runtime>(
     app: AppHandle<R>,
     params: ConnectionParams,
+    probe: Option<bool>,
 ) -> Result<String, String> {
+    let params = expand_ssh_connection_params(&app, &params).await?;
+    let ssh = ssh_endpoint(&params)?;
+    let (remote_host, remote_port) = tunnel_remote(&params)?;
+    let probe = probe.unwrap_or(false);
+
+    tokio::task::spawn_blocking(move || {
+        let tunnel = SshTunnel::new(
+            &ssh,
+            &remote_host,
+            remote_port,
+            &TunnelOptions {
+                keepalive_interval: params
+                    .ssh_keepalive_secs
+                    .map(Duration::from_secs)
+                    .unwrap_or(DEFAULT_KEEPALIVE_INTERVAL),
+                verify_host_key: params.ssh_verify_host_key.unwrap_or(false),
+                ..Default::default()
+            },
+        )
+        .map_err(|e| format!("SSH tunnel failed: {}", e))?;
+
+        // A tunnel that died during the check explains the failure better
+        let checked = ssh_tunnel::check_tunnel(tunnel.local_port, probe).map_err(|e| {
+            match tunnel.failure() {
+                Some(reason) => format!("{}: {}", e, reason),
+                None => e,
+            }
+        });
+        tunnel.stop();
+        checked?;
+
+        Ok(format!(
+            "SSH tunnel to {}:{} via {}@{}:{} works ({})",
+            remote_host,
+            remote_port,
+            ssh.user,
+            ssh.host,
+            ssh.port,
+            tunnel.backend_name()
+        ))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn list_ssh_tunnels() -> Result<Vec<SshTunnelInfo>, String> {
+    let tunnels = get_tunnels().lock().unwrap();
+    let mut infos: Vec<SshTunnelInfo> = tunnels
+        .iter()
+        .map(|(key, tunnel)| SshTunnelInfo {
+            key: key.clone(),
+            local_port: tunnel.local_port,
+            backend: tunnel.backend_name().to_string(),
+            healthy: tunnel.is_healthy(),
+            error: tunnel.failure(),
+        })
+        .collect();
+    infos.sort_by(|a, b| a.key.cmp(&b.key));
+    Ok(infos)
+}
+
+#[tauri::command]
+pub async fn close_ssh_tunnel(key: String) -> Result<(), String> {
+    if !ssh_tunnel::stop_tunnel(&key) {
+        return Err(format!("No active SSH tunnel: {}", key));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn close_all_ssh_tunnels() -> Result<(), String> {
+    ssh_tunnel::stop_all_tunnels();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn test_connection<R: Runtime>(
+    app: AppHandle<R>,
+    params: ConnectionParams,
+) -> Result<ConnectionTestInfo, String> {
+    // DuckDB runs in-process: opening the database is the whole test
+    if params.driver == "duckdb" {
+        let started = Instant::now();
+        let (server_version, database) = duckdb::server_info(&params).await?;
+        return Ok(ConnectionTestInfo {
+            server_version,
+            database: Some(database),
+            latency_ms: started.elapsed().as_millis() as u64,
+        });
+    }
+
+    // Server version plus the database the session actually landed in
+    let info_query = match params.driver.as_str() {
+        "postgres" => "SELECT version(), current_database()::text",
+        "mysql" => "SELECT VERSION(), DATABASE()",
+        "sqlite" => "SELECT sqlite_version(), 'main'",
+        _ => return Err("Unsupported driver".into()),
+    };
+
     let expanded_params = expand_ssh_connection_params(&app, &params).await?;
     let resolved_params = resolve_connection_params(&expanded_params)?;
     println!(
@@ -828,39 +1197,61 @@ pub async fn test_connection<R: Runtime>(
         resolved_params.host, resolved_params.port
     );
 
-    let user = encode(resolved_params.username.as_deref().unwrap_or_default());
-    let pass = encode(resolved_params.password.as_deref().unwrap_or_default());
-    let host = resolved_params.host.as_deref().unwrap_or("localhost");
-
-    let url = match resolved_params.driver.as_str() {
-        "sqlite" => format!("sqlite://{}", resolved_params.database),
-        "postgres" => format!(
-            "postgres://{}:{}@{}:{}/{}",
-            user,
-            pass,
-            host,
-            resolved_params.port.unwrap_or(5432),
-            resolved_params.database
-        ),
-        "mysql" => format!(
-            "mysql://{}:{}@{}:{}/{}",
-            user,
-            pass,
-            host,
-            resolved_params.port.unwrap_or(3306),
-            resolved_params.database
-        ),
-        _ => return Err("Unsupported driver".into()),
-    };
+    let url = build_url(&resolved_params)?;
 
     println!("[Test Connection] URL: {}", url);
 
     let options = AnyConnectOptions::from_str(&url).map_err(|e| e.to_string())?;
-    let mut conn = AnyConnection::connect_with(&options)
+    let timeout = pool_manager::connect_timeout(&resolved_params);
+    let mut conn = tokio::time::timeout(timeout, AnyConnection::connect_with(&options))
+        .await
+        .map_err(|_| format!("Connection timed out after {}s", timeout.as_secs()))?
+        .map_err(|e: sqlx::Error| e.to_string())?;
+    let started = Instant::now();
+    tokio::time::timeout(timeout, conn.ping())
         .await
+        .map_err(|_| format!("Connection timed out after {}s", timeout.as_secs()))?
         .map_err(|e: sqlx::Error| e.to_string())?;
-    conn.ping().await.map_err(|e: sqlx::Error| e.to_string())?;
-    Ok("Connection successful!".to_string())
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let (server_version, database): (String, Option<String>) = sqlx::query_as(info_query)
+        .fetch_one(&mut conn)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(ConnectionTestInfo {
+        server_version,
+        database,
+        latency_ms,
+    })
+}
+
+/// Checks that a saved connection still answers, returning the round trip in ms.
+/// A pool that fails the check (e.g. gone stale while the machine slept) is
+/// rebuilt and checked once more, so the next query runs on fresh connections,
+/// unless it holds an in-memory SQLite database, which rebuilding would wipe.
+#[tauri::command]
+pub async fn ping_connection<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+) -> Result<u64, String> {
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+    let mut started = Instant::now();
+    if let Err(e) = pool_manager::ping_pool(&params).await {
+        if pool_manager::is_memory_database(&params) {
+            return Err(e);
+        }
+        eprintln!(
+            "[Reconnect] Ping to {} failed ({}), rebuilding pool",
+            params.database, e
+        );
+        pool_manager::close_pool(&params).await;
+        started = Instant::now();
+        pool_manager::ping_pool(&params).await?;
+    }
+    Ok(started.elapsed().as_millis() as u64)
 }
 
 #[tauri::command]
@@ -870,13 +1261,23 @@ pub async fn list_databases<R: Runtime>(
 ) -> Result<Vec<String>, String> {
     let expanded_params = expand_ssh_connection_params(&app, &params).await?;
     let resolved_params = resolve_connection_params(&expanded_params)?;
-    
-    match resolved_params.driver.as_str() {
-        "mysql" => mysql::get_databases(&resolved_params).await,
-        "postgres" => postgres::get_databases(&resolved_params).await,
-        "sqlite" => sqlite::get_databases(&resolved_params).await,
-        _ => Err("Unsupported driver".into()),
-    }
+
+    driver_for(&resolved_params)?
+        .get_databases(&resolved_params)
+        .await
+}
+
+#[tauri::command]
+pub async fn get_schemas<R: Runtime>(
+    app: AppHandle<R>,
+    params: ConnectionParams,
+) -> Result<Vec<String>, String> {
+    let expanded_params = expand_ssh_connection_params(&app, &params).await?;
+    let resolved_params = resolve_connection_params(&expanded_params)?;
+
+    driver_for(&resolved_params)?
+        .get_schemas(&resolved_params)
+        .await
 }
 
 #[tauri::command]
@@ -887,12 +1288,20 @@ pub async fn get_tables<R: Runtime>(
     let saved_conn = find_connection_by_id(&app, &connection_id)?;
     let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
     let params = resolve_connection_params(&expanded_params)?;
-    match saved_conn.params.driver.as_str() {
-        "mysql" => mysql::get_tables(&params).await,
-        "postgres" => postgres::get_tables(&params).await,
-        "sqlite" => sqlite::get_tables(&params).await,
-        _ => Err("Unsupported driver".into()),
-    }
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&params, true, || driver.get_tables(&params)).await
+}
+
+#[tauri::command]
+pub async fn get_views<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+) -> Result<Vec<ViewInfo>, String> {
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&params, true, || driver.get_views(&params)).await
 }
 
 #[tauri::command]
@@ -904,12 +1313,107 @@ pub async fn get_columns<R: Runtime>(
     let saved_conn = find_connection_by_id(&app, &connection_id)?;
     let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
     let params = resolve_connection_params(&expanded_params)?;
-    match saved_conn.params.driver.as_str() {
-        "mysql" => mysql::get_columns(&params, &table_name).await,
-        "postgres" => postgres::get_columns(&params, &table_name).await,
-        "sqlite" => sqlite::get_columns(&params, &table_name).await,
-        _ => Err("Unsupported driver".into()),
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&params, true, || driver.get_columns(&params, &table_name)).await
+}
+
+/// Row count for `table_name`. With `exact` unset, MySQL and Postgres answer
+/// from their statistics and flag the result as an estimate.
+#[tauri::command]
+pub async fn get_table_row_count<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+    table_name: String,
+    exact: bool,
+) -> Result<TableRowCount, String> {
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&params, true, || {
+        driver.get_table_row_count(&params, &table_name, exact)
+    })
+    .await
+}
+
+/// Min, max, null and distinct counts of one column. `sample` caps the rows read,
+/// for tables too large to scan in full.
+#[tauri::command]
+pub async fn get_column_stats<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+    table_name: String,
+    column: String,
+    sample: Option<u32>,
+) -> Result<ColumnStats, String> {
+    if sample == Some(0) {
+        return Err("Sample size must be at least 1".into());
+    }
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+    let driver = driver_for(&saved_conn.params)?;
+    let sql = column_stats_sql(&params.driver, &table_name, &column, sample);
+    let result = with_reconnect(&params, true, || {
+        driver.execute_query(&params, &sql, None, 1, None, None)
+    })
+    .await?;
+    let row = result
+        .rows
+        .into_iter()
+        .next()
+        .ok_or("Column statistics query returned no row")?;
+    column_stats_from_row(row, sample.is_some())
+}
+
+/// Rows `search_table` returns when no limit is given
+const SEARCH_TABLE_DEFAULT_LIMIT: u32 = 200;
+
+/// Rows of `table_name` with `term` anywhere in one of its text columns, matched
+/// literally (`%` and `_` in the term are not wildcards). `truncated` is set when
+/// more rows than `limit` match.
+#[tauri::command]
+pub async fn search_table<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+    table_name: String,
+    term: String,
+    limit: Option<u32>,
+) -> Result<QueryResult, String> {
+    if term.is_empty() {
+        return Err("Search term is empty".into());
     }
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+    let driver = driver_for(&saved_conn.params)?;
+    let columns =
+        with_reconnect(&params, true, || driver.get_columns(&params, &table_name)).await?;
+    let text_columns: Vec<String> = columns
+        .into_iter()
+        .filter(|col| is_text_type(&col.data_type))
+        .map(|col| col.name)
+        .collect();
+    let (sql, values) = search_table_sql(&params.driver, &table_name, &text_columns, &term)?;
+    let limit = limit.unwrap_or(SEARCH_TABLE_DEFAULT_LIMIT);
+    with_reconnect(&params, true, || {
+        driver.execute_query_with_params(&params, &sql, &values, Some(limit))
+    })
+    .await
+}
+
+/// `CREATE TABLE` (plus `CREATE INDEX`) statements that recreate `table_name`
+#[tauri::command]
+pub async fn get_table_ddl<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+    table_name: String,
+) -> Result<String, String> {
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&params, true, || driver.get_table_ddl(&params, &table_name)).await
 }
 
 #[tauri::command]
@@ -921,12 +1425,11 @@ pub async fn get_foreign_keys<R: Runtime>(
     let saved_conn = find_connection_by_id(&app, &connection_id)?;
     let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
     let params = resolve_connection_params(&expanded_params)?;
-    match saved_conn.params.driver.as_str() {
-        "mysql" => mysql::get_foreign_keys(&params, &table_name).await,
-        "postgres" => postgres::get_foreign_keys(&params, &table_name).await,
-        "sqlite" => sqlite::get_foreign_keys(&params, &table_name).await,
-        _ => Err("Unsupported driver".into()),
-    }
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&params, true, || {
+        driver.get_foreign_keys(&params, &table_name)
+    })
+    .await
 }
 
 #[tauri::command]
@@ -938,56 +1441,299 @@ pub async fn get_indexes<R: Runtime>(
     let saved_conn = find_connection_by_id(&app, &connection_id)?;
     let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
     let params = resolve_connection_params(&expanded_params)?;
-    match saved_conn.params.driver.as_str() {
-        "mysql" => mysql::get_indexes(&params, &table_name).await,
-        "postgres" => postgres::get_indexes(&params, &table_name).await,
-        "sqlite" => sqlite::get_indexes(&params, &table_name).await,
-        _ => Err("Unsupported driver".into()),
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&params, true, || driver.get_indexes(&params, &table_name)).await
+}
+
+#[tauri::command]
+pub async fn get_triggers<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+    table_name: String,
+) -> Result<Vec<Trigger>, String> {
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&params, true, || driver.get_triggers(&params, &table_name)).await
+}
+
+/// Stored procedures and functions in `schema`, or in the connection's schema
+/// (database for MySQL) when omitted
+#[tauri::command]
+pub async fn get_routines<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+    schema: Option<String>,
+) -> Result<Vec<RoutineInfo>, String> {
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&params, true, || {
+        driver.get_routines(&params, schema.as_deref())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn get_routine_definition<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+    name: String,
+    schema: Option<String>,
+) -> Result<String, String> {
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&params, true, || {
+        driver.get_routine_definition(&params, schema.as_deref(), &name)
+    })
+    .await
+}
+
+/// Runs the stored procedure or function `name`. Procedures may write, so this is
+/// refused on read-only connections.
+#[tauri::command]
+pub async fn call_procedure<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+    name: String,
+    args: Vec<serde_json::Value>,
+) -> Result<ProcedureResult, String> {
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    ensure_writable(&saved_conn.params)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&params, false, || {
+        driver.call_procedure(&params, &name, &args)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn create_index<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+    table_name: String,
+    index_name: String,
+    columns: Vec<String>,
+    unique: bool,
+) -> Result<(), String> {
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    ensure_writable(&saved_conn.params)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&params, false, || {
+        driver.create_index(&params, &table_name, &index_name, &columns, unique)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn drop_index<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+    table_name: String,
+    index_name: String,
+) -> Result<(), String> {
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    ensure_writable(&saved_conn.params)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&params, false, || {
+        driver.drop_index(&params, &table_name, &index_name)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn add_column<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+    table_name: String,
+    column: ColumnDefinition,
+) -> Result<(), String> {
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    ensure_writable(&saved_conn.params)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&params, false, || {
+        driver.add_column(&params, &table_name, &column)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn drop_column<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+    table_name: String,
+    column_name: String,
+) -> Result<(), String> {
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    ensure_writable(&saved_conn.params)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&params, false, || {
+        driver.drop_column(&params, &table_name, &column_name)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn rename_column<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+    table_name: String,
+    column_name: String,
+    new_name: String,
+) -> Result<(), String> {
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    ensure_writable(&saved_conn.params)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&params, false, || {
+        driver.rename_column(&params, &table_name, &column_name, &new_name)
+    })
+    .await
+}
+
+/// Builds the primary key filter for row edits: `pk_values` for composite keys,
+/// otherwise the single `pk_col`/`pk_val` pair sent by older callers. A `pk_val`
+/// without `pk_col` is matched against the table's own primary key
+async fn resolve_pk_filter(
+    params: &ConnectionParams,
+    table: &str,
+    pk_col: Option<String>,
+    pk_val: Option<serde_json::Value>,
+    pk_values: Option<HashMap<String, serde_json::Value>>,
+) -> Result<HashMap<String, serde_json::Value>, String> {
+    match (pk_values, pk_col, pk_val) {
+        (Some(values), _, _) if !values.is_empty() => Ok(values),
+        (_, Some(col), Some(val)) => Ok(HashMap::from([(col, val)])),
+        (_, None, Some(val)) => {
+            let keys = driver_for(params)?.get_primary_keys(params, table).await?;
+            let col = single_primary_key(table, keys)?;
+            Ok(HashMap::from([(col, val)]))
+        }
+        _ => Err("Missing primary key for record".into()),
     }
 }
 
+#[tauri::command]
+pub async fn get_primary_keys<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+    table_name: String,
+) -> Result<Vec<String>, String> {
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&params, true, || {
+        driver.get_primary_keys(&params, &table_name)
+    })
+    .await
+}
+
 #[tauri::command]
 pub async fn delete_record<R: Runtime>(
     app: AppHandle<R>,
     connection_id: String,
     table: String,
-    pk_col: String,
-    pk_val: serde_json::Value,
+    pk_col: Option<String>,
+    pk_val: Option<serde_json::Value>,
+    pk_values: Option<HashMap<String, serde_json::Value>>,
 ) -> Result<u64, String> {
     let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    ensure_writable(&saved_conn.params)?;
     let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
     let params = resolve_connection_params(&expanded_params)?;
-    match saved_conn.params.driver.as_str() {
-        "mysql" => mysql::delete_record(&params, &table, &pk_col, pk_val).await,
-        "postgres" => postgres::delete_record(&params, &table, &pk_col, pk_val).await,
-        "sqlite" => sqlite::delete_record(&params, &table, &pk_col, pk_val).await,
-        _ => Err("Unsupported driver".into()),
-    }
+    let pk = resolve_pk_filter(&params, &table, pk_col, pk_val, pk_values).await?;
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&params, false, || driver.delete_record(&params, &table, &pk)).await
+}
+
+/// Deletes by column values instead of a primary key, for rows of tables that have
+/// none. `null` values match with `IS NULL`. Nothing is deleted unless exactly one
+/// row matches, or at least one when `allow_multiple` is set.
+#[tauri::command]
+pub async fn delete_by_match<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+    table: String,
+    conditions: HashMap<String, serde_json::Value>,
+    allow_multiple: Option<bool>,
+) -> Result<u64, String> {
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    ensure_writable(&saved_conn.params)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+    let driver = driver_for(&saved_conn.params)?;
+    let allow_multiple = allow_multiple.unwrap_or(false);
+    with_reconnect(&params, false, || {
+        driver.delete_by_match(&params, &table, &conditions, allow_multiple)
+    })
+    .await
 }
 
+/// `new_val: null` sets the cell to NULL and `""` to an empty string; the two are
+/// never coerced into each other
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn update_record<R: Runtime>(
     app: AppHandle<R>,
     connection_id: String,
     table: String,
-    pk_col: String,
-    pk_val: serde_json::Value,
+    pk_col: Option<String>,
+    pk_val: Option<serde_json::Value>,
+    pk_values: Option<HashMap<String, serde_json::Value>>,
     col_name: String,
     new_val: serde_json::Value,
 ) -> Result<u64, String> {
     let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    ensure_writable(&saved_conn.params)?;
     let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
     let params = resolve_connection_params(&expanded_params)?;
-    match saved_conn.params.driver.as_str() {
-        "mysql" => mysql::update_record(&params, &table, &pk_col, pk_val, &col_name, new_val).await,
-        "postgres" => {
-            postgres::update_record(&params, &table, &pk_col, pk_val, &col_name, new_val).await
-        }
-        "sqlite" => {
-            sqlite::update_record(&params, &table, &pk_col, pk_val, &col_name, new_val).await
-        }
-        _ => Err("Unsupported driver".into()),
+    let pk = resolve_pk_filter(&params, &table, pk_col, pk_val, pk_values).await?;
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&params, false, || {
+        driver.update_record(&params, &table, &pk, &col_name, new_val.clone())
+    })
+    .await
+}
+
+/// Applies several cell edits in one transaction on one connection, so a grid save
+/// either lands completely or not at all. Every edit must identify its row by key.
+#[tauri::command]
+pub async fn update_records_batch<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+    edits: Vec<RecordEdit>,
+) -> Result<BatchEditResult, String> {
+    if edits.is_empty() {
+        return Err("No edits to apply".into());
     }
+    if let Some(edit) = edits.iter().find(|edit| edit.pk.is_empty()) {
+        return Err(format!(
+            "Edit of {}.{} has no primary key values",
+            edit.table, edit.column
+        ));
+    }
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    ensure_writable(&saved_conn.params)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&params, false, || driver.update_records(&params, &edits)).await
 }
 
 #[tauri::command]
@@ -998,23 +1744,124 @@ pub async fn insert_record<R: Runtime>(
     data: std::collections::HashMap<String, serde_json::Value>,
 ) -> Result<u64, String> {
     let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    ensure_writable(&saved_conn.params)?;
     let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
     let params = resolve_connection_params(&expanded_params)?;
-    match saved_conn.params.driver.as_str() {
-        "mysql" => mysql::insert_record(&params, &table, data).await,
-        "postgres" => postgres::insert_record(&params, &table, data).await,
-        "sqlite" => sqlite::insert_record(&params, &table, data).await,
-        _ => Err("Unsupported driver".into()),
-    }
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&params, false, || {
+        driver.insert_record(&params, &table, data.clone())
+    })
+    .await
+}
+
+/// The DELETE `delete_record` would run and its bound values, without running it
+#[tauri::command]
+pub async fn preview_delete_record<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+    table: String,
+    pk_col: Option<String>,
+    pk_val: Option<serde_json::Value>,
+    pk_values: Option<HashMap<String, serde_json::Value>>,
+) -> Result<StatementPreview, String> {
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+    let pk = resolve_pk_filter(&params, &table, pk_col, pk_val, pk_values).await?;
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&params, true, || {
+        driver.delete_record_sql(&params, &table, &pk)
+    })
+    .await
+}
+
+/// The UPDATE `update_record` would run and its bound values, without running it
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn preview_update_record<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+    table: String,
+    pk_col: Option<String>,
+    pk_val: Option<serde_json::Value>,
+    pk_values: Option<HashMap<String, serde_json::Value>>,
+    col_name: String,
+    new_val: serde_json::Value,
+) -> Result<StatementPreview, String> {
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+    let pk = resolve_pk_filter(&params, &table, pk_col, pk_val, pk_values).await?;
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&params, true, || {
+        driver.update_record_sql(&params, &table, &pk, &col_name, new_val.clone())
+    })
+    .await
+}
+
+/// The INSERT `insert_record` would run and its bound values, without running it
+#[tauri::command]
+pub async fn preview_insert_record<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+    table: String,
+    data: std::collections::HashMap<String, serde_json::Value>,
+) -> Result<StatementPreview, String> {
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&params, true, || {
+        driver.insert_record_sql(&params, &table, data.clone())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn insert_records<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+    table: String,
+    rows: Vec<HashMap<String, serde_json::Value>>,
+) -> Result<u64, String> {
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    ensure_writable(&saved_conn.params)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&params, false, || {
+        driver.insert_records(&params, &table, rows.clone())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn upsert_record<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+    table: String,
+    conflict_cols: Vec<String>,
+    data: HashMap<String, serde_json::Value>,
+) -> Result<u64, String> {
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    ensure_writable(&saved_conn.params)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&params, false, || {
+        driver.upsert_record(&params, &table, &conflict_cols, data.clone())
+    })
+    .await
 }
 
+/// Cancels the query started with `query_id`
 #[tauri::command]
 pub async fn cancel_query(
     state: State<'_, QueryCancellationState>,
-    connection_id: String,
+    query_id: String,
 ) -> Result<(), String> {
     let mut handles = state.handles.lock().unwrap();
-    if let Some(handle) = handles.remove(&connection_id) {
+    if let Some(handle) = handles.remove(&query_id) {
         handle.abort();
         Ok(())
     } else {
@@ -1022,61 +1869,289 @@ pub async fn cancel_query(
     }
 }
 
+/// Runs a query the frontend can cancel with `cancel_query` by its `query_id`.
+/// Without one the query gets an id of its own and cannot be cancelled. With
+/// `stream`, unpaginated rows are only sent as `query-batch` events, up to
+/// `max_rows`, and the result carries no rows of its own.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn execute_query<R: Runtime>(
     app: AppHandle<R>,
     state: State<'_, QueryCancellationState>,
     connection_id: String,
+    query_id: Option<String>,
     query: String,
     limit: Option<u32>,
     page: Option<u32>,
+    offset: Option<u32>,
+    stream: Option<bool>,
+    max_rows: Option<u32>,
 ) -> Result<QueryResult, String> {
     // 1. Sanitize Query (Ignore trailing semicolon)
     let sanitized_query = query.trim().trim_end_matches(';').to_string();
 
     let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    ensure_query_allowed(&saved_conn.params, &sanitized_query)?;
     let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
     let params = resolve_connection_params(&expanded_params)?;
+    let driver = driver_for(&saved_conn.params)?;
 
     // 2. Spawn Cancellable Task
+    let started = Instant::now();
+    let stream = stream.unwrap_or(false);
+    let batch_app = app.clone();
+    let batch_connection_id = connection_id.clone();
     let task = tokio::spawn(async move {
-        match saved_conn.params.driver.as_str() {
-            "mysql" => {
-                mysql::execute_query(&params, &sanitized_query, limit, page.unwrap_or(1)).await
-            }
-            "postgres" => {
-                postgres::execute_query(&params, &sanitized_query, limit, page.unwrap_or(1)).await
-            }
-            "sqlite" => {
-                sqlite::execute_query(&params, &sanitized_query, limit, page.unwrap_or(1)).await
+        // Stream rows to the frontend as they arrive; stop once max_rows is reached.
+        // Cancelling aborts this task, so no further batches follow a cancel.
+        let rows_fetched = AtomicUsize::new(0);
+        let emit_batch = |columns: &[String], rows: &[Vec<serde_json::Value>]| {
+            let sent = rows_fetched.load(Ordering::Relaxed);
+            let rows = match max_rows {
+                Some(max) => &rows[..rows.len().min((max as usize).saturating_sub(sent))],
+                None => rows,
+            };
+            let total = rows_fetched.fetch_add(rows.len(), Ordering::Relaxed) + rows.len();
+            let _ = batch_app.emit(
+                "query-batch",
+                QueryBatchEvent {
+                    connection_id: batch_connection_id.clone(),
+                    columns: columns.to_vec(),
+                    rows: rows.to_vec(),
+                    rows_fetched: total,
+                },
+            );
+            max_rows.map_or(true, |max| total < max as usize)
+        };
+        let on_batch: Option<RowBatchCallback> = stream.then_some(&emit_batch);
+
+        // Only plain reads are run again on reconnect
+        let retry = ensure_select_only(&sanitized_query).is_ok();
+        let mut result = with_reconnect(&params, retry, || {
+            driver.execute_query(
+                &params,
+                &sanitized_query,
+                limit,
+                page.unwrap_or(1),
+                offset,
+                on_batch,
+            )
+        })
+        .await;
+
+        // Batches are checked against max_rows as a whole, so trim any overshoot
+        if let (Ok(res), Some(max)) = (&mut result, max_rows) {
+            if res.row_count.is_some_and(|n| n > max as usize) {
+                res.rows.truncate(max as usize);
+                res.truncated = true;
+                res.row_count = Some(max as usize);
             }
-            _ => Err("Unsupported driver".into()),
         }
+        if let Ok(res) = &mut result {
+            res.column_refs = resolve_column_refs(&params, &sanitized_query, &res.columns).await;
+        }
+        result
     });
 
     // 3. Register Abort Handle
-    let abort_handle = task.abort_handle();
-    {
-        let mut handles = state.handles.lock().unwrap();
-        // If a query is already running for this connection, we overwrite the handle.
-        // Ideally we should cancel the previous one, but the UI should prevent double run.
-        handles.insert(connection_id.clone(), abort_handle);
-    }
+    let query_id = query_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+    let task_id = task.id();
+    state.register(&query_id, task.abort_handle());
+
+    // 4. Emit heartbeats so the UI can tell a slow query from a hung one
+    let heartbeat = {
+        let app = app.clone();
+        let connection_id = connection_id.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(QUERY_HEARTBEAT_INTERVAL);
+            loop {
+                ticker.tick().await;
+                let _ = app.emit(
+                    "query://running",
+                    QueryRunningEvent {
+                        connection_id: connection_id.clone(),
+                        elapsed_ms: started.elapsed().as_millis() as u64,
+                    },
+                );
+            }
+        })
+    };
 
-    // 4. Await & Handle Cancellation
+    // 5. Await & Handle Cancellation
     let result = task.await;
+    heartbeat.abort();
 
-    // 5. Cleanup
-    {
-        let mut handles = state.handles.lock().unwrap();
-        // Only remove if it matches (edge case: multiple queries, but connection_id is unique per tab usually)
-        handles.remove(&connection_id);
-    }
+    // 6. Cleanup
+    state.finish(&query_id, task_id);
 
-    match result {
+    let result = match result {
         Ok(res) => res,
         Err(_) => Err("Query cancelled".into()),
+    };
+
+    let duration_ms = started.elapsed().as_millis() as u64;
+    let row_count = result.as_ref().map(|r| r.rows.len()).unwrap_or(0);
+    query_history::record_query(
+        &app,
+        &connection_id,
+        QueryHistoryEntry {
+            query: query.trim().to_string(),
+            executed_at: chrono::Local::now().to_rfc3339(),
+            duration_ms,
+            row_count,
+            affected_rows: result.as_ref().map(|r| r.affected_rows).unwrap_or(0),
+            success: result.is_ok(),
+            error: result.as_ref().err().cloned(),
+        },
+    );
+
+    let _ = app.emit(
+        "query://done",
+        QueryDoneEvent {
+            connection_id,
+            row_count,
+            duration_ms,
+            success: result.is_ok(),
+        },
+    );
+
+    result
+}
+
+/// For a single-table SELECT, maps each result column that is a foreign key to the
+/// row it references, so the grid can link to it. A failed lookup just omits the map.
+async fn resolve_column_refs(
+    params: &ConnectionParams,
+    query: &str,
+    columns: &[String],
+) -> Option<HashMap<String, ColumnRef>> {
+    let table = simple_select_table(query)?;
+    let foreign_keys = driver_for(params)
+        .ok()?
+        .get_foreign_keys(params, &table)
+        .await
+        .ok()?;
+
+    Some(
+        foreign_keys
+            .into_iter()
+            .filter(|fk| columns.contains(&fk.column_name))
+            .map(|fk| {
+                (
+                    fk.column_name,
+                    ColumnRef {
+                        table: fk.ref_table,
+                        column: fk.ref_column,
+                    },
+                )
+            })
+            .collect(),
+    )
+}
+
+/// Runs a parameterized query; values are bound by the driver, never interpolated
+#[tauri::command]
+pub async fn execute_query_with_params<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+    query: String,
+    params: QueryParams,
+    limit: Option<u32>,
+) -> Result<QueryResult, String> {
+    let sanitized_query = query.trim().trim_end_matches(';').to_string();
+
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    ensure_query_allowed(&saved_conn.params, &sanitized_query)?;
+    let (sql, values) =
+        prepare_bind_params(&sanitized_query, &saved_conn.params.driver, &params)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let conn_params = resolve_connection_params(&expanded_params)?;
+
+    let retry = ensure_select_only(&sql).is_ok();
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&conn_params, retry, || {
+        driver.execute_query_with_params(&conn_params, &sql, &values, limit)
+    })
+    .await
+}
+
+/// Runs a query that may return several result sets (a MySQL CALL, or statements
+/// separated by `;`) and returns all of them. Never retried, as part of it may
+/// already have run.
+#[tauri::command]
+pub async fn execute_query_sets<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+    query: String,
+) -> Result<Vec<QueryResult>, String> {
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    ensure_query_allowed(&saved_conn.params, &query)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+    let driver = driver_for(&saved_conn.params)?;
+    with_reconnect(&params, false, || {
+        driver.execute_query_sets(&params, &query)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn explain_query<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+    query: String,
+) -> Result<ExplainResult, String> {
+    let sanitized_query = query.trim().trim_end_matches(';').to_string();
+    validate_explainable(&sanitized_query)?;
+
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+    let driver = driver_for(&saved_conn.params)?;
+    let plan = with_reconnect(&params, true, || {
+        driver.explain_query(&params, &sanitized_query)
+    })
+    .await?;
+
+    Ok(ExplainResult {
+        driver: saved_conn.params.driver.clone(),
+        plan,
+    })
+}
+
+/// Runs a multi-statement script atomically; see the drivers' `execute_script`
+#[tauri::command]
+pub async fn execute_script<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+    script: String,
+) -> Result<ScriptResult, String> {
+    let statements = split_statements(&script);
+    if statements.is_empty() {
+        return Err("Script contains no statements".into());
     }
+    if is_explicit_transaction(&script) {
+        return Err(
+            "Script manages its own transaction (BEGIN/COMMIT); run it as a query instead".into(),
+        );
+    }
+
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    ensure_query_allowed(&saved_conn.params, &script)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+
+    let started = Instant::now();
+    let driver = driver_for(&saved_conn.params)?;
+    let affected_rows = with_reconnect(&params, false, || {
+        driver.execute_script(&params, &statements)
+    })
+    .await?;
+
+    Ok(ScriptResult {
+        affected_rows,
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
 }
 
 // --- Window Title Management ---
@@ -1,4 +1,7 @@
-use crate::keychain_utils;
+use crate::commands;
+use crate::keychain_utils::{self, SecretStore};
+use crate::models::{SavedConnection, SshConnection};
+use crate::persistence;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::PathBuf;
@@ -20,6 +23,8 @@ pub struct AppConfig {
     pub ai_model: Option<String>,
     pub ai_custom_models: Option<HashMap<String, Vec<String>>>,
     pub ai_ollama_port: Option<u16>,
+    /// "keyring" (default) or "file", see `keychain_utils::SecretStore`
+    pub secret_store: Option<String>,
 }
 
 pub fn get_config_dir(app: &AppHandle) -> Option<PathBuf> {
@@ -88,9 +93,19 @@ pub fn save_config(app: AppHandle, config: AppConfig) -> Result<(), String> {
         if config.ai_ollama_port.is_some() {
             existing_config.ai_ollama_port = config.ai_ollama_port;
         }
+        let secret_store = match &config.secret_store {
+            Some(name) => Some(keychain_utils::SecretStore::parse(name)?),
+            None => None,
+        };
+        if config.secret_store.is_some() {
+            existing_config.secret_store = config.secret_store;
+        }
 
         let content = serde_json::to_string_pretty(&existing_config).map_err(|e| e.to_string())?;
         fs::write(config_path, content).map_err(|e| e.to_string())?;
+        if let Some(store) = secret_store {
+            keychain_utils::set_secret_store(store);
+        }
         Ok(())
     } else {
         Err("Could not resolve config directory".to_string())
@@ -102,6 +117,108 @@ pub fn set_ai_key(provider: String, key: String) -> Result<(), String> {
     keychain_utils::set_ai_key(&provider, &key)
 }
 
+/// Providers whose API key can be kept in the secret store
+const AI_KEY_PROVIDERS: [&str; 3] = ["openai", "anthropic", "openrouter"];
+
+#[derive(Serialize, Debug)]
+pub struct SecretMigration {
+    pub from: String,
+    pub to: String,
+    pub migrated: usize,
+}
+
+/// Every account that may hold a secret: the keychain-backed connections and SSH
+/// connections, plus the AI provider keys. The keyring can't enumerate its
+/// entries, so they are listed from the saved configuration.
+fn secret_accounts(app: &AppHandle) -> Result<Vec<String>, String> {
+    let mut accounts = Vec::new();
+
+    let connections: Vec<SavedConnection> =
+        persistence::read_json_recovering(&commands::get_config_path(app)?)
+            .unwrap_or_default()
+            .unwrap_or_default();
+    for conn in connections {
+        if conn.params.save_in_keychain.unwrap_or(false) {
+            accounts.push(keychain_utils::db_account(&conn.id));
+            accounts.push(keychain_utils::ssh_account(&conn.id));
+            accounts.push(keychain_utils::ssh_passphrase_account(&conn.id));
+        }
+    }
+
+    let ssh_connections: Vec<SshConnection> =
+        persistence::read_json_recovering(&commands::get_ssh_config_path(app)?)
+            .unwrap_or_default()
+            .unwrap_or_default();
+    for ssh in ssh_connections {
+        if ssh.save_in_keychain.unwrap_or(false) {
+            accounts.push(keychain_utils::ssh_account(&ssh.id));
+            accounts.push(keychain_utils::ssh_passphrase_account(&ssh.id));
+        }
+    }
+
+    accounts.extend(
+        AI_KEY_PROVIDERS
+            .iter()
+            .map(|p| keychain_utils::ai_key_account(p)),
+    );
+    Ok(accounts)
+}
+
+/// Moves all secrets from the active store to `to` ("keyring" or "file") and makes
+/// it the active store. Every secret is copied before any is removed, so a failure
+/// part way leaves the old store complete and still selected.
+#[tauri::command]
+pub fn migrate_secrets(app: AppHandle, to: String) -> Result<SecretMigration, String> {
+    let from = keychain_utils::secret_store();
+    let to = SecretStore::parse(&to)?;
+    if from == to {
+        return Ok(SecretMigration {
+            from: from.as_str().to_string(),
+            to: to.as_str().to_string(),
+            migrated: 0,
+        });
+    }
+
+    let mut moved = Vec::new();
+    for account in secret_accounts(&app)? {
+        if let Some(secret) = keychain_utils::get_secret_from(from, &account)? {
+            keychain_utils::set_secret_in(to, &account, &secret)?;
+            moved.push(account);
+        }
+    }
+
+    save_config(
+        app,
+        AppConfig {
+            secret_store: Some(to.as_str().to_string()),
+            ..Default::default()
+        },
+    )?;
+
+    for account in &moved {
+        if let Err(e) = keychain_utils::delete_secret_from(from, account) {
+            eprintln!(
+                "[Keychain] Failed to remove {} from the {} store after migration: {}",
+                account,
+                from.as_str(),
+                e
+            );
+        }
+    }
+    println!(
+        "[Keychain] Migrated {} secrets from {} to {}",
+        moved.len(),
+        from.as_str(),
+        to.as_str()
+    );
+
+    Ok(SecretMigration {
+        from: from.as_str().to_string(),
+        to: to.as_str().to_string(),
+        migrated: moved.len(),
+    })
+}
+
 pub fn get_ai_api_key(provider: &str) -> Result<String, String> {
     // 1. Try Env Var
     let env_var = match provider {
@@ -120,12 +237,13 @@ pub fn get_ai_api_key(provider: &str) -> Result<String, String> {
     }
 
     // 2. Try Keychain
-    keychain_utils::get_ai_key(provider).map_err(|_| {
-        format!(
+    match keychain_utils::get_ai_key(provider)? {
+        Some(key) => Ok(key),
+        None => Err(format!(
             "API Key for {} not found in Keychain or Environment",
             provider
-        )
-    })
+        )),
+    }
 }
 
 #[tauri::command]
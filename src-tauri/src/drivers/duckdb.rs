@@ -0,0 +1,1873 @@
+use crate::drivers::common::{
+    auto_limit_query, batch_edit_result, calculate_offset, check_edited_rows, check_match_count,
+    column_definition_sql, create_index_sql, encode_binary, has_limit_clause, insert_chunk_size,
+    match_bindings, quote_identifier, returns_rows, script_error, sorted_bindings,
+    statement_timeout_error, strip_leading_comments, union_columns, update_bindings,
+    validate_upsert, Driver, RowBatchCallback, RowBuffer,
+};
+use crate::models::{
+    BatchEditResult, ColumnDefinition, ConnectionParams, ForeignKey, Index, Pagination,
+    ProcedureResult, QueryResult, RecordEdit, RoutineInfo, StatementPreview, TableColumn,
+    TableInfo, TableRowCount, Trigger, ViewInfo,
+};
+use crate::pool_manager::get_duckdb_connection;
+use crate::statement_log;
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, NaiveTime};
+use duckdb::types::Value;
+use duckdb::{params_from_iter, Connection, InterruptHandle};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// DuckDB statements that return rows besides the ones every driver knows about
+const DUCKDB_ROW_RETURNING_KEYWORDS: [&str; 4] = ["FROM", "SUMMARIZE", "PIVOT", "UNPIVOT"];
+
+/// Columns and rows handed from the blocking query thread to `on_batch`
+type RowBatch = (Vec<String>, Vec<Vec<serde_json::Value>>);
+
+/// Schema that metadata queries browse and unqualified names resolve in
+fn duckdb_schema(params: &ConnectionParams) -> &str {
+    params
+        .schema
+        .as_deref()
+        .filter(|s| !s.is_empty())
+        .unwrap_or("main")
+}
+
+/// Interrupts the statement running on a connection once dropped, or once the
+/// timeout has passed. `cancel_query` aborts the query task, which drops it, so a
+/// long scan stops on the blocking thread instead of running to completion.
+/// Interrupting a connection that has finished and been closed does nothing.
+struct Interrupter {
+    handle: Arc<InterruptHandle>,
+    timer: Option<tokio::task::JoinHandle<()>>,
+    timed_out: Arc<AtomicBool>,
+    timeout_secs: Option<u64>,
+}
+
+impl Interrupter {
+    fn new(conn: &Connection, timeout_secs: Option<u64>) -> Self {
+        let handle = conn.interrupt_handle();
+        let timed_out = Arc::new(AtomicBool::new(false));
+        let timer = timeout_secs.map(|secs| {
+            let handle = handle.clone();
+            let timed_out = timed_out.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_secs(secs)).await;
+                timed_out.store(true, Ordering::Relaxed);
+                handle.interrupt();
+            })
+        });
+        Self {
+            handle,
+            timer,
+            timed_out,
+            timeout_secs,
+        }
+    }
+
+    /// Reports `err` as a statement timeout if the timer interrupted the statement
+    fn describe(&self, err: String) -> String {
+        match self.timeout_secs {
+            Some(secs) if self.timed_out.load(Ordering::Relaxed) => {
+                statement_timeout_error(secs, &err)
+            }
+            _ => err,
+        }
+    }
+}
+
+impl Drop for Interrupter {
+    fn drop(&mut self) {
+        if let Some(timer) = self.timer.take() {
+            timer.abort();
+        }
+        self.handle.interrupt();
+    }
+}
+
+/// Runs `op` with a new connection on the blocking thread pool, since every DuckDB
+/// call blocks. The connection is closed when `op` returns.
+async fn run<T, E, F>(params: &ConnectionParams, op: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    E: ToString + Send + 'static,
+    F: FnOnce(Connection) -> Result<T, E> + Send + 'static,
+{
+    let conn = get_duckdb_connection(params).await?;
+    let interrupt = Interrupter::new(&conn, params.statement_timeout_secs);
+    tokio::task::spawn_blocking(move || op(conn))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| interrupt.describe(e.to_string()))
+}
+
+/// Converts a JSON value to the DuckDB value bound in its place
+fn to_duckdb_value(val: &serde_json::Value) -> Result<Value, String> {
+    match val {
+        serde_json::Value::Number(n) => Ok(match n.as_i64() {
+            Some(i) => Value::BigInt(i),
+            None => Value::Double(n.as_f64().unwrap_or_default()),
+        }),
+        serde_json::Value::String(s) => Ok(Value::Text(s.clone())),
+        serde_json::Value::Bool(b) => Ok(Value::Boolean(*b)),
+        serde_json::Value::Null => Ok(Value::Null),
+        _ => Err("Unsupported value type".into()),
+    }
+}
+
+fn to_duckdb_values<'a>(
+    values: impl IntoIterator<Item = &'a serde_json::Value>,
+) -> Result<Vec<Value>, String> {
+    values.into_iter().map(to_duckdb_value).collect()
+}
+
+/// Converts a DuckDB value to JSON the way the other drivers report theirs: dates
+/// and times as text, DECIMAL and 128-bit integers as exact strings, binary data
+/// base64-encoded, and nested types as JSON arrays and objects
+fn value_to_json(value: Value) -> serde_json::Value {
+    match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Boolean(v) => v.into(),
+        Value::TinyInt(v) => v.into(),
+        Value::SmallInt(v) => v.into(),
+        Value::Int(v) => v.into(),
+        Value::BigInt(v) => v.into(),
+        Value::UTinyInt(v) => v.into(),
+        Value::USmallInt(v) => v.into(),
+        Value::UInt(v) => v.into(),
+        Value::UBigInt(v) => v.into(),
+        Value::HugeInt(v) => serde_json::Value::String(v.to_string()),
+        Value::UHugeInt(v) => serde_json::Value::String(v.to_string()),
+        Value::Float(v) => float_to_json(v as f64),
+        Value::Double(v) => float_to_json(v),
+        Value::Decimal(v) => serde_json::Value::String(v.to_string()),
+        Value::Timestamp(unit, v) => DateTime::from_timestamp_micros(unit.to_micros(v))
+            .map(|dt| {
+                serde_json::Value::String(dt.naive_utc().format("%Y-%m-%d %H:%M:%S").to_string())
+            })
+            .unwrap_or(serde_json::Value::Null),
+        Value::Date32(days) => NaiveDate::from_ymd_opt(1970, 1, 1)
+            .and_then(|epoch| epoch.checked_add_signed(chrono::Duration::days(days.into())))
+            .map(|date| serde_json::Value::String(date.to_string()))
+            .unwrap_or(serde_json::Value::Null),
+        Value::Time64(unit, v) => {
+            let micros = unit.to_micros(v);
+            NaiveTime::from_num_seconds_from_midnight_opt(
+                (micros / 1_000_000) as u32,
+                (micros % 1_000_000) as u32 * 1000,
+            )
+            .map(|time| serde_json::Value::String(time.to_string()))
+            .unwrap_or(serde_json::Value::Null)
+        }
+        Value::Interval {
+            months,
+            days,
+            nanos,
+        } => serde_json::Value::String(format!(
+            "{} months {} days {} microseconds",
+            months,
+            days,
+            nanos / 1000
+        )),
+        Value::Text(v) | Value::Enum(v) => serde_json::Value::String(v),
+        Value::Blob(v) | Value::Geometry(v) => encode_binary(&v),
+        Value::List(items) | Value::Array(items) => {
+            serde_json::Value::Array(items.into_iter().map(value_to_json).collect())
+        }
+        Value::Struct(fields) => serde_json::Value::Object(
+            fields
+                .iter()
+                .map(|(name, v)| (name.clone(), value_to_json(v.clone())))
+                .collect(),
+        ),
+        // Map keys can be of any type, so entries are kept as `[key, value]` pairs
+        Value::Map(entries) => serde_json::Value::Array(
+            entries
+                .iter()
+                .map(|(k, v)| {
+                    serde_json::Value::Array(vec![
+                        value_to_json(k.clone()),
+                        value_to_json(v.clone()),
+                    ])
+                })
+                .collect(),
+        ),
+        Value::Union(v) => value_to_json(*v),
+        _ => serde_json::Value::Null,
+    }
+}
+
+/// NaN and infinity have no JSON representation and become null
+fn float_to_json(v: f64) -> serde_json::Value {
+    serde_json::Number::from_f64(v)
+        .map(serde_json::Value::Number)
+        .unwrap_or(serde_json::Value::Null)
+}
+
+/// The items of a `VARCHAR[]` value
+fn string_list(value: Value) -> Vec<String> {
+    match value {
+        Value::List(items) | Value::Array(items) => items
+            .into_iter()
+            .filter_map(|item| match item {
+                Value::Text(s) => Some(s),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Reads every column of `row` as JSON
+fn row_to_json(
+    row: &duckdb::Row<'_>,
+    column_count: usize,
+) -> duckdb::Result<Vec<serde_json::Value>> {
+    (0..column_count)
+        .map(|i| row.get_ref(i).map(|v| value_to_json(v.to_owned())))
+        .collect()
+}
+
+/// Like `returns_rows`, plus DuckDB's own row-returning statements such as
+/// `FROM tbl` and `SUMMARIZE tbl`
+fn yields_rows(query: &str) -> bool {
+    let upper = strip_leading_comments(query).to_uppercase();
+    let first_word = upper
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .next()
+        .unwrap_or("");
+    returns_rows(query) || DUCKDB_ROW_RETURNING_KEYWORDS.contains(&first_word)
+}
+
+/// Runs a metadata query with string parameters, collecting each row through `map`
+async fn query_rows<T, F>(
+    params: &ConnectionParams,
+    sql: &'static str,
+    args: Vec<String>,
+    map: F,
+) -> Result<Vec<T>, String>
+where
+    T: Send + 'static,
+    F: FnMut(&duckdb::Row<'_>) -> duckdb::Result<T> + Send + 'static,
+{
+    run(params, move |conn| {
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map(params_from_iter(args), map)?;
+        rows.collect::<duckdb::Result<Vec<T>>>()
+    })
+    .await
+}
+
+/// DuckDB's version and the name of the open database, for `test_connection`
+pub async fn server_info(params: &ConnectionParams) -> Result<(String, String), String> {
+    run(params, |conn| {
+        conn.query_row("SELECT version(), current_database()", [], |r| {
+            Ok((r.get(0)?, r.get(1)?))
+        })
+    })
+    .await
+}
+
+/// DuckDB opens a single database per connection, so there is nothing to switch to
+pub async fn get_databases(_params: &ConnectionParams) -> Result<Vec<String>, String> {
+    Ok(vec![])
+}
+
+pub async fn get_schemas(params: &ConnectionParams) -> Result<Vec<String>, String> {
+    query_rows(
+        params,
+        "SELECT schema_name FROM information_schema.schemata WHERE catalog_name = current_database() AND schema_name NOT IN ('information_schema', 'pg_catalog') ORDER BY schema_name",
+        vec![],
+        |r| r.get(0),
+    )
+    .await
+}
+
+pub async fn get_tables(params: &ConnectionParams) -> Result<Vec<TableInfo>, String> {
+    query_rows(
+        params,
+        "SELECT table_name FROM information_schema.tables WHERE table_catalog = current_database() AND table_schema = ? AND table_type = 'BASE TABLE' ORDER BY table_name ASC",
+        vec![duckdb_schema(params).to_string()],
+        |r| Ok(TableInfo { name: r.get(0)? }),
+    )
+    .await
+}
+
+pub async fn get_views(params: &ConnectionParams) -> Result<Vec<ViewInfo>, String> {
+    query_rows(
+        params,
+        "SELECT view_name, sql FROM duckdb_views() WHERE database_name = current_database() AND schema_name = ? AND NOT internal ORDER BY view_name ASC",
+        vec![duckdb_schema(params).to_string()],
+        |r| {
+            Ok(ViewInfo {
+                name: r.get(0)?,
+                definition: r.get(1)?,
+            })
+        },
+    )
+    .await
+}
+
+/// Columns of the tables in `table_names`, keyed by table. DuckDB has no AUTO_INCREMENT;
+/// a column defaulting to a sequence's `nextval` is the equivalent.
+fn columns_of(
+    conn: &Connection,
+    schema: &str,
+    table_names: &[String],
+) -> duckdb::Result<HashMap<String, Vec<TableColumn>>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT
+            c.table_name,
+            c.column_name,
+            c.data_type,
+            c.is_nullable = 'YES',
+            coalesce(c.column_default, '') LIKE 'nextval(%',
+            EXISTS (
+                SELECT 1 FROM duckdb_constraints() k
+                WHERE k.database_name = c.table_catalog
+                AND k.schema_name = c.table_schema
+                AND k.table_name = c.table_name
+                AND k.constraint_type = 'PRIMARY KEY'
+                AND list_contains(k.constraint_column_names, c.column_name)
+            ),
+            c.column_default,
+            nullif(c.column_comment, '')
+        FROM information_schema.columns c
+        WHERE c.table_catalog = current_database() AND c.table_schema = ? AND c.table_name = ?
+        ORDER BY c.ordinal_position
+    "#,
+    )?;
+
+    let mut result = HashMap::new();
+    for table_name in table_names {
+        let columns = stmt
+            .query_map([schema, table_name.as_str()], |r| {
+                Ok(TableColumn {
+                    name: r.get(1)?,
+                    data_type: r.get(2)?,
+                    is_nullable: r.get(3)?,
+                    is_auto_increment: r.get(4)?,
+                    is_pk: r.get(5)?,
+                    enum_values: None,
+                    default_value: r.get(6)?,
+                    comment: r.get(7)?,
+                })
+            })?
+            .collect::<duckdb::Result<Vec<_>>>()?;
+        result.insert(table_name.clone(), columns);
+    }
+    Ok(result)
+}
+
+/// Foreign keys of the tables in `table_names`, keyed by table, one entry per column.
+/// DuckDB only enforces RESTRICT, so no referential actions are reported.
+fn foreign_keys_of(
+    conn: &Connection,
+    schema: &str,
+    table_names: &[String],
+) -> duckdb::Result<HashMap<String, Vec<ForeignKey>>> {
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT constraint_name, constraint_column_names, referenced_table, referenced_column_names
+        FROM duckdb_constraints()
+        WHERE database_name = current_database()
+        AND schema_name = ?
+        AND table_name = ?
+        AND constraint_type = 'FOREIGN KEY'
+    "#,
+    )?;
+
+    let mut result = HashMap::new();
+    for table_name in table_names {
+        let constraints = stmt
+            .query_map([schema, table_name.as_str()], |r| {
+                Ok((
+                    r.get::<_, String>(0)?,
+                    string_list(r.get(1)?),
+                    r.get::<_, String>(2)?,
+                    string_list(r.get(3)?),
+                ))
+            })?
+            .collect::<duckdb::Result<Vec<_>>>()?;
+
+        let mut fks = Vec::new();
+        for (name, columns, ref_table, ref_columns) in constraints {
+            for (column_name, ref_column) in columns.into_iter().zip(ref_columns) {
+                fks.push(ForeignKey {
+                    name: name.clone(),
+                    column_name,
+                    ref_table: ref_table.clone(),
+                    ref_column,
+                    on_update: None,
+                    on_delete: None,
+                });
+            }
+        }
+        result.insert(table_name.clone(), fks);
+    }
+    Ok(result)
+}
+
+pub async fn get_columns(
+    params: &ConnectionParams,
+    table_name: &str,
+) -> Result<Vec<TableColumn>, String> {
+    let mut columns = get_all_columns_batch(params, &[table_name.to_string()]).await?;
+    Ok(columns.remove(table_name).unwrap_or_default())
+}
+
+pub async fn get_foreign_keys(
+    params: &ConnectionParams,
+    table_name: &str,
+) -> Result<Vec<ForeignKey>, String> {
+    let mut fks = get_all_foreign_keys_batch(params, &[table_name.to_string()]).await?;
+    Ok(fks.remove(table_name).unwrap_or_default())
+}
+
+// Batch function: Get all columns for all tables with one prepared statement
+pub async fn get_all_columns_batch(
+    params: &ConnectionParams,
+    table_names: &[String],
+) -> Result<HashMap<String, Vec<TableColumn>>, String> {
+    let schema = duckdb_schema(params).to_string();
+    let table_names = table_names.to_vec();
+    run(params, move |conn| columns_of(&conn, &schema, &table_names)).await
+}
+
+// Batch function: Get all foreign keys for all tables with one prepared statement
+pub async fn get_all_foreign_keys_batch(
+    params: &ConnectionParams,
+    table_names: &[String],
+) -> Result<HashMap<String, Vec<ForeignKey>>, String> {
+    let schema = duckdb_schema(params).to_string();
+    let table_names = table_names.to_vec();
+    run(params, move |conn| {
+        foreign_keys_of(&conn, &schema, &table_names)
+    })
+    .await
+}
+
+/// Indexes created with CREATE INDEX, plus the primary key and unique constraints,
+/// which DuckDB enforces with indexes of their own
+pub async fn get_indexes(
+    params: &ConnectionParams,
+    table_name: &str,
+) -> Result<Vec<Index>, String> {
+    let query = r#"
+        SELECT index_name, CAST(expressions AS VARCHAR[]), is_unique, is_primary
+        FROM duckdb_indexes()
+        WHERE database_name = current_database() AND schema_name = ? AND table_name = ?
+        UNION ALL
+        SELECT constraint_name, constraint_column_names, true, constraint_type = 'PRIMARY KEY'
+        FROM duckdb_constraints()
+        WHERE database_name = current_database() AND schema_name = ? AND table_name = ?
+        AND constraint_type IN ('PRIMARY KEY', 'UNIQUE')
+        ORDER BY 1
+    "#;
+    let schema = duckdb_schema(params).to_string();
+    let indexes = query_rows(
+        params,
+        query,
+        vec![
+            schema.clone(),
+            table_name.to_string(),
+            schema,
+            table_name.to_string(),
+        ],
+        |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                string_list(r.get(1)?),
+                r.get::<_, bool>(2)?,
+                r.get::<_, bool>(3)?,
+            ))
+        },
+    )
+    .await?;
+
+    // One entry per indexed column, like the other drivers
+    let mut result = Vec::new();
+    for (name, columns, is_unique, is_primary) in indexes {
+        for (i, column) in columns.into_iter().enumerate() {
+            result.push(Index {
+                name: name.clone(),
+                column_name: column.trim_matches('"').to_string(),
+                is_unique,
+                is_primary,
+                seq_in_index: i as i32 + 1,
+            });
+        }
+    }
+    Ok(result)
+}
+
+/// DuckDB has no triggers
+pub async fn get_triggers(
+    _params: &ConnectionParams,
+    _table_name: &str,
+) -> Result<Vec<Trigger>, String> {
+    Ok(Vec::new())
+}
+
+/// Macros in the connection's schema (`CREATE FUNCTION` is an alias of `CREATE
+/// MACRO`); DuckDB has no stored procedures. Overloads are listed once each.
+pub async fn get_routines(
+    params: &ConnectionParams,
+    schema: Option<&str>,
+) -> Result<Vec<RoutineInfo>, String> {
+    let query = r#"
+        SELECT function_name, function_type = 'table_macro', macro_definition
+        FROM duckdb_functions()
+        WHERE database_name = current_database() AND schema_name = ? AND NOT internal
+        AND function_type IN ('macro', 'table_macro')
+        ORDER BY function_name
+    "#;
+    let schema = schema.unwrap_or(duckdb_schema(params)).to_string();
+    query_rows(params, query, vec![schema], |r| {
+        Ok(RoutineInfo {
+            name: r.get(0)?,
+            routine_type: "FUNCTION".to_string(),
+            return_type: r.get::<_, bool>(1)?.then(|| "TABLE".to_string()),
+            definition: r.get(2)?,
+        })
+    })
+    .await
+}
+
+/// `CREATE MACRO` statement of the macro `name`, one per overload
+pub async fn get_routine_definition(
+    params: &ConnectionParams,
+    schema: Option<&str>,
+    name: &str,
+) -> Result<String, String> {
+    let query = r#"
+        SELECT array_to_string(parameters, ', '), function_type = 'table_macro', macro_definition
+        FROM duckdb_functions()
+        WHERE database_name = current_database() AND schema_name = ? AND function_name = ?
+        AND NOT internal AND function_type IN ('macro', 'table_macro')
+    "#;
+    let schema = schema.unwrap_or(duckdb_schema(params)).to_string();
+    let quoted = quote_identifier(name, '"');
+    let definitions = query_rows(params, query, vec![schema, name.to_string()], move |r| {
+        let table = if r.get::<_, bool>(1)? { "TABLE " } else { "" };
+        Ok(format!(
+            "CREATE MACRO {}({}) AS {}{};",
+            quoted,
+            r.get::<_, String>(0)?,
+            table,
+            r.get::<_, String>(2)?
+        ))
+    })
+    .await?;
+
+    if definitions.is_empty() {
+        return Err(format!("Routine '{}' not found", name));
+    }
+    Ok(definitions.join("\n"))
+}
+
+pub async fn call_procedure(
+    _params: &ConnectionParams,
+    _name: &str,
+    _args: &[serde_json::Value],
+) -> Result<ProcedureResult, String> {
+    Err("DuckDB has no stored procedures".into())
+}
+
+pub async fn create_index(
+    params: &ConnectionParams,
+    table: &str,
+    name: &str,
+    columns: &[String],
+    unique: bool,
+) -> Result<(), String> {
+    let sql = create_index_sql(&quote_identifier(table, '"'), name, columns, unique, '"')?;
+    run_ddl(params, &sql).await
+}
+
+/// DuckDB index names are unique per schema, so `_table` is not needed
+pub async fn drop_index(params: &ConnectionParams, _table: &str, name: &str) -> Result<(), String> {
+    let sql = format!("DROP INDEX {}", quote_identifier(name, '"'));
+    run_ddl(params, &sql).await
+}
+
+pub async fn add_column(
+    params: &ConnectionParams,
+    table: &str,
+    column: &ColumnDefinition,
+) -> Result<(), String> {
+    // DuckDB rejects constraints in ADD COLUMN; defaults are still accepted
+    if !column.is_nullable {
+        return Err("DuckDB cannot add a NOT NULL column to an existing table".into());
+    }
+    let sql = format!(
+        "ALTER TABLE {} ADD COLUMN {}",
+        quote_identifier(table, '"'),
+        column_definition_sql(column, '"')?
+    );
+    run_ddl(params, &sql).await
+}
+
+pub async fn drop_column(
+    params: &ConnectionParams,
+    table: &str,
+    column: &str,
+) -> Result<(), String> {
+    let sql = format!(
+        "ALTER TABLE {} DROP COLUMN {}",
+        quote_identifier(table, '"'),
+        quote_identifier(column, '"')
+    );
+    run_ddl(params, &sql).await
+}
+
+pub async fn rename_column(
+    params: &ConnectionParams,
+    table: &str,
+    column: &str,
+    new_name: &str,
+) -> Result<(), String> {
+    let sql = format!(
+        "ALTER TABLE {} RENAME COLUMN {} TO {}",
+        quote_identifier(table, '"'),
+        quote_identifier(column, '"'),
+        quote_identifier(new_name, '"')
+    );
+    run_ddl(params, &sql).await
+}
+
+async fn run_ddl(params: &ConnectionParams, sql: &str) -> Result<(), String> {
+    statement_log::log_statement(params, sql, &[]);
+    let sql = sql.to_string();
+    run(params, move |conn| conn.execute_batch(&sql)).await
+}
+
+/// Runs one statement with `values` bound to its placeholders and returns the
+/// affected row count
+async fn execute_bound(
+    params: &ConnectionParams,
+    sql: String,
+    values: Vec<Value>,
+) -> Result<u64, String> {
+    run(params, move |conn| {
+        conn.execute(&sql, params_from_iter(values))
+    })
+    .await
+    .map(|affected| affected as u64)
+}
+
+/// `WHERE pk1 = ? AND pk2 = ?` covering every primary key column, with the values
+/// to bind. Columns are sorted so the generated statement is stable for composite keys.
+fn pk_filter(pk: &HashMap<String, serde_json::Value>) -> Result<(String, Vec<Value>), String> {
+    if pk.is_empty() {
+        return Err("No primary key values provided".into());
+    }
+
+    let mut filter = String::new();
+    let mut values = Vec::new();
+    for (i, (col, val)) in sorted_bindings(pk).into_iter().enumerate() {
+        if !(val.is_number() || val.is_string()) {
+            return Err("Unsupported PK type".into());
+        }
+        filter.push_str(if i == 0 { " WHERE " } else { " AND " });
+        filter.push_str(&format!("{} = ?", quote_identifier(&col, '"')));
+        values.push(to_duckdb_value(&val)?);
+    }
+    Ok((filter, values))
+}
+
+/// The DELETE run by `delete_record`, with its bound values
+fn delete_statement(
+    table: &str,
+    pk: &HashMap<String, serde_json::Value>,
+) -> Result<(String, Vec<Value>), String> {
+    let (filter, values) = pk_filter(pk)?;
+    Ok((
+        format!("DELETE FROM {}{}", quote_identifier(table, '"'), filter),
+        values,
+    ))
+}
+
+pub async fn delete_record(
+    params: &ConnectionParams,
+    table: &str,
+    pk: &HashMap<String, serde_json::Value>,
+) -> Result<u64, String> {
+    let (sql, values) = delete_statement(table, pk)?;
+    statement_log::log_statement(params, &sql, &sorted_bindings(pk));
+    execute_bound(params, sql, values).await
+}
+
+/// The statement `delete_record` would run, without running it
+pub async fn delete_record_sql(
+    _params: &ConnectionParams,
+    table: &str,
+    pk: &HashMap<String, serde_json::Value>,
+) -> Result<StatementPreview, String> {
+    Ok(StatementPreview {
+        sql: delete_statement(table, pk)?.0,
+        bindings: sorted_bindings(pk),
+    })
+}
+
+/// `WHERE` clause of `delete_by_match` with its bound values: `col IS NULL` for
+/// JSON `null`, otherwise `col = ?`
+fn match_filter(
+    conditions: &HashMap<String, serde_json::Value>,
+) -> Result<(String, Vec<Value>), String> {
+    if conditions.is_empty() {
+        return Err("No values to match provided".into());
+    }
+
+    let mut filter = String::new();
+    let mut values = Vec::new();
+    for (i, (col, val)) in sorted_bindings(conditions).into_iter().enumerate() {
+        filter.push_str(if i == 0 { " WHERE " } else { " AND " });
+        if val.is_null() {
+            filter.push_str(&format!("{} IS NULL", quote_identifier(&col, '"')));
+        } else {
+            filter.push_str(&format!("{} = ?", quote_identifier(&col, '"')));
+            values.push(to_duckdb_value(&val)?);
+        }
+    }
+    Ok((filter, values))
+}
+
+/// Deletes the rows matching `conditions` in a transaction that is only committed
+/// when the row count passes `check_match_count`
+pub async fn delete_by_match(
+    params: &ConnectionParams,
+    table: &str,
+    conditions: &HashMap<String, serde_json::Value>,
+    allow_multiple: bool,
+) -> Result<u64, String> {
+    let (filter, values) = match_filter(conditions)?;
+    let sql = format!("DELETE FROM {}{}", quote_identifier(table, '"'), filter);
+    statement_log::log_statement(params, &sql, &match_bindings(conditions));
+    run(params, move |mut conn| {
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let affected = tx
+            .execute(&sql, params_from_iter(values))
+            .map_err(|e| e.to_string())? as u64;
+        // Returning early drops the transaction, which rolls the delete back
+        check_match_count(affected, allow_multiple)?;
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok::<_, String>(affected)
+    })
+    .await
+}
+
+/// The UPDATE run by `update_record`, with its bound values
+fn update_statement(
+    table: &str,
+    pk: &HashMap<String, serde_json::Value>,
+    col_name: &str,
+    new_val: &serde_json::Value,
+) -> Result<(String, Vec<Value>), String> {
+    let (filter, pk_values) = pk_filter(pk)?;
+    let mut values = vec![to_duckdb_value(new_val)?];
+    values.extend(pk_values);
+    Ok((
+        format!(
+            "UPDATE {} SET {} = ?{}",
+            quote_identifier(table, '"'),
+            quote_identifier(col_name, '"'),
+            filter
+        ),
+        values,
+    ))
+}
+
+/// Sets one column of the rows matching `pk`. JSON `null` writes SQL NULL, while a
+/// string is bound as-is, so `""` stores an empty string rather than NULL
+pub async fn update_record(
+    params: &ConnectionParams,
+    table: &str,
+    pk: &HashMap<String, serde_json::Value>,
+    col_name: &str,
+    new_val: serde_json::Value,
+) -> Result<u64, String> {
+    let (sql, values) = update_statement(table, pk, col_name, &new_val)?;
+    statement_log::log_statement(params, &sql, &update_bindings(pk, col_name, &new_val));
+    execute_bound(params, sql, values).await
+}
+
+/// The statement `update_record` would run, without running it
+pub async fn update_record_sql(
+    _params: &ConnectionParams,
+    table: &str,
+    pk: &HashMap<String, serde_json::Value>,
+    col_name: &str,
+    new_val: serde_json::Value,
+) -> Result<StatementPreview, String> {
+    Ok(StatementPreview {
+        sql: update_statement(table, pk, col_name, &new_val)?.0,
+        bindings: update_bindings(pk, col_name, &new_val),
+    })
+}
+
+/// Runs `edits` in order in one transaction, rolling back on the first failure. An
+/// edit whose statement can't be built fails the batch without reaching the database.
+pub async fn update_records(
+    params: &ConnectionParams,
+    edits: &[RecordEdit],
+) -> Result<BatchEditResult, String> {
+    let mut statements = Vec::with_capacity(edits.len());
+    let mut build_error = None;
+    for edit in edits {
+        match update_statement(&edit.table, &edit.pk, &edit.column, &edit.value) {
+            Ok((sql, values)) => {
+                statement_log::log_statement(
+                    params,
+                    &sql,
+                    &update_bindings(&edit.pk, &edit.column, &edit.value),
+                );
+                statements.push((sql, values));
+            }
+            Err(e) => {
+                build_error = Some(e);
+                break;
+            }
+        }
+    }
+
+    let (applied, failure) = run(params, move |mut conn| {
+        let tx = conn.transaction()?;
+        let mut applied = Vec::with_capacity(statements.len());
+        for (sql, values) in statements {
+            let result = tx
+                .execute(&sql, params_from_iter(values))
+                .map_err(|e| e.to_string())
+                .and_then(|affected| check_edited_rows(affected as u64));
+            match result {
+                Ok(affected) => applied.push(affected),
+                // Dropping the transaction rolls it back
+                Err(e) => return Ok::<_, duckdb::Error>((applied, Some(e))),
+            }
+        }
+        if build_error.is_none() {
+            tx.commit()?;
+        }
+        Ok((applied, build_error))
+    })
+    .await?;
+    Ok(batch_edit_result(edits.len(), applied, failure))
+}
+
+/// The INSERT run by `insert_record`, with columns in `bindings` order
+fn insert_statement(
+    table: &str,
+    bindings: &[(String, serde_json::Value)],
+) -> Result<(String, Vec<Value>), String> {
+    if bindings.is_empty() {
+        return Err("No data to insert".into());
+    }
+
+    let cols: Vec<String> = bindings
+        .iter()
+        .map(|(k, _)| quote_identifier(k, '"'))
+        .collect();
+    let placeholders = vec!["?"; bindings.len()].join(", ");
+    Ok((
+        format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            quote_identifier(table, '"'),
+            cols.join(", "),
+            placeholders
+        ),
+        to_duckdb_values(bindings.iter().map(|(_, v)| v))?,
+    ))
+}
+
+pub async fn insert_record(
+    params: &ConnectionParams,
+    table: &str,
+    data: HashMap<String, serde_json::Value>,
+) -> Result<u64, String> {
+    let bindings = sorted_bindings(&data);
+    let (sql, values) = insert_statement(table, &bindings)?;
+    statement_log::log_statement(params, &sql, &bindings);
+    execute_bound(params, sql, values).await
+}
+
+/// The statement `insert_record` would run, without running it
+pub async fn insert_record_sql(
+    _params: &ConnectionParams,
+    table: &str,
+    data: HashMap<String, serde_json::Value>,
+) -> Result<StatementPreview, String> {
+    let bindings = sorted_bindings(&data);
+    Ok(StatementPreview {
+        sql: insert_statement(table, &bindings)?.0,
+        bindings,
+    })
+}
+
+/// Inserts many rows in one transaction, as multi-row INSERTs of up to 1000 rows.
+/// Columns are the union over all rows; a row missing a column gets NULL.
+pub async fn insert_records(
+    params: &ConnectionParams,
+    table: &str,
+    rows: Vec<HashMap<String, serde_json::Value>>,
+) -> Result<u64, String> {
+    let cols = union_columns(&rows);
+    if cols.is_empty() {
+        return Err("No data to insert".into());
+    }
+    let quoted_cols: Vec<String> = cols.iter().map(|c| quote_identifier(c, '"')).collect();
+    let row_placeholders = format!("({})", vec!["?"; cols.len()].join(", "));
+
+    let mut statements = Vec::new();
+    for chunk in rows.chunks(insert_chunk_size(cols.len())) {
+        let mut logged_bindings = Vec::new();
+        for row in chunk {
+            for col in &cols {
+                let val = row.get(col).cloned().unwrap_or(serde_json::Value::Null);
+                logged_bindings.push((col.clone(), val));
+            }
+        }
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES {}",
+            quote_identifier(table, '"'),
+            quoted_cols.join(", "),
+            vec![row_placeholders.as_str(); chunk.len()].join(", ")
+        );
+        statement_log::log_statement(params, &sql, &logged_bindings);
+        let values = to_duckdb_values(logged_bindings.iter().map(|(_, v)| v))?;
+        statements.push((sql, values));
+    }
+
+    run(params, move |mut conn| {
+        let tx = conn.transaction()?;
+        let mut affected_rows = 0;
+        for (sql, values) in statements {
+            affected_rows += tx.execute(&sql, params_from_iter(values))? as u64;
+        }
+        tx.commit()?;
+        Ok::<_, duckdb::Error>(affected_rows)
+    })
+    .await
+}
+
+/// Inserts the row, or updates its other columns when a row with the same
+/// `conflict_cols` values exists (these must match a primary key or unique index)
+pub async fn upsert_record(
+    params: &ConnectionParams,
+    table: &str,
+    conflict_cols: &[String],
+    data: HashMap<String, serde_json::Value>,
+) -> Result<u64, String> {
+    validate_upsert(conflict_cols, &data)?;
+
+    let entries = sorted_bindings(&data);
+    let (mut sql, values) = insert_statement(table, &entries)?;
+
+    let conflict: Vec<String> = conflict_cols
+        .iter()
+        .map(|c| quote_identifier(c, '"'))
+        .collect();
+    let updates: Vec<String> = entries
+        .iter()
+        .filter(|(k, _)| !conflict_cols.contains(k))
+        .map(|(k, _)| format!("{0} = EXCLUDED.{0}", quote_identifier(k, '"')))
+        .collect();
+    if updates.is_empty() {
+        sql.push_str(&format!(
+            " ON CONFLICT ({}) DO NOTHING",
+            conflict.join(", ")
+        ));
+    } else {
+        sql.push_str(&format!(
+            " ON CONFLICT ({}) DO UPDATE SET {}",
+            conflict.join(", "),
+            updates.join(", ")
+        ));
+    }
+
+    statement_log::log_statement(params, &sql, &entries);
+    execute_bound(params, sql, values).await
+}
+
+/// Extracts ORDER BY clause from a SQL query (case-insensitive)
+fn extract_order_by(query: &str) -> String {
+    let query_upper = query.to_uppercase();
+    if let Some(pos) = query_upper.rfind("ORDER BY") {
+        // Return the ORDER BY clause from the original query (preserving case)
+        query[pos..].trim().to_string()
+    } else {
+        String::new()
+    }
+}
+
+/// Removes ORDER BY clause from a SQL query
+fn remove_order_by(query: &str) -> String {
+    let query_upper = query.to_uppercase();
+    if let Some(pos) = query_upper.rfind("ORDER BY") {
+        query[..pos].trim().to_string()
+    } else {
+        query.to_string()
+    }
+}
+
+pub async fn get_table_ddl(params: &ConnectionParams, table_name: &str) -> Result<String, String> {
+    let mut ddl = query_rows(
+        params,
+        "SELECT sql FROM duckdb_tables() WHERE database_name = current_database() AND schema_name = ? AND table_name = ?",
+        vec![duckdb_schema(params).to_string(), table_name.to_string()],
+        |r| r.get::<_, String>(0),
+    )
+    .await?;
+    match ddl.pop() {
+        Some(sql) => Ok(format!("{};", sql.trim_end().trim_end_matches(';'))),
+        None => Err(format!("Table {} not found", table_name)),
+    }
+}
+
+/// The estimate is DuckDB's own row count from its storage metadata, which is
+/// cheap but can lag behind uncommitted or very recent changes
+pub async fn get_table_row_count(
+    params: &ConnectionParams,
+    table_name: &str,
+    exact: bool,
+) -> Result<TableRowCount, String> {
+    if !exact {
+        let estimate = query_rows(
+            params,
+            "SELECT estimated_size FROM duckdb_tables() WHERE database_name = current_database() AND schema_name = ? AND table_name = ?",
+            vec![duckdb_schema(params).to_string(), table_name.to_string()],
+            |r| r.get::<_, i64>(0),
+        )
+        .await?;
+        if let Some(count) = estimate.first() {
+            return Ok(TableRowCount {
+                count: *count,
+                estimated: true,
+            });
+        }
+    }
+
+    let query = format!("SELECT COUNT(*) FROM {}", quote_identifier(table_name, '"'));
+    let count = run(params, move |conn| {
+        conn.query_row(&query, [], |r| r.get::<_, i64>(0))
+    })
+    .await?;
+    Ok(TableRowCount {
+        count,
+        estimated: false,
+    })
+}
+
+/// DuckDB's JSON plan, parsed; kept as text if it is not valid JSON
+pub async fn explain_query(
+    params: &ConnectionParams,
+    query: &str,
+) -> Result<serde_json::Value, String> {
+    let explain = format!("EXPLAIN (FORMAT JSON) {}", query);
+    let plan: String = run(params, move |conn| {
+        conn.query_row(&explain, [], |r| r.get(1))
+    })
+    .await?;
+    Ok(serde_json::from_str(&plan).unwrap_or(serde_json::Value::String(plan)))
+}
+
+/// Runs the statements in one transaction and returns each statement's affected
+/// row count. The first failure rolls everything back.
+pub async fn execute_script(
+    params: &ConnectionParams,
+    statements: &[&str],
+) -> Result<Vec<u64>, String> {
+    for statement in statements {
+        statement_log::log_statement(params, statement, &[]);
+    }
+    let statements: Vec<String> = statements.iter().map(|s| s.to_string()).collect();
+
+    run(params, move |mut conn| {
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let mut affected_rows = Vec::with_capacity(statements.len());
+        for (i, statement) in statements.iter().enumerate() {
+            match tx.execute(statement, []) {
+                Ok(affected) => affected_rows.push(affected as u64),
+                // Dropping the transaction rolls it back
+                Err(e) => return Err(script_error(i, statement, e)),
+            }
+        }
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(affected_rows)
+    })
+    .await
+}
+
+/// What `execute_query` and `execute_query_with_params` read on the blocking thread
+struct FetchedRows {
+    columns: Vec<String>,
+    column_types: Vec<String>,
+    /// Rows not sent on as batches
+    rows: Vec<Vec<serde_json::Value>>,
+    /// Rows read, including the ones sent on
+    row_count: usize,
+    truncated: bool,
+}
+
+/// Reads the rows of `query`, stopping after `limit` rows. With `batches`, rows are
+/// sent on in groups of `QUERY_BATCH_SIZE` instead of being kept; once the receiver
+/// is gone, reading stops and the result is marked truncated.
+fn fetch_rows(
+    conn: &Connection,
+    query: &str,
+    values: Vec<Value>,
+    limit: Option<u32>,
+    batches: Option<mpsc::Sender<RowBatch>>,
+) -> duckdb::Result<FetchedRows> {
+    let mut stmt = conn.prepare(query)?;
+    let mut rows = stmt.query(params_from_iter(values))?;
+
+    // Column names and types are known once the statement has run, even without rows
+    let (columns, column_types) = match rows.as_ref() {
+        Some(stmt) => (
+            stmt.column_names(),
+            (0..stmt.column_count())
+                .map(|i| format!("{:?}", stmt.column_logical_type(i).id()).to_uppercase())
+                .collect(),
+        ),
+        None => (Vec::new(), Vec::new()),
+    };
+
+    let mut buffer = RowBuffer::new(batches.map(|batches| {
+        move |columns: &[String], rows: &[Vec<serde_json::Value>]| {
+            batches
+                .blocking_send((columns.to_vec(), rows.to_vec()))
+                .is_ok()
+        }
+    }));
+    let mut truncated = false;
+
+    while let Some(row) = rows.next()? {
+        if limit.is_some_and(|l| buffer.row_count() >= l as usize) {
+            truncated = true;
+            break;
+        }
+        if !buffer.push(&columns, row_to_json(row, columns.len())?) {
+            truncated = true;
+            break;
+        }
+    }
+
+    let row_count = buffer.row_count();
+    Ok(FetchedRows {
+        rows: buffer.finish(&columns),
+        columns,
+        column_types,
+        row_count,
+        truncated,
+    })
+}
+
+/// Runs `query` with `values` bound to its (driver-native) placeholders. Row-returning
+/// statements stop after `limit` rows and set `truncated`.
+pub async fn execute_query_with_params(
+    params: &ConnectionParams,
+    query: &str,
+    values: &[serde_json::Value],
+    limit: Option<u32>,
+) -> Result<QueryResult, String> {
+    let logged_bindings: Vec<(String, serde_json::Value)> = values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (format!("${}", i + 1), v.clone()))
+        .collect();
+    statement_log::log_statement(params, query, &logged_bindings);
+
+    let values = to_duckdb_values(values)?;
+    let query = query.to_string();
+    let started = Instant::now();
+
+    if !yields_rows(&query) {
+        let affected_rows = execute_bound(params, query, values).await?;
+        return Ok(QueryResult {
+            columns: Vec::new(),
+            column_types: Vec::new(),
+            rows: Vec::new(),
+            affected_rows,
+            truncated: false,
+            pagination: None,
+            has_more: false,
+            column_refs: None,
+            execution_ms: started.elapsed().as_millis() as u64,
+            row_count: None,
+            auto_limit: None,
+        });
+    }
+
+    let fetched = run(params, move |conn| {
+        fetch_rows(&conn, &query, values, limit, None)
+    })
+    .await?;
+
+    let row_count = fetched.row_count;
+    Ok(QueryResult {
+        columns: fetched.columns,
+        column_types: fetched.column_types,
+        rows: fetched.rows,
+        affected_rows: 0,
+        truncated: fetched.truncated,
+        pagination: None,
+        has_more: fetched.truncated,
+        column_refs: None,
+        execution_ms: started.elapsed().as_millis() as u64,
+        row_count: Some(row_count),
+        auto_limit: None,
+    })
+}
+
+pub async fn execute_query(
+    params: &ConnectionParams,
+    query: &str,
+    limit: Option<u32>,
+    page: u32,
+    offset: Option<u32>,
+    on_batch: Option<RowBatchCallback<'_>>,
+) -> Result<QueryResult, String> {
+    statement_log::log_statement(params, query, &[]);
+    let started = Instant::now();
+
+    // INSERT/UPDATE/DELETE/DDL: run with execute() so the affected row count is reported
+    if !yields_rows(query) {
+        let affected_rows = execute_bound(params, query.to_string(), Vec::new()).await?;
+        return Ok(QueryResult {
+            columns: Vec::new(),
+            column_types: Vec::new(),
+            rows: Vec::new(),
+            affected_rows,
+            truncated: false,
+            pagination: None,
+            has_more: false,
+            column_refs: None,
+            execution_ms: started.elapsed().as_millis() as u64,
+            row_count: None,
+            auto_limit: None,
+        });
+    }
+
+    let is_select = query.trim_start().to_uppercase().starts_with("SELECT");
+    let mut pagination: Option<Pagination> = None;
+    let final_query: String;
+    let mut manual_limit = limit;
+    let mut auto_limit = None;
+
+    // Queries that already carry their own LIMIT are run as-is (capped by manual_limit)
+    if let Some(l) = limit.filter(|_| is_select && !has_limit_clause(query)) {
+        let offset = offset.unwrap_or_else(|| calculate_offset(page, l));
+
+        let count_q = format!("SELECT COUNT(*) FROM ({})", query);
+        let total_rows = run(params, move |conn| {
+            conn.query_row(&count_q, [], |r| r.get::<_, i64>(0))
+        })
+        .await
+        .unwrap_or(0) as u64;
+
+        pagination = Some(Pagination {
+            page,
+            page_size: l,
+            total_rows,
+        });
+
+        // Extract ORDER BY clause from the original query to preserve sorting
+        let order_by_clause = extract_order_by(query);
+
+        // LIMIT l + 1: the extra row (trimmed below) tells whether another page exists
+        if !order_by_clause.is_empty() {
+            // Remove ORDER BY from inner query and add it to outer query
+            let query_without_order = remove_order_by(query);
+            final_query = format!(
+                "SELECT * FROM ({}) {} LIMIT {} OFFSET {}",
+                query_without_order,
+                order_by_clause,
+                l + 1,
+                offset
+            );
+        } else {
+            final_query = format!(
+                "SELECT * FROM ({}) LIMIT {} OFFSET {}",
+                query,
+                l + 1,
+                offset
+            );
+        }
+
+        manual_limit = None;
+    } else if let Some(limited) = auto_limit_query(query, params.default_row_limit) {
+        // A bare SELECT under the connection's auto-limit: the row fetched past it
+        // stops the read and shows the result was cut
+        final_query = limited;
+        manual_limit = params.default_row_limit;
+        auto_limit = params.default_row_limit;
+    } else {
+        final_query = query.to_string();
+    }
+
+    // Paginated results are already bounded by the page size, so only unbounded
+    // queries are streamed to the caller in batches
+    let on_batch = if pagination.is_some() { None } else { on_batch };
+    let (batch_tx, mut batch_rx) = mpsc::channel::<RowBatch>(1);
+    let batch_tx = on_batch.map(|_| batch_tx);
+
+    let fetch = run(params, move |conn| {
+        fetch_rows(&conn, &final_query, Vec::new(), manual_limit, batch_tx)
+    });
+    // Dropping the receiver once the caller has seen enough stops the fetch
+    let forward = async move {
+        if let Some(on_batch) = on_batch {
+            while let Some((columns, rows)) = batch_rx.recv().await {
+                if !on_batch(&columns, &rows) {
+                    break;
+                }
+            }
+        }
+    };
+    let (fetched, ()) = tokio::join!(fetch, forward);
+    let FetchedRows {
+        columns,
+        column_types,
+        rows: mut json_rows,
+        row_count: fetched,
+        truncated,
+    } = fetched?;
+
+    let has_more = match &pagination {
+        Some(p) if json_rows.len() > p.page_size as usize => {
+            json_rows.truncate(p.page_size as usize);
+            true
+        }
+        Some(_) => false,
+        None => truncated,
+    };
+
+    // Streamed rows went to `on_batch` and are not part of the result
+    let row_count = if on_batch.is_some() {
+        fetched
+    } else {
+        json_rows.len()
+    };
+    Ok(QueryResult {
+        columns,
+        column_types,
+        rows: json_rows,
+        affected_rows: 0,
+        truncated,
+        pagination,
+        has_more,
+        column_refs: None,
+        execution_ms: started.elapsed().as_millis() as u64,
+        row_count: Some(row_count),
+        auto_limit: auto_limit.filter(|&n| truncated && row_count >= n as usize),
+    })
+}
+
+/// `Driver` for DuckDB database files and in-memory databases
+pub struct DuckdbDriver;
+
+#[async_trait]
+impl Driver for DuckdbDriver {
+    async fn get_databases(&self, params: &ConnectionParams) -> Result<Vec<String>, String> {
+        get_databases(params).await
+    }
+
+    async fn get_schemas(&self, params: &ConnectionParams) -> Result<Vec<String>, String> {
+        get_schemas(params).await
+    }
+
+    async fn get_tables(&self, params: &ConnectionParams) -> Result<Vec<TableInfo>, String> {
+        get_tables(params).await
+    }
+
+    async fn get_views(&self, params: &ConnectionParams) -> Result<Vec<ViewInfo>, String> {
+        get_views(params).await
+    }
+
+    async fn get_columns(
+        &self,
+        params: &ConnectionParams,
+        table_name: &str,
+    ) -> Result<Vec<TableColumn>, String> {
+        get_columns(params, table_name).await
+    }
+
+    async fn get_foreign_keys(
+        &self,
+        params: &ConnectionParams,
+        table_name: &str,
+    ) -> Result<Vec<ForeignKey>, String> {
+        get_foreign_keys(params, table_name).await
+    }
+
+    async fn get_all_columns_batch(
+        &self,
+        params: &ConnectionParams,
+        table_names: &[String],
+    ) -> Result<HashMap<String, Vec<TableColumn>>, String> {
+        get_all_columns_batch(params, table_names).await
+    }
+
+    async fn get_all_foreign_keys_batch(
+        &self,
+        params: &ConnectionParams,
+        table_names: &[String],
+    ) -> Result<HashMap<String, Vec<ForeignKey>>, String> {
+        get_all_foreign_keys_batch(params, table_names).await
+    }
+
+    async fn get_indexes(
+        &self,
+        params: &ConnectionParams,
+        table_name: &str,
+    ) -> Result<Vec<Index>, String> {
+        get_indexes(params, table_name).await
+    }
+
+    async fn get_triggers(
+        &self,
+        params: &ConnectionParams,
+        table_name: &str,
+    ) -> Result<Vec<Trigger>, String> {
+        get_triggers(params, table_name).await
+    }
+
+    async fn get_routines(
+        &self,
+        params: &ConnectionParams,
+        schema: Option<&str>,
+    ) -> Result<Vec<RoutineInfo>, String> {
+        get_routines(params, schema).await
+    }
+
+    async fn get_routine_definition(
+        &self,
+        params: &ConnectionParams,
+        schema: Option<&str>,
+        name: &str,
+    ) -> Result<String, String> {
+        get_routine_definition(params, schema, name).await
+    }
+
+    async fn call_procedure(
+        &self,
+        params: &ConnectionParams,
+        name: &str,
+        args: &[serde_json::Value],
+    ) -> Result<ProcedureResult, String> {
+        call_procedure(params, name, args).await
+    }
+
+    async fn create_index(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        name: &str,
+        columns: &[String],
+        unique: bool,
+    ) -> Result<(), String> {
+        create_index(params, table, name, columns, unique).await
+    }
+
+    async fn drop_index(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        name: &str,
+    ) -> Result<(), String> {
+        drop_index(params, table, name).await
+    }
+
+    async fn add_column(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        column: &ColumnDefinition,
+    ) -> Result<(), String> {
+        add_column(params, table, column).await
+    }
+
+    async fn drop_column(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        column: &str,
+    ) -> Result<(), String> {
+        drop_column(params, table, column).await
+    }
+
+    async fn rename_column(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        column: &str,
+        new_name: &str,
+    ) -> Result<(), String> {
+        rename_column(params, table, column, new_name).await
+    }
+
+    async fn delete_record(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        pk: &HashMap<String, serde_json::Value>,
+    ) -> Result<u64, String> {
+        delete_record(params, table, pk).await
+    }
+
+    async fn delete_record_sql(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        pk: &HashMap<String, serde_json::Value>,
+    ) -> Result<StatementPreview, String> {
+        delete_record_sql(params, table, pk).await
+    }
+
+    async fn delete_by_match(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        conditions: &HashMap<String, serde_json::Value>,
+        allow_multiple: bool,
+    ) -> Result<u64, String> {
+        delete_by_match(params, table, conditions, allow_multiple).await
+    }
+
+    async fn update_record(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        pk: &HashMap<String, serde_json::Value>,
+        col_name: &str,
+        new_val: serde_json::Value,
+    ) -> Result<u64, String> {
+        update_record(params, table, pk, col_name, new_val).await
+    }
+
+    async fn update_record_sql(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        pk: &HashMap<String, serde_json::Value>,
+        col_name: &str,
+        new_val: serde_json::Value,
+    ) -> Result<StatementPreview, String> {
+        update_record_sql(params, table, pk, col_name, new_val).await
+    }
+
+    async fn update_records(
+        &self,
+        params: &ConnectionParams,
+        edits: &[RecordEdit],
+    ) -> Result<BatchEditResult, String> {
+        update_records(params, edits).await
+    }
+
+    async fn insert_record(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        data: HashMap<String, serde_json::Value>,
+    ) -> Result<u64, String> {
+        insert_record(params, table, data).await
+    }
+
+    async fn insert_record_sql(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        data: HashMap<String, serde_json::Value>,
+    ) -> Result<StatementPreview, String> {
+        insert_record_sql(params, table, data).await
+    }
+
+    async fn insert_records(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        rows: Vec<HashMap<String, serde_json::Value>>,
+    ) -> Result<u64, String> {
+        insert_records(params, table, rows).await
+    }
+
+    async fn upsert_record(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        conflict_cols: &[String],
+        data: HashMap<String, serde_json::Value>,
+    ) -> Result<u64, String> {
+        upsert_record(params, table, conflict_cols, data).await
+    }
+
+    async fn get_table_ddl(
+        &self,
+        params: &ConnectionParams,
+        table_name: &str,
+    ) -> Result<String, String> {
+        get_table_ddl(params, table_name).await
+    }
+
+    async fn get_table_row_count(
+        &self,
+        params: &ConnectionParams,
+        table_name: &str,
+        exact: bool,
+    ) -> Result<TableRowCount, String> {
+        get_table_row_count(params, table_name, exact).await
+    }
+
+    async fn explain_query(
+        &self,
+        params: &ConnectionParams,
+        query: &str,
+    ) -> Result<serde_json::Value, String> {
+        explain_query(params, query).await
+    }
+
+    async fn execute_script(
+        &self,
+        params: &ConnectionParams,
+        statements: &[&str],
+    ) -> Result<Vec<u64>, String> {
+        execute_script(params, statements).await
+    }
+
+    async fn execute_query_with_params(
+        &self,
+        params: &ConnectionParams,
+        query: &str,
+        values: &[serde_json::Value],
+        limit: Option<u32>,
+    ) -> Result<QueryResult, String> {
+        execute_query_with_params(params, query, values, limit).await
+    }
+
+    async fn execute_query(
+        &self,
+        params: &ConnectionParams,
+        query: &str,
+        limit: Option<u32>,
+        page: u32,
+        offset: Option<u32>,
+        on_batch: Option<RowBatchCallback<'_>>,
+    ) -> Result<QueryResult, String> {
+        execute_query(params, query, limit, page, offset, on_batch).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use tempfile::TempDir;
+
+    fn duckdb_params(dir: &TempDir) -> ConnectionParams {
+        ConnectionParams {
+            driver: "duckdb".to_string(),
+            database: dir
+                .path()
+                .join("test.duckdb")
+                .to_string_lossy()
+                .into_owned(),
+            ..Default::default()
+        }
+    }
+
+    async fn run_sql(params: &ConnectionParams, query: &str) -> QueryResult {
+        execute_query(params, query, None, 1, None, None)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_execute_query_reports_rows_and_affected_rows() {
+        let dir = TempDir::new().unwrap();
+        let params = duckdb_params(&dir);
+
+        run_sql(
+            &params,
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name VARCHAR)",
+        )
+        .await;
+        let res = run_sql(
+            &params,
+            "INSERT INTO users VALUES (1, 'ada'), (2, 'grace'), (3, 'linus')",
+        )
+        .await;
+        assert_eq!(res.affected_rows, 3);
+        assert!(res.columns.is_empty());
+
+        let res = run_sql(&params, "SELECT id, name FROM users WHERE id = 1").await;
+        assert_eq!(res.columns, vec!["id", "name"]);
+        assert_eq!(
+            res.rows,
+            vec![vec![
+                serde_json::Value::from(1),
+                serde_json::Value::from("ada")
+            ]]
+        );
+
+        // DuckDB's FROM-first syntax returns rows too, and an empty result keeps its columns
+        let res = run_sql(&params, "FROM users WHERE id > 10").await;
+        assert_eq!(res.columns, vec!["id", "name"]);
+        assert!(res.rows.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_execute_query_paginates_and_streams_batches() {
+        let dir = TempDir::new().unwrap();
+        let params = duckdb_params(&dir);
+
+        run_sql(
+            &params,
+            "CREATE TABLE nums AS SELECT range AS n FROM range(1, 1201)",
+        )
+        .await;
+
+        let query = "SELECT n FROM nums ORDER BY n";
+        let res = execute_query(&params, query, Some(2), 1, Some(1198), None)
+            .await
+            .unwrap();
+        assert_eq!(res.pagination.as_ref().map(|p| p.total_rows), Some(1200));
+        assert_eq!(
+            res.rows,
+            vec![
+                vec![serde_json::Value::from(1199)],
+                vec![serde_json::Value::from(1200)]
+            ]
+        );
+        assert!(!res.has_more);
+
+        let batches = Mutex::new(Vec::new());
+        let on_batch = |_: &[String], rows: &[Vec<serde_json::Value>]| {
+            batches.lock().unwrap().push(rows.len());
+            true
+        };
+        let res = execute_query(
+            &params,
+            "SELECT n FROM nums",
+            None,
+            1,
+            None,
+            Some(&on_batch),
+        )
+        .await
+        .unwrap();
+        // Streamed rows are only handed to the callback, not kept in the result
+        assert!(res.rows.is_empty());
+        assert_eq!(res.row_count, Some(1200));
+        assert_eq!(*batches.lock().unwrap(), vec![500, 500, 200]);
+    }
+
+    #[tokio::test]
+    async fn test_record_mutations_and_introspection() {
+        let dir = TempDir::new().unwrap();
+        let params = duckdb_params(&dir);
+
+        run_sql(
+            &params,
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, label VARCHAR NOT NULL, price DECIMAL(8, 2))",
+        )
+        .await;
+
+        let columns = get_columns(&params, "items").await.unwrap();
+        let names: Vec<&str> = columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["id", "label", "price"]);
+        assert!(columns[0].is_pk);
+        assert!(!columns[1].is_nullable);
+        assert_eq!(
+            DuckdbDriver
+                .get_primary_keys(&params, "items")
+                .await
+                .unwrap(),
+            vec!["id".to_string()]
+        );
+        let tables = get_tables(&params).await.unwrap();
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].name, "items");
+
+        let row = HashMap::from([
+            ("id".to_string(), serde_json::json!(1)),
+            ("label".to_string(), serde_json::json!("pen")),
+            ("price".to_string(), serde_json::json!("1.50")),
+        ]);
+        assert_eq!(insert_record(&params, "items", row).await.unwrap(), 1);
+
+        let pk = HashMap::from([("id".to_string(), serde_json::json!(1))]);
+        let updated = update_record(&params, "items", &pk, "label", serde_json::json!("ink"))
+            .await
+            .unwrap();
+        assert_eq!(updated, 1);
+
+        let res = run_sql(&params, "SELECT label, price FROM items").await;
+        assert_eq!(
+            res.rows,
+            vec![vec![serde_json::json!("ink"), serde_json::json!("1.50")]]
+        );
+
+        assert_eq!(delete_record(&params, "items", &pk).await.unwrap(), 1);
+        let count = get_table_row_count(&params, "items", true).await.unwrap();
+        assert_eq!(count.count, 0);
+        assert!(!count.estimated);
+    }
+
+    #[tokio::test]
+    async fn test_macros_are_listed_as_routines() {
+        let dir = TempDir::new().unwrap();
+        let params = duckdb_params(&dir);
+
+        run_sql(&params, "CREATE MACRO add_one(a) AS a + 1").await;
+        run_sql(
+            &params,
+            "CREATE MACRO first_n(n) AS TABLE SELECT * FROM range(n)",
+        )
+        .await;
+
+        let routines = get_routines(&params, None).await.unwrap();
+        let listed: Vec<(&str, Option<&str>)> = routines
+            .iter()
+            .map(|r| (r.name.as_str(), r.return_type.as_deref()))
+            .collect();
+        assert_eq!(listed, vec![("add_one", None), ("first_n", Some("TABLE"))]);
+
+        let definition = get_routine_definition(&params, None, "add_one")
+            .await
+            .unwrap();
+        assert!(definition.starts_with("CREATE MACRO \"add_one\"(a) AS "));
+        assert!(get_routine_definition(&params, None, "missing")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_value_to_json_formats_temporal_and_nested_values() {
+        let dir = TempDir::new().unwrap();
+        let params = duckdb_params(&dir);
+
+        let res = run_sql(
+            &params,
+            "SELECT DATE '2024-02-29', TIMESTAMP '2024-02-29 13:45:10', TIME '08:30:00', \
+             [1, 2], {'a': 1}, '\\x01\\x02'::BLOB, 123456789012345678901234567890::HUGEINT",
+        )
+        .await;
+        assert_eq!(
+            res.rows[0],
+            vec![
+                serde_json::json!("2024-02-29"),
+                serde_json::json!("2024-02-29 13:45:10"),
+                serde_json::json!("08:30:00"),
+                serde_json::json!([1, 2]),
+                serde_json::json!({"a": 1}),
+                serde_json::json!("base64:AQI="),
+                serde_json::json!("123456789012345678901234567890"),
+            ]
+        );
+    }
+}
@@ -1,9 +1,173 @@
-use crate::drivers::common::extract_mysql_value;
+use crate::drivers::common::{
+    auto_limit_query, bind_json_values, calculate_offset, check_match_count, column_definition_sql,
+    column_type_names, create_index_sql, described_columns, execute_with_json_params,
+    extract_mysql_value, first_keyword, has_limit_clause, insert_chunk_size, match_bindings,
+    mysql_column_definition, quote_identifier, returns_rows, run_record_edits, script_error,
+    sorted_bindings, statement_timeout_error, union_columns, update_bindings, validate_upsert,
+    Driver, RowBatchCallback, RowBuffer, SqlxDatabase, SqlxQuery,
+};
 use crate::models::{
-    ConnectionParams, ForeignKey, Index, Pagination, QueryResult, TableColumn, TableInfo,
+    BatchEditResult, ColumnDefinition, ConnectionParams, ForeignKey, Index, Pagination,
+    ProcedureResult, QueryResult, RecordEdit, RoutineInfo, StatementPreview, TableColumn,
+    TableInfo, TableRowCount, Trigger, ViewInfo,
 };
 use crate::pool_manager::get_mysql_pool;
-use sqlx::{Column, Row};
+use crate::statement_log;
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use sqlx::mysql::{MySqlConnection, MySqlRow};
+use sqlx::query_builder::Separated;
+use sqlx::{Column, Executor, Row};
+use std::collections::HashMap;
+use std::time::Instant;
+
+// MySQL and MariaDB share the protocol and most of the SQL, but differ in:
+// - `VERSION()`: MariaDB reports e.g. `10.11.6-MariaDB-1:10.11.6+maria~ubu2204`, older
+//   servers with a `5.5.5-` prefix, so its numbers only compare against MariaDB releases
+// - statement timeouts: MySQL 5.7.8+ has `max_execution_time` (ms, SELECTs only),
+//   MariaDB 10.1+ has `max_statement_time` (seconds, any statement)
+// - `RENAME COLUMN`: MySQL 8.0.3+, MariaDB 10.5.2+
+// - `information_schema.columns`: `extra` may carry more than `auto_increment`
+//   (`INVISIBLE`, `DEFAULT_GENERATED`, ...) in either case, and some servers return
+//   the catalog columns as binary strings
+
+/// Which server is behind a `mysql` connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerFlavor {
+    Mysql,
+    Mariadb,
+}
+
+/// Server flavor and version, as read from `VERSION()`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServerVersion {
+    pub flavor: ServerFlavor,
+    pub version: (u32, u32, u32),
+}
+
+impl ServerVersion {
+    pub fn parse(version: &str) -> ServerVersion {
+        let flavor = if version.to_lowercase().contains("mariadb") {
+            ServerFlavor::Mariadb
+        } else {
+            ServerFlavor::Mysql
+        };
+        let numbers = match flavor {
+            ServerFlavor::Mariadb => version.strip_prefix("5.5.5-").unwrap_or(version),
+            ServerFlavor::Mysql => version,
+        };
+        let mut parts = numbers
+            .split(|c: char| !c.is_ascii_digit())
+            .take(3)
+            .map(|p| p.parse().unwrap_or(0));
+        let mut next = || parts.next().unwrap_or(0);
+        ServerVersion {
+            flavor,
+            version: (next(), next(), next()),
+        }
+    }
+
+    /// True for a server of `flavor` at version `min` or later
+    pub fn at_least(&self, flavor: ServerFlavor, min: (u32, u32, u32)) -> bool {
+        self.flavor == flavor && self.version >= min
+    }
+
+    pub fn supports_rename_column(&self) -> bool {
+        self.at_least(ServerFlavor::Mysql, (8, 0, 3))
+            || self.at_least(ServerFlavor::Mariadb, (10, 5, 2))
+    }
+
+    /// The session statement capping statement run time, if the server has one
+    pub fn statement_timeout_sql(&self, secs: u64) -> Option<String> {
+        if self.at_least(ServerFlavor::Mysql, (5, 7, 8)) {
+            Some(format!("SET SESSION max_execution_time = {}", secs * 1000))
+        } else if self.at_least(ServerFlavor::Mariadb, (10, 1, 1)) {
+            Some(format!("SET SESSION max_statement_time = {}", secs))
+        } else {
+            None
+        }
+    }
+}
+
+pub async fn server_version(params: &ConnectionParams) -> Result<ServerVersion, String> {
+    let pool = get_mysql_pool(params).await?;
+    let version: String = sqlx::query_scalar("SELECT VERSION()")
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(ServerVersion::parse(&version))
+}
+
+/// Reads an `information_schema` text column, which some servers send as binary
+fn catalog_text(row: &MySqlRow, column: &str) -> String {
+    catalog_optional_text(row, column).unwrap_or_default()
+}
+
+/// Like `catalog_text`, but `None` for SQL NULL
+fn catalog_optional_text(row: &MySqlRow, column: &str) -> Option<String> {
+    row.try_get::<Option<String>, _>(column)
+        .or_else(|_| {
+            row.try_get::<Option<Vec<u8>>, _>(column)
+                .map(|bytes| bytes.map(|bytes| String::from_utf8_lossy(&bytes).into_owned()))
+        })
+        .ok()
+        .flatten()
+}
+
+/// `extra` is a space-separated attribute list such as `auto_increment INVISIBLE`
+fn is_auto_increment(extra: &str) -> bool {
+    extra
+        .split_whitespace()
+        .any(|attr| attr.eq_ignore_ascii_case("auto_increment"))
+}
+
+/// Members of an `enum(...)` or `set(...)` `column_type`, e.g. `enum('a','it''s')`.
+/// `None` for any other type.
+fn enum_values(column_type: &str) -> Option<Vec<String>> {
+    let lower = column_type.to_ascii_lowercase();
+    let prefix = ["enum(", "set("]
+        .into_iter()
+        .find(|prefix| lower.starts_with(prefix))?;
+    let body = column_type[prefix.len()..].strip_suffix(')')?;
+
+    let mut values = Vec::new();
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {}
+            ',' | ' ' => continue,
+            _ => return None,
+        }
+        let mut value = String::new();
+        loop {
+            match chars.next()? {
+                '\'' if chars.peek() == Some(&'\'') => {
+                    chars.next();
+                    value.push('\'');
+                }
+                '\'' => break,
+                '\\' => value.push(chars.next()?),
+                c => value.push(c),
+            }
+        }
+        values.push(value);
+    }
+    Some(values)
+}
+
+/// Maps a row of `information_schema.columns`
+fn catalog_column(row: &MySqlRow) -> TableColumn {
+    TableColumn {
+        name: catalog_text(row, "column_name"),
+        data_type: catalog_text(row, "data_type"),
+        is_pk: catalog_text(row, "column_key").eq_ignore_ascii_case("PRI"),
+        is_nullable: catalog_text(row, "is_nullable").eq_ignore_ascii_case("YES"),
+        is_auto_increment: is_auto_increment(&catalog_text(row, "extra")),
+        enum_values: enum_values(&catalog_text(row, "column_type")),
+        default_value: catalog_optional_text(row, "column_default"),
+        comment: Some(catalog_text(row, "column_comment")).filter(|c| !c.is_empty()),
+    }
+}
 
 pub async fn get_databases(params: &ConnectionParams) -> Result<Vec<String>, String> {
     let pool = get_mysql_pool(params).await?;
@@ -17,10 +181,15 @@ pub async fn get_databases(params: &ConnectionParams) -> Result<Vec<String>, Str
         .collect())
 }
 
+/// MySQL schemas are databases, so the schema selector lists the databases
+pub async fn get_schemas(params: &ConnectionParams) -> Result<Vec<String>, String> {
+    get_databases(params).await
+}
+
 pub async fn get_tables(params: &ConnectionParams) -> Result<Vec<TableInfo>, String> {
     let pool = get_mysql_pool(params).await?;
     let rows = sqlx::query(
-        "SELECT table_name as name FROM information_schema.tables WHERE table_schema = DATABASE() ORDER BY table_name ASC",
+        "SELECT table_name as name FROM information_schema.tables WHERE table_schema = DATABASE() AND table_type = 'BASE TABLE' ORDER BY table_name ASC",
     )
     .fetch_all(&pool)
     .await
@@ -33,6 +202,23 @@ pub async fn get_tables(params: &ConnectionParams) -> Result<Vec<TableInfo>, Str
         .collect())
 }
 
+pub async fn get_views(params: &ConnectionParams) -> Result<Vec<ViewInfo>, String> {
+    let pool = get_mysql_pool(params).await?;
+    let rows = sqlx::query(
+        "SELECT table_name as name, view_definition as definition FROM information_schema.views WHERE table_schema = DATABASE() ORDER BY table_name ASC",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(rows
+        .iter()
+        .map(|r| ViewInfo {
+            name: r.try_get("name").unwrap_or_default(),
+            definition: r.try_get("definition").ok().flatten(),
+        })
+        .collect())
+}
+
 pub async fn get_columns(
     params: &ConnectionParams,
     table_name: &str,
@@ -40,7 +226,8 @@ pub async fn get_columns(
     let pool = get_mysql_pool(params).await?;
 
     let query = r#"
-        SELECT column_name, data_type, column_key, is_nullable, extra
+        SELECT column_name, data_type, column_type, column_key, is_nullable, extra,
+            column_default, column_comment
         FROM information_schema.columns
         WHERE table_schema = DATABASE() AND table_name = ?
         ORDER BY ordinal_position
@@ -52,21 +239,7 @@ pub async fn get_columns(
         .await
         .map_err(|e| e.to_string())?;
 
-    Ok(rows
-        .iter()
-        .map(|r| {
-            let key: String = r.try_get("column_key").unwrap_or_default();
-            let null_str: String = r.try_get("is_nullable").unwrap_or_default();
-            let extra: String = r.try_get("extra").unwrap_or_default();
-            TableColumn {
-                name: r.try_get("column_name").unwrap_or_default(),
-                data_type: r.try_get("data_type").unwrap_or_default(),
-                is_pk: key == "PRI",
-                is_nullable: null_str == "YES",
-                is_auto_increment: extra.contains("auto_increment"),
-            }
-        })
-        .collect())
+    Ok(rows.iter().map(catalog_column).collect())
 }
 
 pub async fn get_foreign_keys(
@@ -120,7 +293,8 @@ pub async fn get_all_columns_batch(
     let pool = get_mysql_pool(params).await?;
 
     let query = r#"
-        SELECT table_name, column_name, data_type, column_key, is_nullable, extra
+        SELECT table_name, column_name, data_type, column_type, column_key, is_nullable, extra,
+            column_default, column_comment
         FROM information_schema.columns
         WHERE table_schema = DATABASE()
         ORDER BY table_name, ordinal_position
@@ -134,20 +308,11 @@ pub async fn get_all_columns_batch(
     let mut result: HashMap<String, Vec<TableColumn>> = HashMap::new();
 
     for row in rows {
-        let table_name: String = row.try_get("table_name").unwrap_or_default();
-        let key: String = row.try_get("column_key").unwrap_or_default();
-        let null_str: String = row.try_get("is_nullable").unwrap_or_default();
-        let extra: String = row.try_get("extra").unwrap_or_default();
-
-        let column = TableColumn {
-            name: row.try_get("column_name").unwrap_or_default(),
-            data_type: row.try_get("data_type").unwrap_or_default(),
-            is_pk: key == "PRI",
-            is_nullable: null_str == "YES",
-            is_auto_increment: extra.contains("auto_increment"),
-        };
-
-        result.entry(table_name).or_insert_with(Vec::new).push(column);
+        let table_name = catalog_text(&row, "table_name");
+        result
+            .entry(table_name)
+            .or_insert_with(Vec::new)
+            .push(catalog_column(&row));
     }
 
     Ok(result)
@@ -243,156 +408,917 @@ pub async fn get_indexes(
         .collect())
 }
 
-pub async fn delete_record(
+pub async fn get_triggers(
     params: &ConnectionParams,
-    table: &str,
-    pk_col: &str,
-    pk_val: serde_json::Value,
-) -> Result<u64, String> {
+    table_name: &str,
+) -> Result<Vec<Trigger>, String> {
     let pool = get_mysql_pool(params).await?;
 
-    let query = format!("DELETE FROM `{}` WHERE `{}` = ?", table, pk_col);
+    let query = r#"
+        SELECT trigger_name, action_timing, event_manipulation, action_statement
+        FROM information_schema.triggers
+        WHERE event_object_schema = DATABASE() AND event_object_table = ?
+        ORDER BY action_timing, event_manipulation, action_order
+    "#;
 
-    let result = match pk_val {
-        serde_json::Value::Number(n) => {
-            if n.is_i64() {
-                sqlx::query(&query)
-                    .bind(n.as_i64())
-                    .execute(&pool)
-                    .await
-            } else if n.is_f64() {
-                sqlx::query(&query)
-                    .bind(n.as_f64())
-                    .execute(&pool)
-                    .await
-            } else {
-                sqlx::query(&query)
-                    .bind(n.to_string())
-                    .execute(&pool)
-                    .await
-            }
-        }
-        serde_json::Value::String(s) => sqlx::query(&query).bind(s).execute(&pool).await,
-        _ => return Err("Unsupported PK type".into()),
+    let rows = sqlx::query(query)
+        .bind(table_name)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .iter()
+        .map(|r| Trigger {
+            name: catalog_text(r, "trigger_name"),
+            timing: catalog_text(r, "action_timing"),
+            event: catalog_text(r, "event_manipulation"),
+            statement: catalog_text(r, "action_statement"),
+            is_enabled: None,
+        })
+        .collect())
+}
+
+/// Procedures and functions in the database `schema` (the connection's by default)
+pub async fn get_routines(
+    params: &ConnectionParams,
+    schema: Option<&str>,
+) -> Result<Vec<RoutineInfo>, String> {
+    let pool = get_mysql_pool(params).await?;
+
+    let query = r#"
+        SELECT routine_name, routine_type, dtd_identifier, routine_definition
+        FROM information_schema.routines
+        WHERE routine_schema = COALESCE(?, DATABASE())
+        ORDER BY routine_name
+    "#;
+
+    let rows = sqlx::query(query)
+        .bind(schema)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .iter()
+        .map(|r| RoutineInfo {
+            name: catalog_text(r, "routine_name"),
+            routine_type: catalog_text(r, "routine_type"),
+            return_type: catalog_optional_text(r, "dtd_identifier"),
+            definition: catalog_optional_text(r, "routine_definition"),
+        })
+        .collect())
+}
+
+/// Full `CREATE PROCEDURE` or `CREATE FUNCTION` statement of `name`
+pub async fn get_routine_definition(
+    params: &ConnectionParams,
+    schema: Option<&str>,
+    name: &str,
+) -> Result<String, String> {
+    let pool = get_mysql_pool(params).await?;
+
+    let routine = sqlx::query(
+        "SELECT routine_schema, routine_type FROM information_schema.routines WHERE routine_schema = COALESCE(?, DATABASE()) AND routine_name = ?",
+    )
+    .bind(schema)
+    .bind(name)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Routine '{}' not found", name))?;
+
+    let (kind, column) = match catalog_text(&routine, "routine_type").as_str() {
+        "FUNCTION" => ("FUNCTION", "Create Function"),
+        _ => ("PROCEDURE", "Create Procedure"),
     };
+    let query = format!(
+        "SHOW CREATE {} {}.{}",
+        kind,
+        quote_identifier(&catalog_text(&routine, "routine_schema"), '`'),
+        quote_identifier(name, '`')
+    );
+    let row = sqlx::query(&query)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
 
-    result.map(|r| r.rows_affected()).map_err(|e| e.to_string())
+    // NULL unless the user created the routine or has SHOW_ROUTINE
+    catalog_optional_text(&row, column).ok_or_else(|| {
+        format!(
+            "The definition of '{}' is not readable with this user's privileges",
+            name
+        )
+    })
 }
 
-pub async fn update_record(
+/// Calls the procedure or function `name` in the connection's database, with `args`
+/// bound to its IN and INOUT parameters in order. OUT and INOUT parameters are passed
+/// as session variables and read back into `out_params` after the CALL; the result
+/// holds the rows of the first result set the procedure returns.
+pub async fn call_procedure(
     params: &ConnectionParams,
-    table: &str,
-    pk_col: &str,
-    pk_val: serde_json::Value,
-    col_name: &str,
-    new_val: serde_json::Value,
-) -> Result<u64, String> {
+    name: &str,
+    args: &[serde_json::Value],
+) -> Result<ProcedureResult, String> {
     let pool = get_mysql_pool(params).await?;
+    // Session variables only live on one connection
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
 
-    let mut qb = sqlx::QueryBuilder::new(format!("UPDATE `{}` SET `{}` = ", table, col_name));
+    let routine = sqlx::query(
+        "SELECT routine_type FROM information_schema.routines WHERE routine_schema = DATABASE() AND routine_name = ?",
+    )
+    .bind(name)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| e.to_string())?
+    .ok_or_else(|| format!("Routine '{}' not found", name))?;
+    let is_function = catalog_text(&routine, "routine_type") == "FUNCTION";
 
-    match new_val {
-        serde_json::Value::Number(n) => {
-            if n.is_i64() {
-                qb.push_bind(n.as_i64());
-            } else {
-                qb.push_bind(n.as_f64());
-            }
+    // Position 0 is a function's return value
+    let parameters: Vec<(String, String)> = sqlx::query(
+        "SELECT parameter_mode, parameter_name FROM information_schema.parameters WHERE specific_schema = DATABASE() AND specific_name = ? AND ordinal_position > 0 ORDER BY ordinal_position",
+    )
+    .bind(name)
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| e.to_string())?
+    .iter()
+    .map(|r| (catalog_text(r, "parameter_mode"), catalog_text(r, "parameter_name")))
+    .collect();
+
+    let inputs = parameters.iter().filter(|(mode, _)| mode != "OUT").count();
+    if inputs != args.len() {
+        return Err(format!(
+            "'{}' takes {} arguments, {} given",
+            name,
+            inputs,
+            args.len()
+        ));
+    }
+
+    let mut qb = sqlx::QueryBuilder::new(format!(
+        "{} {}(",
+        if is_function { "SELECT" } else { "CALL" },
+        quote_identifier(name, '`')
+    ));
+    let mut bindings = Vec::new();
+    let mut out_variables = Vec::new();
+    let mut args = args.iter().cloned();
+    let mut separated = qb.separated(", ");
+    for (i, (mode, parameter_name)) in parameters.iter().enumerate() {
+        if mode != "OUT" && mode != "INOUT" {
+            let val = args.next().unwrap_or(serde_json::Value::Null);
+            bindings.push((parameter_name.clone(), val.clone()));
+            push_value(&mut separated, val)?;
+            continue;
         }
-        serde_json::Value::String(s) => {
-            qb.push_bind(s);
+        let variable = format!("@tabularis_out_{}", i + 1);
+        if mode == "INOUT" {
+            let val = args.next().unwrap_or(serde_json::Value::Null);
+            let set = format!("SET {} = ?", variable);
+            statement_log::log_statement(params, &set, &[(parameter_name.clone(), val.clone())]);
+            bind_json_values(sqlx::query(&set), &[val])?
+                .execute(&mut *conn)
+                .await
+                .map_err(|e| e.to_string())?;
         }
-        serde_json::Value::Bool(b) => {
-            qb.push_bind(b);
+        separated.push(&variable);
+        out_variables.push((variable, parameter_name.clone()));
+    }
+    separated.push_unseparated(")");
+
+    statement_log::log_statement(params, qb.sql(), &bindings);
+    // The rows are those of the first result set that has any
+    let result = first_result_set(fetch_result_sets(&mut conn, qb.build(), None).await?);
+
+    let mut out_params = HashMap::new();
+    if !out_variables.is_empty() {
+        let select: Vec<&str> = out_variables.iter().map(|(v, _)| v.as_str()).collect();
+        let row = sqlx::query(&format!("SELECT {}", select.join(", ")))
+            .fetch_one(&mut *conn)
+            .await
+            .map_err(|e| e.to_string())?;
+        for (i, (_, parameter_name)) in out_variables.iter().enumerate() {
+            out_params.insert(parameter_name.clone(), extract_mysql_value(&row, i));
         }
-        serde_json::Value::Null => {
-            qb.push("NULL");
+    }
+
+    Ok(ProcedureResult {
+        result,
+        out_params,
+    })
+}
+
+/// Reads every result set `query` returns, in order: each SELECT of a procedure, or
+/// each statement of a multi-statement script. A set's columns are read from its
+/// rows, so a set without rows has none. Each set keeps at most `row_limit` rows;
+/// the rest are read past so the later sets still arrive.
+async fn fetch_result_sets<'q, E>(
+    conn: &mut MySqlConnection,
+    query: E,
+    row_limit: Option<u32>,
+) -> Result<Vec<QueryResult>, String>
+where
+    E: 'q + sqlx::Execute<'q, sqlx::MySql>,
+{
+    use futures::stream::StreamExt;
+
+    let mut sets = Vec::new();
+    let mut started = Instant::now();
+    let mut columns: Vec<String> = Vec::new();
+    let mut column_types: Vec<String> = Vec::new();
+    let mut rows = Vec::new();
+    let mut truncated = false;
+    let mut results = conn.fetch_many(query);
+    while let Some(result) = results.next().await {
+        match result.map_err(|e| e.to_string())? {
+            sqlx::Either::Left(done) => {
+                let row_count = (!columns.is_empty()).then_some(rows.len());
+                sets.push(QueryResult {
+                    columns: std::mem::take(&mut columns),
+                    column_types: std::mem::take(&mut column_types),
+                    rows: std::mem::take(&mut rows),
+                    affected_rows: done.rows_affected(),
+                    truncated,
+                    pagination: None,
+                    has_more: truncated,
+                    column_refs: None,
+                    execution_ms: started.elapsed().as_millis() as u64,
+                    row_count,
+                    auto_limit: row_limit.filter(|_| truncated),
+                });
+                started = Instant::now();
+                truncated = false;
+            }
+            sqlx::Either::Right(row) => {
+                if columns.is_empty() {
+                    columns = row.columns().iter().map(|c| c.name().to_string()).collect();
+                    column_types = column_type_names(&row);
+                }
+                if row_limit.is_some_and(|l| rows.len() >= l as usize) {
+                    truncated = true;
+                    continue;
+                }
+                rows.push(
+                    (0..row.columns().len())
+                        .map(|i| extract_mysql_value(&row, i))
+                        .collect::<Vec<_>>(),
+                );
+            }
         }
-        _ => return Err("Unsupported Value type".into()),
     }
+    Ok(sets)
+}
 
-    qb.push(format!(" WHERE `{}` = ", pk_col));
+/// Folds result sets into one for callers that show a single result: the first
+/// set with rows, with the affected rows and run time of all of them
+fn first_result_set(sets: Vec<QueryResult>) -> QueryResult {
+    let affected_rows = sets.iter().map(|s| s.affected_rows).sum();
+    let execution_ms = sets.iter().map(|s| s.execution_ms).sum();
+    let mut first = sets
+        .into_iter()
+        .find(|s| !s.columns.is_empty())
+        .unwrap_or(QueryResult {
+            columns: Vec::new(),
+            column_types: Vec::new(),
+            rows: Vec::new(),
+            affected_rows: 0,
+            truncated: false,
+            pagination: None,
+            has_more: false,
+            column_refs: None,
+            execution_ms: 0,
+            row_count: None,
+            auto_limit: None,
+        });
+    first.affected_rows = affected_rows;
+    first.execution_ms = execution_ms;
+    first.row_count = Some(first.rows.len());
+    first
+}
 
-    match pk_val {
-        serde_json::Value::Number(n) => {
-            if n.is_i64() {
-                qb.push_bind(n.as_i64());
-            } else {
-                qb.push_bind(n.as_f64());
+/// Reads the result of a CALL for the grid: the first result set with rows, windowed
+/// by `limit` and `page` like any other query, or else capped by `default_row_limit`.
+/// A procedure's rows can't be counted or wrapped in a LIMIT, so the window is applied
+/// while reading and there is no pagination total. The sets before it only add their
+/// affected rows.
+async fn fetch_first_result_set(
+    params: &ConnectionParams,
+    conn: &mut MySqlConnection,
+    query: &str,
+    limit: Option<u32>,
+    page: u32,
+    offset: Option<u32>,
+    on_batch: Option<RowBatchCallback<'_>>,
+) -> Result<QueryResult, String> {
+    use futures::stream::StreamExt;
+
+    let (skip, cap, auto_limit) = match limit {
+        Some(l) => (
+            offset.unwrap_or_else(|| calculate_offset(page, l)),
+            Some(l),
+            None,
+        ),
+        None => (0, params.default_row_limit, params.default_row_limit),
+    };
+    let streamed = on_batch.is_some();
+    let mut buffer = RowBuffer::new(on_batch);
+    let started = Instant::now();
+    let mut columns: Vec<String> = Vec::new();
+    let mut column_types: Vec<String> = Vec::new();
+    let mut skipped = 0;
+    let mut affected_rows = 0;
+    let mut truncated = false;
+
+    let mut results = conn.fetch_many(sqlx::raw_sql(query));
+    while let Some(result) = results.next().await {
+        match result.map_err(|e| e.to_string())? {
+            sqlx::Either::Left(done) => {
+                affected_rows += done.rows_affected();
+                // The set with rows is complete; the ones after it are not shown
+                if !columns.is_empty() {
+                    break;
+                }
+            }
+            sqlx::Either::Right(row) => {
+                if columns.is_empty() {
+                    columns = row.columns().iter().map(|c| c.name().to_string()).collect();
+                    column_types = column_type_names(&row);
+                }
+                if skipped < skip {
+                    skipped += 1;
+                    continue;
+                }
+                if cap.is_some_and(|l| buffer.row_count() >= l as usize) {
+                    truncated = true;
+                    break;
+                }
+                let json_row = (0..row.columns().len())
+                    .map(|i| extract_mysql_value(&row, i))
+                    .collect();
+                if !buffer.push(&columns, json_row) {
+                    truncated = true;
+                    break;
+                }
             }
         }
-        serde_json::Value::String(s) => {
-            qb.push_bind(s);
+    }
+    drop(results);
+
+    let fetched = buffer.row_count();
+    let rows = buffer.finish(&columns);
+    // Streamed rows went to `on_batch` and are not part of the result
+    let row_count = if streamed { fetched } else { rows.len() };
+    Ok(QueryResult {
+        columns,
+        column_types,
+        rows,
+        affected_rows,
+        truncated,
+        pagination: None,
+        has_more: truncated,
+        column_refs: None,
+        execution_ms: started.elapsed().as_millis() as u64,
+        row_count: Some(row_count),
+        auto_limit: auto_limit.filter(|&n| truncated && row_count >= n as usize),
+    })
+}
+
+/// Id of `conn` for `with_kill_timeout`, read only when the connection has a
+/// statement timeout
+async fn timeout_connection_id(
+    params: &ConnectionParams,
+    conn: &mut MySqlConnection,
+) -> Result<Option<u64>, String> {
+    if params.statement_timeout_secs.is_none() {
+        return Ok(None);
+    }
+    let id: u64 = sqlx::query_scalar("SELECT CONNECTION_ID()")
+        .fetch_one(conn)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(Some(id))
+}
+
+/// Runs `fetch`, which reads from the connection `connection_id` of `pool`, under the
+/// connection's statement timeout. `max_execution_time` doesn't reach the statements
+/// a procedure runs, so once the timeout expires the running one is stopped with
+/// `KILL QUERY` from another connection.
+async fn with_kill_timeout<T>(
+    params: &ConnectionParams,
+    pool: &sqlx::MySqlPool,
+    connection_id: Option<u64>,
+    fetch: impl std::future::Future<Output = Result<T, String>>,
+) -> Result<T, String> {
+    let (Some(secs), Some(id)) = (params.statement_timeout_secs, connection_id) else {
+        return fetch.await;
+    };
+    match tokio::time::timeout(std::time::Duration::from_secs(secs), fetch).await {
+        Ok(result) => result,
+        Err(_) => {
+            pool.execute(format!("KILL QUERY {}", id).as_str())
+                .await
+                .map_err(|e| e.to_string())?;
+            Err(statement_timeout_error(secs, "stopped with KILL QUERY"))
         }
-        _ => return Err("Unsupported PK type".into()),
     }
+}
 
-    let query = qb.build();
-    let result = query.execute(&pool).await.map_err(|e| e.to_string())?;
-    Ok(result.rows_affected())
+pub async fn create_index(
+    params: &ConnectionParams,
+    table: &str,
+    name: &str,
+    columns: &[String],
+    unique: bool,
+) -> Result<(), String> {
+    let sql = create_index_sql(&quote_identifier(table, '`'), name, columns, unique, '`')?;
+    run_ddl(params, &sql).await
 }
 
-pub async fn insert_record(
+/// MySQL indexes belong to their table, so dropping one goes through ALTER TABLE
+pub async fn drop_index(params: &ConnectionParams, table: &str, name: &str) -> Result<(), String> {
+    let sql = format!(
+        "ALTER TABLE {} DROP INDEX {}",
+        quote_identifier(table, '`'),
+        quote_identifier(name, '`')
+    );
+    run_ddl(params, &sql).await
+}
+
+pub async fn add_column(
     params: &ConnectionParams,
     table: &str,
-    data: std::collections::HashMap<String, serde_json::Value>,
-) -> Result<u64, String> {
-    let pool = get_mysql_pool(params).await?;
+    column: &ColumnDefinition,
+) -> Result<(), String> {
+    let sql = format!(
+        "ALTER TABLE {} ADD COLUMN {}",
+        quote_identifier(table, '`'),
+        column_definition_sql(column, '`')?
+    );
+    run_ddl(params, &sql).await
+}
 
-    let mut cols = Vec::new();
-    let mut vals = Vec::new();
+pub async fn drop_column(
+    params: &ConnectionParams,
+    table: &str,
+    column: &str,
+) -> Result<(), String> {
+    let sql = format!(
+        "ALTER TABLE {} DROP COLUMN {}",
+        quote_identifier(table, '`'),
+        quote_identifier(column, '`')
+    );
+    run_ddl(params, &sql).await
+}
 
-    for (k, v) in data {
-        cols.push(format!("`{}`", k));
-        vals.push(v);
+/// Older servers without `RENAME COLUMN` get `CHANGE COLUMN` with the column's
+/// current definition (from SHOW CREATE TABLE)
+pub async fn rename_column(
+    params: &ConnectionParams,
+    table: &str,
+    column: &str,
+    new_name: &str,
+) -> Result<(), String> {
+    let table = quote_identifier(table, '`');
+    if server_version(params).await?.supports_rename_column() {
+        let sql = format!(
+            "ALTER TABLE {} RENAME COLUMN {} TO {}",
+            table,
+            quote_identifier(column, '`'),
+            quote_identifier(new_name, '`')
+        );
+        return run_ddl(params, &sql).await;
     }
 
-    if cols.is_empty() {
-        return Err("No data to insert".into());
-    }
+    let pool = get_mysql_pool(params).await?;
+    let row = sqlx::query(&format!("SHOW CREATE TABLE {}", table))
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    let create_table: String = row.try_get(1).map_err(|e| e.to_string())?;
+    let definition = mysql_column_definition(&create_table, column)
+        .ok_or_else(|| format!("Column {} not found", column))?;
 
-    let mut qb = sqlx::QueryBuilder::new(format!(
-        "INSERT INTO `{}` ({}) VALUES (",
+    let sql = format!(
+        "ALTER TABLE {} CHANGE COLUMN {} {} {}",
         table,
-        cols.join(", ")
-    ));
+        quote_identifier(column, '`'),
+        quote_identifier(new_name, '`'),
+        definition
+    );
+    run_ddl(params, &sql).await
+}
 
-    let mut separated = qb.separated(", ");
-    for val in vals {
-        match val {
+async fn run_ddl(params: &ConnectionParams, sql: &str) -> Result<(), String> {
+    let pool = get_mysql_pool(params).await?;
+    statement_log::log_statement(params, sql, &[]);
+    sqlx::query(sql)
+        .execute(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Appends `WHERE pk1 = ? AND pk2 = ?` covering every primary key column.
+/// Columns are sorted so the generated statement is stable for composite keys.
+fn push_pk_filter(
+    qb: &mut sqlx::QueryBuilder<'_, sqlx::MySql>,
+    pk: &HashMap<String, serde_json::Value>,
+) -> Result<(), String> {
+    if pk.is_empty() {
+        return Err("No primary key values provided".into());
+    }
+
+    let mut pk_cols: Vec<&String> = pk.keys().collect();
+    pk_cols.sort();
+
+    for (i, col) in pk_cols.into_iter().enumerate() {
+        qb.push(if i == 0 { " WHERE " } else { " AND " });
+        qb.push(format!("{} = ", quote_identifier(col, '`')));
+        match &pk[col] {
             serde_json::Value::Number(n) => {
                 if n.is_i64() {
-                    separated.push_bind(n.as_i64());
+                    qb.push_bind(n.as_i64());
+                } else if n.is_u64() {
+                    qb.push_bind(n.as_u64());
+                } else if n.is_f64() {
+                    qb.push_bind(n.as_f64());
                 } else {
-                    separated.push_bind(n.as_f64());
+                    qb.push_bind(n.to_string());
                 }
             }
             serde_json::Value::String(s) => {
-                separated.push_bind(s);
-            }
-            serde_json::Value::Bool(b) => {
-                separated.push_bind(b);
+                qb.push_bind(s.clone());
             }
-            serde_json::Value::Null => {
-                separated.push("NULL");
-            }
-            _ => return Err("Unsupported value type".into()),
+            _ => return Err("Unsupported PK type".into()),
         }
     }
-    separated.push_unseparated(")");
 
-    let query = qb.build();
-    let result = query.execute(&pool).await.map_err(|e| e.to_string())?;
-    Ok(result.rows_affected())
+    Ok(())
 }
 
-/// Extracts ORDER BY clause from a SQL query (case-insensitive)
-fn extract_order_by(query: &str) -> String {
-    let query_upper = query.to_uppercase();
-    if let Some(pos) = query_upper.rfind("ORDER BY") {
-        query[pos..].trim().to_string()
-    } else {
-        String::new()
+/// The DELETE run by `delete_record`
+fn delete_statement(
+    table: &str,
+    pk: &HashMap<String, serde_json::Value>,
+) -> Result<sqlx::QueryBuilder<'static, sqlx::MySql>, String> {
+    let mut qb = sqlx::QueryBuilder::new(format!("DELETE FROM {}", quote_identifier(table, '`')));
+    push_pk_filter(&mut qb, pk)?;
+    Ok(qb)
+}
+
+pub async fn delete_record(
+    params: &ConnectionParams,
+    table: &str,
+    pk: &HashMap<String, serde_json::Value>,
+) -> Result<u64, String> {
+    let pool = get_mysql_pool(params).await?;
+    let mut qb = delete_statement(table, pk)?;
+
+    statement_log::log_statement(params, qb.sql(), &sorted_bindings(pk));
+    let query = qb.build();
+    let result = query.execute(&pool).await.map_err(|e| e.to_string())?;
+    Ok(result.rows_affected())
+}
+
+/// The statement `delete_record` would run, without running it
+pub async fn delete_record_sql(
+    _params: &ConnectionParams,
+    table: &str,
+    pk: &HashMap<String, serde_json::Value>,
+) -> Result<StatementPreview, String> {
+    Ok(StatementPreview {
+        sql: delete_statement(table, pk)?.into_sql(),
+        bindings: sorted_bindings(pk),
+    })
+}
+
+/// `WHERE` clause of `delete_by_match`: `col IS NULL` for JSON `null`, otherwise
+/// `col = value` bound as `push_value` binds it
+fn push_match_filter(
+    qb: &mut sqlx::QueryBuilder<'_, sqlx::MySql>,
+    conditions: &HashMap<String, serde_json::Value>,
+) -> Result<(), String> {
+    if conditions.is_empty() {
+        return Err("No values to match provided".into());
+    }
+
+    for (i, (col, val)) in sorted_bindings(conditions).into_iter().enumerate() {
+        qb.push(if i == 0 { " WHERE " } else { " AND " });
+        let col = quote_identifier(&col, '`');
+        if val.is_null() {
+            qb.push(format!("{} IS NULL", col));
+        } else {
+            qb.push(format!("{} = ", col));
+            push_value(&mut qb.separated(""), val)
+                .map_err(|e| format!("{} for column {}", e, col))?;
+        }
+    }
+    Ok(())
+}
+
+/// Deletes the rows matching `conditions` in a transaction that is only committed
+/// when the row count passes `check_match_count`
+pub async fn delete_by_match(
+    params: &ConnectionParams,
+    table: &str,
+    conditions: &HashMap<String, serde_json::Value>,
+    allow_multiple: bool,
+) -> Result<u64, String> {
+    let pool = get_mysql_pool(params).await?;
+    let mut qb = sqlx::QueryBuilder::new(format!("DELETE FROM {}", quote_identifier(table, '`')));
+    push_match_filter(&mut qb, conditions)?;
+
+    statement_log::log_statement(params, qb.sql(), &match_bindings(conditions));
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let result = qb
+        .build()
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    // Returning early drops the transaction, which rolls the delete back
+    check_match_count(result.rows_affected(), allow_multiple)?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(result.rows_affected())
+}
+
+/// The UPDATE run by `update_record`
+fn update_statement(
+    table: &str,
+    pk: &HashMap<String, serde_json::Value>,
+    col_name: &str,
+    new_val: serde_json::Value,
+) -> Result<sqlx::QueryBuilder<'static, sqlx::MySql>, String> {
+    let mut qb = sqlx::QueryBuilder::new(format!(
+        "UPDATE {} SET {} = ",
+        quote_identifier(table, '`'),
+        quote_identifier(col_name, '`')
+    ));
+
+    match new_val {
+        serde_json::Value::Number(n) => {
+            if n.is_i64() {
+                qb.push_bind(n.as_i64());
+            } else if n.is_u64() {
+                qb.push_bind(n.as_u64());
+            } else {
+                qb.push_bind(n.as_f64());
+            }
+        }
+        serde_json::Value::String(s) => {
+            qb.push_bind(s);
+        }
+        serde_json::Value::Bool(b) => {
+            qb.push_bind(b);
+        }
+        serde_json::Value::Null => {
+            qb.push("NULL");
+        }
+        _ => return Err("Unsupported Value type".into()),
+    }
+
+    push_pk_filter(&mut qb, pk)?;
+    Ok(qb)
+}
+
+/// Sets one column of the rows matching `pk`. JSON `null` writes SQL NULL, while a
+/// string is bound as-is, so `""` stores an empty string rather than NULL
+pub async fn update_record(
+    params: &ConnectionParams,
+    table: &str,
+    pk: &HashMap<String, serde_json::Value>,
+    col_name: &str,
+    new_val: serde_json::Value,
+) -> Result<u64, String> {
+    let pool = get_mysql_pool(params).await?;
+    let bindings = update_bindings(pk, col_name, &new_val);
+    let mut qb = update_statement(table, pk, col_name, new_val)?;
+
+    statement_log::log_statement(params, qb.sql(), &bindings);
+    let query = qb.build();
+    let result = query.execute(&pool).await.map_err(|e| e.to_string())?;
+    Ok(result.rows_affected())
+}
+
+/// The statement `update_record` would run, without running it
+pub async fn update_record_sql(
+    _params: &ConnectionParams,
+    table: &str,
+    pk: &HashMap<String, serde_json::Value>,
+    col_name: &str,
+    new_val: serde_json::Value,
+) -> Result<StatementPreview, String> {
+    Ok(StatementPreview {
+        bindings: update_bindings(pk, col_name, &new_val),
+        sql: update_statement(table, pk, col_name, new_val)?.into_sql(),
+    })
+}
+
+/// Runs `edits` in order in one transaction, rolling back on the first failure
+pub async fn update_records(
+    params: &ConnectionParams,
+    edits: &[RecordEdit],
+) -> Result<BatchEditResult, String> {
+    let pool = get_mysql_pool(params).await?;
+    run_record_edits(params, &pool, edits, |edit| {
+        update_statement(&edit.table, &edit.pk, &edit.column, edit.value.clone())
+    })
+    .await
+}
+
+/// Binds a JSON value as the next item of a separated list (NULL is written inline)
+fn push_value<'args, Sep: std::fmt::Display>(
+    separated: &mut Separated<'_, 'args, sqlx::MySql, Sep>,
+    val: serde_json::Value,
+) -> Result<(), String> {
+    match val {
+        serde_json::Value::Number(n) => {
+            if n.is_i64() {
+                separated.push_bind(n.as_i64());
+            } else if n.is_u64() {
+                separated.push_bind(n.as_u64());
+            } else {
+                separated.push_bind(n.as_f64());
+            }
+        }
+        serde_json::Value::String(s) => {
+            separated.push_bind(s);
+        }
+        serde_json::Value::Bool(b) => {
+            separated.push_bind(b);
+        }
+        serde_json::Value::Null => {
+            separated.push("NULL");
+        }
+        _ => return Err("Unsupported value type".into()),
+    }
+    Ok(())
+}
+
+/// The INSERT run by `insert_record`, with columns in `bindings` order
+fn insert_statement(
+    table: &str,
+    bindings: &[(String, serde_json::Value)],
+) -> Result<sqlx::QueryBuilder<'static, sqlx::MySql>, String> {
+    if bindings.is_empty() {
+        return Err("No data to insert".into());
+    }
+
+    let cols: Vec<String> = bindings
+        .iter()
+        .map(|(k, _)| quote_identifier(k, '`'))
+        .collect();
+    let mut qb = sqlx::QueryBuilder::new(format!(
+        "INSERT INTO {} ({}) VALUES (",
+        quote_identifier(table, '`'),
+        cols.join(", ")
+    ));
+
+    let mut separated = qb.separated(", ");
+    for (_, val) in bindings {
+        push_value(&mut separated, val.clone())?;
+    }
+    separated.push_unseparated(")");
+    Ok(qb)
+}
+
+pub async fn insert_record(
+    params: &ConnectionParams,
+    table: &str,
+    data: std::collections::HashMap<String, serde_json::Value>,
+) -> Result<u64, String> {
+    let pool = get_mysql_pool(params).await?;
+    let bindings = sorted_bindings(&data);
+    let mut qb = insert_statement(table, &bindings)?;
+
+    statement_log::log_statement(params, qb.sql(), &bindings);
+    let query = qb.build();
+    let result = query.execute(&pool).await.map_err(|e| e.to_string())?;
+    Ok(result.rows_affected())
+}
+
+/// The statement `insert_record` would run, without running it
+pub async fn insert_record_sql(
+    _params: &ConnectionParams,
+    table: &str,
+    data: std::collections::HashMap<String, serde_json::Value>,
+) -> Result<StatementPreview, String> {
+    let bindings = sorted_bindings(&data);
+    Ok(StatementPreview {
+        sql: insert_statement(table, &bindings)?.into_sql(),
+        bindings,
+    })
+}
+
+/// Inserts many rows in one transaction, as multi-row INSERTs of up to 1000 rows.
+/// Columns are the union over all rows; a row missing a column gets NULL.
+pub async fn insert_records(
+    params: &ConnectionParams,
+    table: &str,
+    rows: Vec<HashMap<String, serde_json::Value>>,
+) -> Result<u64, String> {
+    let cols = union_columns(&rows);
+    if cols.is_empty() {
+        return Err("No data to insert".into());
+    }
+    let quoted_cols: Vec<String> = cols.iter().map(|c| quote_identifier(c, '`')).collect();
+
+    let pool = get_mysql_pool(params).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut affected_rows = 0;
+
+    for chunk in rows.chunks(insert_chunk_size(cols.len())) {
+        let mut logged_bindings = Vec::new();
+        let mut qb = sqlx::QueryBuilder::new(format!(
+            "INSERT INTO {} ({}) VALUES ",
+            quote_identifier(table, '`'),
+            quoted_cols.join(", ")
+        ));
+
+        for (i, row) in chunk.iter().enumerate() {
+            if i > 0 {
+                qb.push(", ");
+            }
+            qb.push("(");
+            let mut separated = qb.separated(", ");
+            for col in &cols {
+                let val = row.get(col).cloned().unwrap_or(serde_json::Value::Null);
+                logged_bindings.push((col.clone(), val.clone()));
+                push_value(&mut separated, val)?;
+            }
+            separated.push_unseparated(")");
+        }
+
+        statement_log::log_statement(params, qb.sql(), &logged_bindings);
+        let result = qb
+            .build()
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        affected_rows += result.rows_affected();
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(affected_rows)
+}
+
+/// Inserts the row, or updates its other columns when it collides with an existing
+/// row on the primary key or any unique key (MySQL doesn't take the conflict target;
+/// `conflict_cols` are still required to have values). Affected rows is 1 for an
+/// insert and 2 for an update.
+pub async fn upsert_record(
+    params: &ConnectionParams,
+    table: &str,
+    conflict_cols: &[String],
+    data: HashMap<String, serde_json::Value>,
+) -> Result<u64, String> {
+    validate_upsert(conflict_cols, &data)?;
+    let pool = get_mysql_pool(params).await?;
+
+    let mut entries: Vec<(String, serde_json::Value)> = data.into_iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    let cols: Vec<String> = entries
+        .iter()
+        .map(|(k, _)| quote_identifier(k, '`'))
+        .collect();
+
+    let mut qb = sqlx::QueryBuilder::new(format!(
+        "INSERT INTO {} ({}) VALUES (",
+        quote_identifier(table, '`'),
+        cols.join(", ")
+    ));
+    let mut separated = qb.separated(", ");
+    for (_, val) in &entries {
+        push_value(&mut separated, val.clone())?;
+    }
+    separated.push_unseparated(")");
+
+    // Conflict columns are reassigned to themselves when there is nothing else to update
+    let updates: Vec<String> = entries
+        .iter()
+        .filter(|(k, _)| !conflict_cols.contains(k))
+        .map(|(k, _)| format!("{0} = VALUES({0})", quote_identifier(k, '`')))
+        .collect();
+    if updates.is_empty() {
+        let col = quote_identifier(&conflict_cols[0], '`');
+        qb.push(format!(" ON DUPLICATE KEY UPDATE {0} = {0}", col));
+    } else {
+        qb.push(format!(" ON DUPLICATE KEY UPDATE {}", updates.join(", ")));
+    }
+
+    statement_log::log_statement(params, qb.sql(), &entries);
+    let result = qb.build().execute(&pool).await.map_err(|e| e.to_string())?;
+    Ok(result.rows_affected())
+}
+
+/// Extracts ORDER BY clause from a SQL query (case-insensitive)
+fn extract_order_by(query: &str) -> String {
+    let query_upper = query.to_uppercase();
+    if let Some(pos) = query_upper.rfind("ORDER BY") {
+        query[pos..].trim().to_string()
+    } else {
+        String::new()
     }
 }
 
@@ -411,7 +1337,7 @@ pub async fn get_table_ddl(
     table_name: &str,
 ) -> Result<String, String> {
     let pool = get_mysql_pool(params).await?;
-    let query = format!("SHOW CREATE TABLE `{}`", table_name);
+    let query = format!("SHOW CREATE TABLE {}", quote_identifier(table_name, '`'));
     let row = sqlx::query(&query)
         .fetch_one(&pool)
         .await
@@ -421,24 +1347,192 @@ pub async fn get_table_ddl(
     Ok(format!("{};", create_sql))
 }
 
+/// With `exact` unset the count is InnoDB's `table_rows` estimate, which can be
+/// off by a wide margin. Views have no estimate and are always counted.
+pub async fn get_table_row_count(
+    params: &ConnectionParams,
+    table_name: &str,
+    exact: bool,
+) -> Result<TableRowCount, String> {
+    let pool = get_mysql_pool(params).await?;
+    if !exact {
+        let estimate: Option<Option<i64>> = sqlx::query_scalar(
+            "SELECT CAST(table_rows AS SIGNED) FROM information_schema.tables \
+             WHERE table_schema = DATABASE() AND table_name = ?",
+        )
+        .bind(table_name)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        if let Some(Some(count)) = estimate {
+            return Ok(TableRowCount {
+                count,
+                estimated: true,
+            });
+        }
+    }
+
+    let query = format!("SELECT COUNT(*) FROM {}", quote_identifier(table_name, '`'));
+    let count: i64 = sqlx::query_scalar(&query)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(TableRowCount {
+        count,
+        estimated: false,
+    })
+}
+
+pub async fn explain_query(
+    params: &ConnectionParams,
+    query: &str,
+) -> Result<serde_json::Value, String> {
+    let pool = get_mysql_pool(params).await?;
+    let row = sqlx::query(&format!("EXPLAIN FORMAT=JSON {}", query))
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    let plan: String = row.try_get(0).map_err(|e| e.to_string())?;
+    serde_json::from_str(&plan).map_err(|e| e.to_string())
+}
+
+/// Runs the statements in one transaction and returns each statement's affected
+/// row count. The first failure rolls everything back, except for DDL, which MySQL
+/// commits implicitly.
+pub async fn execute_script(
+    params: &ConnectionParams,
+    statements: &[&str],
+) -> Result<Vec<u64>, String> {
+    let pool = get_mysql_pool(params).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut affected_rows = Vec::with_capacity(statements.len());
+
+    for (i, statement) in statements.iter().enumerate() {
+        statement_log::log_statement(params, statement, &[]);
+        match sqlx::query(statement).execute(&mut *tx).await {
+            Ok(result) => affected_rows.push(result.rows_affected()),
+            Err(e) => {
+                tx.rollback().await.map_err(|e| e.to_string())?;
+                return Err(script_error(i, statement, e));
+            }
+        }
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(affected_rows)
+}
+
+impl SqlxDatabase for sqlx::MySql {
+    fn rows_affected(result: &sqlx::mysql::MySqlQueryResult) -> u64 {
+        result.rows_affected()
+    }
+
+    fn execute_builder<'c>(
+        conn: &'c mut sqlx::MySqlConnection,
+        mut qb: sqlx::QueryBuilder<'static, Self>,
+    ) -> BoxFuture<'c, Result<u64, String>> {
+        Box::pin(async move {
+            let result = qb.build().execute(conn).await.map_err(|e| e.to_string())?;
+            Ok(result.rows_affected())
+        })
+    }
+
+    /// MySQL has unsigned columns, so integers beyond i64 are bound as u64
+    fn bind_number<'q>(query: SqlxQuery<'q, Self>, n: &serde_json::Number) -> SqlxQuery<'q, Self>
+    where
+        i64: sqlx::Encode<'q, Self> + sqlx::Type<Self>,
+        f64: sqlx::Encode<'q, Self> + sqlx::Type<Self>,
+    {
+        if let Some(i) = n.as_i64() {
+            query.bind(i)
+        } else if let Some(u) = n.as_u64() {
+            query.bind(u)
+        } else {
+            query.bind(n.as_f64().unwrap_or_default())
+        }
+    }
+}
+
+/// Runs `query` with `values` bound to its (driver-native) placeholders. Row-returning
+/// statements stop after `limit` rows and set `truncated`.
+pub async fn execute_query_with_params(
+    params: &ConnectionParams,
+    query: &str,
+    values: &[serde_json::Value],
+    limit: Option<u32>,
+) -> Result<QueryResult, String> {
+    let pool = get_mysql_pool(params).await?;
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    execute_with_json_params(params, &mut conn, query, values, limit, extract_mysql_value).await
+}
+
+/// Runs `query` as plain text, so it may be a CALL or several statements separated
+/// by `;`, and returns all of its result sets, each capped by `default_row_limit`
+pub async fn execute_query_sets(
+    params: &ConnectionParams,
+    query: &str,
+) -> Result<Vec<QueryResult>, String> {
+    statement_log::log_statement(params, query, &[]);
+    let pool = get_mysql_pool(params).await?;
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    let connection_id = timeout_connection_id(params, &mut conn).await?;
+    let fetch = fetch_result_sets(&mut conn, sqlx::raw_sql(query), params.default_row_limit);
+    with_kill_timeout(params, &pool, connection_id, fetch).await
+}
+
 pub async fn execute_query(
     params: &ConnectionParams,
     query: &str,
     limit: Option<u32>,
     page: u32,
+    offset: Option<u32>,
+    on_batch: Option<RowBatchCallback<'_>>,
 ) -> Result<QueryResult, String> {
+    statement_log::log_statement(params, query, &[]);
+
     let pool = get_mysql_pool(params).await?;
     let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
 
+    // A procedure can return several result sets; the grid shows the first
+    if first_keyword(query) == "CALL" {
+        let connection_id = timeout_connection_id(params, &mut conn).await?;
+        let fetch = fetch_first_result_set(params, &mut conn, query, limit, page, offset, on_batch);
+        return with_kill_timeout(params, &pool, connection_id, fetch).await;
+    }
+    let started = Instant::now();
+
+    // INSERT/UPDATE/DELETE/DDL: run with execute() so the affected row count is reported
+    if !returns_rows(query) {
+        let result = sqlx::query(query)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| e.to_string())?;
+        return Ok(QueryResult {
+            columns: Vec::new(),
+            column_types: Vec::new(),
+            rows: Vec::new(),
+            affected_rows: result.rows_affected(),
+            truncated: false,
+            pagination: None,
+            has_more: false,
+            column_refs: None,
+            execution_ms: started.elapsed().as_millis() as u64,
+            row_count: None,
+            auto_limit: None,
+        });
+    }
+
     let is_select = query.trim_start().to_uppercase().starts_with("SELECT");
     let mut pagination: Option<Pagination> = None;
     let final_query: String;
     let mut manual_limit = limit;
+    let mut auto_limit = None;
     let mut truncated = false;
 
-    if is_select && limit.is_some() {
+    // Queries that already carry their own LIMIT are run as-is (capped by manual_limit)
+    if is_select && limit.is_some() && !has_limit_clause(query) {
         let l = limit.unwrap();
-        let offset = (page - 1) * l;
+        let offset = offset.unwrap_or_else(|| calculate_offset(page, l));
 
         // Count total rows
         let count_q = format!("SELECT COUNT(*) FROM ({}) as count_wrapper", query);
@@ -463,31 +1557,43 @@ pub async fn execute_query(
         // Extract ORDER BY clause from the original query to preserve sorting
         let order_by_clause = extract_order_by(query);
 
+        // LIMIT l + 1: the extra row (trimmed below) tells whether another page exists
         if !order_by_clause.is_empty() {
             // Remove ORDER BY from inner query and add it to outer query
             let query_without_order = remove_order_by(query);
             final_query = format!(
                 "SELECT * FROM ({}) as data_wrapper {} LIMIT {} OFFSET {}",
-                query_without_order, order_by_clause, l, offset
+                query_without_order, order_by_clause, l + 1, offset
             );
         } else {
             // Wrap query for pagination
             final_query = format!(
                 "SELECT * FROM ({}) as data_wrapper LIMIT {} OFFSET {}",
-                query, l, offset
+                query, l + 1, offset
             );
         }
 
         manual_limit = None; // Disable manual limit since SQL handles it
+    } else if let Some(limited) = auto_limit_query(query, params.default_row_limit) {
+        // A bare SELECT under the connection's auto-limit: the row fetched past it
+        // stops the read and shows the result was cut
+        final_query = limited;
+        manual_limit = params.default_row_limit;
+        auto_limit = params.default_row_limit;
     } else {
         final_query = query.to_string();
     }
 
+    // Paginated results are already bounded by the page size, so only unbounded
+    // queries are streamed to the caller in batches
+    let on_batch = if pagination.is_some() { None } else { on_batch };
+    let mut buffer = RowBuffer::new(on_batch);
+
     // Use fetch instead of fetch_all to support streaming/limit
     let mut rows_stream = sqlx::query(&final_query).fetch(&mut *conn);
 
     let mut columns: Vec<String> = Vec::new();
-    let mut json_rows = Vec::new();
+    let mut column_types: Vec<String> = Vec::new();
 
     use futures::stream::StreamExt; // Correct import
 
@@ -497,11 +1603,12 @@ pub async fn execute_query(
                 // Initialize columns from the first row
                 if columns.is_empty() {
                     columns = row.columns().iter().map(|c| c.name().to_string()).collect();
+                    column_types = column_type_names(&row);
                 }
 
                 // Check limit (only if manual_limit is set)
                 if let Some(l) = manual_limit {
-                    if json_rows.len() >= l as usize {
+                    if buffer.row_count() >= l as usize {
                         truncated = true;
                         break;
                     }
@@ -513,21 +1620,359 @@ pub async fn execute_query(
                     let val = extract_mysql_value(&row, i);
                     json_row.push(val);
                 }
-                json_rows.push(json_row);
+                if !buffer.push(&columns, json_row) {
+                    truncated = true;
+                    break;
+                }
             }
             Err(e) => return Err(e.to_string()),
         }
     }
 
+    drop(rows_stream);
+
+    // No row to read the columns from: ask the database what the statement returns
+    if columns.is_empty() {
+        if let Ok(describe) = conn.describe(&final_query).await {
+            (columns, column_types) = described_columns(&describe);
+        }
+    }
+
+    let fetched = buffer.row_count();
+    let mut json_rows = buffer.finish(&columns);
+
+    let has_more = match &pagination {
+        Some(p) if json_rows.len() > p.page_size as usize => {
+            json_rows.truncate(p.page_size as usize);
+            true
+        }
+        Some(_) => false,
+        None => truncated,
+    };
+
+    // Streamed rows went to `on_batch` and are not part of the result
+    let row_count = if on_batch.is_some() {
+        fetched
+    } else {
+        json_rows.len()
+    };
     Ok(QueryResult {
         columns,
+        column_types,
         rows: json_rows,
         affected_rows: 0,
         truncated,
         pagination,
+        has_more,
+        column_refs: None,
+        execution_ms: started.elapsed().as_millis() as u64,
+        row_count: Some(row_count),
+        auto_limit: auto_limit.filter(|&n| truncated && row_count >= n as usize),
     })
 }
 
+/// `Driver` for MySQL and MariaDB servers
+pub struct MysqlDriver;
+
+#[async_trait]
+impl Driver for MysqlDriver {
+    async fn get_databases(&self, params: &ConnectionParams) -> Result<Vec<String>, String> {
+        get_databases(params).await
+    }
+
+    async fn get_schemas(&self, params: &ConnectionParams) -> Result<Vec<String>, String> {
+        get_schemas(params).await
+    }
+
+    async fn get_tables(&self, params: &ConnectionParams) -> Result<Vec<TableInfo>, String> {
+        get_tables(params).await
+    }
+
+    async fn get_views(&self, params: &ConnectionParams) -> Result<Vec<ViewInfo>, String> {
+        get_views(params).await
+    }
+
+    async fn get_columns(
+        &self,
+        params: &ConnectionParams,
+        table_name: &str,
+    ) -> Result<Vec<TableColumn>, String> {
+        get_columns(params, table_name).await
+    }
+
+    async fn get_foreign_keys(
+        &self,
+        params: &ConnectionParams,
+        table_name: &str,
+    ) -> Result<Vec<ForeignKey>, String> {
+        get_foreign_keys(params, table_name).await
+    }
+
+    async fn get_all_columns_batch(
+        &self,
+        params: &ConnectionParams,
+        _table_names: &[String],
+    ) -> Result<HashMap<String, Vec<TableColumn>>, String> {
+        get_all_columns_batch(params).await
+    }
+
+    async fn get_all_foreign_keys_batch(
+        &self,
+        params: &ConnectionParams,
+        _table_names: &[String],
+    ) -> Result<HashMap<String, Vec<ForeignKey>>, String> {
+        get_all_foreign_keys_batch(params).await
+    }
+
+    async fn get_indexes(
+        &self,
+        params: &ConnectionParams,
+        table_name: &str,
+    ) -> Result<Vec<Index>, String> {
+        get_indexes(params, table_name).await
+    }
+
+    async fn get_triggers(
+        &self,
+        params: &ConnectionParams,
+        table_name: &str,
+    ) -> Result<Vec<Trigger>, String> {
+        get_triggers(params, table_name).await
+    }
+
+    async fn get_routines(
+        &self,
+        params: &ConnectionParams,
+        schema: Option<&str>,
+    ) -> Result<Vec<RoutineInfo>, String> {
+        get_routines(params, schema).await
+    }
+
+    async fn get_routine_definition(
+        &self,
+        params: &ConnectionParams,
+        schema: Option<&str>,
+        name: &str,
+    ) -> Result<String, String> {
+        get_routine_definition(params, schema, name).await
+    }
+
+    async fn call_procedure(
+        &self,
+        params: &ConnectionParams,
+        name: &str,
+        args: &[serde_json::Value],
+    ) -> Result<ProcedureResult, String> {
+        call_procedure(params, name, args).await
+    }
+
+    async fn create_index(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        name: &str,
+        columns: &[String],
+        unique: bool,
+    ) -> Result<(), String> {
+        create_index(params, table, name, columns, unique).await
+    }
+
+    async fn drop_index(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        name: &str,
+    ) -> Result<(), String> {
+        drop_index(params, table, name).await
+    }
+
+    async fn add_column(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        column: &ColumnDefinition,
+    ) -> Result<(), String> {
+        add_column(params, table, column).await
+    }
+
+    async fn drop_column(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        column: &str,
+    ) -> Result<(), String> {
+        drop_column(params, table, column).await
+    }
+
+    async fn rename_column(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        column: &str,
+        new_name: &str,
+    ) -> Result<(), String> {
+        rename_column(params, table, column, new_name).await
+    }
+
+    async fn delete_record(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        pk: &HashMap<String, serde_json::Value>,
+    ) -> Result<u64, String> {
+        delete_record(params, table, pk).await
+    }
+
+    async fn delete_record_sql(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        pk: &HashMap<String, serde_json::Value>,
+    ) -> Result<StatementPreview, String> {
+        delete_record_sql(params, table, pk).await
+    }
+
+    async fn delete_by_match(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        conditions: &HashMap<String, serde_json::Value>,
+        allow_multiple: bool,
+    ) -> Result<u64, String> {
+        delete_by_match(params, table, conditions, allow_multiple).await
+    }
+
+    async fn update_record(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        pk: &HashMap<String, serde_json::Value>,
+        col_name: &str,
+        new_val: serde_json::Value,
+    ) -> Result<u64, String> {
+        update_record(params, table, pk, col_name, new_val).await
+    }
+
+    async fn update_record_sql(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        pk: &HashMap<String, serde_json::Value>,
+        col_name: &str,
+        new_val: serde_json::Value,
+    ) -> Result<StatementPreview, String> {
+        update_record_sql(params, table, pk, col_name, new_val).await
+    }
+
+    async fn update_records(
+        &self,
+        params: &ConnectionParams,
+        edits: &[RecordEdit],
+    ) -> Result<BatchEditResult, String> {
+        update_records(params, edits).await
+    }
+
+    async fn insert_record(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        data: HashMap<String, serde_json::Value>,
+    ) -> Result<u64, String> {
+        insert_record(params, table, data).await
+    }
+
+    async fn insert_record_sql(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        data: HashMap<String, serde_json::Value>,
+    ) -> Result<StatementPreview, String> {
+        insert_record_sql(params, table, data).await
+    }
+
+    async fn insert_records(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        rows: Vec<HashMap<String, serde_json::Value>>,
+    ) -> Result<u64, String> {
+        insert_records(params, table, rows).await
+    }
+
+    async fn upsert_record(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        conflict_cols: &[String],
+        data: HashMap<String, serde_json::Value>,
+    ) -> Result<u64, String> {
+        upsert_record(params, table, conflict_cols, data).await
+    }
+
+    async fn get_table_ddl(
+        &self,
+        params: &ConnectionParams,
+        table_name: &str,
+    ) -> Result<String, String> {
+        get_table_ddl(params, table_name).await
+    }
+
+    async fn get_table_row_count(
+        &self,
+        params: &ConnectionParams,
+        table_name: &str,
+        exact: bool,
+    ) -> Result<TableRowCount, String> {
+        get_table_row_count(params, table_name, exact).await
+    }
+
+    async fn explain_query(
+        &self,
+        params: &ConnectionParams,
+        query: &str,
+    ) -> Result<serde_json::Value, String> {
+        explain_query(params, query).await
+    }
+
+    async fn execute_script(
+        &self,
+        params: &ConnectionParams,
+        statements: &[&str],
+    ) -> Result<Vec<u64>, String> {
+        execute_script(params, statements).await
+    }
+
+    async fn execute_query_with_params(
+        &self,
+        params: &ConnectionParams,
+        query: &str,
+        values: &[serde_json::Value],
+        limit: Option<u32>,
+    ) -> Result<QueryResult, String> {
+        execute_query_with_params(params, query, values, limit).await
+    }
+
+    async fn execute_query(
+        &self,
+        params: &ConnectionParams,
+        query: &str,
+        limit: Option<u32>,
+        page: u32,
+        offset: Option<u32>,
+        on_batch: Option<RowBatchCallback<'_>>,
+    ) -> Result<QueryResult, String> {
+        execute_query(params, query, limit, page, offset, on_batch).await
+    }
+
+    async fn execute_query_sets(
+        &self,
+        params: &ConnectionParams,
+        query: &str,
+    ) -> Result<Vec<QueryResult>, String> {
+        execute_query_sets(params, query).await
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -605,4 +2050,85 @@ mod tests {
             assert_eq!(remove_order_by(query), expected);
         }
     }
+
+    mod server_flavor {
+        use super::*;
+
+        #[test]
+        fn test_parse_mysql_version() {
+            let v = ServerVersion::parse("8.0.36-0ubuntu0.22.04.1");
+            assert_eq!(v.flavor, ServerFlavor::Mysql);
+            assert_eq!(v.version, (8, 0, 36));
+            assert!(v.supports_rename_column());
+        }
+
+        #[test]
+        fn test_parse_mariadb_version() {
+            let v = ServerVersion::parse("10.11.6-MariaDB-1:10.11.6+maria~ubu2204");
+            assert_eq!(v.flavor, ServerFlavor::Mariadb);
+            assert_eq!(v.version, (10, 11, 6));
+            assert!(v.supports_rename_column());
+        }
+
+        #[test]
+        fn test_parse_mariadb_replication_prefix() {
+            let v = ServerVersion::parse("5.5.5-10.4.32-MariaDB");
+            assert_eq!(v.flavor, ServerFlavor::Mariadb);
+            assert_eq!(v.version, (10, 4, 32));
+            // 10.4 would pass a MySQL 8.0 check if the flavors were compared as one
+            assert!(!v.supports_rename_column());
+        }
+
+        #[test]
+        fn test_statement_timeout_sql_by_flavor() {
+            assert_eq!(
+                ServerVersion::parse("8.0.36").statement_timeout_sql(5),
+                Some("SET SESSION max_execution_time = 5000".to_string())
+            );
+            assert_eq!(
+                ServerVersion::parse("11.4.2-MariaDB").statement_timeout_sql(5),
+                Some("SET SESSION max_statement_time = 5".to_string())
+            );
+            assert_eq!(
+                ServerVersion::parse("5.6.51").statement_timeout_sql(5),
+                None
+            );
+            assert_eq!(
+                ServerVersion::parse("10.0.38-MariaDB").statement_timeout_sql(5),
+                None
+            );
+        }
+
+        #[test]
+        fn test_auto_increment_in_extra() {
+            assert!(is_auto_increment("auto_increment"));
+            assert!(is_auto_increment("AUTO_INCREMENT"));
+            // MariaDB lists every attribute of an invisible key column
+            assert!(is_auto_increment("auto_increment INVISIBLE"));
+            assert!(!is_auto_increment(
+                "DEFAULT_GENERATED on update CURRENT_TIMESTAMP"
+            ));
+            assert!(!is_auto_increment("on update current_timestamp()"));
+            assert!(!is_auto_increment(""));
+        }
+
+        #[test]
+        fn test_enum_values_from_column_type() {
+            assert_eq!(
+                enum_values("enum('draft','published')"),
+                Some(vec!["draft".to_string(), "published".to_string()])
+            );
+            assert_eq!(
+                enum_values("SET('a,b','it''s','back\\\\slash')"),
+                Some(vec![
+                    "a,b".to_string(),
+                    "it's".to_string(),
+                    "back\\slash".to_string()
+                ])
+            );
+            assert_eq!(enum_values("enum('')"), Some(vec![String::new()]));
+            assert_eq!(enum_values("varchar(255)"), None);
+            assert_eq!(enum_values("int unsigned"), None);
+        }
+    }
 }
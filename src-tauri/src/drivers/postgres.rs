@@ -1,9 +1,24 @@
-use crate::drivers::common::extract_postgres_value;
+use crate::drivers::common::{
+    auto_limit_query, calculate_offset, check_match_count, column_definition_sql,
+    column_type_names, create_index_sql, described_columns, execute_with_json_params,
+    extract_postgres_value, has_limit_clause, insert_chunk_size, match_bindings,
+    parse_naive_timestamp, parse_timestamp_tz, postgres_schema, quote_identifier, returns_rows,
+    run_record_edits, script_error, sorted_bindings, union_columns, update_bindings,
+    validate_upsert, Driver, RowBatchCallback, RowBuffer,
+};
 use crate::models::{
-    ConnectionParams, ForeignKey, Index, Pagination, QueryResult, TableColumn, TableInfo,
+    BatchEditResult, ColumnDefinition, ConnectionParams, ForeignKey, Index, Pagination,
+    ProcedureResult, QueryResult, RecordEdit, RoutineInfo, StatementPreview, TableColumn,
+    TableInfo, TableRowCount, Trigger, ViewInfo,
 };
 use crate::pool_manager::get_postgres_pool;
-use sqlx::{Column, Row};
+use crate::statement_log;
+use async_trait::async_trait;
+use sqlx::query_builder::Separated;
+use sqlx::{Column, Executor, Row};
+use std::collections::HashMap;
+use std::time::Instant;
+use uuid::Uuid;
 
 
 pub async fn get_databases(params: &ConnectionParams) -> Result<Vec<String>, String> {
@@ -18,11 +33,27 @@ pub async fn get_databases(params: &ConnectionParams) -> Result<Vec<String>, Str
         .collect())
 }
 
+/// Schemas the user has any privilege on, without the system catalogs
+pub async fn get_schemas(params: &ConnectionParams) -> Result<Vec<String>, String> {
+    let pool = get_postgres_pool(params).await?;
+    let rows = sqlx::query(
+        "SELECT schema_name::text FROM information_schema.schemata WHERE schema_name <> 'information_schema' AND schema_name NOT LIKE 'pg\\_%' ORDER BY schema_name",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(rows
+        .iter()
+        .map(|r| r.try_get(0).unwrap_or_default())
+        .collect())
+}
+
 pub async fn get_tables(params: &ConnectionParams) -> Result<Vec<TableInfo>, String> {
     let pool = get_postgres_pool(params).await?;
     let rows = sqlx::query(
-        "SELECT table_name as name FROM information_schema.tables WHERE table_schema = 'public' ORDER BY table_name ASC",
+        "SELECT table_name as name FROM information_schema.tables WHERE table_schema = $1 AND table_type = 'BASE TABLE' ORDER BY table_name ASC",
     )
+    .bind(postgres_schema(params))
     .fetch_all(&pool)
     .await
     .map_err(|e| e.to_string())?;
@@ -34,6 +65,24 @@ pub async fn get_tables(params: &ConnectionParams) -> Result<Vec<TableInfo>, Str
         .collect())
 }
 
+pub async fn get_views(params: &ConnectionParams) -> Result<Vec<ViewInfo>, String> {
+    let pool = get_postgres_pool(params).await?;
+    let rows = sqlx::query(
+        "SELECT viewname as name, definition FROM pg_views WHERE schemaname = $1 ORDER BY viewname ASC",
+    )
+    .bind(postgres_schema(params))
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(rows
+        .iter()
+        .map(|r| ViewInfo {
+            name: r.try_get("name").unwrap_or_default(),
+            definition: r.try_get("definition").ok().flatten(),
+        })
+        .collect())
+}
+
 pub async fn get_columns(
     params: &ConnectionParams,
     table_name: &str,
@@ -51,15 +100,21 @@ pub async fn get_columns(
             (SELECT COUNT(*) FROM information_schema.table_constraints tc
              JOIN information_schema.key_column_usage kcu ON tc.constraint_name = kcu.constraint_name
              WHERE tc.constraint_type = 'PRIMARY KEY'
+             AND kcu.table_schema = c.table_schema
              AND kcu.table_name = c.table_name
-             AND kcu.column_name = c.column_name) > 0 as is_pk
+             AND kcu.column_name = c.column_name) > 0 as is_pk,
+            col_description(
+                format('%I.%I', c.table_schema, c.table_name)::regclass,
+                c.ordinal_position::int
+            ) as column_comment
         FROM information_schema.columns c
-        WHERE c.table_schema = 'public' AND c.table_name = $1
+        WHERE c.table_schema = $2 AND c.table_name = $1
         ORDER BY c.ordinal_position
     "#;
 
     let rows = sqlx::query(query)
         .bind(table_name)
+        .bind(postgres_schema(params))
         .fetch_all(&pool)
         .await
         .map_err(|e| e.to_string())?;
@@ -69,10 +124,13 @@ pub async fn get_columns(
         .map(|r| {
             let null_str: String = r.try_get("is_nullable").unwrap_or_default();
             let is_pk: i64 = r.try_get("is_pk").unwrap_or(0);
-            let default_val: String = r.try_get("column_default").unwrap_or_default();
+            let default_val: Option<String> = r.try_get("column_default").unwrap_or_default();
             let is_identity: String = r.try_get("is_identity").unwrap_or_default(); // YES/NO
 
-            let is_auto = is_identity == "YES" || default_val.contains("nextval");
+            let is_auto = is_identity == "YES"
+                || default_val
+                    .as_deref()
+                    .is_some_and(|d| d.contains("nextval"));
 
             TableColumn {
                 name: r.try_get("column_name").unwrap_or_default(),
@@ -80,6 +138,9 @@ pub async fn get_columns(
                 is_pk: is_pk > 0,
                 is_nullable: null_str == "YES",
                 is_auto_increment: is_auto,
+                enum_values: None,
+                default_value: default_val,
+                comment: r.try_get("column_comment").unwrap_or_default(),
             }
         })
         .collect())
@@ -111,10 +172,12 @@ pub async fn get_foreign_keys(
             ON rc.constraint_name = tc.constraint_name
         WHERE tc.constraint_type = 'FOREIGN KEY'
         AND tc.table_name = $1
+        AND tc.table_schema = $2
     "#;
 
     let rows = sqlx::query(query)
         .bind(table_name)
+        .bind(postgres_schema(params))
         .fetch_all(&pool)
         .await
         .map_err(|e| e.to_string())?;
@@ -150,14 +213,20 @@ pub async fn get_all_columns_batch(
             (SELECT COUNT(*) FROM information_schema.table_constraints tc
              JOIN information_schema.key_column_usage kcu ON tc.constraint_name = kcu.constraint_name
              WHERE tc.constraint_type = 'PRIMARY KEY'
+             AND kcu.table_schema = c.table_schema
              AND kcu.table_name = c.table_name
-             AND kcu.column_name = c.column_name) > 0 as is_pk
+             AND kcu.column_name = c.column_name) > 0 as is_pk,
+            col_description(
+                format('%I.%I', c.table_schema, c.table_name)::regclass,
+                c.ordinal_position::int
+            ) as column_comment
         FROM information_schema.columns c
-        WHERE c.table_schema = 'public'
+        WHERE c.table_schema = $1
         ORDER BY c.table_name, c.ordinal_position
     "#;
 
     let rows = sqlx::query(query)
+        .bind(postgres_schema(params))
         .fetch_all(&pool)
         .await
         .map_err(|e| e.to_string())?;
@@ -168,10 +237,13 @@ pub async fn get_all_columns_batch(
         let table_name: String = row.try_get("table_name").unwrap_or_default();
         let null_str: String = row.try_get("is_nullable").unwrap_or_default();
         let is_pk: i64 = row.try_get("is_pk").unwrap_or(0);
-        let default_val: String = row.try_get("column_default").unwrap_or_default();
+        let default_val: Option<String> = row.try_get("column_default").unwrap_or_default();
         let is_identity: String = row.try_get("is_identity").unwrap_or_default();
 
-        let is_auto = is_identity == "YES" || default_val.contains("nextval");
+        let is_auto = is_identity == "YES"
+            || default_val
+                .as_deref()
+                .is_some_and(|d| d.contains("nextval"));
 
         let column = TableColumn {
             name: row.try_get("column_name").unwrap_or_default(),
@@ -179,6 +251,9 @@ pub async fn get_all_columns_batch(
             is_pk: is_pk > 0,
             is_nullable: null_str == "YES",
             is_auto_increment: is_auto,
+            enum_values: None,
+            default_value: default_val,
+            comment: row.try_get("column_comment").unwrap_or_default(),
         };
 
         result.entry(table_name).or_insert_with(Vec::new).push(column);
@@ -214,10 +289,11 @@ pub async fn get_all_foreign_keys_batch(
             JOIN information_schema.referential_constraints AS rc
             ON rc.constraint_name = tc.constraint_name
         WHERE tc.constraint_type = 'FOREIGN KEY'
-        AND tc.table_schema = 'public'
+        AND tc.table_schema = $1
     "#;
 
     let rows = sqlx::query(query)
+        .bind(postgres_schema(params))
         .fetch_all(&pool)
         .await
         .map_err(|e| e.to_string())?;
@@ -291,141 +367,889 @@ pub async fn get_indexes(
         .collect())
 }
 
-pub async fn delete_record(
+/// Triggers on `table_name`, one entry per event. Internal triggers, such as the
+/// ones enforcing foreign keys, are left out.
+pub async fn get_triggers(
     params: &ConnectionParams,
-    table: &str,
-    pk_col: &str,
-    pk_val: serde_json::Value,
-) -> Result<u64, String> {
+    table_name: &str,
+) -> Result<Vec<Trigger>, String> {
     let pool = get_postgres_pool(params).await?;
 
-    let query = format!("DELETE FROM \"{}\" WHERE \"{}\" = $1", table, pk_col);
+    let query = r#"
+        SELECT
+            t.trigger_name,
+            t.action_timing,
+            t.event_manipulation,
+            t.action_statement,
+            pt.tgenabled <> 'D' as is_enabled
+        FROM information_schema.triggers t
+        JOIN pg_catalog.pg_trigger pt
+            ON pt.tgname = t.trigger_name
+            AND pt.tgrelid = format('%I.%I', t.event_object_schema, t.event_object_table)::regclass
+            AND NOT pt.tgisinternal
+        WHERE t.event_object_schema = $2 AND t.event_object_table = $1
+        ORDER BY t.trigger_name, t.event_manipulation
+    "#;
 
-    let result = match pk_val {
-        serde_json::Value::Number(n) => {
-            if n.is_i64() {
-                sqlx::query(&query)
-                    .bind(n.as_i64())
-                    .execute(&pool)
-                    .await
-            } else {
-                sqlx::query(&query)
-                    .bind(n.as_f64())
-                    .execute(&pool)
-                    .await
+    let rows = sqlx::query(query)
+        .bind(table_name)
+        .bind(postgres_schema(params))
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .iter()
+        .map(|r| Trigger {
+            name: r.try_get("trigger_name").unwrap_or_default(),
+            timing: r.try_get("action_timing").unwrap_or_default(),
+            event: r.try_get("event_manipulation").unwrap_or_default(),
+            statement: r.try_get("action_statement").unwrap_or_default(),
+            is_enabled: r.try_get("is_enabled").ok(),
+        })
+        .collect())
+}
+
+/// Functions and procedures in `schema` (the connection's schema by default).
+/// Overloads are listed once per signature.
+pub async fn get_routines(
+    params: &ConnectionParams,
+    schema: Option<&str>,
+) -> Result<Vec<RoutineInfo>, String> {
+    let pool = get_postgres_pool(params).await?;
+
+    let query = r#"
+        SELECT
+            routine_name,
+            routine_type,
+            CASE WHEN data_type = 'USER-DEFINED' THEN type_udt_name ELSE data_type END
+                as return_type,
+            routine_definition
+        FROM information_schema.routines
+        WHERE routine_schema = $1 AND routine_type IS NOT NULL
+        ORDER BY routine_name
+    "#;
+
+    let rows = sqlx::query(query)
+        .bind(schema.unwrap_or(postgres_schema(params)))
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .iter()
+        .map(|r| RoutineInfo {
+            name: r.try_get("routine_name").unwrap_or_default(),
+            routine_type: r.try_get("routine_type").unwrap_or_default(),
+            return_type: r.try_get("return_type").ok().flatten(),
+            definition: r.try_get("routine_definition").ok().flatten(),
+        })
+        .collect())
+}
+
+/// `CREATE OR REPLACE` statement of the function or procedure `name`, one per
+/// overload
+pub async fn get_routine_definition(
+    params: &ConnectionParams,
+    schema: Option<&str>,
+    name: &str,
+) -> Result<String, String> {
+    let pool = get_postgres_pool(params).await?;
+
+    let query = r#"
+        SELECT pg_get_functiondef(p.oid)
+        FROM pg_catalog.pg_proc p
+        JOIN pg_catalog.pg_namespace n ON n.oid = p.pronamespace
+        WHERE n.nspname = $1 AND p.proname = $2 AND p.prokind IN ('f', 'p')
+        ORDER BY p.oid
+    "#;
+
+    let definitions: Vec<String> = sqlx::query_scalar(query)
+        .bind(schema.unwrap_or(postgres_schema(params)))
+        .bind(name)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if definitions.is_empty() {
+        return Err(format!("Routine '{}' not found", name));
+    }
+    Ok(definitions.join("\n"))
+}
+
+/// Calls the procedure or function `name` in the connection's schema, with `args`
+/// bound to its IN and INOUT parameters in order. Procedures run with CALL (OUT
+/// parameters get NULL, and the INOUT/OUT values come back as the result row);
+/// functions with `SELECT * FROM`, so set-returning ones yield all their rows.
+/// Each argument is cast to its parameter's type, as Postgres does not resolve
+/// function calls by assignment casts.
+pub async fn call_procedure(
+    params: &ConnectionParams,
+    name: &str,
+    args: &[serde_json::Value],
+) -> Result<ProcedureResult, String> {
+    let pool = get_postgres_pool(params).await?;
+    let schema = postgres_schema(params);
+
+    let query = r#"
+        SELECT
+            r.specific_name::text,
+            r.routine_type::text,
+            p.parameter_mode::text,
+            p.parameter_name::text,
+            p.udt_name::text,
+            format('%I.%I', p.udt_schema, p.udt_name) as cast_type
+        FROM information_schema.routines r
+        LEFT JOIN information_schema.parameters p
+            ON p.specific_schema = r.specific_schema AND p.specific_name = r.specific_name
+        WHERE r.routine_schema = $1 AND r.routine_name = $2
+        ORDER BY r.specific_name, p.ordinal_position
+    "#;
+    let rows = sqlx::query(query)
+        .bind(schema)
+        .bind(name)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    if rows.is_empty() {
+        return Err(format!("Routine '{}' not found", name));
+    }
+
+    // One entry per overload: (specific name, routine type, parameters)
+    let mut overloads: Vec<(String, String, Vec<&sqlx::postgres::PgRow>)> = Vec::new();
+    for row in &rows {
+        let specific: String = row.try_get("specific_name").unwrap_or_default();
+        if overloads.last().map(|(s, _, _)| s) != Some(&specific) {
+            let routine_type = row.try_get("routine_type").unwrap_or_default();
+            overloads.push((specific, routine_type, Vec::new()));
+        }
+        // A routine without parameters has one row with NULL parameter columns
+        let parameter_mode: Option<String> = row.try_get("parameter_mode").unwrap_or_default();
+        if parameter_mode.is_some() {
+            if let Some((_, _, parameters)) = overloads.last_mut() {
+                parameters.push(row);
             }
         }
-        serde_json::Value::String(s) => sqlx::query(&query).bind(s).execute(&pool).await,
-        _ => return Err("Unsupported PK type".into()),
+    }
+    let mode = |row: &sqlx::postgres::PgRow| -> String {
+        row.try_get("parameter_mode").unwrap_or_default()
     };
+    let (_, routine_type, parameters) = overloads
+        .iter()
+        .find(|(_, _, parameters)| {
+            parameters.iter().filter(|p| mode(p) != "OUT").count() == args.len()
+        })
+        .ok_or_else(|| format!("No routine '{}' takes {} arguments", name, args.len()))?;
+    let is_procedure = routine_type == "PROCEDURE";
+
+    let qualified = format!(
+        "{}.{}",
+        quote_identifier(schema, '"'),
+        quote_identifier(name, '"')
+    );
+    let mut qb = sqlx::QueryBuilder::new(if is_procedure {
+        format!("CALL {}(", qualified)
+    } else {
+        format!("SELECT * FROM {}(", qualified)
+    });
+    let mut bindings = Vec::new();
+    let mut args = args.iter();
+    let mut separated = qb.separated(", ");
+    for (i, parameter) in parameters.iter().enumerate() {
+        if mode(parameter) == "OUT" {
+            // Procedures take a placeholder for OUT parameters; functions leave them out
+            if is_procedure {
+                separated.push("NULL");
+            }
+            continue;
+        }
+        let parameter_name = parameter
+            .try_get::<Option<String>, _>("parameter_name")
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| format!("${}", i + 1));
+        let udt: String = parameter.try_get("udt_name").unwrap_or_default();
+        let val = args.next().cloned().unwrap_or(serde_json::Value::Null);
+        bindings.push((parameter_name.clone(), val.clone()));
+        push_value(&mut separated, &parameter_name, val, Some(&udt))?;
+        // Polymorphic parameters (anyelement, anyarray, ...) take the argument's type
+        if !udt.starts_with("any") {
+            let cast: String = parameter.try_get("cast_type").unwrap_or_default();
+            separated.push_unseparated(format!("::{}", cast));
+        }
+    }
+    separated.push_unseparated(")");
+
+    statement_log::log_statement(params, qb.sql(), &bindings);
+    let started = Instant::now();
+    let rows = qb
+        .build()
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let (columns, column_types) = rows
+        .first()
+        .map(|row| {
+            let columns = row.columns().iter().map(|c| c.name().to_string()).collect();
+            (columns, column_type_names(row))
+        })
+        .unwrap_or_default();
+    let rows: Vec<Vec<serde_json::Value>> = rows
+        .iter()
+        .map(|row| {
+            (0..row.columns().len())
+                .map(|i| extract_postgres_value(row, i))
+                .collect()
+        })
+        .collect();
+    let row_count = rows.len();
+    Ok(ProcedureResult {
+        result: QueryResult {
+            columns,
+            column_types,
+            rows,
+            affected_rows: 0,
+            truncated: false,
+            pagination: None,
+            has_more: false,
+            column_refs: None,
+            execution_ms: started.elapsed().as_millis() as u64,
+            row_count: Some(row_count),
+            auto_limit: None,
+        },
+        out_params: HashMap::new(),
+    })
+}
 
-    result.map(|r| r.rows_affected()).map_err(|e| e.to_string())
+pub async fn create_index(
+    params: &ConnectionParams,
+    table: &str,
+    name: &str,
+    columns: &[String],
+    unique: bool,
+) -> Result<(), String> {
+    let sql = create_index_sql(&qualified_table(params, table), name, columns, unique, '"')?;
+    run_ddl(params, &sql).await
 }
 
-pub async fn update_record(
+/// Postgres index names are unique per schema, so `_table` is not needed
+pub async fn drop_index(params: &ConnectionParams, _table: &str, name: &str) -> Result<(), String> {
+    let sql = format!(
+        "DROP INDEX {}.{}",
+        quote_identifier(postgres_schema(params), '"'),
+        quote_identifier(name, '"')
+    );
+    run_ddl(params, &sql).await
+}
+
+pub async fn add_column(
     params: &ConnectionParams,
     table: &str,
-    pk_col: &str,
-    pk_val: serde_json::Value,
-    col_name: &str,
-    new_val: serde_json::Value,
-) -> Result<u64, String> {
+    column: &ColumnDefinition,
+) -> Result<(), String> {
+    let sql = format!(
+        "ALTER TABLE {} ADD COLUMN {}",
+        qualified_table(params, table),
+        column_definition_sql(column, '"')?
+    );
+    run_ddl(params, &sql).await
+}
+
+pub async fn drop_column(
+    params: &ConnectionParams,
+    table: &str,
+    column: &str,
+) -> Result<(), String> {
+    let sql = format!(
+        "ALTER TABLE {} DROP COLUMN {}",
+        qualified_table(params, table),
+        quote_identifier(column, '"')
+    );
+    run_ddl(params, &sql).await
+}
+
+pub async fn rename_column(
+    params: &ConnectionParams,
+    table: &str,
+    column: &str,
+    new_name: &str,
+) -> Result<(), String> {
+    let sql = format!(
+        "ALTER TABLE {} RENAME COLUMN {} TO {}",
+        qualified_table(params, table),
+        quote_identifier(column, '"'),
+        quote_identifier(new_name, '"')
+    );
+    run_ddl(params, &sql).await
+}
+
+/// `"schema"."table"` for the connection's schema
+fn qualified_table(params: &ConnectionParams, table: &str) -> String {
+    format!(
+        "{}.{}",
+        quote_identifier(postgres_schema(params), '"'),
+        quote_identifier(table, '"')
+    )
+}
+
+async fn run_ddl(params: &ConnectionParams, sql: &str) -> Result<(), String> {
     let pool = get_postgres_pool(params).await?;
+    statement_log::log_statement(params, sql, &[]);
+    sqlx::query(sql)
+        .execute(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
 
-    let mut qb = sqlx::QueryBuilder::new(format!("UPDATE \"{}\" SET \"{}\" = ", table, col_name));
+/// Postgres type name (`udt_name`) of every column of `table`. Writes use it to bind
+/// values with the column's type where Postgres has no implicit cast from text.
+async fn column_udt_types(
+    pool: &sqlx::Pool<sqlx::Postgres>,
+    schema: &str,
+    table: &str,
+) -> Result<HashMap<String, String>, String> {
+    let query = r#"
+        SELECT column_name::text, udt_name::text
+        FROM information_schema.columns
+        WHERE table_schema = $2 AND table_name = $1
+    "#;
 
-    match new_val {
-        serde_json::Value::Number(n) => {
-            if n.is_i64() {
-                qb.push_bind(n.as_i64());
-            } else {
-                qb.push_bind(n.as_f64());
+    let rows = sqlx::query(query)
+        .bind(table)
+        .bind(schema)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .iter()
+        .filter_map(|r| Some((r.try_get(0).ok()?, r.try_get(1).ok()?)))
+        .collect())
+}
+
+/// The `push`/`push_bind` surface shared by `QueryBuilder` and its `Separated` lists
+trait PushBind<'args> {
+    fn push_sql(&mut self, sql: &str);
+    fn bind<T>(&mut self, value: T)
+    where
+        T: 'args + sqlx::Encode<'args, sqlx::Postgres> + sqlx::Type<sqlx::Postgres>;
+}
+
+impl<'args> PushBind<'args> for sqlx::QueryBuilder<'args, sqlx::Postgres> {
+    fn push_sql(&mut self, sql: &str) {
+        self.push(sql);
+    }
+
+    fn bind<T>(&mut self, value: T)
+    where
+        T: 'args + sqlx::Encode<'args, sqlx::Postgres> + sqlx::Type<sqlx::Postgres>,
+    {
+        self.push_bind(value);
+    }
+}
+
+impl<'args, Sep: std::fmt::Display> PushBind<'args> for Separated<'_, 'args, sqlx::Postgres, Sep> {
+    fn push_sql(&mut self, sql: &str) {
+        self.push(sql);
+    }
+
+    fn bind<T>(&mut self, value: T)
+    where
+        T: 'args + sqlx::Encode<'args, sqlx::Postgres> + sqlx::Type<sqlx::Postgres>,
+    {
+        self.push_bind(value);
+    }
+}
+
+/// Binds a JSON value for column `col` of Postgres type `udt` (NULL is written
+/// inline). Strings go into `uuid` columns as UUIDs; `json`/`jsonb` columns take
+/// any value as a JSON document, with strings parsed as JSON text when possible;
+/// array columns take a JSON array (or its text) of matching elements.
+fn push_value<'args>(
+    target: &mut impl PushBind<'args>,
+    col: &str,
+    val: serde_json::Value,
+    udt: Option<&str>,
+) -> Result<(), String> {
+    match (val, udt) {
+        (serde_json::Value::Null, _) => target.push_sql("NULL"),
+        (serde_json::Value::String(s), Some("json" | "jsonb")) => {
+            target.bind(serde_json::from_str(&s).unwrap_or(serde_json::Value::String(s)));
+        }
+        (val, Some("json" | "jsonb")) => target.bind(val),
+        (serde_json::Value::String(s), Some("uuid")) => {
+            let uuid = Uuid::parse_str(&s)
+                .map_err(|_| format!("Invalid UUID for column \"{}\": {}", col, s))?;
+            target.bind(uuid);
+        }
+        (serde_json::Value::String(s), Some("timestamptz")) => {
+            let ts = parse_timestamp_tz(&s)
+                .ok_or_else(|| format!("Invalid timestamp for column \"{}\": {}", col, s))?;
+            target.bind(ts);
+        }
+        (serde_json::Value::String(s), Some("timestamp")) => {
+            let ts = parse_naive_timestamp(&s)
+                .ok_or_else(|| format!("Invalid timestamp for column \"{}\": {}", col, s))?;
+            target.bind(ts);
+        }
+        (serde_json::Value::String(s), Some(udt)) if udt.starts_with('_') => {
+            match serde_json::from_str(&s) {
+                Ok(serde_json::Value::Array(items)) => push_array(target, col, items, udt)?,
+                _ => return Err(format!("Expected a JSON array for column \"{}\"", col)),
             }
         }
-        serde_json::Value::String(s) => {
-            qb.push_bind(s);
+        (serde_json::Value::Array(items), Some(udt)) if udt.starts_with('_') => {
+            push_array(target, col, items, udt)?;
         }
-        serde_json::Value::Bool(b) => {
-            qb.push_bind(b);
+        (serde_json::Value::Number(n), _) => {
+            if n.is_i64() {
+                target.bind(n.as_i64());
+            } else {
+                target.bind(n.as_f64());
+            }
         }
-        serde_json::Value::Null => {
-            qb.push("NULL");
+        (serde_json::Value::String(s), _) => target.bind(s),
+        (serde_json::Value::Bool(b), _) => target.bind(b),
+        _ => return Err(format!("Unsupported value type for column \"{}\"", col)),
+    }
+    Ok(())
+}
+
+/// Converts every element with `convert`; NULL elements stay NULL
+fn array_elements<T>(
+    col: &str,
+    items: Vec<serde_json::Value>,
+    convert: impl Fn(&serde_json::Value) -> Option<T>,
+) -> Result<Vec<Option<T>>, String> {
+    items
+        .iter()
+        .map(|item| match item {
+            serde_json::Value::Null => Ok(None),
+            item => convert(item)
+                .map(Some)
+                .ok_or_else(|| format!("Invalid array element for column \"{}\": {}", col, item)),
+        })
+        .collect()
+}
+
+/// Binds a JSON array into an array column; `udt` is the array type, e.g. `_int4`
+fn push_array<'args>(
+    target: &mut impl PushBind<'args>,
+    col: &str,
+    items: Vec<serde_json::Value>,
+    udt: &str,
+) -> Result<(), String> {
+    match udt {
+        "_text" | "_varchar" | "_bpchar" => target.bind(array_elements(col, items, |v| {
+            Some(
+                v.as_str()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| v.to_string()),
+            )
+        })?),
+        "_int2" => target.bind(array_elements(col, items, |v| {
+            v.as_i64().and_then(|n| i16::try_from(n).ok())
+        })?),
+        "_int4" => target.bind(array_elements(col, items, |v| {
+            v.as_i64().and_then(|n| i32::try_from(n).ok())
+        })?),
+        "_int8" => target.bind(array_elements(col, items, serde_json::Value::as_i64)?),
+        "_float8" => target.bind(array_elements(col, items, serde_json::Value::as_f64)?),
+        "_bool" => target.bind(array_elements(col, items, serde_json::Value::as_bool)?),
+        _ => {
+            return Err(format!(
+                "Unsupported array type {} for column \"{}\"",
+                udt, col
+            ))
         }
-        _ => return Err("Unsupported Value type".into()),
     }
+    Ok(())
+}
 
-    qb.push(format!(" WHERE \"{}\" = ", pk_col));
+/// Appends `WHERE pk1 = ? AND pk2 = ?` covering every primary key column.
+/// Columns are sorted so the generated statement is stable for composite keys.
+fn push_pk_filter(
+    qb: &mut sqlx::QueryBuilder<'_, sqlx::Postgres>,
+    pk: &HashMap<String, serde_json::Value>,
+    udt_types: &HashMap<String, String>,
+) -> Result<(), String> {
+    if pk.is_empty() {
+        return Err("No primary key values provided".into());
+    }
 
-    match pk_val {
-        serde_json::Value::Number(n) => {
-            if n.is_i64() {
-                qb.push_bind(n.as_i64());
-            } else {
-                qb.push_bind(n.as_f64());
+    let mut pk_cols: Vec<&String> = pk.keys().collect();
+    pk_cols.sort();
+
+    for (i, col) in pk_cols.into_iter().enumerate() {
+        qb.push(if i == 0 { " WHERE " } else { " AND " });
+        qb.push(format!("{} = ", quote_identifier(col, '"')));
+        match &pk[col] {
+            val @ (serde_json::Value::Number(_) | serde_json::Value::String(_)) => {
+                push_value(qb, col, val.clone(), udt_types.get(col).map(String::as_str))?;
             }
+            _ => return Err("Unsupported PK type".into()),
         }
-        serde_json::Value::String(s) => {
-            qb.push_bind(s);
-        }
-        _ => return Err("Unsupported PK type".into()),
     }
 
+    Ok(())
+}
+
+/// The DELETE run by `delete_record`
+fn delete_statement(
+    table: &str,
+    pk: &HashMap<String, serde_json::Value>,
+    udt_types: &HashMap<String, String>,
+) -> Result<sqlx::QueryBuilder<'static, sqlx::Postgres>, String> {
+    let mut qb = sqlx::QueryBuilder::new(format!("DELETE FROM {}", quote_identifier(table, '"')));
+    push_pk_filter(&mut qb, pk, udt_types)?;
+    Ok(qb)
+}
+
+pub async fn delete_record(
+    params: &ConnectionParams,
+    table: &str,
+    pk: &HashMap<String, serde_json::Value>,
+) -> Result<u64, String> {
+    let pool = get_postgres_pool(params).await?;
+    let udt_types = column_udt_types(&pool, postgres_schema(params), table).await?;
+    let mut qb = delete_statement(table, pk, &udt_types)?;
+
+    statement_log::log_statement(params, qb.sql(), &sorted_bindings(pk));
     let query = qb.build();
     let result = query.execute(&pool).await.map_err(|e| e.to_string())?;
     Ok(result.rows_affected())
 }
 
-pub async fn insert_record(
+/// The statement `delete_record` would run, without running it. Column types are
+/// still looked up, so values that wouldn't bind are reported here too.
+pub async fn delete_record_sql(
     params: &ConnectionParams,
     table: &str,
-    data: std::collections::HashMap<String, serde_json::Value>,
+    pk: &HashMap<String, serde_json::Value>,
+) -> Result<StatementPreview, String> {
+    let pool = get_postgres_pool(params).await?;
+    let udt_types = column_udt_types(&pool, postgres_schema(params), table).await?;
+    Ok(StatementPreview {
+        sql: delete_statement(table, pk, &udt_types)?.into_sql(),
+        bindings: sorted_bindings(pk),
+    })
+}
+
+/// `WHERE` clause of `delete_by_match`: `col IS NULL` for JSON `null`, otherwise
+/// `col = value` bound with the column's type
+fn push_match_filter(
+    qb: &mut sqlx::QueryBuilder<'_, sqlx::Postgres>,
+    conditions: &HashMap<String, serde_json::Value>,
+    udt_types: &HashMap<String, String>,
+) -> Result<(), String> {
+    if conditions.is_empty() {
+        return Err("No values to match provided".into());
+    }
+
+    for (i, (col, val)) in sorted_bindings(conditions).into_iter().enumerate() {
+        qb.push(if i == 0 { " WHERE " } else { " AND " });
+        if val.is_null() {
+            qb.push(format!("{} IS NULL", quote_identifier(&col, '"')));
+        } else {
+            qb.push(format!("{} = ", quote_identifier(&col, '"')));
+            push_value(qb, &col, val, udt_types.get(&col).map(String::as_str))?;
+        }
+    }
+    Ok(())
+}
+
+/// Deletes the rows matching `conditions` in a transaction that is only committed
+/// when the row count passes `check_match_count`
+pub async fn delete_by_match(
+    params: &ConnectionParams,
+    table: &str,
+    conditions: &HashMap<String, serde_json::Value>,
+    allow_multiple: bool,
+) -> Result<u64, String> {
+    let pool = get_postgres_pool(params).await?;
+    let udt_types = column_udt_types(&pool, postgres_schema(params), table).await?;
+    let mut qb = sqlx::QueryBuilder::new(format!("DELETE FROM {}", quote_identifier(table, '"')));
+    push_match_filter(&mut qb, conditions, &udt_types)?;
+
+    statement_log::log_statement(params, qb.sql(), &match_bindings(conditions));
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let result = qb
+        .build()
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    // Returning early drops the transaction, which rolls the delete back
+    check_match_count(result.rows_affected(), allow_multiple)?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(result.rows_affected())
+}
+
+/// The UPDATE run by `update_record`
+fn update_statement(
+    table: &str,
+    pk: &HashMap<String, serde_json::Value>,
+    col_name: &str,
+    new_val: serde_json::Value,
+    udt_types: &HashMap<String, String>,
+) -> Result<sqlx::QueryBuilder<'static, sqlx::Postgres>, String> {
+    let mut qb = sqlx::QueryBuilder::new(format!(
+        "UPDATE {} SET {} = ",
+        quote_identifier(table, '"'),
+        quote_identifier(col_name, '"')
+    ));
+    push_value(
+        &mut qb,
+        col_name,
+        new_val,
+        udt_types.get(col_name).map(String::as_str),
+    )?;
+    push_pk_filter(&mut qb, pk, udt_types)?;
+    Ok(qb)
+}
+
+/// Sets one column of the rows matching `pk`. JSON `null` writes SQL NULL, while a
+/// string is bound as-is, so `""` stores an empty string rather than NULL (and is
+/// rejected by non-text columns)
+pub async fn update_record(
+    params: &ConnectionParams,
+    table: &str,
+    pk: &HashMap<String, serde_json::Value>,
+    col_name: &str,
+    new_val: serde_json::Value,
 ) -> Result<u64, String> {
     let pool = get_postgres_pool(params).await?;
+    let udt_types = column_udt_types(&pool, postgres_schema(params), table).await?;
+    let bindings = update_bindings(pk, col_name, &new_val);
+    let mut qb = update_statement(table, pk, col_name, new_val, &udt_types)?;
+
+    statement_log::log_statement(params, qb.sql(), &bindings);
+    let query = qb.build();
+    let result = query.execute(&pool).await.map_err(|e| e.to_string())?;
+    Ok(result.rows_affected())
+}
 
-    let mut cols = Vec::new();
-    let mut vals = Vec::new();
+/// The statement `update_record` would run, without running it
+pub async fn update_record_sql(
+    params: &ConnectionParams,
+    table: &str,
+    pk: &HashMap<String, serde_json::Value>,
+    col_name: &str,
+    new_val: serde_json::Value,
+) -> Result<StatementPreview, String> {
+    let pool = get_postgres_pool(params).await?;
+    let udt_types = column_udt_types(&pool, postgres_schema(params), table).await?;
+    Ok(StatementPreview {
+        bindings: update_bindings(pk, col_name, &new_val),
+        sql: update_statement(table, pk, col_name, new_val, &udt_types)?.into_sql(),
+    })
+}
 
-    for (k, v) in data {
-        cols.push(format!("\"{}\"", k));
-        vals.push(v);
+/// Runs `edits` in order in one transaction, rolling back on the first failure
+pub async fn update_records(
+    params: &ConnectionParams,
+    edits: &[RecordEdit],
+) -> Result<BatchEditResult, String> {
+    let pool = get_postgres_pool(params).await?;
+    let mut udt_types_by_table: HashMap<&str, HashMap<String, String>> = HashMap::new();
+    for edit in edits {
+        if !udt_types_by_table.contains_key(edit.table.as_str()) {
+            let udt_types = column_udt_types(&pool, postgres_schema(params), &edit.table).await?;
+            udt_types_by_table.insert(&edit.table, udt_types);
+        }
     }
 
-    if cols.is_empty() {
+    run_record_edits(params, &pool, edits, |edit| {
+        update_statement(
+            &edit.table,
+            &edit.pk,
+            &edit.column,
+            edit.value.clone(),
+            &udt_types_by_table[edit.table.as_str()],
+        )
+    })
+    .await
+}
+
+/// The INSERT run by `insert_record`, with columns in `bindings` order
+fn insert_statement(
+    table: &str,
+    bindings: &[(String, serde_json::Value)],
+    udt_types: &HashMap<String, String>,
+) -> Result<sqlx::QueryBuilder<'static, sqlx::Postgres>, String> {
+    if bindings.is_empty() {
         return Err("No data to insert".into());
     }
 
+    let cols: Vec<String> = bindings
+        .iter()
+        .map(|(k, _)| quote_identifier(k, '"'))
+        .collect();
     let mut qb = sqlx::QueryBuilder::new(format!(
-        "INSERT INTO \"{}\" ({}) VALUES (",
-        table,
+        "INSERT INTO {} ({}) VALUES (",
+        quote_identifier(table, '"'),
         cols.join(", ")
     ));
 
     let mut separated = qb.separated(", ");
-    for val in vals {
-        match val {
-            serde_json::Value::Number(n) => {
-                if n.is_i64() {
-                    separated.push_bind(n.as_i64());
-                } else {
-                    separated.push_bind(n.as_f64());
-                }
-            }
-            serde_json::Value::String(s) => {
-                separated.push_bind(s);
-            }
-            serde_json::Value::Bool(b) => {
-                separated.push_bind(b);
+    for (col, val) in bindings {
+        push_value(
+            &mut separated,
+            col,
+            val.clone(),
+            udt_types.get(col).map(String::as_str),
+        )?;
+    }
+    separated.push_unseparated(")");
+    Ok(qb)
+}
+
+pub async fn insert_record(
+    params: &ConnectionParams,
+    table: &str,
+    data: std::collections::HashMap<String, serde_json::Value>,
+) -> Result<u64, String> {
+    let pool = get_postgres_pool(params).await?;
+    let udt_types = column_udt_types(&pool, postgres_schema(params), table).await?;
+    let bindings = sorted_bindings(&data);
+    let mut qb = insert_statement(table, &bindings, &udt_types)?;
+
+    statement_log::log_statement(params, qb.sql(), &bindings);
+    let query = qb.build();
+    let result = query.execute(&pool).await.map_err(|e| e.to_string())?;
+    Ok(result.rows_affected())
+}
+
+/// The statement `insert_record` would run, without running it
+pub async fn insert_record_sql(
+    params: &ConnectionParams,
+    table: &str,
+    data: std::collections::HashMap<String, serde_json::Value>,
+) -> Result<StatementPreview, String> {
+    let pool = get_postgres_pool(params).await?;
+    let udt_types = column_udt_types(&pool, postgres_schema(params), table).await?;
+    let bindings = sorted_bindings(&data);
+    Ok(StatementPreview {
+        sql: insert_statement(table, &bindings, &udt_types)?.into_sql(),
+        bindings,
+    })
+}
+
+/// Inserts many rows in one transaction, as multi-row INSERTs of up to 1000 rows.
+/// Columns are the union over all rows; a row missing a column gets NULL.
+pub async fn insert_records(
+    params: &ConnectionParams,
+    table: &str,
+    rows: Vec<HashMap<String, serde_json::Value>>,
+) -> Result<u64, String> {
+    let cols = union_columns(&rows);
+    if cols.is_empty() {
+        return Err("No data to insert".into());
+    }
+    let quoted_cols: Vec<String> = cols.iter().map(|c| quote_identifier(c, '"')).collect();
+
+    let pool = get_postgres_pool(params).await?;
+    let udt_types = column_udt_types(&pool, postgres_schema(params), table).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut affected_rows = 0;
+
+    for chunk in rows.chunks(insert_chunk_size(cols.len())) {
+        let mut logged_bindings = Vec::new();
+        let mut qb = sqlx::QueryBuilder::new(format!(
+            "INSERT INTO {} ({}) VALUES ",
+            quote_identifier(table, '"'),
+            quoted_cols.join(", ")
+        ));
+
+        for (i, row) in chunk.iter().enumerate() {
+            if i > 0 {
+                qb.push(", ");
             }
-            serde_json::Value::Null => {
-                separated.push("NULL");
+            qb.push("(");
+            let mut separated = qb.separated(", ");
+            for col in &cols {
+                let val = row.get(col).cloned().unwrap_or(serde_json::Value::Null);
+                logged_bindings.push((col.clone(), val.clone()));
+                push_value(
+                    &mut separated,
+                    col,
+                    val,
+                    udt_types.get(col).map(String::as_str),
+                )?;
             }
-            _ => return Err("Unsupported value type".into()),
+            separated.push_unseparated(")");
         }
+
+        statement_log::log_statement(params, qb.sql(), &logged_bindings);
+        let result = qb
+            .build()
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        affected_rows += result.rows_affected();
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(affected_rows)
+}
+
+/// Inserts the row, or updates its other columns when a row with the same
+/// `conflict_cols` values exists (these must match a primary key or unique index)
+pub async fn upsert_record(
+    params: &ConnectionParams,
+    table: &str,
+    conflict_cols: &[String],
+    data: HashMap<String, serde_json::Value>,
+) -> Result<u64, String> {
+    validate_upsert(conflict_cols, &data)?;
+    let pool = get_postgres_pool(params).await?;
+    let udt_types = column_udt_types(&pool, postgres_schema(params), table).await?;
+
+    let mut entries: Vec<(String, serde_json::Value)> = data.into_iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    let cols: Vec<String> = entries
+        .iter()
+        .map(|(k, _)| quote_identifier(k, '"'))
+        .collect();
+
+    let mut qb = sqlx::QueryBuilder::new(format!(
+        "INSERT INTO {} ({}) VALUES (",
+        quote_identifier(table, '"'),
+        cols.join(", ")
+    ));
+    let mut separated = qb.separated(", ");
+    for (col, val) in &entries {
+        push_value(
+            &mut separated,
+            col,
+            val.clone(),
+            udt_types.get(col).map(String::as_str),
+        )?;
     }
     separated.push_unseparated(")");
 
-    let query = qb.build();
-    let result = query.execute(&pool).await.map_err(|e| e.to_string())?;
+    let conflict: Vec<String> = conflict_cols
+        .iter()
+        .map(|c| quote_identifier(c, '"'))
+        .collect();
+    let updates: Vec<String> = entries
+        .iter()
+        .filter(|(k, _)| !conflict_cols.contains(k))
+        .map(|(k, _)| format!("{0} = EXCLUDED.{0}", quote_identifier(k, '"')))
+        .collect();
+    if updates.is_empty() {
+        qb.push(format!(" ON CONFLICT ({}) DO NOTHING", conflict.join(", ")));
+    } else {
+        qb.push(format!(
+            " ON CONFLICT ({}) DO UPDATE SET {}",
+            conflict.join(", "),
+            updates.join(", ")
+        ));
+    }
+
+    statement_log::log_statement(params, qb.sql(), &entries);
+    let result = qb.build().execute(&pool).await.map_err(|e| e.to_string())?;
     Ok(result.rows_affected())
 }
 
@@ -449,40 +1273,276 @@ fn remove_order_by(query: &str) -> String {
     }
 }
 
-pub async fn get_table_ddl(
+/// A column as needed to restate it in `CREATE TABLE`
+struct DdlColumn {
+    name: String,
+    /// `format_type()` output, e.g. `character varying(50)` or `integer[]`
+    data_type: String,
+    is_nullable: bool,
+    default: Option<String>,
+    /// `ALWAYS` or `BY DEFAULT` for identity columns
+    identity: Option<String>,
+    /// Expression of a stored generated column
+    generated: Option<String>,
+}
+
+/// Serial columns come back as an integer with a `nextval()` default on their
+/// own sequence, which would not exist where the DDL is replayed
+fn serial_type(data_type: &str) -> Option<&'static str> {
+    match data_type {
+        "smallint" => Some("smallserial"),
+        "integer" => Some("serial"),
+        "bigint" => Some("bigserial"),
+        _ => None,
+    }
+}
+
+fn ddl_column_sql(col: &DdlColumn) -> String {
+    let serial = col
+        .default
+        .as_deref()
+        .filter(|d| d.starts_with("nextval("))
+        .and_then(|_| serial_type(&col.data_type));
+    let mut sql = format!(
+        "{} {}",
+        quote_identifier(&col.name, '"'),
+        serial.unwrap_or(&col.data_type)
+    );
+    if let Some(expr) = &col.generated {
+        sql.push_str(&format!(" GENERATED ALWAYS AS ({}) STORED", expr));
+    } else if let Some(kind) = &col.identity {
+        sql.push_str(&format!(" GENERATED {} AS IDENTITY", kind));
+    } else if let Some(default) = col.default.as_deref().filter(|_| serial.is_none()) {
+        sql.push_str(&format!(" DEFAULT {}", default));
+    }
+    if !col.is_nullable {
+        sql.push_str(" NOT NULL");
+    }
+    sql
+}
+
+/// Rebuilds `CREATE TABLE` from the catalog: columns with their types, defaults,
+/// identity and generated expressions, then the primary key, unique, check and
+/// foreign key constraints, followed by `CREATE INDEX` for the other indexes
+pub async fn get_table_ddl(params: &ConnectionParams, table_name: &str) -> Result<String, String> {
+    table_ddl(params, table_name, true).await
+}
+
+/// `get_table_ddl` without the foreign keys, for dumps that add them with
+/// `get_foreign_key_constraints` once every table exists
+pub async fn get_table_ddl_without_foreign_keys(
     params: &ConnectionParams,
     table_name: &str,
 ) -> Result<String, String> {
-    let cols = get_columns(params, table_name).await?;
-    if cols.is_empty() {
-        return Err(format!("Table {} not found or empty", table_name));
+    table_ddl(params, table_name, false).await
+}
+
+/// Name and definition of each foreign key on `table_name`
+pub async fn get_foreign_key_constraints(
+    params: &ConnectionParams,
+    table_name: &str,
+) -> Result<Vec<(String, String)>, String> {
+    let pool = get_postgres_pool(params).await?;
+    sqlx::query_as(
+        "SELECT conname::text, pg_get_constraintdef(oid) FROM pg_constraint \
+         WHERE conrelid = $1::regclass AND contype = 'f' ORDER BY conname",
+    )
+    .bind(qualified_table(params, table_name))
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())
+}
+
+async fn table_ddl(
+    params: &ConnectionParams,
+    table_name: &str,
+    with_foreign_keys: bool,
+) -> Result<String, String> {
+    let pool = get_postgres_pool(params).await?;
+    let table = qualified_table(params, table_name);
+
+    // information_schema for the standard attributes, pg_attribute for the full type
+    let rows = sqlx::query(
+        r#"
+        SELECT c.column_name::text AS name,
+               format_type(a.atttypid, a.atttypmod) AS data_type,
+               c.is_nullable::text AS is_nullable,
+               c.column_default::text AS column_default,
+               c.is_identity::text AS is_identity,
+               c.identity_generation::text AS identity_generation,
+               c.generation_expression::text AS generation_expression
+        FROM information_schema.columns c
+        JOIN pg_attribute a
+          ON a.attrelid = format('%I.%I', c.table_schema, c.table_name)::regclass
+         AND a.attname = c.column_name
+        WHERE c.table_schema = $1 AND c.table_name = $2
+        ORDER BY c.ordinal_position
+        "#,
+    )
+    .bind(postgres_schema(params))
+    .bind(table_name)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    if rows.is_empty() {
+        return Err(format!("Table {} not found", table_name));
     }
 
-    let mut defs = Vec::new();
-    let mut pks = Vec::new();
+    let mut defs: Vec<String> = rows
+        .iter()
+        .map(|r| {
+            let is_identity: Option<String> = r.try_get("is_identity").ok().flatten();
+            ddl_column_sql(&DdlColumn {
+                name: r.try_get("name").unwrap_or_default(),
+                data_type: r.try_get("data_type").unwrap_or_default(),
+                is_nullable: r.try_get::<String, _>("is_nullable").unwrap_or_default() == "YES",
+                default: r.try_get("column_default").ok().flatten(),
+                identity: r
+                    .try_get("identity_generation")
+                    .ok()
+                    .flatten()
+                    .filter(|_| is_identity.as_deref() == Some("YES")),
+                generated: r.try_get("generation_expression").ok().flatten(),
+            })
+        })
+        .collect();
 
-    for col in cols {
-        let mut def = format!("\"{}\" {}", col.name, col.data_type);
-        
-        if !col.is_nullable {
-            def.push_str(" NOT NULL");
-        }
-        
-        if col.is_pk {
-            pks.push(format!("\"{}\"", col.name));
+    let constraints: Vec<(String, String)> = sqlx::query_as(
+        "SELECT conname::text, pg_get_constraintdef(oid) FROM pg_constraint \
+         WHERE conrelid = $1::regclass AND contype IN ('p', 'u', 'c', 'f') \
+         AND ($2 OR contype <> 'f') \
+         ORDER BY array_position(ARRAY['p', 'u', 'c', 'f'], contype::text), conname",
+    )
+    .bind(&table)
+    .bind(with_foreign_keys)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    defs.extend(
+        constraints
+            .into_iter()
+            .map(|(name, def)| format!("CONSTRAINT {} {}", quote_identifier(&name, '"'), def)),
+    );
+
+    // Indexes backing a constraint are created by the constraint itself
+    let indexes: Vec<String> = sqlx::query_scalar(
+        "SELECT pg_get_indexdef(i.indexrelid) FROM pg_index i \
+         JOIN pg_class ic ON ic.oid = i.indexrelid \
+         WHERE i.indrelid = $1::regclass \
+         AND NOT EXISTS (SELECT 1 FROM pg_constraint c WHERE c.conindid = i.indexrelid) \
+         ORDER BY ic.relname",
+    )
+    .bind(&table)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut ddl = format!("CREATE TABLE {} (\n  {}\n);", table, defs.join(",\n  "));
+    for index in indexes {
+        ddl.push_str(&format!("\n{};", index));
+    }
+    Ok(ddl)
+}
+
+/// With `exact` unset the count is `pg_class.reltuples`, as of the last
+/// VACUUM or ANALYZE. Tables that were never analyzed report -1 there (0 before
+/// Postgres 14, which is indistinguishable from empty) and are counted instead.
+pub async fn get_table_row_count(
+    params: &ConnectionParams,
+    table_name: &str,
+    exact: bool,
+) -> Result<TableRowCount, String> {
+    let pool = get_postgres_pool(params).await?;
+    if !exact {
+        let estimate: Option<i64> = sqlx::query_scalar(
+            "SELECT c.reltuples::bigint FROM pg_class c \
+             JOIN pg_namespace n ON n.oid = c.relnamespace \
+             WHERE n.nspname = $1 AND c.relname = $2",
+        )
+        .bind(postgres_schema(params))
+        .bind(table_name)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        if let Some(count) = estimate.filter(|count| *count >= 0) {
+            return Ok(TableRowCount {
+                count,
+                estimated: true,
+            });
         }
-        defs.push(def);
     }
 
-    if !pks.is_empty() {
-        defs.push(format!("PRIMARY KEY ({})", pks.join(", ")));
+    let query = format!(
+        "SELECT COUNT(*) FROM {}",
+        qualified_table(params, table_name)
+    );
+    let count: i64 = sqlx::query_scalar(&query)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(TableRowCount {
+        count,
+        estimated: false,
+    })
+}
+
+pub async fn explain_query(
+    params: &ConnectionParams,
+    query: &str,
+) -> Result<serde_json::Value, String> {
+    let pool = get_postgres_pool(params).await?;
+    let row = sqlx::query(&format!("EXPLAIN (FORMAT JSON) {}", query))
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    row.try_get::<serde_json::Value, _>(0)
+        .map_err(|e| e.to_string())
+}
+
+/// Runs the statements in one transaction and returns each statement's affected
+/// row count. The first failure rolls everything back.
+pub async fn execute_script(
+    params: &ConnectionParams,
+    statements: &[&str],
+) -> Result<Vec<u64>, String> {
+    let pool = get_postgres_pool(params).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut affected_rows = Vec::with_capacity(statements.len());
+
+    for (i, statement) in statements.iter().enumerate() {
+        statement_log::log_statement(params, statement, &[]);
+        match sqlx::query(statement).execute(&mut *tx).await {
+            Ok(result) => affected_rows.push(result.rows_affected()),
+            Err(e) => {
+                tx.rollback().await.map_err(|e| e.to_string())?;
+                return Err(script_error(i, statement, e));
+            }
+        }
     }
 
-    Ok(format!(
-        "CREATE TABLE public.\"{}\" (\n  {}\n);",
-        table_name,
-        defs.join(",\n  ")
-    ))
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(affected_rows)
+}
+
+/// Runs `query` with `values` bound to its (driver-native) placeholders. Row-returning
+/// statements stop after `limit` rows and set `truncated`.
+pub async fn execute_query_with_params(
+    params: &ConnectionParams,
+    query: &str,
+    values: &[serde_json::Value],
+    limit: Option<u32>,
+) -> Result<QueryResult, String> {
+    let pool = get_postgres_pool(params).await?;
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    execute_with_json_params(
+        params,
+        &mut conn,
+        query,
+        values,
+        limit,
+        extract_postgres_value,
+    )
+    .await
 }
 
 pub async fn execute_query(
@@ -490,19 +1550,47 @@ pub async fn execute_query(
     query: &str,
     limit: Option<u32>,
     page: u32,
+    offset: Option<u32>,
+    on_batch: Option<RowBatchCallback<'_>>,
 ) -> Result<QueryResult, String> {
+    statement_log::log_statement(params, query, &[]);
+
     let pool = get_postgres_pool(params).await?;
     let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    let started = Instant::now();
+
+    // INSERT/UPDATE/DELETE/DDL: run with execute() so the affected row count is reported
+    if !returns_rows(query) {
+        let result = sqlx::query(query)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| e.to_string())?;
+        return Ok(QueryResult {
+            columns: Vec::new(),
+            column_types: Vec::new(),
+            rows: Vec::new(),
+            affected_rows: result.rows_affected(),
+            truncated: false,
+            pagination: None,
+            has_more: false,
+            column_refs: None,
+            execution_ms: started.elapsed().as_millis() as u64,
+            row_count: None,
+            auto_limit: None,
+        });
+    }
 
     let is_select = query.trim_start().to_uppercase().starts_with("SELECT");
     let mut pagination: Option<Pagination> = None;
     let final_query: String;
     let mut manual_limit = limit;
+    let mut auto_limit = None;
     let mut truncated = false;
 
-    if is_select && limit.is_some() {
+    // Queries that already carry their own LIMIT are run as-is (capped by manual_limit)
+    if is_select && limit.is_some() && !has_limit_clause(query) {
         let l = limit.unwrap();
-        let offset = (page - 1) * l;
+        let offset = offset.unwrap_or_else(|| calculate_offset(page, l));
 
         let count_q = format!("SELECT COUNT(*) FROM ({}) as count_wrapper", query);
         let count_res = sqlx::query(&count_q).fetch_one(&mut *conn).await;
@@ -525,30 +1613,42 @@ pub async fn execute_query(
         // Extract ORDER BY clause from the original query to preserve sorting
         let order_by_clause = extract_order_by(query);
 
+        // LIMIT l + 1: the extra row (trimmed below) tells whether another page exists
         if !order_by_clause.is_empty() {
             // Remove ORDER BY from inner query and add it to outer query
             let query_without_order = remove_order_by(query);
             final_query = format!(
                 "SELECT * FROM ({}) as data_wrapper {} LIMIT {} OFFSET {}",
-                query_without_order, order_by_clause, l, offset
+                query_without_order, order_by_clause, l + 1, offset
             );
         } else {
             final_query = format!(
                 "SELECT * FROM ({}) as data_wrapper LIMIT {} OFFSET {}",
-                query, l, offset
+                query, l + 1, offset
             );
         }
 
         manual_limit = None;
+    } else if let Some(limited) = auto_limit_query(query, params.default_row_limit) {
+        // A bare SELECT under the connection's auto-limit: the row fetched past it
+        // stops the read and shows the result was cut
+        final_query = limited;
+        manual_limit = params.default_row_limit;
+        auto_limit = params.default_row_limit;
     } else {
         final_query = query.to_string();
     }
 
+    // Paginated results are already bounded by the page size, so only unbounded
+    // queries are streamed to the caller in batches
+    let on_batch = if pagination.is_some() { None } else { on_batch };
+    let mut buffer = RowBuffer::new(on_batch);
+
     // Streaming
     let mut rows_stream = sqlx::query(&final_query).fetch(&mut *conn);
 
     let mut columns: Vec<String> = Vec::new();
-    let mut json_rows = Vec::new();
+    let mut column_types: Vec<String> = Vec::new();
 
     use futures::stream::StreamExt;
 
@@ -557,10 +1657,11 @@ pub async fn execute_query(
             Ok(row) => {
                 if columns.is_empty() {
                     columns = row.columns().iter().map(|c| c.name().to_string()).collect();
+                    column_types = column_type_names(&row);
                 }
 
                 if let Some(l) = manual_limit {
-                    if json_rows.len() >= l as usize {
+                    if buffer.row_count() >= l as usize {
                         truncated = true;
                         break;
                     }
@@ -571,17 +1672,405 @@ pub async fn execute_query(
                     let val = extract_postgres_value(&row, i);
                     json_row.push(val);
                 }
-                json_rows.push(json_row);
+                if !buffer.push(&columns, json_row) {
+                    truncated = true;
+                    break;
+                }
             }
             Err(e) => return Err(e.to_string()),
         }
     }
 
+    drop(rows_stream);
+
+    // No row to read the columns from: ask the database what the statement returns
+    if columns.is_empty() {
+        if let Ok(describe) = conn.describe(&final_query).await {
+            (columns, column_types) = described_columns(&describe);
+        }
+    }
+
+    let fetched = buffer.row_count();
+    let mut json_rows = buffer.finish(&columns);
+
+    let has_more = match &pagination {
+        Some(p) if json_rows.len() > p.page_size as usize => {
+            json_rows.truncate(p.page_size as usize);
+            true
+        }
+        Some(_) => false,
+        None => truncated,
+    };
+
+    // Streamed rows went to `on_batch` and are not part of the result
+    let row_count = if on_batch.is_some() {
+        fetched
+    } else {
+        json_rows.len()
+    };
     Ok(QueryResult {
         columns,
+        column_types,
         rows: json_rows,
         affected_rows: 0,
         truncated,
         pagination,
+        has_more,
+        column_refs: None,
+        execution_ms: started.elapsed().as_millis() as u64,
+        row_count: Some(row_count),
+        auto_limit: auto_limit.filter(|&n| truncated && row_count >= n as usize),
     })
 }
+
+/// `Driver` for PostgreSQL servers
+pub struct PostgresDriver;
+
+#[async_trait]
+impl Driver for PostgresDriver {
+    async fn get_databases(&self, params: &ConnectionParams) -> Result<Vec<String>, String> {
+        get_databases(params).await
+    }
+
+    async fn get_schemas(&self, params: &ConnectionParams) -> Result<Vec<String>, String> {
+        get_schemas(params).await
+    }
+
+    async fn get_tables(&self, params: &ConnectionParams) -> Result<Vec<TableInfo>, String> {
+        get_tables(params).await
+    }
+
+    async fn get_views(&self, params: &ConnectionParams) -> Result<Vec<ViewInfo>, String> {
+        get_views(params).await
+    }
+
+    async fn get_columns(
+        &self,
+        params: &ConnectionParams,
+        table_name: &str,
+    ) -> Result<Vec<TableColumn>, String> {
+        get_columns(params, table_name).await
+    }
+
+    async fn get_foreign_keys(
+        &self,
+        params: &ConnectionParams,
+        table_name: &str,
+    ) -> Result<Vec<ForeignKey>, String> {
+        get_foreign_keys(params, table_name).await
+    }
+
+    async fn get_all_columns_batch(
+        &self,
+        params: &ConnectionParams,
+        _table_names: &[String],
+    ) -> Result<HashMap<String, Vec<TableColumn>>, String> {
+        get_all_columns_batch(params).await
+    }
+
+    async fn get_all_foreign_keys_batch(
+        &self,
+        params: &ConnectionParams,
+        _table_names: &[String],
+    ) -> Result<HashMap<String, Vec<ForeignKey>>, String> {
+        get_all_foreign_keys_batch(params).await
+    }
+
+    async fn get_indexes(
+        &self,
+        params: &ConnectionParams,
+        table_name: &str,
+    ) -> Result<Vec<Index>, String> {
+        get_indexes(params, table_name).await
+    }
+
+    async fn get_triggers(
+        &self,
+        params: &ConnectionParams,
+        table_name: &str,
+    ) -> Result<Vec<Trigger>, String> {
+        get_triggers(params, table_name).await
+    }
+
+    async fn get_routines(
+        &self,
+        params: &ConnectionParams,
+        schema: Option<&str>,
+    ) -> Result<Vec<RoutineInfo>, String> {
+        get_routines(params, schema).await
+    }
+
+    async fn get_routine_definition(
+        &self,
+        params: &ConnectionParams,
+        schema: Option<&str>,
+        name: &str,
+    ) -> Result<String, String> {
+        get_routine_definition(params, schema, name).await
+    }
+
+    async fn call_procedure(
+        &self,
+        params: &ConnectionParams,
+        name: &str,
+        args: &[serde_json::Value],
+    ) -> Result<ProcedureResult, String> {
+        call_procedure(params, name, args).await
+    }
+
+    async fn create_index(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        name: &str,
+        columns: &[String],
+        unique: bool,
+    ) -> Result<(), String> {
+        create_index(params, table, name, columns, unique).await
+    }
+
+    async fn drop_index(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        name: &str,
+    ) -> Result<(), String> {
+        drop_index(params, table, name).await
+    }
+
+    async fn add_column(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        column: &ColumnDefinition,
+    ) -> Result<(), String> {
+        add_column(params, table, column).await
+    }
+
+    async fn drop_column(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        column: &str,
+    ) -> Result<(), String> {
+        drop_column(params, table, column).await
+    }
+
+    async fn rename_column(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        column: &str,
+        new_name: &str,
+    ) -> Result<(), String> {
+        rename_column(params, table, column, new_name).await
+    }
+
+    async fn delete_record(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        pk: &HashMap<String, serde_json::Value>,
+    ) -> Result<u64, String> {
+        delete_record(params, table, pk).await
+    }
+
+    async fn delete_record_sql(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        pk: &HashMap<String, serde_json::Value>,
+    ) -> Result<StatementPreview, String> {
+        delete_record_sql(params, table, pk).await
+    }
+
+    async fn delete_by_match(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        conditions: &HashMap<String, serde_json::Value>,
+        allow_multiple: bool,
+    ) -> Result<u64, String> {
+        delete_by_match(params, table, conditions, allow_multiple).await
+    }
+
+    async fn update_record(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        pk: &HashMap<String, serde_json::Value>,
+        col_name: &str,
+        new_val: serde_json::Value,
+    ) -> Result<u64, String> {
+        update_record(params, table, pk, col_name, new_val).await
+    }
+
+    async fn update_record_sql(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        pk: &HashMap<String, serde_json::Value>,
+        col_name: &str,
+        new_val: serde_json::Value,
+    ) -> Result<StatementPreview, String> {
+        update_record_sql(params, table, pk, col_name, new_val).await
+    }
+
+    async fn update_records(
+        &self,
+        params: &ConnectionParams,
+        edits: &[RecordEdit],
+    ) -> Result<BatchEditResult, String> {
+        update_records(params, edits).await
+    }
+
+    async fn insert_record(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        data: HashMap<String, serde_json::Value>,
+    ) -> Result<u64, String> {
+        insert_record(params, table, data).await
+    }
+
+    async fn insert_record_sql(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        data: HashMap<String, serde_json::Value>,
+    ) -> Result<StatementPreview, String> {
+        insert_record_sql(params, table, data).await
+    }
+
+    async fn insert_records(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        rows: Vec<HashMap<String, serde_json::Value>>,
+    ) -> Result<u64, String> {
+        insert_records(params, table, rows).await
+    }
+
+    async fn upsert_record(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        conflict_cols: &[String],
+        data: HashMap<String, serde_json::Value>,
+    ) -> Result<u64, String> {
+        upsert_record(params, table, conflict_cols, data).await
+    }
+
+    async fn get_table_ddl(
+        &self,
+        params: &ConnectionParams,
+        table_name: &str,
+    ) -> Result<String, String> {
+        get_table_ddl(params, table_name).await
+    }
+
+    async fn get_table_row_count(
+        &self,
+        params: &ConnectionParams,
+        table_name: &str,
+        exact: bool,
+    ) -> Result<TableRowCount, String> {
+        get_table_row_count(params, table_name, exact).await
+    }
+
+    async fn explain_query(
+        &self,
+        params: &ConnectionParams,
+        query: &str,
+    ) -> Result<serde_json::Value, String> {
+        explain_query(params, query).await
+    }
+
+    async fn execute_script(
+        &self,
+        params: &ConnectionParams,
+        statements: &[&str],
+    ) -> Result<Vec<u64>, String> {
+        execute_script(params, statements).await
+    }
+
+    async fn execute_query_with_params(
+        &self,
+        params: &ConnectionParams,
+        query: &str,
+        values: &[serde_json::Value],
+        limit: Option<u32>,
+    ) -> Result<QueryResult, String> {
+        execute_query_with_params(params, query, values, limit).await
+    }
+
+    async fn execute_query(
+        &self,
+        params: &ConnectionParams,
+        query: &str,
+        limit: Option<u32>,
+        page: u32,
+        offset: Option<u32>,
+        on_batch: Option<RowBatchCallback<'_>>,
+    ) -> Result<QueryResult, String> {
+        execute_query(params, query, limit, page, offset, on_batch).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(data_type: &str, default: Option<&str>) -> DdlColumn {
+        DdlColumn {
+            name: "id".to_string(),
+            data_type: data_type.to_string(),
+            is_nullable: false,
+            default: default.map(str::to_string),
+            identity: None,
+            generated: None,
+        }
+    }
+
+    #[test]
+    fn test_ddl_column_keeps_type_and_default() {
+        let mut col = column("character varying(50)", Some("'new'::character varying"));
+        col.name = "status".to_string();
+        col.is_nullable = true;
+        assert_eq!(
+            ddl_column_sql(&col),
+            "\"status\" character varying(50) DEFAULT 'new'::character varying"
+        );
+    }
+
+    #[test]
+    fn test_ddl_column_restates_serial() {
+        let col = column("bigint", Some("nextval('items_id_seq'::regclass)"));
+        assert_eq!(ddl_column_sql(&col), "\"id\" bigserial NOT NULL");
+        // A sequence default on a non-integer column is kept as written
+        let col = column("numeric", Some("nextval('items_id_seq'::regclass)"));
+        assert_eq!(
+            ddl_column_sql(&col),
+            "\"id\" numeric DEFAULT nextval('items_id_seq'::regclass) NOT NULL"
+        );
+    }
+
+    #[test]
+    fn test_ddl_column_identity_and_generated() {
+        let mut col = column("integer", None);
+        col.identity = Some("BY DEFAULT".to_string());
+        assert_eq!(
+            ddl_column_sql(&col),
+            "\"id\" integer GENERATED BY DEFAULT AS IDENTITY NOT NULL"
+        );
+
+        let mut col = column("numeric", None);
+        col.name = "total".to_string();
+        col.is_nullable = true;
+        col.generated = Some("(price * (qty)::numeric)".to_string());
+        assert_eq!(
+            ddl_column_sql(&col),
+            "\"total\" numeric GENERATED ALWAYS AS ((price * (qty)::numeric)) STORED"
+        );
+    }
+}
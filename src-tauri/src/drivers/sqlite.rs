@@ -1,15 +1,37 @@
-use crate::drivers::common::extract_sqlite_value;
+use crate::drivers::common::{
+    auto_limit_query, calculate_offset, check_match_count, column_definition_sql,
+    column_type_names, create_index_sql, described_columns, execute_with_json_params,
+    extract_sqlite_value, has_limit_clause, insert_chunk_size, match_bindings, quote_identifier,
+    returns_rows, run_record_edits, script_error, sorted_bindings, statement_timeout_error,
+    top_level_tokens, union_columns, update_bindings, validate_upsert, Driver, RowBatchCallback,
+    RowBuffer,
+};
 use crate::models::{
-    ConnectionParams, ForeignKey, Index, Pagination, QueryResult, TableColumn, TableInfo,
+    BatchEditResult, ColumnDefinition, ConnectionParams, ForeignKey, Index, Pagination,
+    ProcedureResult, QueryResult, RecordEdit, RoutineInfo, StatementPreview, TableColumn,
+    TableInfo, TableRowCount, Trigger, ViewInfo,
 };
-use sqlx::{Column, Row};
-use crate::pool_manager::get_sqlite_pool;
+use async_trait::async_trait;
+use sqlx::sqlite::SqliteConnection;
+use sqlx::query_builder::Separated;
+use sqlx::{Column, Executor, Row};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use crate::pool_manager::{get_sqlite_pool, SQLITE_INTERRUPT_CHECK_OPS};
+use crate::statement_log;
 
 pub async fn get_databases(_params: &ConnectionParams) -> Result<Vec<String>, String> {
     // SQLite doesn't support multiple databases in the same connection
     Ok(vec![])
 }
 
+pub async fn get_schemas(_params: &ConnectionParams) -> Result<Vec<String>, String> {
+    // SQLite has no schemas to select between
+    Ok(vec![])
+}
+
 pub async fn get_tables(params: &ConnectionParams) -> Result<Vec<TableInfo>, String> {
     let pool = get_sqlite_pool(params).await?;
     let rows = sqlx::query(
@@ -26,6 +48,23 @@ pub async fn get_tables(params: &ConnectionParams) -> Result<Vec<TableInfo>, Str
         .collect())
 }
 
+pub async fn get_views(params: &ConnectionParams) -> Result<Vec<ViewInfo>, String> {
+    let pool = get_sqlite_pool(params).await?;
+    let rows = sqlx::query(
+        "SELECT name, sql as definition FROM sqlite_master WHERE type='view' ORDER BY name ASC",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(rows
+        .iter()
+        .map(|r| ViewInfo {
+            name: r.try_get("name").unwrap_or_default(),
+            definition: r.try_get("definition").ok().flatten(),
+        })
+        .collect())
+}
+
 pub async fn get_columns(
     params: &ConnectionParams,
     table_name: &str,
@@ -35,7 +74,7 @@ pub async fn get_columns(
     // PRAGMA table_info doesn't explicitly say "AUTO_INCREMENT"
     // But INTEGER PRIMARY KEY is implicitly so in sqlite.
     // Also if 'pk' > 0 and type is INTEGER.
-    let query = format!("PRAGMA table_info('{}')", table_name);
+    let query = format!("PRAGMA table_info({})", quote_identifier(table_name, '"'));
 
     let rows = sqlx::query(&query)
         .fetch_all(&pool)
@@ -57,6 +96,9 @@ pub async fn get_columns(
                 is_pk: pk > 0,
                 is_nullable: notnull == 0,
                 is_auto_increment: is_auto,
+                enum_values: None,
+                default_value: r.try_get("dflt_value").ok().flatten(),
+                comment: None,
             }
         })
         .collect())
@@ -68,7 +110,10 @@ pub async fn get_foreign_keys(
 ) -> Result<Vec<ForeignKey>, String> {
     let pool = get_sqlite_pool(params).await?;
 
-    let query = format!("PRAGMA foreign_key_list('{}')", table_name);
+    let query = format!(
+        "PRAGMA foreign_key_list({})",
+        quote_identifier(table_name, '"')
+    );
     let rows = sqlx::query(&query)
         .fetch_all(&pool)
         .await
@@ -105,7 +150,7 @@ pub async fn get_all_columns_batch(
     let mut result: HashMap<String, Vec<TableColumn>> = HashMap::new();
 
     for table_name in table_names {
-        let query = format!("PRAGMA table_info('{}')", table_name);
+        let query = format!("PRAGMA table_info({})", quote_identifier(table_name, '"'));
         let rows = sqlx::query(&query)
             .fetch_all(&pool)
             .await
@@ -122,6 +167,9 @@ pub async fn get_all_columns_batch(
                     is_pk: pk > 0,
                     is_nullable: notnull == 0,
                     is_auto_increment: false, // SQLite doesn't expose this via table_info easily, typically AUTOINCREMENT on INTEGER PRIMARY KEY
+                    enum_values: None,
+                    default_value: r.try_get("dflt_value").ok().flatten(),
+                    comment: None,
                 }
             })
             .collect();
@@ -142,7 +190,10 @@ pub async fn get_all_foreign_keys_batch(
     let mut result: HashMap<String, Vec<ForeignKey>> = HashMap::new();
 
     for table_name in table_names {
-        let query = format!("PRAGMA foreign_key_list('{}')", table_name);
+        let query = format!(
+            "PRAGMA foreign_key_list({})",
+            quote_identifier(table_name, '"')
+        );
         let rows = sqlx::query(&query)
             .fetch_all(&pool)
             .await
@@ -179,7 +230,7 @@ pub async fn get_indexes(
 ) -> Result<Vec<Index>, String> {
     let pool = get_sqlite_pool(params).await?;
 
-    let list_query = format!("PRAGMA index_list('{}')", table_name);
+    let list_query = format!("PRAGMA index_list({})", quote_identifier(table_name, '"'));
     let indexes = sqlx::query(&list_query)
         .fetch_all(&pool)
         .await
@@ -192,7 +243,7 @@ pub async fn get_indexes(
         let unique: i32 = idx_row.try_get("unique").unwrap_or(0);
         let origin: String = idx_row.try_get("origin").unwrap_or_default(); // pk for primary key
 
-        let info_query = format!("PRAGMA index_info('{}')", name);
+        let info_query = format!("PRAGMA index_info({})", quote_identifier(&name, '"'));
         let info_rows = sqlx::query(&info_query)
             .fetch_all(&pool)
             .await
@@ -212,48 +263,359 @@ pub async fn get_indexes(
     Ok(result)
 }
 
-pub async fn delete_record(
+/// Timing, event and body of a `CREATE TRIGGER` statement, which is all SQLite
+/// keeps of a trigger. Without BEFORE, AFTER or INSTEAD OF a trigger fires before.
+fn trigger_parts(sql: &str) -> (String, String, String) {
+    let words: Vec<String> = top_level_tokens(sql)
+        .iter()
+        .map(|w| w.to_ascii_uppercase())
+        .collect();
+    // CREATE [TEMP] TRIGGER [IF NOT EXISTS] name
+    let mut i = words
+        .iter()
+        .position(|w| w == "TRIGGER")
+        .map_or(words.len(), |i| i + 1);
+    if words.get(i).map(String::as_str) == Some("IF") {
+        i += 3;
+    }
+    i += 1;
+
+    let timing = match words.get(i).map(String::as_str) {
+        Some("INSTEAD") => {
+            i += 2;
+            "INSTEAD OF".to_string()
+        }
+        Some(w @ ("BEFORE" | "AFTER")) => {
+            i += 1;
+            w.to_string()
+        }
+        _ => "BEFORE".to_string(),
+    };
+    let event = words.get(i).cloned().unwrap_or_default();
+
+    let body = body_start(sql).map_or(sql, |at| &sql[at..]);
+
+    (timing, event, body.trim().to_string())
+}
+
+/// Byte offset of the first `BEGIN` keyword outside quotes
+fn body_start(sql: &str) -> Option<usize> {
+    let bytes = sql.as_bytes();
+    let is_word = |b: u8| b.is_ascii_alphanumeric() || b == b'_';
+    let mut quote = None;
+    for (at, &b) in bytes.iter().enumerate() {
+        match quote {
+            Some(close) if b == close => quote = None,
+            Some(_) => {}
+            None if matches!(b, b'\'' | b'"' | b'`') => quote = Some(b),
+            None if b == b'[' => quote = Some(b']'),
+            None => {
+                let keyword = bytes[at..]
+                    .get(..5)
+                    .is_some_and(|w| w.eq_ignore_ascii_case(b"BEGIN"));
+                if keyword
+                    && (at == 0 || !is_word(bytes[at - 1]))
+                    && bytes.get(at + 5).map_or(true, |&b| !is_word(b))
+                {
+                    return Some(at);
+                }
+            }
+        }
+    }
+    None
+}
+
+pub async fn get_triggers(
+    params: &ConnectionParams,
+    table_name: &str,
+) -> Result<Vec<Trigger>, String> {
+    let pool = get_sqlite_pool(params).await?;
+
+    let rows = sqlx::query(
+        "SELECT name, sql FROM sqlite_master WHERE type = 'trigger' AND tbl_name = ? ORDER BY name",
+    )
+    .bind(table_name)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .iter()
+        .map(|r| {
+            let sql: String = r.try_get("sql").unwrap_or_default();
+            let (timing, event, statement) = trigger_parts(&sql);
+            Trigger {
+                name: r.try_get("name").unwrap_or_default(),
+                timing,
+                event,
+                statement,
+                is_enabled: None,
+            }
+        })
+        .collect())
+}
+
+/// SQLite has no stored procedures or functions
+pub async fn get_routines(
+    _params: &ConnectionParams,
+    _schema: Option<&str>,
+) -> Result<Vec<RoutineInfo>, String> {
+    Ok(Vec::new())
+}
+
+pub async fn get_routine_definition(
+    _params: &ConnectionParams,
+    _schema: Option<&str>,
+    name: &str,
+) -> Result<String, String> {
+    Err(format!("Routine '{}' not found", name))
+}
+
+pub async fn call_procedure(
+    _params: &ConnectionParams,
+    _name: &str,
+    _args: &[serde_json::Value],
+) -> Result<ProcedureResult, String> {
+    Err("SQLite has no stored procedures".into())
+}
+
+pub async fn create_index(
     params: &ConnectionParams,
     table: &str,
-    pk_col: &str,
-    pk_val: serde_json::Value,
-) -> Result<u64, String> {
+    name: &str,
+    columns: &[String],
+    unique: bool,
+) -> Result<(), String> {
+    let sql = create_index_sql(&quote_identifier(table, '"'), name, columns, unique, '"')?;
+    run_ddl(params, &sql).await
+}
+
+/// SQLite index names are unique per database, so `_table` is not needed
+pub async fn drop_index(params: &ConnectionParams, _table: &str, name: &str) -> Result<(), String> {
+    let sql = format!("DROP INDEX {}", quote_identifier(name, '"'));
+    run_ddl(params, &sql).await
+}
+
+/// SQLite cannot fill existing rows of a new NOT NULL column without a default, so
+/// that case is rejected up front with a clearer message than SQLite's own
+pub async fn add_column(
+    params: &ConnectionParams,
+    table: &str,
+    column: &ColumnDefinition,
+) -> Result<(), String> {
+    let has_default = column
+        .default_value
+        .as_deref()
+        .is_some_and(|d| !d.trim().is_empty());
+    if !column.is_nullable && !has_default {
+        return Err("SQLite cannot add a NOT NULL column without a default value".into());
+    }
+    let sql = format!(
+        "ALTER TABLE {} ADD COLUMN {}",
+        quote_identifier(table, '"'),
+        column_definition_sql(column, '"')?
+    );
+    run_ddl(params, &sql).await
+}
+
+/// Needs SQLite 3.35+; older libraries report an explicit error instead of a syntax error
+pub async fn drop_column(
+    params: &ConnectionParams,
+    table: &str,
+    column: &str,
+) -> Result<(), String> {
+    require_sqlite_version(params, (3, 35, 0), "DROP COLUMN").await?;
+    let sql = format!(
+        "ALTER TABLE {} DROP COLUMN {}",
+        quote_identifier(table, '"'),
+        quote_identifier(column, '"')
+    );
+    run_ddl(params, &sql).await
+}
+
+/// Needs SQLite 3.25+
+pub async fn rename_column(
+    params: &ConnectionParams,
+    table: &str,
+    column: &str,
+    new_name: &str,
+) -> Result<(), String> {
+    require_sqlite_version(params, (3, 25, 0), "RENAME COLUMN").await?;
+    let sql = format!(
+        "ALTER TABLE {} RENAME COLUMN {} TO {}",
+        quote_identifier(table, '"'),
+        quote_identifier(column, '"'),
+        quote_identifier(new_name, '"')
+    );
+    run_ddl(params, &sql).await
+}
+
+async fn require_sqlite_version(
+    params: &ConnectionParams,
+    min: (u32, u32, u32),
+    feature: &str,
+) -> Result<(), String> {
     let pool = get_sqlite_pool(params).await?;
+    let version: String = sqlx::query_scalar("SELECT sqlite_version()")
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut parts = version.split('.').map(|p| p.parse::<u32>().unwrap_or(0));
+    let current = (
+        parts.next().unwrap_or(0),
+        parts.next().unwrap_or(0),
+        parts.next().unwrap_or(0),
+    );
+    if current < min {
+        return Err(format!(
+            "{} requires SQLite {}.{}.{} or newer (found {})",
+            feature, min.0, min.1, min.2, version
+        ));
+    }
+    Ok(())
+}
+
+async fn run_ddl(params: &ConnectionParams, sql: &str) -> Result<(), String> {
+    let pool = get_sqlite_pool(params).await?;
+    statement_log::log_statement(params, sql, &[]);
+    sqlx::query(sql)
+        .execute(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
 
-    let query = format!("DELETE FROM \"{}\" WHERE \"{}\" = ?", table, pk_col);
+/// Appends `WHERE pk1 = ? AND pk2 = ?` covering every primary key column.
+/// Columns are sorted so the generated statement is stable for composite keys.
+fn push_pk_filter(
+    qb: &mut sqlx::QueryBuilder<'_, sqlx::Sqlite>,
+    pk: &HashMap<String, serde_json::Value>,
+) -> Result<(), String> {
+    if pk.is_empty() {
+        return Err("No primary key values provided".into());
+    }
 
-    let result = match pk_val {
-        serde_json::Value::Number(n) => {
-            if n.is_i64() {
-                sqlx::query(&query)
-                    .bind(n.as_i64())
-                    .execute(&pool)
-                    .await
-            } else {
-                sqlx::query(&query)
-                    .bind(n.as_f64())
-                    .execute(&pool)
-                    .await
+    let mut pk_cols: Vec<&String> = pk.keys().collect();
+    pk_cols.sort();
+
+    for (i, col) in pk_cols.into_iter().enumerate() {
+        qb.push(if i == 0 { " WHERE " } else { " AND " });
+        qb.push(format!("{} = ", quote_identifier(col, '"')));
+        match &pk[col] {
+            serde_json::Value::Number(n) => {
+                if n.is_i64() {
+                    qb.push_bind(n.as_i64());
+                } else {
+                    qb.push_bind(n.as_f64());
+                }
+            }
+            serde_json::Value::String(s) => {
+                qb.push_bind(s.clone());
             }
+            _ => return Err("Unsupported PK type".into()),
         }
-        serde_json::Value::String(s) => sqlx::query(&query).bind(s).execute(&pool).await,
-        _ => return Err("Unsupported PK type".into()),
-    };
+    }
 
-    result.map(|r| r.rows_affected()).map_err(|e| e.to_string())
+    Ok(())
 }
 
-pub async fn update_record(
+/// The DELETE run by `delete_record`
+fn delete_statement(
+    table: &str,
+    pk: &HashMap<String, serde_json::Value>,
+) -> Result<sqlx::QueryBuilder<'static, sqlx::Sqlite>, String> {
+    let mut qb = sqlx::QueryBuilder::new(format!("DELETE FROM {}", quote_identifier(table, '"')));
+    push_pk_filter(&mut qb, pk)?;
+    Ok(qb)
+}
+
+pub async fn delete_record(
     params: &ConnectionParams,
     table: &str,
-    pk_col: &str,
-    pk_val: serde_json::Value,
-    col_name: &str,
-    new_val: serde_json::Value,
+    pk: &HashMap<String, serde_json::Value>,
+) -> Result<u64, String> {
+    let pool = get_sqlite_pool(params).await?;
+    let mut qb = delete_statement(table, pk)?;
+
+    statement_log::log_statement(params, qb.sql(), &sorted_bindings(pk));
+    let query = qb.build();
+    let result = query.execute(&pool).await.map_err(|e| e.to_string())?;
+    Ok(result.rows_affected())
+}
+
+/// The statement `delete_record` would run, without running it
+pub async fn delete_record_sql(
+    _params: &ConnectionParams,
+    table: &str,
+    pk: &HashMap<String, serde_json::Value>,
+) -> Result<StatementPreview, String> {
+    Ok(StatementPreview {
+        sql: delete_statement(table, pk)?.into_sql(),
+        bindings: sorted_bindings(pk),
+    })
+}
+
+/// `WHERE` clause of `delete_by_match`: `col IS NULL` for JSON `null`, otherwise
+/// `col = value` bound as `push_value` binds it
+fn push_match_filter(
+    qb: &mut sqlx::QueryBuilder<'_, sqlx::Sqlite>,
+    conditions: &HashMap<String, serde_json::Value>,
+) -> Result<(), String> {
+    if conditions.is_empty() {
+        return Err("No values to match provided".into());
+    }
+
+    for (i, (col, val)) in sorted_bindings(conditions).into_iter().enumerate() {
+        qb.push(if i == 0 { " WHERE " } else { " AND " });
+        let col = quote_identifier(&col, '"');
+        if val.is_null() {
+            qb.push(format!("{} IS NULL", col));
+        } else {
+            qb.push(format!("{} = ", col));
+            push_value(&mut qb.separated(""), val)
+                .map_err(|e| format!("{} for column {}", e, col))?;
+        }
+    }
+    Ok(())
+}
+
+/// Deletes the rows matching `conditions` in a transaction that is only committed
+/// when the row count passes `check_match_count`
+pub async fn delete_by_match(
+    params: &ConnectionParams,
+    table: &str,
+    conditions: &HashMap<String, serde_json::Value>,
+    allow_multiple: bool,
 ) -> Result<u64, String> {
     let pool = get_sqlite_pool(params).await?;
+    let mut qb = sqlx::QueryBuilder::new(format!("DELETE FROM {}", quote_identifier(table, '"')));
+    push_match_filter(&mut qb, conditions)?;
+
+    statement_log::log_statement(params, qb.sql(), &match_bindings(conditions));
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let result = qb
+        .build()
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| e.to_string())?;
+    // Returning early drops the transaction, which rolls the delete back
+    check_match_count(result.rows_affected(), allow_multiple)?;
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(result.rows_affected())
+}
 
-    let mut qb = sqlx::QueryBuilder::new(format!("UPDATE \"{}\" SET \"{}\" = ", table, col_name));
+/// The UPDATE run by `update_record`
+fn update_statement(
+    table: &str,
+    pk: &HashMap<String, serde_json::Value>,
+    col_name: &str,
+    new_val: serde_json::Value,
+) -> Result<sqlx::QueryBuilder<'static, sqlx::Sqlite>, String> {
+    let mut qb = sqlx::QueryBuilder::new(format!(
+        "UPDATE {} SET {} = ",
+        quote_identifier(table, '"'),
+        quote_identifier(col_name, '"')
+    ));
 
     match new_val {
         serde_json::Value::Number(n) => {
@@ -275,25 +637,107 @@ pub async fn update_record(
         _ => return Err("Unsupported Value type".into()),
     }
 
-    qb.push(format!(" WHERE \"{}\" = ", pk_col));
+    push_pk_filter(&mut qb, pk)?;
+    Ok(qb)
+}
+
+/// Sets one column of the rows matching `pk`. JSON `null` writes SQL NULL, while a
+/// string is bound as-is, so `""` stores an empty string rather than NULL
+pub async fn update_record(
+    params: &ConnectionParams,
+    table: &str,
+    pk: &HashMap<String, serde_json::Value>,
+    col_name: &str,
+    new_val: serde_json::Value,
+) -> Result<u64, String> {
+    let pool = get_sqlite_pool(params).await?;
+    let bindings = update_bindings(pk, col_name, &new_val);
+    let mut qb = update_statement(table, pk, col_name, new_val)?;
+
+    statement_log::log_statement(params, qb.sql(), &bindings);
+    let query = qb.build();
+    let result = query.execute(&pool).await.map_err(|e| e.to_string())?;
+    Ok(result.rows_affected())
+}
+
+/// The statement `update_record` would run, without running it
+pub async fn update_record_sql(
+    _params: &ConnectionParams,
+    table: &str,
+    pk: &HashMap<String, serde_json::Value>,
+    col_name: &str,
+    new_val: serde_json::Value,
+) -> Result<StatementPreview, String> {
+    Ok(StatementPreview {
+        bindings: update_bindings(pk, col_name, &new_val),
+        sql: update_statement(table, pk, col_name, new_val)?.into_sql(),
+    })
+}
+
+/// Runs `edits` in order in one transaction, rolling back on the first failure
+pub async fn update_records(
+    params: &ConnectionParams,
+    edits: &[RecordEdit],
+) -> Result<BatchEditResult, String> {
+    let pool = get_sqlite_pool(params).await?;
+    run_record_edits(params, &pool, edits, |edit| {
+        update_statement(&edit.table, &edit.pk, &edit.column, edit.value.clone())
+    })
+    .await
+}
 
-    match pk_val {
+/// Binds a JSON value as the next item of a separated list (NULL is written inline)
+fn push_value<'args, Sep: std::fmt::Display>(
+    separated: &mut Separated<'_, 'args, sqlx::Sqlite, Sep>,
+    val: serde_json::Value,
+) -> Result<(), String> {
+    match val {
         serde_json::Value::Number(n) => {
             if n.is_i64() {
-                qb.push_bind(n.as_i64());
+                separated.push_bind(n.as_i64());
             } else {
-                qb.push_bind(n.as_f64());
+                separated.push_bind(n.as_f64());
             }
         }
         serde_json::Value::String(s) => {
-            qb.push_bind(s);
+            separated.push_bind(s);
+        }
+        serde_json::Value::Bool(b) => {
+            separated.push_bind(b);
+        }
+        serde_json::Value::Null => {
+            separated.push("NULL");
         }
-        _ => return Err("Unsupported PK type".into()),
+        _ => return Err("Unsupported value type".into()),
     }
+    Ok(())
+}
 
-    let query = qb.build();
-    let result = query.execute(&pool).await.map_err(|e| e.to_string())?;
-    Ok(result.rows_affected())
+/// The INSERT run by `insert_record`, with columns in `bindings` order
+fn insert_statement(
+    table: &str,
+    bindings: &[(String, serde_json::Value)],
+) -> Result<sqlx::QueryBuilder<'static, sqlx::Sqlite>, String> {
+    if bindings.is_empty() {
+        return Err("No data to insert".into());
+    }
+
+    let cols: Vec<String> = bindings
+        .iter()
+        .map(|(k, _)| quote_identifier(k, '"'))
+        .collect();
+    let mut qb = sqlx::QueryBuilder::new(format!(
+        "INSERT INTO {} ({}) VALUES (",
+        quote_identifier(table, '"'),
+        cols.join(", ")
+    ));
+
+    let mut separated = qb.separated(", ");
+    for (_, val) in bindings {
+        push_value(&mut separated, val.clone())?;
+    }
+    separated.push_unseparated(")");
+    Ok(qb)
 }
 
 pub async fn insert_record(
@@ -302,51 +746,130 @@ pub async fn insert_record(
     data: std::collections::HashMap<String, serde_json::Value>,
 ) -> Result<u64, String> {
     let pool = get_sqlite_pool(params).await?;
+    let bindings = sorted_bindings(&data);
+    let mut qb = insert_statement(table, &bindings)?;
 
-    let mut cols = Vec::new();
-    let mut vals = Vec::new();
+    statement_log::log_statement(params, qb.sql(), &bindings);
+    let query = qb.build();
+    let result = query.execute(&pool).await.map_err(|e| e.to_string())?;
+    Ok(result.rows_affected())
+}
 
-    for (k, v) in data {
-        cols.push(format!("\"{}\"", k));
-        vals.push(v);
-    }
+/// The statement `insert_record` would run, without running it
+pub async fn insert_record_sql(
+    _params: &ConnectionParams,
+    table: &str,
+    data: std::collections::HashMap<String, serde_json::Value>,
+) -> Result<StatementPreview, String> {
+    let bindings = sorted_bindings(&data);
+    Ok(StatementPreview {
+        sql: insert_statement(table, &bindings)?.into_sql(),
+        bindings,
+    })
+}
 
+/// Inserts many rows in one transaction, as multi-row INSERTs of up to 1000 rows.
+/// Columns are the union over all rows; a row missing a column gets NULL.
+pub async fn insert_records(
+    params: &ConnectionParams,
+    table: &str,
+    rows: Vec<HashMap<String, serde_json::Value>>,
+) -> Result<u64, String> {
+    let cols = union_columns(&rows);
     if cols.is_empty() {
         return Err("No data to insert".into());
     }
+    let quoted_cols: Vec<String> = cols.iter().map(|c| quote_identifier(c, '"')).collect();
+
+    let pool = get_sqlite_pool(params).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut affected_rows = 0;
+
+    for chunk in rows.chunks(insert_chunk_size(cols.len())) {
+        let mut logged_bindings = Vec::new();
+        let mut qb = sqlx::QueryBuilder::new(format!(
+            "INSERT INTO {} ({}) VALUES ",
+            quote_identifier(table, '"'),
+            quoted_cols.join(", ")
+        ));
+
+        for (i, row) in chunk.iter().enumerate() {
+            if i > 0 {
+                qb.push(", ");
+            }
+            qb.push("(");
+            let mut separated = qb.separated(", ");
+            for col in &cols {
+                let val = row.get(col).cloned().unwrap_or(serde_json::Value::Null);
+                logged_bindings.push((col.clone(), val.clone()));
+                push_value(&mut separated, val)?;
+            }
+            separated.push_unseparated(")");
+        }
+
+        statement_log::log_statement(params, qb.sql(), &logged_bindings);
+        let result = qb
+            .build()
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| e.to_string())?;
+        affected_rows += result.rows_affected();
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(affected_rows)
+}
+
+/// Inserts the row, or updates its other columns when a row with the same
+/// `conflict_cols` values exists (these must match a primary key or unique index)
+pub async fn upsert_record(
+    params: &ConnectionParams,
+    table: &str,
+    conflict_cols: &[String],
+    data: HashMap<String, serde_json::Value>,
+) -> Result<u64, String> {
+    validate_upsert(conflict_cols, &data)?;
+    let pool = get_sqlite_pool(params).await?;
+
+    let mut entries: Vec<(String, serde_json::Value)> = data.into_iter().collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    let cols: Vec<String> = entries
+        .iter()
+        .map(|(k, _)| quote_identifier(k, '"'))
+        .collect();
 
     let mut qb = sqlx::QueryBuilder::new(format!(
-        "INSERT INTO \"{}\" ({}) VALUES (",
-        table,
+        "INSERT INTO {} ({}) VALUES (",
+        quote_identifier(table, '"'),
         cols.join(", ")
     ));
-
     let mut separated = qb.separated(", ");
-    for val in vals {
-        match val {
-            serde_json::Value::Number(n) => {
-                if n.is_i64() {
-                    separated.push_bind(n.as_i64());
-                } else {
-                    separated.push_bind(n.as_f64());
-                }
-            }
-            serde_json::Value::String(s) => {
-                separated.push_bind(s);
-            }
-            serde_json::Value::Bool(b) => {
-                separated.push_bind(b);
-            }
-            serde_json::Value::Null => {
-                separated.push("NULL");
-            }
-            _ => return Err("Unsupported value type".into()),
-        }
+    for (_, val) in &entries {
+        push_value(&mut separated, val.clone())?;
     }
     separated.push_unseparated(")");
 
-    let query = qb.build();
-    let result = query.execute(&pool).await.map_err(|e| e.to_string())?;
+    let conflict: Vec<String> = conflict_cols
+        .iter()
+        .map(|c| quote_identifier(c, '"'))
+        .collect();
+    let updates: Vec<String> = entries
+        .iter()
+        .filter(|(k, _)| !conflict_cols.contains(k))
+        .map(|(k, _)| format!("{0} = EXCLUDED.{0}", quote_identifier(k, '"')))
+        .collect();
+    if updates.is_empty() {
+        qb.push(format!(" ON CONFLICT ({}) DO NOTHING", conflict.join(", ")));
+    } else {
+        qb.push(format!(
+            " ON CONFLICT ({}) DO UPDATE SET {}",
+            conflict.join(", "),
+            updates.join(", ")
+        ));
+    }
+
+    statement_log::log_statement(params, qb.sql(), &entries);
+    let result = qb.build().execute(&pool).await.map_err(|e| e.to_string())?;
     Ok(result.rows_affected())
 }
 
@@ -371,37 +894,251 @@ fn remove_order_by(query: &str) -> String {
     }
 }
 
+/// The table's stored `CREATE TABLE` followed by its `CREATE INDEX` statements.
+/// Indexes SQLite creates for PRIMARY KEY / UNIQUE have no SQL and are skipped.
 pub async fn get_table_ddl(
     params: &ConnectionParams,
     table_name: &str,
 ) -> Result<String, String> {
     let pool = get_sqlite_pool(params).await?;
     let query = "SELECT sql FROM sqlite_master WHERE type='table' AND name = ?";
-    let row: (String,) = sqlx::query_as(query)
+    let row: Option<(String,)> = sqlx::query_as(query)
         .bind(table_name)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    let (table_sql,) = row.ok_or_else(|| format!("Table {} not found", table_name))?;
+
+    let indexes: Vec<String> = sqlx::query_scalar(
+        "SELECT sql FROM sqlite_master WHERE type='index' AND tbl_name = ? AND sql IS NOT NULL ORDER BY name",
+    )
+    .bind(table_name)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut ddl = format!("{};", table_sql);
+    for index in indexes {
+        ddl.push_str(&format!("\n{};", index));
+    }
+    Ok(ddl)
+}
+
+/// SQLite keeps no row statistics, so the count is always exact.
+pub async fn get_table_row_count(
+    params: &ConnectionParams,
+    table_name: &str,
+    _exact: bool,
+) -> Result<TableRowCount, String> {
+    let pool = get_sqlite_pool(params).await?;
+    let query = format!("SELECT COUNT(*) FROM {}", quote_identifier(table_name, '"'));
+    let count: i64 = sqlx::query_scalar(&query)
         .fetch_one(&pool)
         .await
         .map_err(|e| e.to_string())?;
-    Ok(format!("{};", row.0))
+    Ok(TableRowCount {
+        count,
+        estimated: false,
+    })
 }
 
-pub async fn execute_query(
+pub async fn explain_query(
     params: &ConnectionParams,
     query: &str,
-    limit: Option<u32>,
-    page: u32,
-) -> Result<QueryResult, String> {
+) -> Result<serde_json::Value, String> {
+    let pool = get_sqlite_pool(params).await?;
+    let rows = sqlx::query(&format!("EXPLAIN QUERY PLAN {}", query))
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Each row is one plan node; `parent` links it to the enclosing node
+    Ok(serde_json::Value::Array(
+        rows.iter()
+            .map(|r| {
+                serde_json::json!({
+                    "id": r.try_get::<i64, _>("id").unwrap_or_default(),
+                    "parent": r.try_get::<i64, _>("parent").unwrap_or_default(),
+                    "detail": r.try_get::<String, _>("detail").unwrap_or_default(),
+                })
+            })
+            .collect(),
+    ))
+}
+
+/// Runs the statements in one transaction and returns each statement's affected
+/// row count. The first failure rolls everything back.
+pub async fn execute_script(
+    params: &ConnectionParams,
+    statements: &[&str],
+) -> Result<Vec<u64>, String> {
+    let pool = get_sqlite_pool(params).await?;
+    let mut tx = pool.begin().await.map_err(|e| e.to_string())?;
+    let mut affected_rows = Vec::with_capacity(statements.len());
+
+    for (i, statement) in statements.iter().enumerate() {
+        statement_log::log_statement(params, statement, &[]);
+        let interrupt = StatementInterrupt::install(&mut tx, params.statement_timeout_secs).await?;
+        let result = sqlx::query(statement).execute(&mut *tx).await;
+        interrupt.uninstall(&mut tx).await?;
+        match result {
+            Ok(result) => affected_rows.push(result.rows_affected()),
+            Err(e) => {
+                tx.rollback().await.map_err(|e| e.to_string())?;
+                return Err(script_error(
+                    i,
+                    statement,
+                    interrupt.describe(e.to_string()),
+                ));
+            }
+        }
+    }
+
+    tx.commit().await.map_err(|e| e.to_string())?;
+    Ok(affected_rows)
+}
+
+/// Runs `query` with `values` bound to its (driver-native) placeholders. Row-returning
+/// statements stop after `limit` rows and set `truncated`.
+pub async fn execute_query_with_params(
+    params: &ConnectionParams,
+    query: &str,
+    values: &[serde_json::Value],
+    limit: Option<u32>,
+) -> Result<QueryResult, String> {
     let pool = get_sqlite_pool(params).await?;
     let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    let interrupt = StatementInterrupt::install(&mut conn, params.statement_timeout_secs).await?;
+    execute_with_json_params(
+        params,
+        &mut conn,
+        query,
+        values,
+        limit,
+        extract_sqlite_value,
+    )
+    .await
+    .map_err(|e| interrupt.describe(e))
+}
+
+/// Makes SQLite interrupt what a connection runs once this is dropped, or once the
+/// connection's statement timeout has passed. `cancel_query` aborts the query task,
+/// which drops it, so a long scan stops on the worker thread instead of running to
+/// completion. The pool clears the handler before the connection is handed out again.
+struct StatementInterrupt {
+    _alive: Arc<()>,
+    timed_out: Arc<AtomicBool>,
+    timeout_secs: Option<u64>,
+}
+
+impl StatementInterrupt {
+    async fn install(
+        conn: &mut SqliteConnection,
+        timeout_secs: Option<u64>,
+    ) -> Result<Self, String> {
+        let alive = Arc::new(());
+        let token = Arc::downgrade(&alive);
+        let timed_out = Arc::new(AtomicBool::new(false));
+        let flag = timed_out.clone();
+        let deadline = timeout_secs.map(|secs| Instant::now() + Duration::from_secs(secs));
+        conn.lock_handle()
+            .await
+            .map_err(|e| e.to_string())?
+            .set_progress_handler(SQLITE_INTERRUPT_CHECK_OPS, move || {
+                if deadline.is_some_and(|d| Instant::now() >= d) {
+                    flag.store(true, Ordering::Relaxed);
+                    return false;
+                }
+                token.strong_count() > 0
+            });
+        Ok(Self {
+            _alive: alive,
+            timed_out,
+            timeout_secs,
+        })
+    }
+
+    /// Removes the handler, so statements that follow on the connection run freely
+    async fn uninstall(&self, conn: &mut SqliteConnection) -> Result<(), String> {
+        conn.lock_handle()
+            .await
+            .map_err(|e| e.to_string())?
+            .remove_progress_handler();
+        Ok(())
+    }
+
+    /// Reports `err` as a statement timeout if the deadline interrupted the statement
+    fn describe(&self, err: String) -> String {
+        match self.timeout_secs {
+            Some(secs) if self.timed_out.load(Ordering::Relaxed) => {
+                statement_timeout_error(secs, &err)
+            }
+            _ => err,
+        }
+    }
+}
+
+pub async fn execute_query(
+    params: &ConnectionParams,
+    query: &str,
+    limit: Option<u32>,
+    page: u32,
+    offset: Option<u32>,
+    on_batch: Option<RowBatchCallback<'_>>,
+) -> Result<QueryResult, String> {
+    statement_log::log_statement(params, query, &[]);
+
+    let pool = get_sqlite_pool(params).await?;
+    let mut conn = pool.acquire().await.map_err(|e| e.to_string())?;
+    let interrupt = StatementInterrupt::install(&mut conn, params.statement_timeout_secs).await?;
+    run_query(&mut conn, params, query, limit, page, offset, on_batch)
+        .await
+        .map_err(|e| interrupt.describe(e))
+}
+
+/// `execute_query` on a connection whose interrupt is already installed
+async fn run_query(
+    conn: &mut SqliteConnection,
+    params: &ConnectionParams,
+    query: &str,
+    limit: Option<u32>,
+    page: u32,
+    offset: Option<u32>,
+    on_batch: Option<RowBatchCallback<'_>>,
+) -> Result<QueryResult, String> {
+    let started = Instant::now();
+
+    // INSERT/UPDATE/DELETE/DDL: run with execute() so the affected row count is reported
+    if !returns_rows(query) {
+        let result = sqlx::query(query)
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| e.to_string())?;
+        return Ok(QueryResult {
+            columns: Vec::new(),
+            column_types: Vec::new(),
+            rows: Vec::new(),
+            affected_rows: result.rows_affected(),
+            truncated: false,
+            pagination: None,
+            has_more: false,
+            column_refs: None,
+            execution_ms: started.elapsed().as_millis() as u64,
+            row_count: None,
+            auto_limit: None,
+        });
+    }
 
     let is_select = query.trim_start().to_uppercase().starts_with("SELECT");
     let mut pagination: Option<Pagination> = None;
     let final_query: String;
     let mut manual_limit = limit;
+    let mut auto_limit = None;
 
-    if is_select && limit.is_some() {
+    // Queries that already carry their own LIMIT are run as-is (capped by manual_limit)
+    if is_select && limit.is_some() && !has_limit_clause(query) {
         let l = limit.unwrap();
-        let offset = (page - 1) * l;
+        let offset = offset.unwrap_or_else(|| calculate_offset(page, l));
 
         let count_q = format!("SELECT COUNT(*) FROM ({})", query);
         let count_res = sqlx::query(&count_q).fetch_one(&mut *conn).await;
@@ -421,27 +1158,44 @@ pub async fn execute_query(
         // Extract ORDER BY clause from the original query to preserve sorting
         let order_by_clause = extract_order_by(query);
 
+        // LIMIT l + 1: the extra row (trimmed below) tells whether another page exists
         if !order_by_clause.is_empty() {
             // Remove ORDER BY from inner query and add it to outer query
             let query_without_order = remove_order_by(query);
             final_query = format!(
                 "SELECT * FROM ({}) {} LIMIT {} OFFSET {}",
-                query_without_order, order_by_clause, l, offset
+                query_without_order, order_by_clause, l + 1, offset
             );
         } else {
-            final_query = format!("SELECT * FROM ({}) LIMIT {} OFFSET {}", query, l, offset);
+            final_query = format!(
+                "SELECT * FROM ({}) LIMIT {} OFFSET {}",
+                query,
+                l + 1,
+                offset
+            );
         }
 
         manual_limit = None;
+    } else if let Some(limited) = auto_limit_query(query, params.default_row_limit) {
+        // A bare SELECT under the connection's auto-limit: the row fetched past it
+        // stops the read and shows the result was cut
+        final_query = limited;
+        manual_limit = params.default_row_limit;
+        auto_limit = params.default_row_limit;
     } else {
         final_query = query.to_string();
     }
 
+    // Paginated results are already bounded by the page size, so only unbounded
+    // queries are streamed to the caller in batches
+    let on_batch = if pagination.is_some() { None } else { on_batch };
+    let mut buffer = RowBuffer::new(on_batch);
+
     // Streaming
     let mut rows_stream = sqlx::query(&final_query).fetch(&mut *conn);
 
     let mut columns: Vec<String> = Vec::new();
-    let mut json_rows = Vec::new();
+    let mut column_types: Vec<String> = Vec::new();
     let mut truncated = false;
 
     use futures::stream::StreamExt;
@@ -451,10 +1205,11 @@ pub async fn execute_query(
             Ok(row) => {
                 if columns.is_empty() {
                     columns = row.columns().iter().map(|c| c.name().to_string()).collect();
+                    column_types = column_type_names(&row);
                 }
 
                 if let Some(l) = manual_limit {
-                    if json_rows.len() >= l as usize {
+                    if buffer.row_count() >= l as usize {
                         truncated = true;
                         break;
                     }
@@ -465,17 +1220,1478 @@ pub async fn execute_query(
                     let val = extract_sqlite_value(&row, i);
                     json_row.push(val);
                 }
-                json_rows.push(json_row);
+                if !buffer.push(&columns, json_row) {
+                    truncated = true;
+                    break;
+                }
             }
             Err(e) => return Err(e.to_string()),
         }
     }
 
+    drop(rows_stream);
+
+    // No row to read the columns from: ask the database what the statement returns
+    if columns.is_empty() {
+        if let Ok(describe) = conn.describe(&final_query).await {
+            (columns, column_types) = described_columns(&describe);
+        }
+    }
+
+    let fetched = buffer.row_count();
+    let mut json_rows = buffer.finish(&columns);
+
+    let has_more = match &pagination {
+        Some(p) if json_rows.len() > p.page_size as usize => {
+            json_rows.truncate(p.page_size as usize);
+            true
+        }
+        Some(_) => false,
+        None => truncated,
+    };
+
+    // Streamed rows went to `on_batch` and are not part of the result
+    let row_count = if on_batch.is_some() {
+        fetched
+    } else {
+        json_rows.len()
+    };
     Ok(QueryResult {
         columns,
+        column_types,
         rows: json_rows,
         affected_rows: 0,
         truncated,
         pagination,
+        has_more,
+        column_refs: None,
+        execution_ms: started.elapsed().as_millis() as u64,
+        row_count: Some(row_count),
+        auto_limit: auto_limit.filter(|&n| truncated && row_count >= n as usize),
     })
 }
+
+/// `Driver` for SQLite database files
+pub struct SqliteDriver;
+
+#[async_trait]
+impl Driver for SqliteDriver {
+    async fn get_databases(&self, params: &ConnectionParams) -> Result<Vec<String>, String> {
+        get_databases(params).await
+    }
+
+    async fn get_schemas(&self, params: &ConnectionParams) -> Result<Vec<String>, String> {
+        get_schemas(params).await
+    }
+
+    async fn get_tables(&self, params: &ConnectionParams) -> Result<Vec<TableInfo>, String> {
+        get_tables(params).await
+    }
+
+    async fn get_views(&self, params: &ConnectionParams) -> Result<Vec<ViewInfo>, String> {
+        get_views(params).await
+    }
+
+    async fn get_columns(
+        &self,
+        params: &ConnectionParams,
+        table_name: &str,
+    ) -> Result<Vec<TableColumn>, String> {
+        get_columns(params, table_name).await
+    }
+
+    async fn get_foreign_keys(
+        &self,
+        params: &ConnectionParams,
+        table_name: &str,
+    ) -> Result<Vec<ForeignKey>, String> {
+        get_foreign_keys(params, table_name).await
+    }
+
+    async fn get_all_columns_batch(
+        &self,
+        params: &ConnectionParams,
+        table_names: &[String],
+    ) -> Result<HashMap<String, Vec<TableColumn>>, String> {
+        get_all_columns_batch(params, table_names).await
+    }
+
+    async fn get_all_foreign_keys_batch(
+        &self,
+        params: &ConnectionParams,
+        table_names: &[String],
+    ) -> Result<HashMap<String, Vec<ForeignKey>>, String> {
+        get_all_foreign_keys_batch(params, table_names).await
+    }
+
+    async fn get_indexes(
+        &self,
+        params: &ConnectionParams,
+        table_name: &str,
+    ) -> Result<Vec<Index>, String> {
+        get_indexes(params, table_name).await
+    }
+
+    async fn get_triggers(
+        &self,
+        params: &ConnectionParams,
+        table_name: &str,
+    ) -> Result<Vec<Trigger>, String> {
+        get_triggers(params, table_name).await
+    }
+
+    async fn get_routines(
+        &self,
+        params: &ConnectionParams,
+        schema: Option<&str>,
+    ) -> Result<Vec<RoutineInfo>, String> {
+        get_routines(params, schema).await
+    }
+
+    async fn get_routine_definition(
+        &self,
+        params: &ConnectionParams,
+        schema: Option<&str>,
+        name: &str,
+    ) -> Result<String, String> {
+        get_routine_definition(params, schema, name).await
+    }
+
+    async fn call_procedure(
+        &self,
+        params: &ConnectionParams,
+        name: &str,
+        args: &[serde_json::Value],
+    ) -> Result<ProcedureResult, String> {
+        call_procedure(params, name, args).await
+    }
+
+    async fn create_index(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        name: &str,
+        columns: &[String],
+        unique: bool,
+    ) -> Result<(), String> {
+        create_index(params, table, name, columns, unique).await
+    }
+
+    async fn drop_index(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        name: &str,
+    ) -> Result<(), String> {
+        drop_index(params, table, name).await
+    }
+
+    async fn add_column(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        column: &ColumnDefinition,
+    ) -> Result<(), String> {
+        add_column(params, table, column).await
+    }
+
+    async fn drop_column(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        column: &str,
+    ) -> Result<(), String> {
+        drop_column(params, table, column).await
+    }
+
+    async fn rename_column(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        column: &str,
+        new_name: &str,
+    ) -> Result<(), String> {
+        rename_column(params, table, column, new_name).await
+    }
+
+    async fn delete_record(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        pk: &HashMap<String, serde_json::Value>,
+    ) -> Result<u64, String> {
+        delete_record(params, table, pk).await
+    }
+
+    async fn delete_record_sql(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        pk: &HashMap<String, serde_json::Value>,
+    ) -> Result<StatementPreview, String> {
+        delete_record_sql(params, table, pk).await
+    }
+
+    async fn delete_by_match(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        conditions: &HashMap<String, serde_json::Value>,
+        allow_multiple: bool,
+    ) -> Result<u64, String> {
+        delete_by_match(params, table, conditions, allow_multiple).await
+    }
+
+    async fn update_record(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        pk: &HashMap<String, serde_json::Value>,
+        col_name: &str,
+        new_val: serde_json::Value,
+    ) -> Result<u64, String> {
+        update_record(params, table, pk, col_name, new_val).await
+    }
+
+    async fn update_record_sql(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        pk: &HashMap<String, serde_json::Value>,
+        col_name: &str,
+        new_val: serde_json::Value,
+    ) -> Result<StatementPreview, String> {
+        update_record_sql(params, table, pk, col_name, new_val).await
+    }
+
+    async fn update_records(
+        &self,
+        params: &ConnectionParams,
+        edits: &[RecordEdit],
+    ) -> Result<BatchEditResult, String> {
+        update_records(params, edits).await
+    }
+
+    async fn insert_record(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        data: HashMap<String, serde_json::Value>,
+    ) -> Result<u64, String> {
+        insert_record(params, table, data).await
+    }
+
+    async fn insert_record_sql(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        data: HashMap<String, serde_json::Value>,
+    ) -> Result<StatementPreview, String> {
+        insert_record_sql(params, table, data).await
+    }
+
+    async fn insert_records(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        rows: Vec<HashMap<String, serde_json::Value>>,
+    ) -> Result<u64, String> {
+        insert_records(params, table, rows).await
+    }
+
+    async fn upsert_record(
+        &self,
+        params: &ConnectionParams,
+        table: &str,
+        conflict_cols: &[String],
+        data: HashMap<String, serde_json::Value>,
+    ) -> Result<u64, String> {
+        upsert_record(params, table, conflict_cols, data).await
+    }
+
+    async fn get_table_ddl(
+        &self,
+        params: &ConnectionParams,
+        table_name: &str,
+    ) -> Result<String, String> {
+        get_table_ddl(params, table_name).await
+    }
+
+    async fn get_table_row_count(
+        &self,
+        params: &ConnectionParams,
+        table_name: &str,
+        exact: bool,
+    ) -> Result<TableRowCount, String> {
+        get_table_row_count(params, table_name, exact).await
+    }
+
+    async fn explain_query(
+        &self,
+        params: &ConnectionParams,
+        query: &str,
+    ) -> Result<serde_json::Value, String> {
+        explain_query(params, query).await
+    }
+
+    async fn execute_script(
+        &self,
+        params: &ConnectionParams,
+        statements: &[&str],
+    ) -> Result<Vec<u64>, String> {
+        execute_script(params, statements).await
+    }
+
+    async fn execute_query_with_params(
+        &self,
+        params: &ConnectionParams,
+        query: &str,
+        values: &[serde_json::Value],
+        limit: Option<u32>,
+    ) -> Result<QueryResult, String> {
+        execute_query_with_params(params, query, values, limit).await
+    }
+
+    async fn execute_query(
+        &self,
+        params: &ConnectionParams,
+        query: &str,
+        limit: Option<u32>,
+        page: u32,
+        offset: Option<u32>,
+        on_batch: Option<RowBatchCallback<'_>>,
+    ) -> Result<QueryResult, String> {
+        execute_query(params, query, limit, page, offset, on_batch).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drivers::common::{QUERY_BATCH_SIZE, STATEMENT_TIMEOUT_ERROR};
+    use std::sync::Mutex;
+    use tempfile::NamedTempFile;
+
+    fn sqlite_params(file: &NamedTempFile) -> ConnectionParams {
+        ConnectionParams {
+            driver: "sqlite".to_string(),
+            database: file.path().to_string_lossy().into_owned(),
+            ..Default::default()
+        }
+    }
+
+    async fn run(params: &ConnectionParams, query: &str) -> QueryResult {
+        execute_query(params, query, None, 1, None, None)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_execute_query_reports_affected_rows() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+
+        run(
+            &params,
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, active INTEGER)",
+        )
+        .await;
+        run(&params, "INSERT INTO users (active) VALUES (0), (0), (1)").await;
+
+        let res = run(
+            &params,
+            "-- bulk\nUPDATE users SET active = 1 WHERE active = 0",
+        )
+        .await;
+        assert_eq!(res.affected_rows, 2);
+        assert!(res.columns.is_empty());
+        assert!(res.rows.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_execute_query_fetches_rows_for_select_and_returning() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+
+        run(
+            &params,
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)",
+        )
+        .await;
+
+        let res = run(
+            &params,
+            "INSERT INTO users (name) VALUES ('ada') RETURNING id",
+        )
+        .await;
+        assert_eq!(res.columns, vec!["id"]);
+        assert_eq!(res.rows, vec![vec![serde_json::Value::from(1)]]);
+
+        let res = run(
+            &params,
+            "WITH named AS (SELECT name FROM users) SELECT * FROM named",
+        )
+        .await;
+        assert_eq!(res.affected_rows, 0);
+        assert_eq!(res.rows, vec![vec![serde_json::Value::from("ada")]]);
+    }
+
+    #[tokio::test]
+    async fn test_execute_query_paginates_with_has_more() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+
+        run(&params, "CREATE TABLE nums (n INTEGER)").await;
+        run(&params, "INSERT INTO nums VALUES (1), (2), (3), (4), (5)").await;
+
+        let query = "SELECT n FROM nums ORDER BY n";
+        let res = execute_query(&params, query, Some(2), 1, None, None)
+            .await
+            .unwrap();
+        assert_eq!(res.rows.len(), 2);
+        assert!(res.has_more);
+
+        let res = execute_query(&params, query, Some(2), 1, Some(3), None)
+            .await
+            .unwrap();
+        assert_eq!(
+            res.rows,
+            vec![
+                vec![serde_json::Value::from(4)],
+                vec![serde_json::Value::from(5)]
+            ]
+        );
+        assert!(!res.has_more);
+
+        // A query with its own LIMIT is not wrapped a second time
+        let limited = "SELECT n FROM nums ORDER BY n LIMIT 3";
+        let res = execute_query(&params, limited, Some(2), 1, None, None)
+            .await
+            .unwrap();
+        assert!(res.pagination.is_none());
+        assert_eq!(res.rows.len(), 2);
+        assert!(res.has_more);
+    }
+
+    #[tokio::test]
+    async fn test_execute_query_applies_auto_limit() {
+        let file = NamedTempFile::new().unwrap();
+        let mut params = sqlite_params(&file);
+        params.default_row_limit = Some(3);
+
+        run(&params, "CREATE TABLE nums (n INTEGER)").await;
+        run(&params, "INSERT INTO nums VALUES (1), (2), (3), (4), (5)").await;
+
+        let res = run(&params, "SELECT n FROM nums ORDER BY n -- all").await;
+        assert_eq!(res.rows.len(), 3);
+        assert_eq!(res.auto_limit, Some(3));
+        assert!(res.truncated);
+
+        // Results within the limit, or limited by the query itself, are not flagged
+        let res = run(&params, "SELECT n FROM nums WHERE n < 3").await;
+        assert_eq!(res.rows.len(), 2);
+        assert_eq!(res.auto_limit, None);
+        let res = run(&params, "SELECT n FROM nums LIMIT 4").await;
+        assert_eq!(res.rows.len(), 4);
+        assert_eq!(res.auto_limit, None);
+    }
+
+    #[tokio::test]
+    async fn test_execute_query_streams_batches() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+
+        run(&params, "CREATE TABLE nums (n INTEGER)").await;
+        run(
+            &params,
+            "WITH RECURSIVE seq(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < 1200) \
+             INSERT INTO nums SELECT n FROM seq",
+        )
+        .await;
+
+        let batches = Mutex::new(Vec::new());
+        let on_batch = |_: &[String], rows: &[Vec<serde_json::Value>]| {
+            batches.lock().unwrap().push(rows.len());
+            true
+        };
+        let res = execute_query(
+            &params,
+            "SELECT n FROM nums",
+            None,
+            1,
+            None,
+            Some(&on_batch),
+        )
+        .await
+        .unwrap();
+        // Streamed rows are only handed to the callback, not kept in the result
+        assert!(res.rows.is_empty());
+        assert_eq!(res.row_count, Some(1200));
+        assert_eq!(
+            *batches.lock().unwrap(),
+            vec![QUERY_BATCH_SIZE, QUERY_BATCH_SIZE, 200]
+        );
+
+        // Returning false from the callback stops the fetch after that batch
+        let stop = |_: &[String], _: &[Vec<serde_json::Value>]| false;
+        let res = execute_query(&params, "SELECT n FROM nums", None, 1, None, Some(&stop))
+            .await
+            .unwrap();
+        assert_eq!(res.row_count, Some(QUERY_BATCH_SIZE));
+        assert!(res.truncated);
+    }
+
+    #[tokio::test]
+    async fn test_explain_query_returns_plan_nodes() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+
+        run(
+            &params,
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)",
+        )
+        .await;
+
+        let plan = explain_query(&params, "SELECT * FROM users WHERE id = 1")
+            .await
+            .unwrap();
+        let nodes = plan.as_array().unwrap();
+        assert!(!nodes.is_empty());
+        assert!(nodes[0]["detail"].as_str().unwrap().contains("users"));
+    }
+
+    async fn create_user_roles(params: &ConnectionParams) {
+        run(
+            params,
+            "CREATE TABLE user_roles (user_id INTEGER, role_id INTEGER, note TEXT, \
+             PRIMARY KEY (user_id, role_id))",
+        )
+        .await;
+        run(
+            params,
+            "INSERT INTO user_roles VALUES (1, 1, 'a'), (1, 2, 'b'), (2, 1, 'c')",
+        )
+        .await;
+    }
+
+    fn composite_pk(user_id: i64, role_id: i64) -> HashMap<String, serde_json::Value> {
+        HashMap::from([
+            ("user_id".to_string(), serde_json::Value::from(user_id)),
+            ("role_id".to_string(), serde_json::Value::from(role_id)),
+        ])
+    }
+
+    #[tokio::test]
+    async fn test_update_record_with_composite_key() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+        create_user_roles(&params).await;
+
+        let updated = update_record(
+            &params,
+            "user_roles",
+            &composite_pk(1, 2),
+            "note",
+            serde_json::Value::from("changed"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(updated, 1);
+
+        let res = run(
+            &params,
+            "SELECT note FROM user_roles ORDER BY user_id, role_id",
+        )
+        .await;
+        let notes: Vec<&str> = res.rows.iter().map(|r| r[0].as_str().unwrap()).collect();
+        assert_eq!(notes, vec!["a", "changed", "c"]);
+    }
+
+    #[tokio::test]
+    async fn test_get_primary_keys() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+        create_user_roles(&params).await;
+        run(&params, "CREATE TABLE notes (body TEXT)").await;
+
+        assert_eq!(
+            SqliteDriver
+                .get_primary_keys(&params, "user_roles")
+                .await
+                .unwrap(),
+            vec!["user_id".to_string(), "role_id".to_string()]
+        );
+        assert!(SqliteDriver
+            .get_primary_keys(&params, "notes")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_table_ddl_includes_explicit_indexes() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+        run(
+            &params,
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, sku TEXT UNIQUE, name TEXT)",
+        )
+        .await;
+        run(&params, "CREATE INDEX idx_items_name ON items (name)").await;
+
+        assert_eq!(
+            get_table_ddl(&params, "items").await.unwrap(),
+            "CREATE TABLE items (id INTEGER PRIMARY KEY, sku TEXT UNIQUE, name TEXT);\n\
+             CREATE INDEX idx_items_name ON items (name);"
+        );
+        assert_eq!(
+            get_table_ddl(&params, "missing").await.unwrap_err(),
+            "Table missing not found"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_table_row_count_is_always_exact() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+        create_user_roles(&params).await;
+
+        for exact in [false, true] {
+            let count = get_table_row_count(&params, "user_roles", exact)
+                .await
+                .unwrap();
+            assert_eq!(count.count, 3);
+            assert!(!count.estimated);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_sql_previews_without_running() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+        create_user_roles(&params).await;
+
+        let delete = delete_record_sql(&params, "user_roles", &composite_pk(1, 2))
+            .await
+            .unwrap();
+        assert_eq!(
+            delete.sql,
+            "DELETE FROM \"user_roles\" WHERE \"role_id\" = ? AND \"user_id\" = ?"
+        );
+        assert_eq!(
+            delete.bindings,
+            vec![
+                ("role_id".to_string(), serde_json::json!(2)),
+                ("user_id".to_string(), serde_json::json!(1)),
+            ]
+        );
+
+        let update = update_record_sql(
+            &params,
+            "user_roles",
+            &composite_pk(1, 2),
+            "note",
+            serde_json::Value::Null,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            update.sql,
+            "UPDATE \"user_roles\" SET \"note\" = NULL WHERE \"role_id\" = ? AND \"user_id\" = ?"
+        );
+        assert_eq!(
+            update.bindings[0],
+            ("note".to_string(), serde_json::Value::Null)
+        );
+
+        let data = HashMap::from([
+            ("user_id".to_string(), serde_json::json!(9)),
+            ("note".to_string(), serde_json::json!("x")),
+            ("role_id".to_string(), serde_json::json!(9)),
+        ]);
+        let insert = insert_record_sql(&params, "user_roles", data)
+            .await
+            .unwrap();
+        assert_eq!(
+            insert.sql,
+            "INSERT INTO \"user_roles\" (\"note\", \"role_id\", \"user_id\") VALUES (?, ?, ?)"
+        );
+        assert_eq!(insert.bindings.len(), 3);
+
+        // Nothing was written
+        let res = run(
+            &params,
+            "SELECT COUNT(*) FROM user_roles WHERE note IS NULL OR user_id = 9",
+        )
+        .await;
+        assert_eq!(res.rows[0][0], serde_json::json!(0));
+    }
+
+    #[tokio::test]
+    async fn test_record_operations_with_quotes_in_identifiers() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+        run(
+            &params,
+            "CREATE TABLE \"a\"\"b\" (\"id\"\"x\" INTEGER PRIMARY KEY, \"foo`bar\" TEXT)",
+        )
+        .await;
+        let table = "a\"b";
+
+        let data = HashMap::from([
+            ("id\"x".to_string(), serde_json::json!(1)),
+            ("foo`bar".to_string(), serde_json::json!("before")),
+        ]);
+        assert_eq!(insert_record(&params, table, data).await.unwrap(), 1);
+
+        let pk = HashMap::from([("id\"x".to_string(), serde_json::json!(1))]);
+        let updated = update_record(&params, table, &pk, "foo`bar", serde_json::json!("after"))
+            .await
+            .unwrap();
+        assert_eq!(updated, 1);
+        let res = run(&params, "SELECT \"foo`bar\" FROM \"a\"\"b\"").await;
+        assert_eq!(res.rows[0][0], serde_json::json!("after"));
+
+        let columns = get_columns(&params, table).await.unwrap();
+        assert!(columns.iter().any(|c| c.name == "id\"x" && c.is_pk));
+
+        assert_eq!(delete_record(&params, table, &pk).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_update_record_null_and_empty_string() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+        run(
+            &params,
+            "CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT)",
+        )
+        .await;
+        run(&params, "INSERT INTO notes (id, body) VALUES (1, NULL)").await;
+        let pk = HashMap::from([("id".to_string(), serde_json::Value::from(1))]);
+        let check = "SELECT body IS NULL, body FROM notes WHERE id = 1";
+
+        // NULL -> ''
+        update_record(&params, "notes", &pk, "body", serde_json::Value::from(""))
+            .await
+            .unwrap();
+        let res = run(&params, check).await;
+        assert_eq!(res.rows[0][0], serde_json::json!(0));
+        assert_eq!(res.rows[0][1], serde_json::json!(""));
+
+        // '' -> NULL
+        update_record(&params, "notes", &pk, "body", serde_json::Value::Null)
+            .await
+            .unwrap();
+        let res = run(&params, check).await;
+        assert_eq!(res.rows[0][0], serde_json::json!(1));
+        assert!(res.rows[0][1].is_null());
+    }
+
+    #[tokio::test]
+    async fn test_update_records_commits_or_rolls_back_together() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+        run(
+            &params,
+            "CREATE TABLE notes (id INTEGER PRIMARY KEY, body TEXT NOT NULL)",
+        )
+        .await;
+        run(&params, "INSERT INTO notes VALUES (1, 'a'), (2, 'b')").await;
+        let edit = |id: i64, value: serde_json::Value| RecordEdit {
+            table: "notes".to_string(),
+            pk: HashMap::from([("id".to_string(), serde_json::Value::from(id))]),
+            column: "body".to_string(),
+            value,
+        };
+        let bodies = "SELECT body FROM notes ORDER BY id";
+
+        let res = update_records(&params, &[edit(1, "x".into()), edit(2, "y".into())])
+            .await
+            .unwrap();
+        assert!(res.committed);
+        assert!(res.results.iter().all(|r| r.success && r.rows_affected == 1));
+        assert_eq!(
+            run(&params, bodies).await.rows,
+            vec![vec![serde_json::json!("x")], vec![serde_json::json!("y")]]
+        );
+
+        // The NOT NULL violation rolls back the first edit and skips the last
+        let edits = [
+            edit(1, "z".into()),
+            edit(2, serde_json::Value::Null),
+            edit(1, "w".into()),
+        ];
+        let res = update_records(&params, &edits).await.unwrap();
+        assert!(!res.committed);
+        assert!(res.results[0].success);
+        assert!(res.results[1].error.as_deref().unwrap().contains("NOT NULL"));
+        assert!(!res.results[2].success);
+        assert_eq!(
+            run(&params, bodies).await.rows,
+            vec![vec![serde_json::json!("x")], vec![serde_json::json!("y")]]
+        );
+
+        // A stale edit of a deleted row is a conflict that rolls the batch back
+        let res = update_records(&params, &[edit(1, "z".into()), edit(3, "v".into())])
+            .await
+            .unwrap();
+        assert!(!res.committed);
+        assert!(res.results[1]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("changed or deleted"));
+        assert_eq!(
+            run(&params, bodies).await.rows,
+            vec![vec![serde_json::json!("x")], vec![serde_json::json!("y")]]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delete_by_match_without_primary_key() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+        run(&params, "CREATE TABLE log (level INTEGER, msg TEXT)").await;
+        run(
+            &params,
+            "INSERT INTO log VALUES (NULL, 'a'), (NULL, 'a'), (1, 'b')",
+        )
+        .await;
+        let count = "SELECT COUNT(*) FROM log";
+        let conditions = |level: serde_json::Value, msg: &str| {
+            HashMap::from([
+                ("level".to_string(), level),
+                ("msg".to_string(), serde_json::Value::from(msg)),
+            ])
+        };
+
+        let err = delete_by_match(&params, "log", &conditions(5.into(), "b"), false)
+            .await
+            .unwrap_err();
+        assert!(err.contains("No row matches"));
+
+        // NULL matches with IS NULL; two matches are rolled back unless allowed
+        let nulls = conditions(serde_json::Value::Null, "a");
+        let err = delete_by_match(&params, "log", &nulls, false)
+            .await
+            .unwrap_err();
+        assert!(err.contains("match 2 rows"));
+        assert_eq!(run(&params, count).await.rows[0][0], serde_json::json!(3));
+
+        let deleted = delete_by_match(&params, "log", &nulls, true).await.unwrap();
+        assert_eq!(deleted, 2);
+        let deleted = delete_by_match(&params, "log", &conditions(1.into(), "b"), false)
+            .await
+            .unwrap();
+        assert_eq!(deleted, 1);
+        assert_eq!(run(&params, count).await.rows[0][0], serde_json::json!(0));
+    }
+
+    #[tokio::test]
+    async fn test_get_columns_reports_default_values() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+        run(
+            &params,
+            "CREATE TABLE item (id INTEGER PRIMARY KEY, status TEXT DEFAULT 'new', created TEXT DEFAULT CURRENT_TIMESTAMP)",
+        )
+        .await;
+
+        let columns = get_columns(&params, "item").await.unwrap();
+        let defaults: Vec<Option<&str>> = columns
+            .iter()
+            .map(|c| c.default_value.as_deref())
+            .collect();
+        assert_eq!(defaults, vec![None, Some("'new'"), Some("CURRENT_TIMESTAMP")]);
+        assert!(columns.iter().all(|c| c.comment.is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_get_triggers_reads_timing_and_event() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+        run(
+            &params,
+            "CREATE TABLE item (id INTEGER PRIMARY KEY, name TEXT)",
+        )
+        .await;
+        run(&params, "CREATE TABLE item_log (id INTEGER, note TEXT)").await;
+        run(
+            &params,
+            "CREATE TRIGGER IF NOT EXISTS \"item begin\" AFTER UPDATE OF name ON item\nBEGIN INSERT INTO item_log VALUES (new.id, 'renamed'); END",
+        )
+        .await;
+        run(
+            &params,
+            "CREATE TRIGGER item_check DELETE ON item BEGIN SELECT 1; END",
+        )
+        .await;
+
+        let triggers = get_triggers(&params, "item").await.unwrap();
+        let parts: Vec<(&str, &str, &str)> = triggers
+            .iter()
+            .map(|t| (t.name.as_str(), t.timing.as_str(), t.event.as_str()))
+            .collect();
+        assert_eq!(
+            parts,
+            vec![
+                ("item begin", "AFTER", "UPDATE"),
+                ("item_check", "BEFORE", "DELETE")
+            ]
+        );
+        assert_eq!(
+            triggers[0].statement,
+            "BEGIN INSERT INTO item_log VALUES (new.id, 'renamed'); END"
+        );
+        assert!(get_triggers(&params, "item_log").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_delete_record_with_composite_and_single_key() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+        create_user_roles(&params).await;
+
+        let deleted = delete_record(&params, "user_roles", &composite_pk(2, 1))
+            .await
+            .unwrap();
+        assert_eq!(deleted, 1);
+
+        // A single-entry map behaves like the old pk_col/pk_val pair
+        let single = HashMap::from([("user_id".to_string(), serde_json::Value::from(1))]);
+        let deleted = delete_record(&params, "user_roles", &single).await.unwrap();
+        assert_eq!(deleted, 2);
+
+        assert!(delete_record(&params, "user_roles", &HashMap::new())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_execute_script_commits_all_statements() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+
+        let affected = execute_script(
+            &params,
+            &[
+                "CREATE TABLE t (id INTEGER PRIMARY KEY, v TEXT)",
+                "INSERT INTO t (v) VALUES ('a'), ('b')",
+                "UPDATE t SET v = 'c' WHERE id = 2",
+            ],
+        )
+        .await
+        .unwrap();
+        assert_eq!(affected, vec![0, 2, 1]);
+
+        let res = run(&params, "SELECT v FROM t ORDER BY id").await;
+        assert_eq!(
+            res.rows,
+            vec![
+                vec![serde_json::Value::from("a")],
+                vec![serde_json::Value::from("c")]
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_script_rolls_back_on_error() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+        run(&params, "CREATE TABLE t (id INTEGER PRIMARY KEY)").await;
+
+        let err = execute_script(
+            &params,
+            &[
+                "INSERT INTO t (id) VALUES (1)",
+                "INSERT INTO t (id) VALUES (1)",
+            ],
+        )
+        .await
+        .unwrap_err();
+        assert!(err.starts_with("Statement 2 failed"), "{}", err);
+
+        let res = run(&params, "SELECT COUNT(*) FROM t").await;
+        assert_eq!(res.rows, vec![vec![serde_json::Value::from(0)]]);
+    }
+
+    #[tokio::test]
+    async fn test_get_views_lists_views_separately() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+        run(
+            &params,
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, active INTEGER)",
+        )
+        .await;
+        run(
+            &params,
+            "CREATE VIEW active_users AS SELECT id FROM users WHERE active = 1",
+        )
+        .await;
+
+        let tables = get_tables(&params).await.unwrap();
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].name, "users");
+
+        let views = get_views(&params).await.unwrap();
+        assert_eq!(views.len(), 1);
+        assert_eq!(views[0].name, "active_users");
+        assert_eq!(
+            views[0].definition.as_deref(),
+            Some("CREATE VIEW active_users AS SELECT id FROM users WHERE active = 1")
+        );
+    }
+
+    const ENDLESS_COUNT: &str = "WITH RECURSIVE c(x) AS \
+        (SELECT 1 UNION ALL SELECT x + 1 FROM c WHERE x < 1000000000000) \
+        SELECT count(*) FROM c";
+
+    #[tokio::test]
+    async fn test_statement_interrupt_stops_running_statement_on_drop() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+        let pool = get_sqlite_pool(&params).await.unwrap();
+        let mut conn = pool.acquire().await.unwrap();
+
+        let token = StatementInterrupt::install(&mut conn, None).await.unwrap();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            drop(token);
+        });
+
+        let result = tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            sqlx::query(ENDLESS_COUNT).fetch_one(&mut *conn),
+        )
+        .await
+        .expect("statement was not interrupted");
+        let err = result
+            .err()
+            .expect("statement finished instead of being interrupted");
+        assert!(err.to_string().contains("interrupted"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_statement_timeout_interrupts_query() {
+        let file = NamedTempFile::new().unwrap();
+        let mut params = sqlite_params(&file);
+        params.statement_timeout_secs = Some(1);
+
+        let result = tokio::time::timeout(
+            std::time::Duration::from_secs(10),
+            execute_query(&params, ENDLESS_COUNT, None, 1, None, None),
+        )
+        .await
+        .expect("statement ran past its timeout");
+        let err = result.expect_err("statement finished before its timeout");
+        assert!(err.starts_with(STATEMENT_TIMEOUT_ERROR), "{}", err);
+
+        // Quick statements on the same pool are unaffected
+        assert_eq!(run(&params, "SELECT 1").await.rows.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_aborted_query_does_not_affect_next_query() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+
+        let task = tokio::spawn({
+            let params = params.clone();
+            async move { execute_query(&params, ENDLESS_COUNT, None, 1, None, None).await }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        task.abort();
+        assert!(task.await.unwrap_err().is_cancelled());
+
+        let res =
+            tokio::time::timeout(std::time::Duration::from_secs(10), run(&params, "SELECT 1"))
+                .await
+                .expect("pool connection stayed busy after cancel");
+        assert_eq!(res.rows, vec![vec![serde_json::Value::from(1)]]);
+    }
+
+    #[tokio::test]
+    async fn test_execute_query_with_params_binds_values() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+        run(
+            &params,
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT)",
+        )
+        .await;
+
+        let inserted = execute_query_with_params(
+            &params,
+            "INSERT INTO users (id, name) VALUES (?, ?), (?, ?)",
+            &[
+                serde_json::Value::from(1),
+                serde_json::Value::from("ada'); DROP TABLE users; --"),
+                serde_json::Value::from(2),
+                serde_json::Value::Null,
+            ],
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(inserted.affected_rows, 2);
+
+        let res = execute_query_with_params(
+            &params,
+            "SELECT name FROM users WHERE id >= ? ORDER BY id",
+            &[serde_json::Value::from(1)],
+            Some(1),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            res.rows,
+            vec![vec![serde_json::Value::from("ada'); DROP TABLE users; --")]]
+        );
+        assert!(res.truncated);
+    }
+
+    #[tokio::test]
+    async fn test_insert_records_unions_columns() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+        run(
+            &params,
+            "CREATE TABLE t (id INTEGER PRIMARY KEY, a TEXT, b INTEGER)",
+        )
+        .await;
+
+        let mut rows: Vec<HashMap<String, serde_json::Value>> = (1..=1500)
+            .map(|i| {
+                [("id".to_string(), serde_json::Value::from(i))]
+                    .into_iter()
+                    .collect()
+            })
+            .collect();
+        rows[0].insert("a".to_string(), serde_json::Value::from("x"));
+        rows[1].insert("b".to_string(), serde_json::Value::from(2));
+
+        assert_eq!(insert_records(&params, "t", rows).await.unwrap(), 1500);
+
+        let res = run(&params, "SELECT id, a, b FROM t WHERE id <= 2 ORDER BY id").await;
+        assert_eq!(
+            res.rows,
+            vec![
+                vec![1.into(), "x".into(), serde_json::Value::Null],
+                vec![2.into(), serde_json::Value::Null, 2.into()],
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_insert_records_rolls_back_all_chunks() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+        run(&params, "CREATE TABLE t (id INTEGER PRIMARY KEY)").await;
+
+        // The duplicate sits in the second chunk, after the first has been inserted
+        let rows: Vec<HashMap<String, serde_json::Value>> = (1..=1200)
+            .chain([1200])
+            .map(|i| {
+                [("id".to_string(), serde_json::Value::from(i))]
+                    .into_iter()
+                    .collect()
+            })
+            .collect();
+        assert!(insert_records(&params, "t", rows).await.is_err());
+
+        let res = run(&params, "SELECT COUNT(*) FROM t").await;
+        assert_eq!(res.rows, vec![vec![serde_json::Value::from(0)]]);
+    }
+
+    #[tokio::test]
+    async fn test_upsert_record_updates_on_conflict() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+        run(
+            &params,
+            "CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT)",
+        )
+        .await;
+
+        let conflict = vec!["id".to_string()];
+        for name in ["first", "second"] {
+            let data = [
+                ("id".to_string(), serde_json::Value::from(1)),
+                ("name".to_string(), serde_json::Value::from(name)),
+            ]
+            .into_iter()
+            .collect();
+            upsert_record(&params, "t", &conflict, data).await.unwrap();
+        }
+
+        let res = run(&params, "SELECT id, name FROM t").await;
+        assert_eq!(
+            res.rows,
+            vec![vec![
+                serde_json::Value::from(1),
+                serde_json::Value::from("second")
+            ]]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_execute_query_reports_column_types() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+        run(
+            &params,
+            "CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT)",
+        )
+        .await;
+        run(&params, "INSERT INTO t (name) VALUES ('a')").await;
+
+        let res = run(&params, "SELECT id, name FROM t").await;
+        assert_eq!(res.column_types, vec!["INTEGER", "TEXT"]);
+
+        let empty = run(&params, "SELECT id, name FROM t WHERE 1 = 0").await;
+        assert!(empty.rows.is_empty());
+        assert_eq!(empty.columns, vec!["id", "name"]);
+        assert_eq!(empty.column_types, vec!["INTEGER", "TEXT"]);
+    }
+
+    #[tokio::test]
+    async fn test_execute_query_with_params_keeps_columns_without_rows() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+        run(
+            &params,
+            "CREATE TABLE t (id INTEGER PRIMARY KEY, name TEXT)",
+        )
+        .await;
+
+        let res = execute_query_with_params(
+            &params,
+            "SELECT id, name FROM t WHERE name = ?",
+            &[serde_json::Value::from("missing")],
+            None,
+        )
+        .await
+        .unwrap();
+        assert!(res.rows.is_empty());
+        assert_eq!(res.columns, vec!["id", "name"]);
+        assert_eq!(res.column_types, vec!["INTEGER", "TEXT"]);
+    }
+
+    #[tokio::test]
+    async fn test_create_and_drop_index() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+        run(
+            &params,
+            "CREATE TABLE \"odd\"\"table\" (id INTEGER, email TEXT)",
+        )
+        .await;
+
+        let columns = vec!["email".to_string()];
+        create_index(&params, "odd\"table", "idx\"email", &columns, true)
+            .await
+            .unwrap();
+        let indexes = get_indexes(&params, "odd\"table").await.unwrap();
+        assert_eq!(indexes.len(), 1);
+        assert_eq!(indexes[0].name, "idx\"email");
+        assert!(indexes[0].is_unique);
+
+        drop_index(&params, "odd\"table", "idx\"email")
+            .await
+            .unwrap();
+        assert!(get_indexes(&params, "odd\"table").await.unwrap().is_empty());
+    }
+
+    fn text_column(name: &str, is_nullable: bool, default_value: Option<&str>) -> ColumnDefinition {
+        ColumnDefinition {
+            name: name.to_string(),
+            data_type: "TEXT".to_string(),
+            is_nullable,
+            default_value: default_value.map(str::to_string),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_add_rename_and_drop_column() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+        run(&params, "CREATE TABLE t (id INTEGER PRIMARY KEY)").await;
+        run(&params, "INSERT INTO t (id) VALUES (1)").await;
+
+        add_column(&params, "t", &text_column("status", false, Some("'new'")))
+            .await
+            .unwrap();
+        let res = run(&params, "SELECT status FROM t").await;
+        assert_eq!(res.rows[0][0], "new");
+
+        rename_column(&params, "t", "status", "state")
+            .await
+            .unwrap();
+        let names: Vec<String> = get_columns(&params, "t")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|c| c.name)
+            .collect();
+        assert_eq!(names, vec!["id", "state"]);
+
+        drop_column(&params, "t", "state").await.unwrap();
+        assert_eq!(get_columns(&params, "t").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_add_not_null_column_requires_default() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+        run(&params, "CREATE TABLE t (id INTEGER PRIMARY KEY)").await;
+
+        let err = add_column(&params, "t", &text_column("status", false, None))
+            .await
+            .unwrap_err();
+        assert!(err.contains("default value"));
+    }
+
+    #[tokio::test]
+    async fn test_locked_database_waits_for_busy_timeout() {
+        use sqlx::Connection;
+
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+        run(&params, "CREATE TABLE t (id INTEGER)").await;
+
+        // Another process holds a write lock for a short while
+        let url = crate::drivers::common::build_url(&params).unwrap();
+        let mut other = SqliteConnection::connect(&url).await.unwrap();
+        sqlx::query("BEGIN IMMEDIATE")
+            .execute(&mut other)
+            .await
+            .unwrap();
+        sqlx::query("INSERT INTO t VALUES (1)")
+            .execute(&mut other)
+            .await
+            .unwrap();
+
+        let no_wait = ConnectionParams {
+            sqlite_busy_timeout_ms: Some(0),
+            ..sqlite_params(&file)
+        };
+        let err = execute_query(&no_wait, "INSERT INTO t VALUES (2)", None, 1, None, None)
+            .await
+            .unwrap_err();
+        assert!(err.contains("locked"), "{}", err);
+
+        let release = tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            sqlx::query("COMMIT").execute(&mut other).await.unwrap();
+        });
+        let res = run(&params, "INSERT INTO t VALUES (3)").await;
+        assert_eq!(res.affected_rows, 1);
+        release.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_wal_and_read_only_open() {
+        let file = NamedTempFile::new().unwrap();
+        let params = ConnectionParams {
+            sqlite_wal: Some(true),
+            ..sqlite_params(&file)
+        };
+        let res = run(&params, "PRAGMA journal_mode").await;
+        assert_eq!(res.rows, vec![vec![serde_json::Value::from("wal")]]);
+
+        let read_only = ConnectionParams {
+            read_only: Some(true),
+            ..sqlite_params(&file)
+        };
+        let err = execute_query(
+            &read_only,
+            "CREATE TABLE t (id INTEGER)",
+            None,
+            1,
+            None,
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert!(err.contains("readonly"), "{}", err);
+        // query_only also covers attached databases, which mode=ro does not
+        let res = run(&read_only, "PRAGMA query_only").await;
+        assert_eq!(res.rows, vec![vec![serde_json::Value::from(1)]]);
+    }
+
+    #[tokio::test]
+    async fn test_connection_string_opens_read_only() {
+        let file = NamedTempFile::new().unwrap();
+        let params = ConnectionParams {
+            read_only: Some(true),
+            connection_string: Some(format!("sqlite://{}", file.path().display())),
+            ..sqlite_params(&file)
+        };
+        // Even with query_only lifted, the file itself is open read-only
+        run(&params, "PRAGMA query_only = OFF").await;
+        let err = execute_query(&params, "CREATE TABLE t (id INTEGER)", None, 1, None, None)
+            .await
+            .unwrap_err();
+        assert!(err.contains("readonly"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_attached_database_can_be_joined() {
+        let main = NamedTempFile::new().unwrap();
+        let other = NamedTempFile::new().unwrap();
+        let other_params = sqlite_params(&other);
+        run(&other_params, "CREATE TABLE teams (id INTEGER, name TEXT)").await;
+        run(&other_params, "INSERT INTO teams VALUES (1, 'core')").await;
+
+        let params = ConnectionParams {
+            attach: Some(vec![(
+                "org".to_string(),
+                other.path().to_string_lossy().into_owned(),
+            )]),
+            ..sqlite_params(&main)
+        };
+        run(&params, "CREATE TABLE users (name TEXT, team_id INTEGER)").await;
+        run(&params, "INSERT INTO users VALUES ('ada', 1)").await;
+
+        let res = run(
+            &params,
+            "SELECT u.name, t.name FROM users u JOIN org.teams t ON t.id = u.team_id",
+        )
+        .await;
+        assert_eq!(
+            res.rows,
+            vec![vec![
+                serde_json::Value::from("ada"),
+                serde_json::Value::from("core")
+            ]]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_attach_missing_file_errors() {
+        let file = NamedTempFile::new().unwrap();
+        let params = ConnectionParams {
+            attach: Some(vec![("org".to_string(), "/nonexistent/org.db".to_string())]),
+            ..sqlite_params(&file)
+        };
+        let err = execute_query(&params, "SELECT 1", None, 1, None, None)
+            .await
+            .unwrap_err();
+        assert!(err.contains("not found"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_execute_query_reports_row_count() {
+        let file = NamedTempFile::new().unwrap();
+        let params = sqlite_params(&file);
+
+        let res = run(&params, "CREATE TABLE t (id INTEGER)").await;
+        assert_eq!(res.row_count, None);
+        run(&params, "INSERT INTO t VALUES (1), (2), (3)").await;
+
+        let res = run(&params, "SELECT * FROM t WHERE id > 1").await;
+        assert_eq!(res.row_count, Some(2));
+    }
+}
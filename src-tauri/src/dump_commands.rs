@@ -1,10 +1,9 @@
 use crate::commands::{find_connection_by_id, resolve_connection_params};
-use crate::drivers::{mysql, postgres, sqlite};
-use crate::models::ConnectionParams;
+use crate::drivers::common::{driver_for, ensure_writable, identifier_quote, quote_identifier};
+use crate::drivers::postgres;
+use crate::export::{write_table_inserts, ExportCancel};
 use crate::pool_manager::{get_mysql_pool, get_postgres_pool, get_sqlite_pool};
-use futures::TryStreamExt;
 use serde::{Deserialize, Serialize};
-use sqlx::Row;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufRead, BufReader, BufWriter, Read, Write};
@@ -62,12 +61,9 @@ pub async fn dump_database<R: Runtime>(
         writeln!(writer, "-- Date: {}\n", chrono::Local::now().to_rfc3339()).map_err(|e| e.to_string())?;
 
         // Get tables
-        let all_tables = match driver.as_str() {
-            "mysql" => mysql::get_tables(&params).await?,
-            "postgres" => postgres::get_tables(&params).await?,
-            "sqlite" => sqlite::get_tables(&params).await?,
-            _ => return Err("Unsupported driver".into()),
-        };
+        let db_driver = driver_for(&params)?;
+        let quote = identifier_quote(&driver);
+        let all_tables = db_driver.get_tables(&params).await?;
 
         let tables_to_process: Vec<String> = if let Some(selection) = &options.tables {
             selection.clone()
@@ -75,16 +71,29 @@ pub async fn dump_database<R: Runtime>(
             all_tables.into_iter().map(|t| t.name).collect()
         };
 
-        for table in tables_to_process {
+        // Postgres checks foreign keys as soon as they are created, so they are
+        // dropped before the tables and added back once every table is loaded
+        let mut foreign_keys = Vec::new();
+        if options.structure && driver == "postgres" {
+            for table in &tables_to_process {
+                for (name, def) in postgres::get_foreign_key_constraints(&params, table).await? {
+                    foreign_keys.push((quote_identifier(table, quote), quote_identifier(&name, quote), def));
+                }
+            }
+            for (table, name, _) in &foreign_keys {
+                writeln!(writer, "ALTER TABLE IF EXISTS {} DROP CONSTRAINT IF EXISTS {};", table, name).map_err(|e| e.to_string())?;
+            }
+        }
+
+        for table in &tables_to_process {
             if options.structure {
                 writeln!(writer, "-- Structure for table `{}`", table).map_err(|e| e.to_string())?;
-                writeln!(writer, "DROP TABLE IF EXISTS `{}`;", table).map_err(|e| e.to_string())?;
+                writeln!(writer, "DROP TABLE IF EXISTS {};", quote_identifier(table, quote)).map_err(|e| e.to_string())?;
                 
-                let ddl = match driver.as_str() {
-                    "mysql" => mysql::get_table_ddl(&params, &table).await?,
-                    "postgres" => postgres::get_table_ddl(&params, &table).await?,
-                    "sqlite" => sqlite::get_table_ddl(&params, &table).await?,
-                    _ => return Err("Unsupported driver".into()),
+                let ddl = if driver == "postgres" {
+                    postgres::get_table_ddl_without_foreign_keys(&params, table).await?
+                } else {
+                    db_driver.get_table_ddl(&params, table).await?
                 };
                 
                 writeln!(writer, "{}\n", ddl).map_err(|e| e.to_string())?;
@@ -92,11 +101,18 @@ pub async fn dump_database<R: Runtime>(
 
             if options.data {
                 writeln!(writer, "-- Data for table `{}`", table).map_err(|e| e.to_string())?;
-                export_table_data(&mut writer, &params, &driver, &table).await?;
+                write_table_inserts(&mut writer, &params, table, &ExportCancel::default(), &|_| {}).await?;
                 writeln!(writer, "\n").map_err(|e| e.to_string())?;
             }
         }
 
+        if !foreign_keys.is_empty() {
+            writeln!(writer, "-- Foreign keys").map_err(|e| e.to_string())?;
+        }
+        for (table, name, def) in &foreign_keys {
+            writeln!(writer, "ALTER TABLE {} ADD CONSTRAINT {} {};", table, name, def).map_err(|e| e.to_string())?;
+        }
+
         writer.flush().map_err(|e| e.to_string())?;
         Ok::<(), String>(())
     });
@@ -123,117 +139,6 @@ pub async fn dump_database<R: Runtime>(
     }
 }
 
-async fn export_table_data(
-    writer: &mut BufWriter<File>,
-    params: &ConnectionParams,
-    driver: &str,
-    table: &str,
-) -> Result<(), String> {
-    // We need to implement streaming fetch manually here because we need raw values, not JSON strings if possible,
-    // or we parse JSON strings back to SQL literals.
-    // The current drivers return JSON-like values via `extract_value`.
-    // Let's reuse `extract_value` logic but format for SQL.
-
-    // Ideally we should use specific batch size
-    let query = format!("SELECT * FROM {}", match driver {
-        "mysql" => format!("`{}`", table),
-        "postgres" => format!("\"{}\"", table), // public schema assumed
-        "sqlite" => format!("\"{}\"", table),
-        _ => table.to_string()
-    });
-
-    match driver {
-        "mysql" => {
-            use crate::pool_manager::get_mysql_pool;
-            use crate::drivers::common::extract_mysql_value; // Returns String (JSON value or "NULL")
-            
-            let pool = get_mysql_pool(params).await?;
-            let mut rows = sqlx::query(&query).fetch(&pool);
-            
-            let mut batch = Vec::new();
-            while let Some(row) = rows.try_next().await.map_err(|e| e.to_string())? {
-                 let mut values = Vec::new();
-                 for i in 0..row.columns().len() {
-                     let val = extract_mysql_value(&row, i); 
-                     values.push(escape_sql_value(val));
-                 }
-                 batch.push(format!("({})", values.join(", ")));
-
-                 if batch.len() >= 100 {
-                     writeln!(writer, "INSERT INTO `{}` VALUES {};", table, batch.join(", ")).map_err(|e| e.to_string())?;
-                     batch.clear();
-                 }
-            }
-            if !batch.is_empty() {
-                writeln!(writer, "INSERT INTO `{}` VALUES {};", table, batch.join(", ")).map_err(|e| e.to_string())?;
-            }
-        },
-        "postgres" => {
-            use crate::pool_manager::get_postgres_pool;
-            use crate::drivers::common::extract_postgres_value;
-            
-            let pool = get_postgres_pool(params).await?;
-            let mut rows = sqlx::query(&query).fetch(&pool);
-            
-            let mut batch = Vec::new();
-             while let Some(row) = rows.try_next().await.map_err(|e| e.to_string())? {
-                 let mut values = Vec::new();
-                 for i in 0..row.columns().len() {
-                     let val = extract_postgres_value(&row, i);
-                     values.push(escape_sql_value(val));
-                 }
-                 batch.push(format!("({})", values.join(", ")));
-                 
-                 if batch.len() >= 100 {
-                     writeln!(writer, "INSERT INTO \"{}\" VALUES {};", table, batch.join(", ")).map_err(|e| e.to_string())?;
-                     batch.clear();
-                 }
-            }
-            if !batch.is_empty() {
-                writeln!(writer, "INSERT INTO \"{}\" VALUES {};", table, batch.join(", ")).map_err(|e| e.to_string())?;
-            }
-        },
-        "sqlite" => {
-            use crate::pool_manager::get_sqlite_pool;
-            use crate::drivers::common::extract_sqlite_value;
-            
-            let pool = get_sqlite_pool(params).await?;
-            let mut rows = sqlx::query(&query).fetch(&pool);
-            
-            let mut batch = Vec::new();
-             while let Some(row) = rows.try_next().await.map_err(|e| e.to_string())? {
-                 let mut values = Vec::new();
-                 for i in 0..row.columns().len() {
-                     let val = extract_sqlite_value(&row, i);
-                     values.push(escape_sql_value(val));
-                 }
-                 batch.push(format!("({})", values.join(", ")));
-                 
-                 if batch.len() >= 100 {
-                     writeln!(writer, "INSERT INTO \"{}\" VALUES {};", table, batch.join(", ")).map_err(|e| e.to_string())?;
-                     batch.clear();
-                 }
-            }
-            if !batch.is_empty() {
-                writeln!(writer, "INSERT INTO \"{}\" VALUES {};", table, batch.join(", ")).map_err(|e| e.to_string())?;
-            }
-        },
-        _ => return Err("Unsupported driver".into()),
-    }
-
-    Ok(())
-}
-
-fn escape_sql_value(val: serde_json::Value) -> String {
-    match val {
-        serde_json::Value::Null => "NULL".to_string(),
-        serde_json::Value::Number(n) => n.to_string(),
-        serde_json::Value::Bool(b) => if b { "1".to_string() } else { "0".to_string() }, // Most SQL dialects
-        serde_json::Value::String(s) => format!("'{}'", s.replace("'", "''").replace("\\", "\\\\")), // Basic escaping
-        _ => format!("'{}'", val.to_string().replace("'", "''")), // Fallback
-    }
-}
-
 #[derive(Debug, Serialize, Clone)]
 pub struct ImportProgress {
     pub statements_executed: usize,
@@ -368,6 +273,7 @@ pub async fn import_database<R: Runtime>(
     file_path: String,
 ) -> Result<(), String> {
     let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    ensure_writable(&saved_conn.params)?;
     let params = resolve_connection_params(&saved_conn.params)?;
     let driver = saved_conn.params.driver.clone();
     let app_handle = app.clone();
@@ -536,22 +442,3 @@ fn create_sql_reader(file: File, file_path: &str) -> Result<Box<dyn BufRead + Se
         Ok(Box::new(reader))
     }
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use serde_json::json;
-
-    #[test]
-    fn test_escape_sql_value() {
-        assert_eq!(escape_sql_value(json!(null)), "NULL");
-        assert_eq!(escape_sql_value(json!(123)), "123");
-        assert_eq!(escape_sql_value(json!(12.34)), "12.34");
-        assert_eq!(escape_sql_value(json!(true)), "1");
-        assert_eq!(escape_sql_value(json!(false)), "0");
-        assert_eq!(escape_sql_value(json!("hello")), "'hello'");
-        assert_eq!(escape_sql_value(json!("O'Reilly")), "'O''Reilly'");
-        assert_eq!(escape_sql_value(json!("Back\\slash")), "'Back\\\\slash'");
-        assert_eq!(escape_sql_value(json!("Multi\nLine")), "'Multi\nLine'");
-    }
-}
@@ -1,18 +1,55 @@
-use crate::commands::{find_connection_by_id, resolve_connection_params};
-use crate::drivers::common::{extract_mysql_value, extract_postgres_value, extract_sqlite_value};
+use crate::commands::{
+    expand_ssh_connection_params, find_connection_by_id, resolve_connection_params,
+};
+use crate::drivers::common::{
+    described_columns, driver_for, ensure_query_allowed, extract_mysql_value,
+    extract_postgres_value, extract_sqlite_value, has_limit_clause, has_order_by_clause,
+    identifier_quote, parse_naive_timestamp, parse_timestamp_tz, quote_identifier,
+};
+use crate::models::ConnectionParams;
+use crate::persistence;
 use crate::pool_manager::{get_mysql_pool, get_postgres_pool, get_sqlite_pool};
-use futures::StreamExt;
-use serde::Serialize;
-use sqlx::{Column, Row};
+use chrono::{NaiveDate, NaiveTime};
+use futures::{Stream, StreamExt};
+use rust_xlsxwriter::{Format, Workbook, Worksheet, XlsxError};
+use serde::{Deserialize, Serialize};
+use sqlx::{Column, Executor, Row, TypeInfo, ValueRef};
 use std::collections::HashMap;
-use std::fs::File;
+use std::fs::{self, File, OpenOptions};
 use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use tauri::{AppHandle, Emitter, Runtime, State};
-use tokio::task::AbortHandle;
+use tokio::sync::Notify;
+
+/// Number of rows written between progress events
+const EXPORT_BATCH_SIZE: u64 = 100;
+
+/// `json` writes a single array of objects, `jsonl` one object per line.
+/// `parquet` needs a build with the `parquet` feature.
+const EXPORT_FORMATS: [&str; 5] = ["csv", "json", "jsonl", "xlsx", "parquet"];
+
+/// Formats whose files can be extended, so an interrupted export can be resumed
+const RESUMABLE_FORMATS: [&str; 2] = ["csv", "jsonl"];
+
+/// Rows written between checkpoints of a resumable export
+const CHECKPOINT_ROWS: u64 = 10_000;
+
+/// Rows of an Excel sheet, the header included
+const XLSX_MAX_ROWS: u32 = 1_048_576;
+
+/// Longest text an Excel cell holds; longer values are truncated
+const XLSX_MAX_STRING_CHARS: usize = 32_767;
+
+/// Integers beyond this lose precision as Excel numbers and are written as text
+const XLSX_MAX_EXACT_INT: i64 = 1 << 53;
+
+/// Rows per INSERT statement written by `export_table_inserts`
+const INSERT_BATCH_ROWS: usize = 100;
 
 pub struct ExportCancellationState {
-    pub handles: Arc<Mutex<HashMap<String, AbortHandle>>>,
+    pub handles: Arc<Mutex<HashMap<String, Arc<ExportCancel>>>>,
 }
 
 impl Default for ExportCancellationState {
@@ -23,9 +60,236 @@ impl Default for ExportCancellationState {
     }
 }
 
+/// Cancel signal of one export. The flag is read between rows; the notification
+/// also wakes an export still waiting for the query's first row.
+#[derive(Default)]
+pub struct ExportCancel {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl ExportCancel {
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+        self.notify.notify_waiters();
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Resolves once the export is cancelled
+    async fn cancelled(&self) {
+        loop {
+            // Registered before the flag is read, so a cancel in between still wakes it
+            let notified = self.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// The next row of the export, racing the query against the cancel signal. `None`
+/// at the end of the rows, or once cancelled, which also sets `canceled`.
+async fn next_row<S: Stream + Unpin>(
+    rows: &mut S,
+    cancel: &ExportCancel,
+    canceled: &mut bool,
+) -> Option<S::Item> {
+    if !cancel.is_cancelled() {
+        tokio::select! {
+            row = rows.next() => return row,
+            _ = cancel.cancelled() => {}
+        }
+    }
+    *canceled = true;
+    None
+}
+
+/// Payload of `export-progress`, sent every `EXPORT_BATCH_SIZE` rows
 #[derive(Clone, Serialize)]
 struct ExportProgress {
-    rows_processed: u64,
+    connection_id: String,
+    rows_written: u64,
+    /// Rows expected in total, when known up front (may be an estimate)
+    total_rows: Option<u64>,
+    /// `rows_written` as a share of `total_rows`, capped at 100
+    percentage: Option<f64>,
+}
+
+/// Payload of `export-complete`, sent once the file is closed (also after a cancel)
+#[derive(Clone, Serialize)]
+struct ExportComplete {
+    connection_id: String,
+    file_path: String,
+    rows_written: u64,
+    canceled: bool,
+}
+
+/// Payload of `export-error`
+#[derive(Clone, Serialize)]
+struct ExportError {
+    connection_id: String,
+    file_path: String,
+    error: String,
+}
+
+/// Emits the `export-progress` events of one export
+struct ProgressReporter<'a, R: Runtime> {
+    app: &'a AppHandle<R>,
+    connection_id: &'a str,
+    total_rows: Option<u64>,
+}
+
+impl<R: Runtime> ProgressReporter<'_, R> {
+    fn rows_written(&self, rows_written: u64) {
+        let percentage = self
+            .total_rows
+            .filter(|total| *total > 0)
+            .map(|total| (rows_written as f64 * 100.0 / total as f64).min(100.0));
+        let progress = ExportProgress {
+            connection_id: self.connection_id.to_string(),
+            rows_written,
+            total_rows: self.total_rows,
+            percentage,
+        };
+        self.app.emit("export-progress", progress).unwrap_or(());
+    }
+}
+
+/// CSV output settings. Fields containing the delimiter, quotes or newlines are
+/// always quoted per RFC 4180.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CsvOptions {
+    /// Field separator, e.g. `,`, `;` or `\t` (defaults to `,`)
+    pub delimiter: Option<char>,
+    /// Write the column names as the first row (defaults to true)
+    pub include_header: Option<bool>,
+    /// Write NULL as `\N` instead of an empty field
+    pub null_as_escape: Option<bool>,
+}
+
+impl CsvOptions {
+    fn delimiter_byte(&self) -> Result<u8, String> {
+        let delimiter = self.delimiter.unwrap_or(',');
+        if !delimiter.is_ascii() || delimiter == '"' || delimiter == '\n' || delimiter == '\r' {
+            return Err(format!("Invalid CSV delimiter: {:?}", delimiter));
+        }
+        Ok(delimiter as u8)
+    }
+
+    fn null_value(&self) -> &'static str {
+        if self.null_as_escape.unwrap_or(false) {
+            "\\N"
+        } else {
+            ""
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportResult {
+    pub rows_written: u64,
+    /// True when the export was stopped by `cancel_export`; the file then holds
+    /// the rows written so far (a JSON export is still closed as a valid array).
+    pub canceled: bool,
+}
+
+/// Contents of the `<file>.progress` sidecar of an unfinished CSV or JSONL export:
+/// the first `bytes_written` bytes of the file hold `rows_written` complete rows
+#[derive(Debug, Serialize, Deserialize)]
+struct ExportCheckpoint {
+    query: String,
+    format: String,
+    rows_written: u64,
+    bytes_written: u64,
+}
+
+/// Saves the checkpoints of one resumable export
+struct Checkpointer {
+    path: PathBuf,
+    query: String,
+    format: String,
+}
+
+impl Checkpointer {
+    fn new(file_path: &str, query: &str, format: &str) -> Self {
+        Self {
+            path: progress_path(file_path),
+            query: query.to_string(),
+            format: format.to_string(),
+        }
+    }
+
+    /// Records `rows_written`; the caller has flushed every row to `file`
+    fn save(&self, rows_written: u64, file: &File) -> Result<(), String> {
+        let checkpoint = ExportCheckpoint {
+            query: self.query.clone(),
+            format: self.format.clone(),
+            rows_written,
+            bytes_written: file.metadata().map_err(|e| e.to_string())?.len(),
+        };
+        let json = serde_json::to_string_pretty(&checkpoint).map_err(|e| e.to_string())?;
+        persistence::write_atomic(&self.path, json.as_bytes())
+    }
+
+    fn load(&self) -> Result<Option<ExportCheckpoint>, String> {
+        match fs::read_to_string(&self.path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map(Some)
+                .map_err(|e| format!("Invalid export progress file: {}", e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    fn remove(&self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                eprintln!("[Export] Could not remove {}: {}", self.path.display(), e);
+            }
+        }
+    }
+}
+
+fn progress_path(file_path: &str) -> PathBuf {
+    PathBuf::from(format!("{}.progress", file_path))
+}
+
+/// The query that continues an export from `checkpoint`: the original one, skipping
+/// the rows already written. Only queries with a top-level ORDER BY and no LIMIT of
+/// their own are accepted, since the skipped rows must be the ones written before;
+/// the sort should be on a unique key for that to hold.
+fn resume_query(
+    driver: &str,
+    query: &str,
+    format: &str,
+    checkpoint: &ExportCheckpoint,
+) -> Result<String, String> {
+    if !RESUMABLE_FORMATS.contains(&format) {
+        return Err("Only CSV and JSONL exports can be resumed".into());
+    }
+    if checkpoint.query != query || checkpoint.format != format {
+        return Err("The unfinished export was started with a different query or format".into());
+    }
+    if !has_order_by_clause(query) {
+        return Err(
+            "Resuming needs a query with ORDER BY on a unique key, so rows come back in the same order"
+                .into(),
+        );
+    }
+    if has_limit_clause(query) {
+        return Err("Queries with their own LIMIT cannot be resumed".into());
+    }
+    let offset = checkpoint.rows_written;
+    Ok(match driver {
+        // Neither accepts OFFSET without a LIMIT
+        "mysql" => format!("{}\nLIMIT {}, 18446744073709551615", query, offset),
+        "sqlite" => format!("{}\nLIMIT -1 OFFSET {}", query, offset),
+        _ => format!("{}\nOFFSET {}", query, offset),
+    })
 }
 
 #[tauri::command]
@@ -33,16 +297,21 @@ pub async fn cancel_export(
     state: State<'_, ExportCancellationState>,
     connection_id: String,
 ) -> Result<(), String> {
-    let mut handles = state.handles.lock().unwrap();
-    if let Some(handle) = handles.remove(&connection_id) {
-        handle.abort();
-        Ok(())
-    } else {
-        Ok(())
+    let handles = state.handles.lock().unwrap();
+    if let Some(cancel) = handles.get(&connection_id) {
+        cancel.cancel();
     }
+    Ok(())
 }
 
+/// Streams the rows of `query` into `file_path`. With `count_rows` the rows are
+/// counted first, so the `export-progress` events carry a percentage.
+///
+/// CSV and JSONL exports keep a `<file>.progress` checkpoint until they complete.
+/// After a cancel or failure, `resume` appends the remaining rows to the file
+/// instead of starting over (see `resume_query` for the queries this works with).
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn export_query_to_file<R: Runtime>(
     app: AppHandle<R>,
     state: State<'_, ExportCancellationState>,
@@ -50,267 +319,1144 @@ pub async fn export_query_to_file<R: Runtime>(
     query: String,
     file_path: String,
     format: String,
-) -> Result<(), String> {
+    csv_options: Option<CsvOptions>,
+    count_rows: Option<bool>,
+    resume: Option<bool>,
+) -> Result<ExportResult, String> {
+    let csv_options = csv_options.unwrap_or_default();
     let sanitized_query = query.trim().trim_end_matches(';').to_string();
     let saved_conn = find_connection_by_id(&app, &connection_id)?;
-    let params = resolve_connection_params(&saved_conn.params)?;
-    let driver = saved_conn.params.driver.clone();
-
-    let task = tokio::spawn(async move {
-        let file = File::create(&file_path).map_err(|e| e.to_string())?;
-        let mut writer = BufWriter::new(file);
-        let mut count = 0u64;
-
-        match driver.as_str() {
-            "mysql" => {
-                let pool = get_mysql_pool(&params).await?;
-                let mut rows = sqlx::query(&sanitized_query).fetch(&pool);
-
-                if format == "csv" {
-                    let mut csv_wtr = csv::WriterBuilder::new().from_writer(writer);
-                    let mut headers_written = false;
-
-                    while let Some(row_res) = rows.next().await {
-                        let row = row_res.map_err(|e| e.to_string())?;
-
-                        if !headers_written {
-                            let headers: Vec<String> =
-                                row.columns().iter().map(|c| c.name().to_string()).collect();
-                            csv_wtr.write_record(&headers).map_err(|e| e.to_string())?;
-                            headers_written = true;
-                        }
-
-                        let mut record = Vec::new();
-                        for i in 0..row.columns().len() {
-                            let val = extract_mysql_value(&row, i);
-                            match val {
-                                serde_json::Value::String(s) => record.push(s),
-                                serde_json::Value::Null => record.push("NULL".to_string()),
-                                v => record.push(v.to_string()),
-                            }
-                        }
-                        csv_wtr.write_record(&record).map_err(|e| e.to_string())?;
-
-                        count += 1;
-                        if count % 100 == 0 {
-                            app.emit(
-                                "export_progress",
-                                ExportProgress {
-                                    rows_processed: count,
-                                },
-                            )
-                            .unwrap_or(());
-                        }
-                    }
-                    csv_wtr.flush().map_err(|e| e.to_string())?;
-                } else {
-                    writer.write_all(b"[").map_err(|e| e.to_string())?;
-                    let mut first = true;
-
-                    while let Some(row_res) = rows.next().await {
-                        let row = row_res.map_err(|e| e.to_string())?;
-
-                        if !first {
-                            writer.write_all(b",").map_err(|e| e.to_string())?;
-                        }
-                        first = false;
-
-                        let mut obj = serde_json::Map::new();
-                        for i in 0..row.columns().len() {
-                            let name = row.column(i).name().to_string();
-                            let val = extract_mysql_value(&row, i);
-                            obj.insert(name, val);
-                        }
-                        serde_json::to_writer(&mut writer, &obj).map_err(|e| e.to_string())?;
-
-                        count += 1;
-                        if count % 100 == 0 {
-                            app.emit(
-                                "export_progress",
-                                ExportProgress {
-                                    rows_processed: count,
-                                },
-                            )
-                            .unwrap_or(());
-                        }
-                    }
-                    writer.write_all(b"]").map_err(|e| e.to_string())?;
-                    writer.flush().map_err(|e| e.to_string())?;
-                }
-            }
-            "postgres" => {
-                let pool = get_postgres_pool(&params).await?;
-                let mut rows = sqlx::query(&sanitized_query).fetch(&pool);
-
-                if format == "csv" {
-                    let mut csv_wtr = csv::WriterBuilder::new().from_writer(writer);
-                    let mut headers_written = false;
-
-                    while let Some(row_res) = rows.next().await {
-                        let row = row_res.map_err(|e| e.to_string())?;
-
-                        if !headers_written {
-                            let headers: Vec<String> =
-                                row.columns().iter().map(|c| c.name().to_string()).collect();
-                            csv_wtr.write_record(&headers).map_err(|e| e.to_string())?;
-                            headers_written = true;
-                        }
-
-                        let mut record = Vec::new();
-                        for i in 0..row.columns().len() {
-                            let val = extract_postgres_value(&row, i);
-                            match val {
-                                serde_json::Value::String(s) => record.push(s),
-                                serde_json::Value::Null => record.push("NULL".to_string()),
-                                v => record.push(v.to_string()),
-                            }
-                        }
-                        csv_wtr.write_record(&record).map_err(|e| e.to_string())?;
-
-                        count += 1;
-                        if count % 100 == 0 {
-                            app.emit(
-                                "export_progress",
-                                ExportProgress {
-                                    rows_processed: count,
-                                },
-                            )
-                            .unwrap_or(());
-                        }
-                    }
-                    csv_wtr.flush().map_err(|e| e.to_string())?;
-                } else {
-                    writer.write_all(b"[").map_err(|e| e.to_string())?;
-                    let mut first = true;
-
-                    while let Some(row_res) = rows.next().await {
-                        let row = row_res.map_err(|e| e.to_string())?;
-
-                        if !first {
-                            writer.write_all(b",").map_err(|e| e.to_string())?;
-                        }
-                        first = false;
-
-                        let mut obj = serde_json::Map::new();
-                        for i in 0..row.columns().len() {
-                            let name = row.column(i).name().to_string();
-                            let val = extract_postgres_value(&row, i);
-                            obj.insert(name, val);
-                        }
-                        serde_json::to_writer(&mut writer, &obj).map_err(|e| e.to_string())?;
-
-                        count += 1;
-                        if count % 100 == 0 {
-                            app.emit(
-                                "export_progress",
-                                ExportProgress {
-                                    rows_processed: count,
-                                },
-                            )
-                            .unwrap_or(());
-                        }
-                    }
-                    writer.write_all(b"]").map_err(|e| e.to_string())?;
-                    writer.flush().map_err(|e| e.to_string())?;
-                }
+    ensure_query_allowed(&saved_conn.params, &sanitized_query)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+
+    let checkpointer = RESUMABLE_FORMATS
+        .contains(&format.as_str())
+        .then(|| Checkpointer::new(&file_path, &sanitized_query, &format));
+    let resume_from = if resume.unwrap_or(false) {
+        let checkpoint = checkpointer
+            .as_ref()
+            .map(Checkpointer::load)
+            .transpose()?
+            .flatten()
+            .ok_or_else(|| format!("No unfinished export to resume for {}", file_path))?;
+        let query = resume_query(&params.driver, &sanitized_query, &format, &checkpoint)?;
+        println!(
+            "[Export] Resuming export after {} rows: {}",
+            checkpoint.rows_written, file_path
+        );
+        Some((query, checkpoint))
+    } else {
+        None
+    };
+
+    let cancel = Arc::new(ExportCancel::default());
+    {
+        let mut handles = state.handles.lock().unwrap();
+        handles.insert(connection_id.clone(), cancel.clone());
+    }
+
+    // The count runs the query once more, so it is only done on request
+    let total_rows = if count_rows.unwrap_or(false) {
+        count_query_rows(&params, &sanitized_query).await
+    } else {
+        None
+    };
+    let progress = ProgressReporter {
+        app: &app,
+        connection_id: &connection_id,
+        total_rows,
+    };
+    let result = run_export(
+        &progress,
+        &params,
+        &sanitized_query,
+        &file_path,
+        &format,
+        &csv_options,
+        &cancel,
+        checkpointer.as_ref(),
+        resume_from,
+    )
+    .await;
+
+    // The checkpoint is kept for a resume until the file is complete
+    if let (Ok(res), Some(checkpointer)) = (&result, &checkpointer) {
+        if !res.canceled {
+            checkpointer.remove();
+        }
+    }
+
+    {
+        let mut handles = state.handles.lock().unwrap();
+        handles.remove(&connection_id);
+    }
+
+    report_outcome(&app, &connection_id, &file_path, &result);
+    result
+}
+
+/// Emits `export-complete` or `export-error` for a finished export
+fn report_outcome<R: Runtime>(
+    app: &AppHandle<R>,
+    connection_id: &str,
+    file_path: &str,
+    result: &Result<ExportResult, String>,
+) {
+    match result {
+        Ok(res) => {
+            if res.canceled {
+                println!(
+                    "[Export] Export canceled after {} rows: {}",
+                    res.rows_written, file_path
+                );
             }
-            "sqlite" => {
-                let pool = get_sqlite_pool(&params).await?;
-                let mut rows = sqlx::query(&sanitized_query).fetch(&pool);
-
-                if format == "csv" {
-                    let mut csv_wtr = csv::WriterBuilder::new().from_writer(writer);
-                    let mut headers_written = false;
-
-                    while let Some(row_res) = rows.next().await {
-                        let row = row_res.map_err(|e| e.to_string())?;
-
-                        if !headers_written {
-                            let headers: Vec<String> =
-                                row.columns().iter().map(|c| c.name().to_string()).collect();
-                            csv_wtr.write_record(&headers).map_err(|e| e.to_string())?;
-                            headers_written = true;
-                        }
-
-                        let mut record = Vec::new();
-                        for i in 0..row.columns().len() {
-                            let val = extract_sqlite_value(&row, i);
-                            match val {
-                                serde_json::Value::String(s) => record.push(s),
-                                serde_json::Value::Null => record.push("NULL".to_string()),
-                                v => record.push(v.to_string()),
-                            }
-                        }
-                        csv_wtr.write_record(&record).map_err(|e| e.to_string())?;
-
-                        count += 1;
-                        if count % 100 == 0 {
-                            app.emit(
-                                "export_progress",
-                                ExportProgress {
-                                    rows_processed: count,
-                                },
-                            )
-                            .unwrap_or(());
-                        }
-                    }
-                    csv_wtr.flush().map_err(|e| e.to_string())?;
-                } else {
-                    writer.write_all(b"[").map_err(|e| e.to_string())?;
-                    let mut first = true;
-
-                    while let Some(row_res) = rows.next().await {
-                        let row = row_res.map_err(|e| e.to_string())?;
-
-                        if !first {
-                            writer.write_all(b",").map_err(|e| e.to_string())?;
-                        }
-                        first = false;
-
-                        let mut obj = serde_json::Map::new();
-                        for i in 0..row.columns().len() {
-                            let name = row.column(i).name().to_string();
-                            let val = extract_sqlite_value(&row, i);
-                            obj.insert(name, val);
-                        }
-                        serde_json::to_writer(&mut writer, &obj).map_err(|e| e.to_string())?;
-
-                        count += 1;
-                        if count % 100 == 0 {
-                            app.emit(
-                                "export_progress",
-                                ExportProgress {
-                                    rows_processed: count,
-                                },
-                            )
-                            .unwrap_or(());
-                        }
-                    }
-                    writer.write_all(b"]").map_err(|e| e.to_string())?;
-                    writer.flush().map_err(|e| e.to_string())?;
-                }
+            let complete = ExportComplete {
+                connection_id: connection_id.to_string(),
+                file_path: file_path.to_string(),
+                rows_written: res.rows_written,
+                canceled: res.canceled,
+            };
+            app.emit("export-complete", complete).unwrap_or(());
+        }
+        Err(e) => {
+            let error = ExportError {
+                connection_id: connection_id.to_string(),
+                file_path: file_path.to_string(),
+                error: e.clone(),
+            };
+            app.emit("export-error", error).unwrap_or(());
+        }
+    }
+}
+
+/// Row count of `query`, or None if it cannot be counted
+async fn count_query_rows(params: &ConnectionParams, query: &str) -> Option<u64> {
+    let count_query = format!("SELECT COUNT(*) FROM ({}) AS export_count", query);
+    let count: Result<i64, sqlx::Error> = match params.driver.as_str() {
+        "mysql" => {
+            let pool = get_mysql_pool(params).await.ok()?;
+            sqlx::query_scalar(&count_query).fetch_one(&pool).await
+        }
+        "postgres" => {
+            let pool = get_postgres_pool(params).await.ok()?;
+            sqlx::query_scalar(&count_query).fetch_one(&pool).await
+        }
+        "sqlite" => {
+            let pool = get_sqlite_pool(params).await.ok()?;
+            sqlx::query_scalar(&count_query).fetch_one(&pool).await
+        }
+        _ => return None,
+    };
+    match count {
+        Ok(count) => u64::try_from(count).ok(),
+        Err(e) => {
+            eprintln!("[Export] Could not count the rows to export: {}", e);
+            None
+        }
+    }
+}
+
+/// Runs `query` into a new file, or with `resume_from` the resume query into the
+/// file cut back to its last checkpoint
+#[allow(clippy::too_many_arguments)]
+async fn run_export<R: Runtime>(
+    progress: &ProgressReporter<'_, R>,
+    params: &ConnectionParams,
+    query: &str,
+    file_path: &str,
+    format: &str,
+    csv_options: &CsvOptions,
+    cancel: &ExportCancel,
+    checkpointer: Option<&Checkpointer>,
+    resume_from: Option<(String, ExportCheckpoint)>,
+) -> Result<ExportResult, String> {
+    if !EXPORT_FORMATS.contains(&format) {
+        return Err(format!("Unsupported export format: {}", format));
+    }
+    if format == "parquet" && !cfg!(feature = "parquet") {
+        return Err("Parquet export is not available in this build".into());
+    }
+
+    let (query, start_rows, file) = match &resume_from {
+        Some((resume_query, checkpoint)) => {
+            // Rows written after the checkpoint are dropped and fetched again
+            let file = OpenOptions::new()
+                .write(true)
+                .open(file_path)
+                .map_err(|e| e.to_string())?;
+            file.set_len(checkpoint.bytes_written)
+                .map_err(|e| e.to_string())?;
+            drop(file);
+            let file = OpenOptions::new()
+                .append(true)
+                .open(file_path)
+                .map_err(|e| e.to_string())?;
+            (resume_query.as_str(), checkpoint.rows_written, file)
+        }
+        None => {
+            let file = File::create(file_path).map_err(|e| e.to_string())?;
+            // Replaces any checkpoint of an earlier run of this file
+            if let Some(checkpointer) = checkpointer {
+                checkpointer.save(0, &file)?;
             }
-            _ => return Err("Unsupported driver".into()),
+            (query, 0, file)
         }
+    };
+    let writer = BufWriter::new(file);
+    let output = ExportOutput {
+        writer,
+        format,
+        csv_options,
+        start_rows,
+        checkpointer,
+    };
 
-        Ok(())
-    });
+    match params.driver.as_str() {
+        "mysql" => {
+            let pool = get_mysql_pool(params).await?;
+            let rows = sqlx::query(query).fetch(&pool);
+            write_rows(
+                progress,
+                rows,
+                extract_mysql_value,
+                || describe_columns(&pool, query),
+                output,
+                cancel,
+            )
+            .await
+        }
+        "postgres" => {
+            let pool = get_postgres_pool(params).await?;
+            let rows = sqlx::query(query).fetch(&pool);
+            write_rows(
+                progress,
+                rows,
+                extract_postgres_value,
+                || describe_columns(&pool, query),
+                output,
+                cancel,
+            )
+            .await
+        }
+        "sqlite" => {
+            let pool = get_sqlite_pool(params).await?;
+            let rows = sqlx::query(query).fetch(&pool);
+            write_rows(
+                progress,
+                rows,
+                extract_sqlite_value,
+                || describe_columns(&pool, query),
+                output,
+                cancel,
+            )
+            .await
+        }
+        _ => Err("Unsupported driver".into()),
+    }
+}
+
+/// Writes every row of `table_name` as multi-row `INSERT INTO t (cols) VALUES ...`
+/// statements in the connection's own dialect. Cancellation keeps the statements
+/// written so far, each of them complete.
+#[tauri::command]
+pub async fn export_table_inserts<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, ExportCancellationState>,
+    connection_id: String,
+    table_name: String,
+    file_path: String,
+) -> Result<ExportResult, String> {
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
 
-    let abort_handle = task.abort_handle();
+    let cancel = Arc::new(ExportCancel::default());
     {
         let mut handles = state.handles.lock().unwrap();
-        handles.insert(connection_id.clone(), abort_handle);
+        handles.insert(connection_id.clone(), cancel.clone());
     }
 
-    let result = task.await;
+    // The table's row estimate is cheap and good enough for a percentage
+    let total_rows = match driver_for(&params) {
+        Ok(driver) => driver
+            .get_table_row_count(&params, &table_name, false)
+            .await
+            .ok()
+            .and_then(|count| u64::try_from(count.count).ok()),
+        Err(_) => None,
+    };
+    let progress = ProgressReporter {
+        app: &app,
+        connection_id: &connection_id,
+        total_rows,
+    };
+    let result = run_insert_export(&progress, &params, &table_name, &file_path, &cancel).await;
 
     {
         let mut handles = state.handles.lock().unwrap();
         handles.remove(&connection_id);
     }
 
-    match result {
-        Ok(res) => res,
-        Err(_) => Err("Export cancelled".into()),
+    report_outcome(&app, &connection_id, &file_path, &result);
+    result
+}
+
+async fn run_insert_export<R: Runtime>(
+    progress: &ProgressReporter<'_, R>,
+    params: &ConnectionParams,
+    table_name: &str,
+    file_path: &str,
+    cancel: &ExportCancel,
+) -> Result<ExportResult, String> {
+    let file = File::create(file_path).map_err(|e| e.to_string())?;
+    let mut writer = BufWriter::new(file);
+    let result = write_table_inserts(&mut writer, params, table_name, cancel, &|rows| {
+        progress.rows_written(rows)
+    })
+    .await?;
+    writer.flush().map_err(|e| e.to_string())?;
+    Ok(result)
+}
+
+/// Streams every row of `table_name` into `writer` as batched INSERT statements,
+/// calling `on_progress` with the rows written so far. Also writes the data of a
+/// database dump, which is stopped by aborting it instead of through `cancel`.
+pub async fn write_table_inserts<W: Write>(
+    writer: &mut W,
+    params: &ConnectionParams,
+    table_name: &str,
+    cancel: &ExportCancel,
+    on_progress: &(dyn Fn(u64) + Sync),
+) -> Result<ExportResult, String> {
+    let driver = params.driver.as_str();
+    let quote = identifier_quote(driver);
+    // Unqualified, so Postgres resolves it through the search_path
+    let table = quote_identifier(table_name, quote);
+    let query = format!("SELECT * FROM {}", table);
+    let target = InsertTarget {
+        driver,
+        table: &table,
+        quote,
+    };
+
+    match driver {
+        "mysql" => {
+            let pool = get_mysql_pool(params).await?;
+            let rows = sqlx::query(&query).fetch(&pool);
+            write_inserts(
+                writer,
+                rows,
+                extract_mysql_value,
+                mysql_binary,
+                &target,
+                cancel,
+                on_progress,
+            )
+            .await
+        }
+        "postgres" => {
+            let pool = get_postgres_pool(params).await?;
+            let rows = sqlx::query(&query).fetch(&pool);
+            write_inserts(
+                writer,
+                rows,
+                extract_postgres_value,
+                postgres_binary,
+                &target,
+                cancel,
+                on_progress,
+            )
+            .await
+        }
+        "sqlite" => {
+            let pool = get_sqlite_pool(params).await?;
+            let rows = sqlx::query(&query).fetch(&pool);
+            write_inserts(
+                writer,
+                rows,
+                extract_sqlite_value,
+                sqlite_binary,
+                &target,
+                cancel,
+                on_progress,
+            )
+            .await
+        }
+        _ => Err("Unsupported driver".into()),
+    }
+}
+
+/// Table and dialect the INSERT statements are written for
+struct InsertTarget<'a> {
+    driver: &'a str,
+    /// Already quoted
+    table: &'a str,
+    quote: char,
+}
+
+fn mysql_binary(row: &sqlx::mysql::MySqlRow, index: usize) -> Option<Vec<u8>> {
+    let type_name = row.columns()[index].type_info().name();
+    if type_name.contains("BLOB") || type_name.contains("BINARY") {
+        row.try_get(index).ok()
+    } else {
+        None
+    }
+}
+
+fn postgres_binary(row: &sqlx::postgres::PgRow, index: usize) -> Option<Vec<u8>> {
+    if row.columns()[index].type_info().name() == "BYTEA" {
+        row.try_get(index).ok()
+    } else {
+        None
+    }
+}
+
+/// SQLite types values, not columns, so the stored value decides
+fn sqlite_binary(row: &sqlx::sqlite::SqliteRow, index: usize) -> Option<Vec<u8>> {
+    let raw = row.try_get_raw(index).ok()?;
+    if raw.type_info().name() == "BLOB" {
+        row.try_get(index).ok()
+    } else {
+        None
+    }
+}
+
+/// Streams rows into batched INSERT statements. A batch is only written once
+/// complete, so a canceled export still ends with a full statement.
+async fn write_inserts<W, T, S>(
+    writer: &mut W,
+    mut rows: S,
+    extract: fn(&T, usize) -> serde_json::Value,
+    binary: fn(&T, usize) -> Option<Vec<u8>>,
+    target: &InsertTarget<'_>,
+    cancel: &ExportCancel,
+    on_progress: &(dyn Fn(u64) + Sync),
+) -> Result<ExportResult, String>
+where
+    W: Write,
+    T: Row,
+    S: Stream<Item = Result<T, sqlx::Error>> + Unpin,
+{
+    let mut count = 0u64;
+    let mut canceled = false;
+    let mut header = String::new();
+    let mut batch: Vec<String> = Vec::with_capacity(INSERT_BATCH_ROWS);
+
+    while let Some(row_res) = next_row(&mut rows, cancel, &mut canceled).await {
+        let row = row_res.map_err(|e| e.to_string())?;
+
+        if header.is_empty() {
+            let columns: Vec<String> = row
+                .columns()
+                .iter()
+                .map(|c| quote_identifier(c.name(), target.quote))
+                .collect();
+            header = format!(
+                "INSERT INTO {} ({}) VALUES",
+                target.table,
+                columns.join(", ")
+            );
+        }
+
+        let values: Vec<String> = (0..row.columns().len())
+            .map(|i| match binary(&row, i) {
+                Some(bytes) => hex_literal(target.driver, &bytes),
+                None => {
+                    let type_name = row.columns()[i].type_info().name();
+                    sql_literal(target.driver, type_name, &extract(&row, i))
+                }
+            })
+            .collect();
+        batch.push(format!("({})", values.join(", ")));
+
+        count += 1;
+        if batch.len() >= INSERT_BATCH_ROWS {
+            write_insert_batch(writer, &header, &mut batch)?;
+        }
+        if count % EXPORT_BATCH_SIZE == 0 {
+            on_progress(count);
+        }
+    }
+
+    write_insert_batch(writer, &header, &mut batch)?;
+
+    Ok(ExportResult {
+        rows_written: count,
+        canceled,
+    })
+}
+
+fn write_insert_batch(
+    writer: &mut impl Write,
+    header: &str,
+    batch: &mut Vec<String>,
+) -> Result<(), String> {
+    if batch.is_empty() {
+        return Ok(());
+    }
+    writeln!(writer, "{}\n{};", header, batch.join(",\n")).map_err(|e| e.to_string())?;
+    batch.clear();
+    Ok(())
+}
+
+/// SQL literal for an exported value. `type_name` is the column's sqlx type name,
+/// used to tell Postgres arrays from JSON arrays.
+fn sql_literal(driver: &str, type_name: &str, value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => "NULL".to_string(),
+        serde_json::Value::Bool(b) => match (driver, b) {
+            ("postgres", true) => "TRUE".to_string(),
+            ("postgres", false) => "FALSE".to_string(),
+            (_, true) => "1".to_string(),
+            (_, false) => "0".to_string(),
+        },
+        serde_json::Value::Number(n) => n.to_string(),
+        serde_json::Value::String(s) => string_literal(driver, s),
+        serde_json::Value::Array(items) if driver == "postgres" && type_name.ends_with("[]") => {
+            string_literal(driver, &postgres_array_literal(items))
+        }
+        other => string_literal(driver, &other.to_string()),
+    }
+}
+
+/// Quotes a string literal; only MySQL treats backslashes as escapes by default
+fn string_literal(driver: &str, s: &str) -> String {
+    let escaped = s.replace('\'', "''");
+    if driver == "mysql" {
+        format!("'{}'", escaped.replace('\\', "\\\\"))
+    } else {
+        format!("'{}'", escaped)
+    }
+}
+
+/// Postgres array input syntax, e.g. `{1,NULL,"a \"b\""}`
+fn postgres_array_literal(items: &[serde_json::Value]) -> String {
+    let elements: Vec<String> = items
+        .iter()
+        .map(|item| match item {
+            serde_json::Value::Null => "NULL".to_string(),
+            serde_json::Value::Array(inner) => postgres_array_literal(inner),
+            serde_json::Value::Bool(_) | serde_json::Value::Number(_) => item.to_string(),
+            serde_json::Value::String(s) => quote_array_element(s),
+            other => quote_array_element(&other.to_string()),
+        })
+        .collect();
+    format!("{{{}}}", elements.join(","))
+}
+
+fn quote_array_element(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn hex_literal(driver: &str, bytes: &[u8]) -> String {
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    if driver == "postgres" {
+        format!("'\\x{}'::bytea", hex)
+    } else {
+        format!("X'{}'", hex)
+    }
+}
+
+/// Column names of `query` without fetching any row, empty if it cannot be described
+async fn describe_columns<'p, DB: sqlx::Database>(
+    pool: &'p sqlx::Pool<DB>,
+    query: &str,
+) -> Vec<String>
+where
+    &'p sqlx::Pool<DB>: sqlx::Executor<'p, Database = DB>,
+{
+    match pool.describe(query).await {
+        Ok(describe) => described_columns(&describe).0,
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Where and how `write_rows` writes. A resumed export starts at `start_rows`, with
+/// the file already holding those rows (and the CSV header).
+struct ExportOutput<'a> {
+    writer: BufWriter<File>,
+    format: &'a str,
+    csv_options: &'a CsvOptions,
+    start_rows: u64,
+    checkpointer: Option<&'a Checkpointer>,
+}
+
+/// Streams rows into the output file. A cancel stops it before the next row, even
+/// while the query is still running; the partial file is then flushed and closed. A CSV
+/// export of an empty result still gets a header row, taken from `describe`.
+/// CSV and JSONL exports save a checkpoint every `CHECKPOINT_ROWS` rows and when
+/// they stop.
+async fn write_rows<R, T, S, D, F>(
+    progress: &ProgressReporter<'_, R>,
+    mut rows: S,
+    extract: fn(&T, usize) -> serde_json::Value,
+    describe: D,
+    output: ExportOutput<'_>,
+    cancel: &ExportCancel,
+) -> Result<ExportResult, String>
+where
+    R: Runtime,
+    T: Row,
+    S: Stream<Item = Result<T, sqlx::Error>> + Unpin,
+    D: FnOnce() -> F,
+    F: std::future::Future<Output = Vec<String>>,
+{
+    let ExportOutput {
+        mut writer,
+        format,
+        csv_options,
+        start_rows,
+        checkpointer,
+    } = output;
+    let mut count = start_rows;
+    let mut canceled = false;
+
+    if format == "csv" {
+        let mut csv_wtr = csv::WriterBuilder::new()
+            .delimiter(csv_options.delimiter_byte()?)
+            .quote_style(csv::QuoteStyle::Necessary)
+            .from_writer(writer);
+        let mut headers_written = !csv_options.include_header.unwrap_or(true) || start_rows > 0;
+
+        while let Some(row_res) = next_row(&mut rows, cancel, &mut canceled).await {
+            let row = row_res.map_err(|e| e.to_string())?;
+
+            if !headers_written {
+                let headers: Vec<String> =
+                    row.columns().iter().map(|c| c.name().to_string()).collect();
+                csv_wtr.write_record(&headers).map_err(|e| e.to_string())?;
+                headers_written = true;
+            }
+
+            let mut record = Vec::new();
+            for i in 0..row.columns().len() {
+                match extract(&row, i) {
+                    serde_json::Value::String(s) => record.push(s),
+                    serde_json::Value::Null => record.push(csv_options.null_value().to_string()),
+                    v => record.push(v.to_string()),
+                }
+            }
+            csv_wtr.write_record(&record).map_err(|e| e.to_string())?;
+
+            count += 1;
+            if count % EXPORT_BATCH_SIZE == 0 {
+                progress.rows_written(count);
+            }
+            if let Some(checkpointer) = checkpointer.filter(|_| count % CHECKPOINT_ROWS == 0) {
+                csv_wtr.flush().map_err(|e| e.to_string())?;
+                checkpointer.save(count, csv_wtr.get_ref().get_ref())?;
+            }
+        }
+
+        if !headers_written && !canceled {
+            drop(rows);
+            let headers = describe().await;
+            if !headers.is_empty() {
+                csv_wtr.write_record(&headers).map_err(|e| e.to_string())?;
+            }
+        }
+        csv_wtr.flush().map_err(|e| e.to_string())?;
+        if let Some(checkpointer) = checkpointer {
+            checkpointer.save(count, csv_wtr.get_ref().get_ref())?;
+        }
+    } else if format == "xlsx" {
+        // Constant memory mode flushes each row to a temp file once the next one starts
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet_with_constant_memory();
+        let formats = XlsxFormats::new();
+        let mut kinds: Option<Vec<XlsxKind>> = None;
+
+        while let Some(row_res) = next_row(&mut rows, cancel, &mut canceled).await {
+            let row = row_res.map_err(|e| e.to_string())?;
+
+            let kinds = kinds.get_or_insert_with(|| {
+                row.columns()
+                    .iter()
+                    .map(|c| XlsxKind::of(c.type_info().name()))
+                    .collect()
+            });
+            if count == 0 {
+                let headers: Vec<&str> = row.columns().iter().map(|c| c.name()).collect();
+                write_xlsx_header(sheet, &headers, &formats)?;
+            }
+            if count + 1 >= XLSX_MAX_ROWS as u64 {
+                return Err(format!(
+                    "Excel sheets hold at most {} rows; export as CSV instead",
+                    XLSX_MAX_ROWS - 1
+                ));
+            }
+
+            let excel_row = count as u32 + 1;
+            for (i, kind) in kinds.iter().enumerate() {
+                write_xlsx_cell(
+                    sheet,
+                    excel_row,
+                    i as u16,
+                    *kind,
+                    extract(&row, i),
+                    &formats,
+                )
+                .map_err(|e| e.to_string())?;
+            }
+
+            count += 1;
+            if count % EXPORT_BATCH_SIZE == 0 {
+                progress.rows_written(count);
+            }
+        }
+
+        if count == 0 && !canceled {
+            drop(rows);
+            let headers = describe().await;
+            let headers: Vec<&str> = headers.iter().map(String::as_str).collect();
+            write_xlsx_header(sheet, &headers, &formats)?;
+        }
+        workbook.save_to_writer(writer).map_err(|e| e.to_string())?;
+    } else if format == "parquet" {
+        #[cfg(feature = "parquet")]
+        {
+            use crate::drivers::common::column_type_names;
+            use crate::parquet_export::ParquetRowWriter;
+
+            // The schema comes from the first row's column types
+            let mut out = Some(writer);
+            let mut parquet = None;
+            while let Some(row_res) = next_row(&mut rows, cancel, &mut canceled).await {
+                let row = row_res.map_err(|e| e.to_string())?;
+
+                if let Some(out) = out.take() {
+                    let names: Vec<String> =
+                        row.columns().iter().map(|c| c.name().to_string()).collect();
+                    let types = column_type_names(&row);
+                    parquet = Some(ParquetRowWriter::new(out, &names, &types)?);
+                }
+                if let Some(parquet) = parquet.as_mut() {
+                    parquet.push((0..row.columns().len()).map(|i| extract(&row, i)).collect())?;
+                }
+
+                count += 1;
+                if count % EXPORT_BATCH_SIZE == 0 {
+                    progress.rows_written(count);
+                }
+            }
+
+            if let Some(out) = out {
+                // No rows: an empty file that still carries the column names
+                drop(rows);
+                let names = describe().await;
+                parquet = Some(ParquetRowWriter::new(out, &names, &[])?);
+            }
+            if let Some(parquet) = parquet {
+                parquet.finish()?;
+            }
+        }
+    } else if format == "jsonl" {
+        // One object per line, flushed as it is written so memory stays flat and
+        // consumers can tail the file while the export runs
+        while let Some(row_res) = next_row(&mut rows, cancel, &mut canceled).await {
+            let row = row_res.map_err(|e| e.to_string())?;
+
+            serde_json::to_writer(&mut writer, &row_to_object(&row, extract))
+                .map_err(|e| e.to_string())?;
+            writer.write_all(b"\n").map_err(|e| e.to_string())?;
+            writer.flush().map_err(|e| e.to_string())?;
+
+            count += 1;
+            if count % EXPORT_BATCH_SIZE == 0 {
+                progress.rows_written(count);
+            }
+            if let Some(checkpointer) = checkpointer.filter(|_| count % CHECKPOINT_ROWS == 0) {
+                checkpointer.save(count, writer.get_ref())?;
+            }
+        }
+        if let Some(checkpointer) = checkpointer {
+            checkpointer.save(count, writer.get_ref())?;
+        }
+    } else {
+        writer.write_all(b"[").map_err(|e| e.to_string())?;
+        let mut first = true;
+
+        while let Some(row_res) = next_row(&mut rows, cancel, &mut canceled).await {
+            let row = row_res.map_err(|e| e.to_string())?;
+
+            if !first {
+                writer.write_all(b",").map_err(|e| e.to_string())?;
+            }
+            first = false;
+
+            serde_json::to_writer(&mut writer, &row_to_object(&row, extract))
+                .map_err(|e| e.to_string())?;
+
+            count += 1;
+            if count % EXPORT_BATCH_SIZE == 0 {
+                progress.rows_written(count);
+            }
+        }
+        writer.write_all(b"]").map_err(|e| e.to_string())?;
+        writer.flush().map_err(|e| e.to_string())?;
+    }
+
+    Ok(ExportResult {
+        rows_written: count,
+        canceled,
+    })
+}
+
+/// How a column's values are written to an Excel cell, from its sqlx type name
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum XlsxKind {
+    Date,
+    DateTime,
+    Time,
+    /// DECIMAL / NUMERIC, which the drivers hand over as strings
+    Decimal,
+    Other,
+}
+
+impl XlsxKind {
+    fn of(type_name: &str) -> XlsxKind {
+        let name = type_name.to_uppercase();
+        if name == "DATE" {
+            XlsxKind::Date
+        } else if name.starts_with("TIMESTAMP") || name == "DATETIME" {
+            XlsxKind::DateTime
+        } else if name == "TIME" {
+            XlsxKind::Time
+        } else if name == "DECIMAL" || name == "NUMERIC" {
+            XlsxKind::Decimal
+        } else {
+            XlsxKind::Other
+        }
+    }
+}
+
+struct XlsxFormats {
+    header: Format,
+    date: Format,
+    datetime: Format,
+    time: Format,
+}
+
+impl XlsxFormats {
+    fn new() -> Self {
+        Self {
+            header: Format::new().set_bold(),
+            date: Format::new().set_num_format("yyyy-mm-dd"),
+            datetime: Format::new().set_num_format("yyyy-mm-dd hh:mm:ss"),
+            time: Format::new().set_num_format("hh:mm:ss"),
+        }
+    }
+}
+
+fn write_xlsx_header(
+    sheet: &mut Worksheet,
+    headers: &[&str],
+    formats: &XlsxFormats,
+) -> Result<(), String> {
+    for (i, header) in headers.iter().enumerate() {
+        sheet
+            .write_string_with_format(0, i as u16, *header, &formats.header)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Writes numbers as numbers and date/time columns as Excel dates. Anything that
+/// does not parse as its column's type is written as text; NULL leaves the cell empty.
+fn write_xlsx_cell(
+    sheet: &mut Worksheet,
+    row: u32,
+    col: u16,
+    kind: XlsxKind,
+    value: serde_json::Value,
+    formats: &XlsxFormats,
+) -> Result<(), XlsxError> {
+    let text = match value {
+        serde_json::Value::Null => return Ok(()),
+        serde_json::Value::Bool(b) => {
+            sheet.write_boolean(row, col, b)?;
+            return Ok(());
+        }
+        serde_json::Value::Number(n) => {
+            let exact = n.as_i64().map_or(true, |v| v.abs() <= XLSX_MAX_EXACT_INT)
+                && n.as_u64().map_or(true, |v| v <= XLSX_MAX_EXACT_INT as u64);
+            match n.as_f64() {
+                Some(v) if exact => {
+                    sheet.write_number(row, col, v)?;
+                    return Ok(());
+                }
+                _ => n.to_string(),
+            }
+        }
+        serde_json::Value::String(s) => s,
+        other => other.to_string(),
+    };
+
+    match kind {
+        XlsxKind::Date => {
+            if let Ok(date) = NaiveDate::parse_from_str(&text, "%Y-%m-%d") {
+                sheet.write_datetime_with_format(row, col, date, &formats.date)?;
+                return Ok(());
+            }
+        }
+        XlsxKind::DateTime => {
+            // Zoned timestamps keep the wall-clock time they were returned with
+            let datetime = parse_naive_timestamp(&text)
+                .or_else(|| parse_timestamp_tz(&text).map(|dt| dt.naive_local()));
+            if let Some(datetime) = datetime {
+                sheet.write_datetime_with_format(row, col, datetime, &formats.datetime)?;
+                return Ok(());
+            }
+        }
+        XlsxKind::Time => {
+            if let Ok(time) = NaiveTime::parse_from_str(&text, "%H:%M:%S%.f") {
+                sheet.write_datetime_with_format(row, col, time, &formats.time)?;
+                return Ok(());
+            }
+        }
+        XlsxKind::Decimal => {
+            if let Ok(v) = text.parse::<f64>() {
+                sheet.write_number(row, col, v)?;
+                return Ok(());
+            }
+        }
+        XlsxKind::Other => {}
+    }
+
+    let text: String = text.chars().take(XLSX_MAX_STRING_CHARS).collect();
+    sheet.write_string(row, col, text)?;
+    Ok(())
+}
+
+/// Maps a row to a JSON object keyed by column name; values keep their JSON type
+fn row_to_object<T: Row>(
+    row: &T,
+    extract: fn(&T, usize) -> serde_json::Value,
+) -> serde_json::Map<String, serde_json::Value> {
+    row.columns()
+        .iter()
+        .enumerate()
+        .map(|(i, column)| (column.name().to_string(), extract(row, i)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_csv_options_defaults() {
+        let options = CsvOptions::default();
+        assert_eq!(options.delimiter_byte(), Ok(b','));
+        assert_eq!(options.null_value(), "");
+    }
+
+    #[test]
+    fn test_csv_options_custom() {
+        let options = CsvOptions {
+            delimiter: Some('\t'),
+            include_header: Some(false),
+            null_as_escape: Some(true),
+        };
+        assert_eq!(options.delimiter_byte(), Ok(b'\t'));
+        assert_eq!(options.null_value(), "\\N");
+    }
+
+    #[test]
+    fn test_csv_options_rejects_invalid_delimiter() {
+        for delimiter in ['"', '\n', '€'] {
+            let options = CsvOptions {
+                delimiter: Some(delimiter),
+                ..Default::default()
+            };
+            assert!(options.delimiter_byte().is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_next_row_cancels_a_query_waiting_for_rows() {
+        let cancel = Arc::new(ExportCancel::default());
+        let canceller = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            canceller.cancel();
+        });
+
+        let mut rows = futures::stream::pending::<Result<(), ()>>();
+        let mut canceled = false;
+        assert!(next_row(&mut rows, &cancel, &mut canceled).await.is_none());
+        assert!(canceled);
+    }
+
+    #[tokio::test]
+    async fn test_next_row_reads_rows_until_the_end() {
+        let cancel = ExportCancel::default();
+        let mut rows = futures::stream::iter([1, 2]);
+        let mut canceled = false;
+        assert_eq!(next_row(&mut rows, &cancel, &mut canceled).await, Some(1));
+        assert_eq!(next_row(&mut rows, &cancel, &mut canceled).await, Some(2));
+        assert_eq!(next_row(&mut rows, &cancel, &mut canceled).await, None);
+        assert!(!canceled);
+    }
+
+    #[test]
+    fn test_xlsx_kind_from_type_name() {
+        assert_eq!(XlsxKind::of("DATE"), XlsxKind::Date);
+        assert_eq!(XlsxKind::of("TIMESTAMPTZ"), XlsxKind::DateTime);
+        assert_eq!(XlsxKind::of("DATETIME"), XlsxKind::DateTime);
+        assert_eq!(XlsxKind::of("TIME"), XlsxKind::Time);
+        assert_eq!(XlsxKind::of("NUMERIC"), XlsxKind::Decimal);
+        assert_eq!(XlsxKind::of("VARCHAR"), XlsxKind::Other);
+    }
+
+    #[test]
+    fn test_xlsx_cells_of_every_kind() {
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet_with_constant_memory();
+        let formats = XlsxFormats::new();
+        let cells = [
+            (XlsxKind::Other, json!(42)),
+            (XlsxKind::Other, json!(9_007_199_254_740_993i64)),
+            (XlsxKind::Other, json!(true)),
+            (XlsxKind::Other, json!(null)),
+            (XlsxKind::Other, json!({"a": [1, 2]})),
+            (XlsxKind::Other, json!("x".repeat(40_000))),
+            (XlsxKind::Decimal, json!("12.50")),
+            (XlsxKind::Date, json!("2024-02-29")),
+            (XlsxKind::DateTime, json!("2024-02-29 13:45:10.5")),
+            (XlsxKind::DateTime, json!("2024-02-29T13:45:10+02:00")),
+            (XlsxKind::Time, json!("08:30:00")),
+            // Falls back to text
+            (XlsxKind::Date, json!("infinity")),
+        ];
+        for (col, (kind, value)) in cells.into_iter().enumerate() {
+            write_xlsx_cell(sheet, 1, col as u16, kind, value, &formats).unwrap();
+        }
+        let bytes = workbook.save_to_buffer().unwrap();
+        assert!(bytes.starts_with(b"PK"));
+    }
+
+    #[test]
+    fn test_sql_literal_per_driver() {
+        for driver in ["mysql", "postgres", "sqlite"] {
+            assert_eq!(sql_literal(driver, "TEXT", &json!(null)), "NULL");
+            assert_eq!(sql_literal(driver, "INT", &json!(-42)), "-42");
+            assert_eq!(
+                sql_literal(driver, "TEXT", &json!("O'Reilly")),
+                "'O''Reilly'"
+            );
+        }
+        assert_eq!(sql_literal("postgres", "BOOL", &json!(true)), "TRUE");
+        assert_eq!(sql_literal("mysql", "BOOLEAN", &json!(false)), "0");
+        // Backslashes only escape in MySQL string literals
+        assert_eq!(
+            sql_literal("mysql", "TEXT", &json!("C:\\tmp")),
+            "'C:\\\\tmp'"
+        );
+        assert_eq!(
+            sql_literal("postgres", "TEXT", &json!("C:\\tmp")),
+            "'C:\\tmp'"
+        );
+        assert_eq!(
+            sql_literal("mysql", "JSON", &json!({"a": "it's"})),
+            "'{\"a\":\"it''s\"}'"
+        );
+    }
+
+    #[test]
+    fn test_sql_literal_of_dumped_values() {
+        let literal = |value: serde_json::Value| sql_literal("mysql", "TEXT", &value);
+        assert_eq!(literal(json!(null)), "NULL");
+        assert_eq!(literal(json!(123)), "123");
+        assert_eq!(literal(json!(12.34)), "12.34");
+        assert_eq!(literal(json!(true)), "1");
+        assert_eq!(literal(json!(false)), "0");
+        assert_eq!(literal(json!("hello")), "'hello'");
+        assert_eq!(literal(json!("O'Reilly")), "'O''Reilly'");
+        assert_eq!(literal(json!("Back\\slash")), "'Back\\\\slash'");
+        assert_eq!(literal(json!("Multi\nLine")), "'Multi\nLine'");
+    }
+
+    #[test]
+    fn test_postgres_arrays_and_json() {
+        let value = json!([1, null, 3]);
+        assert_eq!(sql_literal("postgres", "INT4[]", &value), "'{1,NULL,3}'");
+        assert_eq!(sql_literal("postgres", "JSONB", &value), "'[1,null,3]'");
+        assert_eq!(
+            sql_literal(
+                "postgres",
+                "TEXT[][]",
+                &json!([["a\"b", "c'd"], ["e,f", "g\\h"]])
+            ),
+            "'{{\"a\\\"b\",\"c''d\"},{\"e,f\",\"g\\\\h\"}}'"
+        );
+    }
+
+    #[test]
+    fn test_hex_literal_per_driver() {
+        assert_eq!(hex_literal("mysql", &[0xde, 0xad, 0x01]), "X'dead01'");
+        assert_eq!(hex_literal("sqlite", &[]), "X''");
+        assert_eq!(hex_literal("postgres", &[0xbe, 0xef]), "'\\xbeef'::bytea");
+    }
+    fn checkpoint(query: &str, format: &str, rows_written: u64) -> ExportCheckpoint {
+        ExportCheckpoint {
+            query: query.to_string(),
+            format: format.to_string(),
+            rows_written,
+            bytes_written: 0,
+        }
+    }
+
+    #[test]
+    fn test_resume_query_skips_written_rows() {
+        let query = "SELECT * FROM events ORDER BY id";
+        let cp = checkpoint(query, "csv", 20_000);
+        assert_eq!(
+            resume_query("postgres", query, "csv", &cp).unwrap(),
+            "SELECT * FROM events ORDER BY id\nOFFSET 20000"
+        );
+        assert!(resume_query("mysql", query, "csv", &cp)
+            .unwrap()
+            .ends_with("LIMIT 20000, 18446744073709551615"));
+        assert!(resume_query("sqlite", query, "csv", &cp)
+            .unwrap()
+            .ends_with("LIMIT -1 OFFSET 20000"));
+    }
+
+    #[test]
+    fn test_resume_query_rejects_unstable_queries() {
+        let unsorted = "SELECT * FROM events";
+        assert!(
+            resume_query("postgres", unsorted, "csv", &checkpoint(unsorted, "csv", 5))
+                .unwrap_err()
+                .contains("ORDER BY")
+        );
+        let limited = "SELECT * FROM events ORDER BY id LIMIT 10";
+        assert!(resume_query("postgres", limited, "csv", &checkpoint(limited, "csv", 5)).is_err());
+
+        let query = "SELECT * FROM events ORDER BY id";
+        assert!(resume_query("postgres", query, "json", &checkpoint(query, "json", 5)).is_err());
+        let other = checkpoint("SELECT * FROM users ORDER BY id", "csv", 5);
+        assert!(resume_query("postgres", query, "csv", &other).is_err());
+    }
+
+    #[test]
+    fn test_checkpoint_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().join("out.csv");
+        let file_path = file_path.to_str().unwrap();
+        let checkpointer = Checkpointer::new(file_path, "SELECT 1 ORDER BY 1", "csv");
+        assert!(checkpointer.load().unwrap().is_none());
+
+        let mut file = File::create(file_path).unwrap();
+        file.write_all(b"a,b\n1,2\n").unwrap();
+        checkpointer.save(1, &file).unwrap();
+        let saved = checkpointer.load().unwrap().unwrap();
+        assert_eq!((saved.rows_written, saved.bytes_written), (1, 8));
+
+        checkpointer.remove();
+        assert!(!progress_path(file_path).exists());
     }
 }
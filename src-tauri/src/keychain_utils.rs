@@ -1,24 +1,157 @@
+use crate::config::AppConfig;
+use crate::paths;
+use crate::secret_file::SecretFile;
 use keyring::Entry;
+use once_cell::sync::Lazy;
+use std::fs;
+use std::sync::RwLock;
 
 const SERVICE_NAME: &str = "tabularis";
 
+/// Where secrets are kept, chosen with the `secretStore` setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretStore {
+    /// The OS keyring (Keychain, Credential Manager, Secret Service)
+    Keyring,
+    /// The encrypted `secrets.enc` file in the app config directory
+    File,
+}
+
+impl SecretStore {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "keyring" => Ok(SecretStore::Keyring),
+            "file" => Ok(SecretStore::File),
+            other => Err(format!(
+                "Unknown secret store '{}', expected 'keyring' or 'file'",
+                other
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SecretStore::Keyring => "keyring",
+            SecretStore::File => "file",
+        }
+    }
+}
+
+static SECRET_STORE: Lazy<RwLock<SecretStore>> = Lazy::new(|| RwLock::new(configured_store()));
+
+/// Reads the setting straight from config.json, so the MCP server (which has no
+/// app handle) picks the same store as the GUI
+fn configured_store() -> SecretStore {
+    let path = paths::get_app_config_dir().join("config.json");
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<AppConfig>(&content).ok())
+        .and_then(|config| config.secret_store)
+        .and_then(|name| SecretStore::parse(&name).ok())
+        .unwrap_or(SecretStore::Keyring)
+}
+
+pub fn secret_store() -> SecretStore {
+    *SECRET_STORE.read().unwrap()
+}
+
+pub fn set_secret_store(store: SecretStore) {
+    println!("[Keychain] Using secret store: {}", store.as_str());
+    *SECRET_STORE.write().unwrap() = store;
+}
+
+/// Turns keyring failures into something the user can act on. Missing or locked
+/// keyrings are common on headless Linux, where no Secret Service is running.
+fn keyring_error(e: keyring::Error) -> String {
+    match e {
+        keyring::Error::PlatformFailure(_) | keyring::Error::NoStorageAccess(_) => format!(
+            "The OS keyring is unavailable ({}). Start or unlock a keyring service (e.g. GNOME Keyring or KWallet), or switch Secret storage to the encrypted file in Settings.",
+            e
+        ),
+        other => other.to_string(),
+    }
+}
+
+pub fn get_secret_from(store: SecretStore, account: &str) -> Result<Option<String>, String> {
+    match store {
+        SecretStore::Keyring => {
+            let entry = Entry::new(SERVICE_NAME, account).map_err(keyring_error)?;
+            match entry.get_password() {
+                Ok(secret) => Ok(Some(secret)),
+                Err(keyring::Error::NoEntry) => Ok(None),
+                Err(e) => Err(keyring_error(e)),
+            }
+        }
+        SecretStore::File => SecretFile::open_default()?.get(account),
+    }
+}
+
+pub fn set_secret_in(store: SecretStore, account: &str, secret: &str) -> Result<(), String> {
+    match store {
+        SecretStore::Keyring => Entry::new(SERVICE_NAME, account)
+            .and_then(|entry| entry.set_password(secret))
+            .map_err(keyring_error),
+        SecretStore::File => SecretFile::open_default()?.set(account, secret),
+    }
+}
+
+pub fn delete_secret_from(store: SecretStore, account: &str) -> Result<(), String> {
+    match store {
+        SecretStore::Keyring => {
+            let entry = Entry::new(SERVICE_NAME, account).map_err(keyring_error)?;
+            match entry.delete_credential() {
+                Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
+                Err(e) => Err(keyring_error(e)),
+            }
+        }
+        SecretStore::File => SecretFile::open_default()?.delete(account),
+    }
+}
+
+fn set_secret(account: &str, secret: &str) -> Result<(), String> {
+    set_secret_in(secret_store(), account, secret)
+}
+
+fn get_secret(account: &str) -> Result<Option<String>, String> {
+    get_secret_from(secret_store(), account)
+}
+
+fn delete_secret(account: &str) -> Result<(), String> {
+    delete_secret_from(secret_store(), account)
+}
+
+pub fn db_account(connection_id: &str) -> String {
+    format!("{}:db", connection_id)
+}
+
+pub fn ssh_account(connection_id: &str) -> String {
+    format!("{}:ssh", connection_id)
+}
+
+pub fn ssh_passphrase_account(connection_id: &str) -> String {
+    format!("{}:ssh_passphrase", connection_id)
+}
+
+pub fn ai_key_account(provider: &str) -> String {
+    format!("ai_key:{}", provider)
+}
+
 pub fn set_db_password(connection_id: &str, password: &str) -> Result<(), String> {
     println!("[Keychain] Setting DB password for {}", connection_id);
-    let entry =
-        Entry::new(SERVICE_NAME, &format!("{}:db", connection_id)).map_err(|e| e.to_string())?;
-    entry.set_password(password).map_err(|e| {
+    set_secret(&db_account(connection_id), password).map_err(|e| {
         println!("[Keychain] Error setting password: {}", e);
-        e.to_string()
+        e
     })
 }
 
-pub fn get_db_password(connection_id: &str) -> Result<String, String> {
+/// `None` when no password is stored for the connection
+pub fn get_db_password(connection_id: &str) -> Result<Option<String>, String> {
     println!("[Keychain] Getting DB password for {}", connection_id);
-    let entry =
-        Entry::new(SERVICE_NAME, &format!("{}:db", connection_id)).map_err(|e| e.to_string())?;
-    match entry.get_password() {
+    match get_secret(&db_account(connection_id)) {
         Ok(pwd) => {
-            println!("[Keychain] Password found for {}", connection_id);
+            if pwd.is_some() {
+                println!("[Keychain] Password found for {}", connection_id);
+            }
             Ok(pwd)
         }
         Err(e) => {
@@ -26,38 +159,30 @@ pub fn get_db_password(connection_id: &str) -> Result<String, String> {
                 "[Keychain] Error getting password for {}: {}",
                 connection_id, e
             );
-            Err(e.to_string())
+            Err(e)
         }
     }
 }
 
 pub fn delete_db_password(connection_id: &str) -> Result<(), String> {
-    let entry =
-        Entry::new(SERVICE_NAME, &format!("{}:db", connection_id)).map_err(|e| e.to_string())?;
-    match entry.delete_credential() {
-        Ok(_) => Ok(()),
-        Err(keyring::Error::NoEntry) => Ok(()),
-        Err(e) => Err(e.to_string()),
-    }
+    delete_secret(&db_account(connection_id))
 }
 
 pub fn set_ssh_password(connection_id: &str, password: &str) -> Result<(), String> {
     println!("[Keychain] Setting SSH password for {}", connection_id);
-    let entry =
-        Entry::new(SERVICE_NAME, &format!("{}:ssh", connection_id)).map_err(|e| e.to_string())?;
-    entry.set_password(password).map_err(|e| {
+    set_secret(&ssh_account(connection_id), password).map_err(|e| {
         println!("[Keychain] Error setting SSH password: {}", e);
-        e.to_string()
+        e
     })
 }
 
-pub fn get_ssh_password(connection_id: &str) -> Result<String, String> {
+pub fn get_ssh_password(connection_id: &str) -> Result<Option<String>, String> {
     println!("[Keychain] Getting SSH password for {}", connection_id);
-    let entry =
-        Entry::new(SERVICE_NAME, &format!("{}:ssh", connection_id)).map_err(|e| e.to_string())?;
-    match entry.get_password() {
+    match get_secret(&ssh_account(connection_id)) {
         Ok(pwd) => {
-            println!("[Keychain] SSH Password found for {}", connection_id);
+            if pwd.is_some() {
+                println!("[Keychain] SSH Password found for {}", connection_id);
+            }
             Ok(pwd)
         }
         Err(e) => {
@@ -65,40 +190,36 @@ pub fn get_ssh_password(connection_id: &str) -> Result<String, String> {
                 "[Keychain] Error getting SSH password for {}: {}",
                 connection_id, e
             );
-            Err(e.to_string())
+            Err(e)
         }
     }
 }
 
 pub fn delete_ssh_password(connection_id: &str) -> Result<(), String> {
-    let entry =
-        Entry::new(SERVICE_NAME, &format!("{}:ssh", connection_id)).map_err(|e| e.to_string())?;
-    match entry.delete_credential() {
-        Ok(_) => Ok(()),
-        Err(keyring::Error::NoEntry) => Ok(()),
-        Err(e) => Err(e.to_string()),
-    }
+    delete_secret(&ssh_account(connection_id))
 }
 
 pub fn set_ssh_key_passphrase(connection_id: &str, passphrase: &str) -> Result<(), String> {
-    println!("[Keychain] Setting SSH key passphrase for {}", connection_id);
-    let entry =
-        Entry::new(SERVICE_NAME, &format!("{}:ssh_passphrase", connection_id))
-            .map_err(|e| e.to_string())?;
-    entry.set_password(passphrase).map_err(|e| {
+    println!(
+        "[Keychain] Setting SSH key passphrase for {}",
+        connection_id
+    );
+    set_secret(&ssh_passphrase_account(connection_id), passphrase).map_err(|e| {
         println!("[Keychain] Error setting SSH key passphrase: {}", e);
-        e.to_string()
+        e
     })
 }
 
-pub fn get_ssh_key_passphrase(connection_id: &str) -> Result<String, String> {
-    println!("[Keychain] Getting SSH key passphrase for {}", connection_id);
-    let entry =
-        Entry::new(SERVICE_NAME, &format!("{}:ssh_passphrase", connection_id))
-            .map_err(|e| e.to_string())?;
-    match entry.get_password() {
+pub fn get_ssh_key_passphrase(connection_id: &str) -> Result<Option<String>, String> {
+    println!(
+        "[Keychain] Getting SSH key passphrase for {}",
+        connection_id
+    );
+    match get_secret(&ssh_passphrase_account(connection_id)) {
         Ok(pwd) => {
-            println!("[Keychain] SSH key passphrase found for {}", connection_id);
+            if pwd.is_some() {
+                println!("[Keychain] SSH key passphrase found for {}", connection_id);
+            }
             Ok(pwd)
         }
         Err(e) => {
@@ -106,52 +227,43 @@ pub fn get_ssh_key_passphrase(connection_id: &str) -> Result<String, String> {
                 "[Keychain] Error getting SSH key passphrase for {}: {}",
                 connection_id, e
             );
-            Err(e.to_string())
+            Err(e)
         }
     }
 }
 
 pub fn delete_ssh_key_passphrase(connection_id: &str) -> Result<(), String> {
-    let entry =
-        Entry::new(SERVICE_NAME, &format!("{}:ssh_passphrase", connection_id))
-            .map_err(|e| e.to_string())?;
-    match entry.delete_credential() {
-        Ok(_) => Ok(()),
-        Err(keyring::Error::NoEntry) => Ok(()),
-        Err(e) => Err(e.to_string()),
-    }
+    delete_secret(&ssh_passphrase_account(connection_id))
 }
 
 pub fn set_ai_key(provider: &str, key: &str) -> Result<(), String> {
     println!("[Keychain] Setting AI key for {}", provider);
-    let entry =
-        Entry::new(SERVICE_NAME, &format!("ai_key:{}", provider)).map_err(|e| e.to_string())?;
-    entry.set_password(key).map_err(|e| {
+    set_secret(&ai_key_account(provider), key).map_err(|e| {
         println!("[Keychain] Error setting AI key: {}", e);
-        e.to_string()
+        e
     })
 }
 
-pub fn get_ai_key(provider: &str) -> Result<String, String> {
-    println!("[Keychain] Getting AI key for {}", provider);
-    let entry =
-        Entry::new(SERVICE_NAME, &format!("ai_key:{}", provider)).map_err(|e| e.to_string())?;
-    match entry.get_password() {
-        Ok(pwd) => Ok(pwd),
-        Err(keyring::Error::NoEntry) => Err("No key found".to_string()),
-        Err(e) => {
-            println!("[Keychain] Error getting AI key for {}: {}", provider, e);
-            Err(e.to_string())
-        }
-    }
+pub fn get_ai_key(provider: &str) -> Result<Option<String>, String> {
+    get_secret(&ai_key_account(provider)).map_err(|e| {
+        println!("[Keychain] Error getting AI key for {}: {}", provider, e);
+        e
+    })
 }
 
 pub fn delete_ai_key(provider: &str) -> Result<(), String> {
-    let entry =
-        Entry::new(SERVICE_NAME, &format!("ai_key:{}", provider)).map_err(|e| e.to_string())?;
-    match entry.delete_credential() {
-        Ok(_) => Ok(()),
-        Err(keyring::Error::NoEntry) => Ok(()),
-        Err(e) => Err(e.to_string()),
+    delete_secret(&ai_key_account(provider))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_store_names_round_trip() {
+        for store in [SecretStore::Keyring, SecretStore::File] {
+            assert_eq!(SecretStore::parse(store.as_str()).unwrap(), store);
+        }
+        assert!(SecretStore::parse("vault").is_err());
     }
 }
@@ -5,11 +5,18 @@ pub mod dump_commands; // Added
 pub mod export;
 pub mod keychain_utils;
 pub mod models;
+pub mod notifications;
+#[cfg(feature = "parquet")]
+pub mod parquet_export;
 pub mod persistence;
 pub mod paths; // Added
 pub mod pool_manager;
+pub mod query_history;
+pub mod result_diff;
 pub mod saved_queries;
+pub mod secret_file;
 pub mod ssh_tunnel;
+pub mod statement_log;
 pub mod mcp;
 pub mod theme_commands;
 pub mod theme_models;
@@ -17,12 +24,14 @@ pub mod theme_models;
 pub mod dump_commands_tests;
 pub mod drivers {
     pub mod common;
+    pub mod duckdb;
     pub mod mysql;
     pub mod postgres;
     pub mod sqlite;
 }
 
 use clap::Parser;
+use tauri::Manager;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -38,6 +47,9 @@ struct Args {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // ssh may run this executable to ask for a key passphrase; answer and exit
+    ssh_tunnel::answer_askpass_request();
+
     // Check for CLI args first
     // We use try_parse because on some platforms (like GUI launch) args might be weird
     // or Tauri might want to handle them. But for --mcp we need priority.
@@ -74,13 +86,20 @@ pub fn run() {
         .manage(commands::QueryCancellationState::default())
         .manage(export::ExportCancellationState::default())
         .manage(dump_commands::DumpCancellationState::default())
+        .manage(notifications::NotificationState::default())
         .invoke_handler(tauri::generate_handler![
             commands::test_connection,
+            commands::ping_connection,
             commands::list_databases,
+            commands::get_schemas,
             commands::save_connection,
             commands::delete_connection,
             commands::update_connection,
             commands::duplicate_connection,
+            commands::move_connections,
+            commands::rename_connection_group,
+            commands::export_connections,
+            commands::import_connections,
             commands::get_connections,
             // SSH Connections
             commands::get_ssh_connections,
@@ -88,28 +107,65 @@ pub fn run() {
             commands::update_ssh_connection,
             commands::delete_ssh_connection,
             commands::test_ssh_connection,
+            commands::test_ssh_tunnel,
+            commands::list_ssh_tunnels,
+            commands::close_ssh_tunnel,
+            commands::close_all_ssh_tunnels,
             commands::get_tables,
+            commands::get_views,
             commands::get_columns,
+            commands::get_table_row_count,
+            commands::get_column_stats,
+            commands::search_table,
+            commands::get_table_ddl,
+            commands::get_primary_keys,
             commands::get_foreign_keys,
             commands::get_indexes,
+            commands::get_triggers,
+            commands::get_routines,
+            commands::get_routine_definition,
+            commands::call_procedure,
+            commands::create_index,
+            commands::drop_index,
+            commands::add_column,
+            commands::drop_column,
+            commands::rename_column,
             commands::delete_record,
+            commands::delete_by_match,
             commands::update_record,
+            commands::update_records_batch,
             commands::insert_record,
+            commands::preview_delete_record,
+            commands::preview_update_record,
+            commands::preview_insert_record,
+            commands::insert_records,
+            commands::upsert_record,
             commands::execute_query,
+            commands::execute_query_with_params,
+            commands::execute_query_sets,
             commands::cancel_query,
+            commands::explain_query,
+            commands::execute_script,
             commands::set_window_title,
             commands::open_er_diagram_window,
             export::export_query_to_file,
+            export::export_table_inserts,
             export::cancel_export,
             saved_queries::get_saved_queries,
             saved_queries::save_query,
             saved_queries::update_saved_query,
             saved_queries::delete_saved_query,
+            saved_queries::toggle_favorite,
+            saved_queries::list_tags,
+            query_history::get_query_history,
+            query_history::clear_query_history,
+            result_diff::diff_results,
             // Config
             config::get_config,
             config::save_config,
             config::set_ai_key,
             config::check_ai_key,
+            config::migrate_secrets,
             config::get_system_prompt,
             config::save_system_prompt,
             config::reset_system_prompt,
@@ -124,6 +180,7 @@ pub fn run() {
             // MCP
             mcp::install::get_mcp_status,
             mcp::install::install_mcp_config,
+            mcp::transport::start_mcp_server,
             // Themes
             theme_commands::get_all_themes,
             theme_commands::get_theme,
@@ -137,7 +194,19 @@ pub fn run() {
             dump_commands::import_database,
             dump_commands::cancel_import,
             dump_commands::cancel_dump,
+            // Notifications
+            notifications::subscribe_notifications,
+            notifications::unsubscribe_notifications,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // `Exit` fires on actual application quit, including on macOS where closing
+            // the last window keeps the app alive.
+            if let tauri::RunEvent::Exit = event {
+                app.state::<notifications::NotificationState>().stop_all();
+                tauri::async_runtime::block_on(pool_manager::close_all_pools());
+                ssh_tunnel::stop_all_tunnels();
+            }
+        });
 }
@@ -0,0 +1,135 @@
+use super::protocol::*;
+use super::{handle_call_tool, handle_list_resources, handle_list_tools, handle_read_resource};
+use serde_json::json;
+
+/// MCP revision this server implements
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// Answers one JSON-RPC message. Notifications (no id) never get a reply.
+pub async fn handle_request(req: JsonRpcRequest) -> Option<JsonRpcResponse> {
+    if req.id.is_none() {
+        if req.method == "notifications/initialized" {
+            eprintln!("[MCP] Client initialized.");
+        }
+        return None;
+    }
+
+    let id = req.id.clone();
+    let result = match req.method.as_str() {
+        "initialize" => handle_initialize(req.params),
+        "resources/list" => handle_list_resources().await,
+        "resources/read" => handle_read_resource(req.params).await,
+        "tools/list" => handle_list_tools(),
+        "tools/call" => handle_call_tool(req.params).await,
+        _ => Err(JsonRpcError {
+            code: -32601,
+            message: "Method not found".to_string(),
+            data: None,
+        }),
+    };
+
+    let (res, err) = match result {
+        Ok(val) => (Some(val), None),
+        Err(e) => (None, Some(e)),
+    };
+
+    Some(JsonRpcResponse {
+        jsonrpc: "2.0".to_string(),
+        id,
+        result: res,
+        error: err,
+    })
+}
+
+/// Advertises the server (name and version from the crate metadata) and its
+/// resources and tools
+fn handle_initialize(params: Option<serde_json::Value>) -> Result<serde_json::Value, JsonRpcError> {
+    // Client details are only logged, so incomplete params don't fail the handshake
+    match params.and_then(|p| serde_json::from_value::<InitializeParams>(p).ok()) {
+        Some(params) => eprintln!(
+            "[MCP] Initializing for {} {} (protocol {})",
+            params.client_info.name, params.client_info.version, params.protocol_version
+        ),
+        None => eprintln!("[MCP] Initializing for an unidentified client"),
+    }
+
+    let result = InitializeResult {
+        protocol_version: PROTOCOL_VERSION.to_string(),
+        capabilities: ServerCapabilities {
+            resources: Some(json!({ "listChanged": false })),
+            tools: Some(json!({ "listChanged": false })),
+            prompts: None,
+        },
+        server_info: ServerInfo {
+            name: env!("CARGO_PKG_NAME").to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        },
+    };
+    serde_json::to_value(result).map_err(|e| JsonRpcError {
+        code: -32603,
+        message: e.to_string(),
+        data: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(
+        id: Option<serde_json::Value>,
+        method: &str,
+        params: serde_json::Value,
+    ) -> JsonRpcRequest {
+        JsonRpcRequest {
+            jsonrpc: "2.0".to_string(),
+            method: method.to_string(),
+            params: Some(params),
+            id,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_initialize() {
+        let params = json!({
+            "protocolVersion": "2024-11-05",
+            "capabilities": {},
+            "clientInfo": { "name": "test-client", "version": "1.0" }
+        });
+        let resp = handle_request(request(Some(json!(1)), "initialize", params))
+            .await
+            .unwrap();
+
+        assert_eq!(resp.id, Some(json!(1)));
+        let result = resp.result.unwrap();
+        assert_eq!(result["protocolVersion"], PROTOCOL_VERSION);
+        assert_eq!(result["serverInfo"]["version"], env!("CARGO_PKG_VERSION"));
+        assert!(result["capabilities"]["tools"].is_object());
+    }
+
+    #[tokio::test]
+    async fn test_initialize_without_client_info() {
+        let resp = handle_request(request(Some(json!(1)), "initialize", json!({})))
+            .await
+            .unwrap();
+        assert!(resp.error.is_none());
+        assert_eq!(
+            resp.result.unwrap()["serverInfo"]["name"],
+            env!("CARGO_PKG_NAME")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_notifications_get_no_reply() {
+        let resp = handle_request(request(None, "notifications/initialized", json!({}))).await;
+        assert!(resp.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_method() {
+        let resp = handle_request(request(Some(json!("a")), "prompts/list", json!({})))
+            .await
+            .unwrap();
+        assert_eq!(resp.error.unwrap().code, -32601);
+    }
+}
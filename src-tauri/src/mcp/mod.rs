@@ -1,106 +1,21 @@
-use std::io::{self, BufRead, Write};
 use serde_json::json;
 use crate::commands;
 use crate::persistence;
 use crate::paths;
-use crate::drivers::{mysql, postgres, sqlite};
+use crate::drivers::common::{
+    describe_statement_timeout, driver_for, ensure_query_allowed, ensure_select_only,
+};
+use crate::models::SavedConnection;
+use crate::saved_queries::{self, SavedQuery};
 
 pub mod protocol;
 pub mod install;
+mod handler;
+pub mod transport;
 use protocol::*;
 
 pub async fn run_mcp_server() {
-    eprintln!("[MCP] Starting Tabularis MCP Server...");
-    
-    let stdin = io::stdin();
-    let mut stdout = io::stdout();
-    let mut iterator = stdin.lock().lines();
-
-    while let Some(line_result) = iterator.next() {
-        match line_result {
-            Ok(line) => {
-                if line.trim().is_empty() {
-                    continue;
-                }
-                
-                // Log input to stderr for debugging
-                eprintln!("[MCP] Received: {}", line);
-                
-                match serde_json::from_str::<JsonRpcRequest>(&line) {
-                    Ok(request) => {
-                        let response = handle_request(request).await;
-                        if let Some(resp) = response {
-                            let json = serde_json::to_string(&resp).unwrap();
-                            // Log output to stderr
-                            eprintln!("[MCP] Sending: {}", json);
-                            stdout.write_all(json.as_bytes()).unwrap();
-                            stdout.write_all(b"\n").unwrap();
-                            stdout.flush().unwrap();
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("[MCP] Error parsing JSON: {}", e);
-                    }
-                }
-            }
-            Err(e) => {
-                eprintln!("[MCP] Error reading stdin: {}", e);
-                break;
-            }
-        }
-    }
-}
-
-async fn handle_request(req: JsonRpcRequest) -> Option<JsonRpcResponse> {
-    // Notifications (no id)
-    if req.id.is_none() {
-        if req.method == "notifications/initialized" {
-            eprintln!("[MCP] Client initialized.");
-        }
-        return None;
-    }
-
-    let id = req.id.clone();
-    let result = match req.method.as_str() {
-        "initialize" => handle_initialize(req.params),
-        "resources/list" => handle_list_resources().await,
-        "resources/read" => handle_read_resource(req.params).await,
-        "tools/list" => handle_list_tools(),
-        "tools/call" => handle_call_tool(req.params).await,
-        _ => Err(JsonRpcError {
-            code: -32601,
-            message: "Method not found".to_string(),
-            data: None,
-        }),
-    };
-
-    let (res, err) = match result {
-        Ok(val) => (Some(val), None),
-        Err(e) => (None, Some(e)),
-    };
-
-    Some(JsonRpcResponse {
-        jsonrpc: "2.0".to_string(),
-        id,
-        result: res,
-        error: err,
-    })
-}
-
-fn handle_initialize(_params: Option<serde_json::Value>) -> Result<serde_json::Value, JsonRpcError> {
-    let result = InitializeResult {
-        protocol_version: "2024-11-05".to_string(), 
-        capabilities: ServerCapabilities {
-            resources: Some(json!({ "listChanged": false })),
-            tools: Some(json!({ "listChanged": false })),
-            prompts: None,
-        },
-        server_info: ServerInfo {
-            name: "tabularis-mcp".to_string(),
-            version: "0.1.0".to_string(),
-        },
-    };
-    Ok(serde_json::to_value(result).unwrap())
+    transport::run_stdio().await;
 }
 
 async fn handle_list_resources() -> Result<serde_json::Value, JsonRpcError> {
@@ -123,7 +38,7 @@ async fn handle_list_resources() -> Result<serde_json::Value, JsonRpcError> {
     });
 
     // Add schema resources for each connection
-    for conn in connections {
+    for conn in &connections {
         resources.push(Resource {
             uri: format!("tabularis://{}/schema", conn.id),
             name: format!("Schema: {}", conn.name),
@@ -132,6 +47,20 @@ async fn handle_list_resources() -> Result<serde_json::Value, JsonRpcError> {
         });
     }
 
+    // Add each saved query as SQL text
+    for query in load_saved_queries()? {
+        let connection = connections
+            .iter()
+            .find(|c| c.id == query.connection_id)
+            .map_or("an unknown connection", |c| c.name.as_str());
+        resources.push(Resource {
+            uri: format!("{}{}", QUERY_URI_PREFIX, query.id),
+            name: format!("Query: {}", query.name),
+            description: Some(format!("Saved query for {}", connection)),
+            mime_type: Some("text/sql".to_string()),
+        });
+    }
+
     Ok(json!({
         "resources": resources
     }))
@@ -172,6 +101,20 @@ async fn handle_read_resource(params: Option<serde_json::Value>) -> Result<serde
         }));
     }
 
+    if let Some(query_id) = uri.strip_prefix(QUERY_URI_PREFIX) {
+        let query = load_saved_queries()?
+            .into_iter()
+            .find(|q| q.id == query_id)
+            .ok_or_else(|| resource_not_found(uri))?;
+
+        let content = ResourceContent {
+            uri: uri.to_string(),
+            mime_type: Some("text/sql".to_string()),
+            text: Some(query.sql),
+        };
+        return Ok(json!({ "contents": [content] }));
+    }
+
     if uri.starts_with("tabularis://") && uri.ends_with("/schema") {
         let parts: Vec<&str> = uri.split('/').collect();
         // uri format: tabularis://{id}/schema -> ["tabularis:", "", "{id}", "schema"]
@@ -200,12 +143,11 @@ async fn handle_read_resource(params: Option<serde_json::Value>) -> Result<serde
             data: None
         })?;
 
-        let tables = match conn.params.driver.as_str() {
-             "mysql" => mysql::get_tables(&params).await,
-             "postgres" => postgres::get_tables(&params).await,
-             "sqlite" => sqlite::get_tables(&params).await,
-             _ => Err("Unsupported driver".into()),
-        }.map_err(|e| JsonRpcError { code: -32000, message: e, data: None })?;
+        let tables = match driver_for(&params) {
+            Ok(driver) => driver.get_tables(&params).await,
+            Err(e) => Err(e),
+        }
+        .map_err(|e| JsonRpcError { code: -32000, message: e, data: None })?;
 
         // Format as simplified DDL or JSON
         let schema_json = serde_json::to_string_pretty(&tables).unwrap();
@@ -219,27 +161,69 @@ async fn handle_read_resource(params: Option<serde_json::Value>) -> Result<serde
         }));
     }
 
-    Err(JsonRpcError {
-        code: -32602,
-        message: "Resource not found".to_string(),
+    Err(resource_not_found(uri))
+}
+
+/// Saved queries are served as `tabularis://query/{id}`
+const QUERY_URI_PREFIX: &str = "tabularis://query/";
+
+fn load_saved_queries() -> Result<Vec<SavedQuery>, JsonRpcError> {
+    let dir = saved_queries::queries_dir_in(&paths::get_app_config_dir());
+    saved_queries::load_all_queries(&dir).map_err(|e| JsonRpcError {
+        code: -32000,
+        message: format!("Failed to load saved queries: {}", e),
         data: None,
     })
 }
 
+/// MCP's error code for an unknown resource URI
+fn resource_not_found(uri: &str) -> JsonRpcError {
+    JsonRpcError {
+        code: -32002,
+        message: "Resource not found".to_string(),
+        data: Some(json!({ "uri": uri })),
+    }
+}
+
 fn handle_list_tools() -> Result<serde_json::Value, JsonRpcError> {
     let tools = vec![
         Tool {
             name: "run_query".to_string(),
-            description: Some("Execute a SQL query on a specific connection".to_string()),
+            description: Some("Execute a read-only SQL query on a specific connection".to_string()),
             input_schema: json!({
                 "type": "object",
                 "properties": {
                     "connection_id": { "type": "string", "description": "The ID or Name of the connection (from tabularis://connections)" },
-                    "query": { "type": "string", "description": "The SQL query to execute" }
+                    "sql": { "type": "string", "description": "The SQL query to execute; only read-only statements (SELECT, WITH, SHOW, EXPLAIN, ...) are allowed" }
                 },
-                "required": ["connection_id", "query"]
+                "required": ["connection_id", "sql"]
             }),
-        }
+        },
+        Tool {
+            name: "list_tables".to_string(),
+            description: Some("List the tables of a connection's database".to_string()),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": { "type": "string", "description": "The ID or Name of the connection (from tabularis://connections)" }
+                },
+                "required": ["connection_id"]
+            }),
+        },
+        Tool {
+            name: "describe_table".to_string(),
+            description: Some(
+                "Describe the columns of a table: name, type, nullability, primary key".to_string(),
+            ),
+            input_schema: json!({
+                "type": "object",
+                "properties": {
+                    "connection_id": { "type": "string", "description": "The ID or Name of the connection (from tabularis://connections)" },
+                    "table": { "type": "string", "description": "The table name (from list_tables)" }
+                },
+                "required": ["connection_id", "table"]
+            }),
+        },
     ];
 
     Ok(json!({
@@ -247,57 +231,135 @@ fn handle_list_tools() -> Result<serde_json::Value, JsonRpcError> {
     }))
 }
 
-async fn handle_call_tool(params: Option<serde_json::Value>) -> Result<serde_json::Value, JsonRpcError> {
-    let params = params.ok_or(JsonRpcError { code: -32602, message: "Missing params".to_string(), data: None })?;
-    let name = params["name"].as_str().unwrap_or("");
-    let args = params["arguments"].as_object().ok_or(JsonRpcError { code: -32602, message: "Missing arguments".to_string(), data: None })?;
+async fn handle_call_tool(
+    params: Option<serde_json::Value>,
+) -> Result<serde_json::Value, JsonRpcError> {
+    let params: CallToolParams =
+        params
+            .and_then(|p| serde_json::from_value(p).ok())
+            .ok_or(JsonRpcError {
+                code: -32602,
+                message: "Missing params".to_string(),
+                data: None,
+            })?;
+    let args = params.arguments.unwrap_or_else(|| json!({}));
+    let args = args.as_object().ok_or(JsonRpcError {
+        code: -32602,
+        message: "Missing arguments".to_string(),
+        data: None,
+    })?;
 
-    if name == "run_query" {
-        let conn_id = args.get("connection_id").and_then(|v| v.as_str()).ok_or(JsonRpcError {
-            code: -32602, message: "Missing connection_id".to_string(), data: None
-        })?;
-        let query = args.get("query").and_then(|v| v.as_str()).ok_or(JsonRpcError {
-            code: -32602, message: "Missing query".to_string(), data: None
-        })?;
+    let result = call_tools(&params.name, args).await?;
+    Ok(serde_json::to_value(result).unwrap())
+}
 
-        let config_path = paths::get_app_config_dir().join("connections.json");
-        let connections = persistence::load_connections(&config_path)
-             .map_err(|e| JsonRpcError { code: -32000, message: e, data: None })?;
+/// Runs a tool. Only an unknown tool is a JSON-RPC error; failures of the tool
+/// itself come back as a result with `isError` so the client can show them.
+async fn call_tools(
+    name: &str,
+    args: &serde_json::Map<String, serde_json::Value>,
+) -> Result<CallToolResult, JsonRpcError> {
+    let result = match name {
+        "run_query" => run_query(args).await,
+        "list_tables" => list_tables(args).await,
+        "describe_table" => describe_table(args).await,
+        _ => {
+            return Err(JsonRpcError {
+                code: -32601,
+                message: "Tool not found".to_string(),
+                data: None,
+            })
+        }
+    };
 
-        // Try to find by ID or exact name (case-insensitive) first, then partial name match
-        let conn = connections.iter()
-            .find(|c| c.id == conn_id || c.name.eq_ignore_ascii_case(conn_id))
-            .or_else(|| connections.iter().find(|c| c.name.to_lowercase().contains(&conn_id.to_lowercase())))
-            .ok_or(JsonRpcError {
-                code: -32000,
-                message: format!("Connection not found: {}", conn_id),
-                data: None
-            })?;
+    Ok(match result {
+        Ok(value) => text_result(value, None),
+        Err(e) => text_result(serde_json::Value::String(e), Some(true)),
+    })
+}
 
-        let db_params = commands::resolve_connection_params(&conn.params).map_err(|e| JsonRpcError {
-            code: -32000,
-            message: e,
-            data: None
-        })?;
+/// Runs a read-only query and returns up to 100 rows. Only SELECT-like statements
+/// pass, and the connection is opened read-only as a server-side backstop.
+async fn run_query(
+    args: &serde_json::Map<String, serde_json::Value>,
+) -> Result<serde_json::Value, String> {
+    let conn = find_connection(required_arg(args, "connection_id")?)?;
+    // `query` is the argument name used by earlier versions of this tool
+    let query = required_arg(args, "sql").or_else(|_| required_arg(args, "query"))?;
+
+    ensure_select_only(query)?;
+    let mut db_params = commands::resolve_connection_params(&conn.params)?;
+    db_params.read_only = Some(true);
+    ensure_query_allowed(&db_params, query)?;
+
+    let result = driver_for(&db_params)?
+        .execute_query(&db_params, query, Some(100), 1, None, None)
+        .await
+        .map_err(|e| describe_statement_timeout(&db_params, e))?;
+    serde_json::to_value(result).map_err(|e| e.to_string())
+}
 
-        let result = match conn.params.driver.as_str() {
-             "mysql" => mysql::execute_query(&db_params, query, Some(100), 1).await,
-             "postgres" => postgres::execute_query(&db_params, query, Some(100), 1).await,
-             "sqlite" => sqlite::execute_query(&db_params, query, Some(100), 1).await,
-             _ => Err("Unsupported driver".into()),
-        }.map_err(|e| JsonRpcError { code: -32000, message: e, data: None })?;
+async fn list_tables(
+    args: &serde_json::Map<String, serde_json::Value>,
+) -> Result<serde_json::Value, String> {
+    let conn = find_connection(required_arg(args, "connection_id")?)?;
+    let db_params = commands::resolve_connection_params(&conn.params)?;
 
-        return Ok(json!({
-            "content": [{
-                "type": "text",
-                "text": serde_json::to_string_pretty(&result).unwrap()
-            }]
-        }));
-    }
+    let tables = driver_for(&db_params)?.get_tables(&db_params).await?;
+    serde_json::to_value(tables).map_err(|e| e.to_string())
+}
 
-    Err(JsonRpcError {
-        code: -32601,
-        message: "Tool not found".to_string(),
-        data: None,
-    })
+async fn describe_table(
+    args: &serde_json::Map<String, serde_json::Value>,
+) -> Result<serde_json::Value, String> {
+    let conn = find_connection(required_arg(args, "connection_id")?)?;
+    let table = required_arg(args, "table")?;
+    let db_params = commands::resolve_connection_params(&conn.params)?;
+
+    let columns = driver_for(&db_params)?
+        .get_columns(&db_params, table)
+        .await?;
+    serde_json::to_value(columns).map_err(|e| e.to_string())
+}
+
+fn required_arg<'a>(
+    args: &'a serde_json::Map<String, serde_json::Value>,
+    name: &str,
+) -> Result<&'a str, String> {
+    args.get(name)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| format!("Missing argument: {}", name))
+}
+
+/// Finds a connection by id or exact name (case-insensitive), then by partial name
+fn find_connection(conn_id: &str) -> Result<SavedConnection, String> {
+    let config_path = paths::get_app_config_dir().join("connections.json");
+    let mut connections = persistence::load_connections(&config_path)?;
+
+    let needle = conn_id.to_lowercase();
+    let idx = connections
+        .iter()
+        .position(|c| c.id == conn_id || c.name.eq_ignore_ascii_case(conn_id))
+        .or_else(|| {
+            connections
+                .iter()
+                .position(|c| c.name.to_lowercase().contains(&needle))
+        })
+        .ok_or_else(|| format!("Connection not found: {}", conn_id))?;
+    Ok(connections.swap_remove(idx))
+}
+
+/// Wraps `value` in a text tool result: strings as-is, anything else as pretty JSON
+fn text_result(value: serde_json::Value, is_error: Option<bool>) -> CallToolResult {
+    let text = match value {
+        serde_json::Value::String(s) => s,
+        value => serde_json::to_string_pretty(&value).unwrap_or_default(),
+    };
+    CallToolResult {
+        content: vec![ToolContent {
+            r#type: "text".to_string(),
+            text,
+        }],
+        is_error,
+    }
 }
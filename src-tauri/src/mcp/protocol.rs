@@ -103,7 +103,7 @@ pub struct CallToolParams {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CallToolResult {
     pub content: Vec<ToolContent>,
-    #[serde(rename = "isError")]
+    #[serde(rename = "isError", skip_serializing_if = "Option::is_none")]
     pub is_error: Option<bool>,
 }
 
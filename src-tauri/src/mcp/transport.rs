@@ -0,0 +1,137 @@
+use super::handler::handle_request;
+use super::protocol::{JsonRpcError, JsonRpcRequest, JsonRpcResponse};
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set while `start_mcp_server` has the stdio loop running inside the desktop app
+static SERVER_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Serves newline-delimited JSON-RPC from `input` until it closes, writing one
+/// response line per request to `output` and flushing after each
+pub async fn serve<I: BufRead, O: Write>(input: I, mut output: O) -> io::Result<()> {
+    for line in input.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        // Log input to stderr for debugging
+        eprintln!("[MCP] Received: {}", line);
+
+        let response = match serde_json::from_str::<JsonRpcRequest>(&line) {
+            Ok(request) => handle_request(request).await,
+            Err(e) => {
+                eprintln!("[MCP] Error parsing JSON: {}", e);
+                parse_error(&line, &e)
+            }
+        };
+
+        if let Some(resp) = response {
+            let json = serde_json::to_string(&resp)?;
+            eprintln!("[MCP] Sending: {}", json);
+            output.write_all(json.as_bytes())?;
+            output.write_all(b"\n")?;
+            output.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// A -32700 reply to a malformed message, when an id can still be read from it
+fn parse_error(line: &str, err: &serde_json::Error) -> Option<JsonRpcResponse> {
+    let message: serde_json::Value = serde_json::from_str(line).ok()?;
+    let id = message.get("id").filter(|id| !id.is_null())?.clone();
+    Some(JsonRpcResponse {
+        jsonrpc: "2.0".to_string(),
+        id: Some(id),
+        result: None,
+        error: Some(JsonRpcError {
+            code: -32700,
+            message: format!("Parse error: {}", err),
+            data: None,
+        }),
+    })
+}
+
+/// Serves the process' stdin/stdout until stdin closes
+pub async fn run_stdio() {
+    eprintln!("[MCP] Starting Tabularis MCP Server...");
+    let stdin = io::stdin();
+    if let Err(e) = serve(stdin.lock(), io::stdout()).await {
+        eprintln!("[MCP] Transport error: {}", e);
+    }
+    eprintln!("[MCP] Server stopped.");
+}
+
+/// Lets the desktop app act as an MCP server for a local LLM client that launched
+/// it: serves stdin/stdout on a dedicated thread until stdin closes
+#[tauri::command]
+pub async fn start_mcp_server() -> Result<(), String> {
+    if SERVER_RUNNING.swap(true, Ordering::SeqCst) {
+        return Err("MCP server is already running".into());
+    }
+
+    std::thread::Builder::new()
+        .name("mcp-stdio".to_string())
+        .spawn(|| {
+            tauri::async_runtime::block_on(run_stdio());
+            SERVER_RUNNING.store(false, Ordering::SeqCst);
+        })
+        .map_err(|e| {
+            SERVER_RUNNING.store(false, Ordering::SeqCst);
+            e.to_string()
+        })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn serve_lines(input: &str) -> Vec<serde_json::Value> {
+        let mut output = Vec::new();
+        serve(input.as_bytes(), &mut output).await.unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_serve_replies_per_request() {
+        let input = concat!(
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "tools/list"}"#,
+            "\n\n",
+            r#"{"jsonrpc": "2.0", "method": "notifications/initialized"}"#,
+            "\n",
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "nope"}"#,
+            "\n",
+        );
+        let responses = serve_lines(input).await;
+
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0]["id"], 1);
+        assert!(responses[0]["result"]["tools"].is_array());
+        assert_eq!(responses[1]["id"], 2);
+        assert_eq!(responses[1]["error"]["code"], -32601);
+    }
+
+    #[tokio::test]
+    async fn test_serve_parse_errors() {
+        let input = concat!(
+            r#"{"id": 7, "method": 42}"#,
+            "\n",
+            "not json\n",
+            r#"{"jsonrpc": "2.0", "id": 8, "method": "tools/list"}"#,
+            "\n",
+        );
+        let responses = serve_lines(input).await;
+
+        // Without an id there is nothing to reply to, and the loop keeps going
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0]["id"], 7);
+        assert_eq!(responses[0]["error"]["code"], -32700);
+        assert_eq!(responses[1]["id"], 8);
+    }
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct SshConnection {
@@ -15,6 +16,12 @@ pub struct SshConnection {
     pub key_file: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub key_passphrase: Option<String>,
+    // Jump hosts (ProxyJump) passed through in order, each `[user@]host[:port]`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jump_hosts: Option<Vec<String>>,
+    // Check server keys against ~/.ssh/known_hosts (off by default, recommended on)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verify_host_key: Option<bool>,
     pub save_in_keychain: Option<bool>,
 }
 
@@ -30,6 +37,10 @@ pub struct SshConnectionInput {
     pub key_file: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub key_passphrase: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub jump_hosts: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verify_host_key: Option<bool>,
     pub save_in_keychain: Option<bool>,
 }
 
@@ -44,9 +55,22 @@ pub struct SshTestParams {
     pub key_file: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub key_passphrase: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verify_host_key: Option<bool>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone)]
+/// An open SSH tunnel, keyed by `user@ssh_host:ssh_port:remote_host->remote_port`
+#[derive(Debug, Serialize)]
+pub struct SshTunnelInfo {
+    pub key: String,
+    pub local_port: u16,
+    pub backend: String,
+    pub healthy: bool,
+    /// Why an unhealthy tunnel failed, e.g. the last stderr lines of system ssh
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
 pub struct ConnectionParams {
     pub driver: String,
     pub host: Option<String>,
@@ -54,6 +78,10 @@ pub struct ConnectionParams {
     pub username: Option<String>,
     pub password: Option<String>,
     pub database: String,
+    // Postgres schema to browse and put first on the search_path (defaults to public);
+    // for MySQL it selects the database instead
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
     // SSH Tunnel
     pub ssh_enabled: Option<bool>,
     pub ssh_connection_id: Option<String>,
@@ -70,7 +98,83 @@ pub struct ConnectionParams {
     pub ssh_key_file: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ssh_key_passphrase: Option<String>,
+    // Jump hosts (ProxyJump) between here and the SSH server, each `[user@]host[:port]`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssh_jump_hosts: Option<Vec<String>>,
+    // Check SSH server keys against ~/.ssh/known_hosts instead of accepting any key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssh_verify_host_key: Option<bool>,
+    // Local end of the SSH tunnel: a fixed port instead of a random one, and whether
+    // to fall back to a random port when it is taken (otherwise connecting fails)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssh_local_port: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssh_local_port_fallback: Option<bool>,
+    // Seconds between SSH keepalives (defaults to 30)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssh_keepalive_secs: Option<u64>,
     pub save_in_keychain: Option<bool>,
+    // Reject write statements and record edits (Postgres sessions are also read-only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_only: Option<bool>,
+    // Debugging: append every executed statement to a per-connection log file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub log_statements: Option<bool>,
+    // Advanced: a complete connection URL (e.g. from a cloud provider) used instead of
+    // the host/port/user fields and `extra_params`. Its scheme must match `driver`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connection_string: Option<String>,
+    // Advanced: extra driver options appended to the connection URL as query parameters
+    // (e.g. application_name, sslmode). Unknown keys are passed through to sqlx as-is.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extra_params: Option<HashMap<String, String>>,
+    // Seconds to wait for a connection before giving up (defaults to 10)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connect_timeout_secs: Option<u64>,
+    // SQLite: milliseconds to wait on a locked database before failing (defaults to
+    // 5000), and whether to switch the database to WAL journaling
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sqlite_busy_timeout_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sqlite_wal: Option<bool>,
+    // SQLite: extra database files attached on every connection as (alias, path), so
+    // queries can reference `alias.table`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attach: Option<Vec<(String, String)>>,
+    // Postgres: session TimeZone (e.g. `Europe/Berlin`) for now(), casts and
+    // date functions; timestamptz values are always returned with their offset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    // Pool sizing: connections kept open at most (defaults to 10, 5 for SQLite) and
+    // at least, and seconds an unused connection stays open before it is closed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_connections: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_connections: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idle_timeout_secs: Option<u64>,
+    // Label appended to `tabularis` in the server's session list (Postgres
+    // application_name; sqlx sends no MySQL connect attributes to carry it); the
+    // connection's name when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_name: Option<String>,
+    // Seconds a statement may run before the server aborts it (MySQL: SELECTs only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub statement_timeout_secs: Option<u64>,
+    // Safeguard: SELECTs run without a LIMIT or pagination fetch at most this many
+    // rows (`LIMIT n` is appended); off when unset or 0
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_row_limit: Option<u32>,
+}
+
+/// Returned by `test_connection` so users can confirm which server they reached
+#[derive(Debug, Serialize)]
+pub struct ConnectionTestInfo {
+    pub server_version: String,
+    /// Database (or schema, for SQLite) the session is using
+    pub database: Option<String>,
+    /// Round trip of a ping on the fresh connection
+    pub latency_ms: u64,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -78,6 +182,17 @@ pub struct SavedConnection {
     pub id: String,
     pub name: String,
     pub params: ConnectionParams,
+    /// Folder path in the connection tree, nested with `/` (e.g. `Prod/EU`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+}
+
+/// Result of `import_connections`. Exports carry no secrets, so the listed
+/// connections need their password entered again before they can connect.
+#[derive(Debug, Serialize)]
+pub struct ConnectionImport {
+    pub imported: Vec<SavedConnection>,
+    pub password_required: Vec<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -85,6 +200,14 @@ pub struct TableInfo {
     pub name: String,
 }
 
+/// A view, listed separately from base tables. `definition` is the defining SQL
+/// when the catalog exposes it.
+#[derive(Debug, Serialize)]
+pub struct ViewInfo {
+    pub name: String,
+    pub definition: Option<String>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct TableColumn {
     pub name: String,
@@ -92,6 +215,24 @@ pub struct TableColumn {
     pub is_pk: bool,
     pub is_nullable: bool,
     pub is_auto_increment: bool,
+    /// Permitted members of a MySQL `ENUM` or `SET` column, in declaration order
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enum_values: Option<Vec<String>>,
+    /// Default expression as the catalog reports it, e.g. `0`, `'n/a'::text` or `now()`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_value: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+/// A column to add with `add_column`. `data_type` and `default_value` are SQL as
+/// written in the column definition, e.g. `VARCHAR(255)` and `'n/a'` or `now()`.
+#[derive(Debug, Deserialize)]
+pub struct ColumnDefinition {
+    pub name: String,
+    pub data_type: String,
+    pub is_nullable: bool,
+    pub default_value: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -113,6 +254,32 @@ pub struct Index {
     pub seq_in_index: i32,
 }
 
+#[derive(Debug, Serialize)]
+pub struct Trigger {
+    pub name: String,
+    /// `BEFORE`, `AFTER` or `INSTEAD OF`
+    pub timing: String,
+    /// `INSERT`, `UPDATE` or `DELETE`
+    pub event: String,
+    /// What the trigger runs, e.g. `BEGIN ... END` or `EXECUTE FUNCTION audit()`
+    pub statement: String,
+    /// Postgres only: `false` once disabled with `ALTER TABLE ... DISABLE TRIGGER`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_enabled: Option<bool>,
+}
+
+/// A stored procedure or function. `definition` is the body when the catalog
+/// exposes it to the current user.
+#[derive(Debug, Serialize)]
+pub struct RoutineInfo {
+    pub name: String,
+    /// `PROCEDURE` or `FUNCTION`
+    pub routine_type: String,
+    /// `None` for procedures
+    pub return_type: Option<String>,
+    pub definition: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Pagination {
     pub page: u32,
@@ -120,14 +287,129 @@ pub struct Pagination {
     pub total_rows: u64,
 }
 
+/// Bind values for `execute_query_with_params`: a list for positional placeholders
+/// (`?`, or `$1` on Postgres) or a map for named `:name` placeholders
+#[derive(Debug, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum QueryParams {
+    Positional(Vec<serde_json::Value>),
+    Named(HashMap<String, serde_json::Value>),
+}
+
+/// Query plan returned by `explain_query`. `plan` holds the driver's native output:
+/// the JSON document for MySQL/Postgres, the `EXPLAIN QUERY PLAN` rows for SQLite.
+#[derive(Debug, Serialize)]
+pub struct ExplainResult {
+    pub driver: String,
+    pub plan: serde_json::Value,
+}
+
+/// Outcome of `execute_script`: one affected-row count per statement, in order
 #[derive(Debug, Serialize)]
+pub struct ScriptResult {
+    pub affected_rows: Vec<u64>,
+    pub duration_ms: u64,
+}
+
+/// The table/column a result column points to through a foreign key
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ColumnRef {
+    pub table: String,
+    pub column: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct QueryResult {
     pub columns: Vec<String>,
+    /// Database type name per column, e.g. `INT4` or `TIMESTAMPTZ`
+    #[serde(default)]
+    pub column_types: Vec<String>,
     pub rows: Vec<Vec<serde_json::Value>>,
     pub affected_rows: u64,
     #[serde(default)]
     pub truncated: bool,
     pub pagination: Option<Pagination>,
+    /// True when rows exist beyond the returned page
+    #[serde(default)]
+    pub has_more: bool,
+    /// Foreign-key targets keyed by result column; only set for single-table SELECTs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column_refs: Option<HashMap<String, ColumnRef>>,
+    /// Time spent running the statement and fetching its rows
+    #[serde(default)]
+    pub execution_ms: u64,
+    /// Rows returned; `None` for statements that return no rows
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub row_count: Option<usize>,
+    /// The connection's `default_row_limit`, when it cut this result short
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_limit: Option<u32>,
+}
+
+/// What `call_procedure` returns: the routine's rows, plus the values of OUT and
+/// INOUT parameters by name where the driver reports them separately (MySQL)
+#[derive(Debug, Serialize)]
+pub struct ProcedureResult {
+    #[serde(flatten)]
+    pub result: QueryResult,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub out_params: HashMap<String, serde_json::Value>,
+}
+
+/// A row edit rendered without running it: the SQL with the driver's placeholders
+/// and the `(column, value)` pairs written, in statement order. JSON nulls appear
+/// inline as `NULL` rather than as placeholders.
+#[derive(Debug, Serialize)]
+pub struct StatementPreview {
+    pub sql: String,
+    pub bindings: Vec<(String, serde_json::Value)>,
+}
+
+/// One cell edit for `update_records_batch`: sets `column` to `value` in the rows
+/// of `table` matching `pk`
+#[derive(Debug, Deserialize, Clone)]
+pub struct RecordEdit {
+    pub table: String,
+    pub pk: HashMap<String, serde_json::Value>,
+    pub column: String,
+    pub value: serde_json::Value,
+}
+
+/// Outcome of a batch of edits run in one transaction. The first failing edit rolls
+/// back the whole batch (`committed` is false) and the edits after it are not run.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct BatchEditResult {
+    pub committed: bool,
+    pub results: Vec<RecordEditResult>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct RecordEditResult {
+    pub success: bool,
+    pub rows_affected: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Profile of one column from `get_column_stats`. With `sampled` set, the figures
+/// cover only the sampled rows.
+#[derive(Debug, Serialize)]
+pub struct ColumnStats {
+    pub min: serde_json::Value,
+    pub max: serde_json::Value,
+    pub total: u64,
+    pub non_null: u64,
+    pub null: u64,
+    pub distinct: u64,
+    pub sampled: bool,
+}
+
+/// A table's row count. `estimated` is set when the value comes from planner
+/// statistics rather than a `COUNT(*)`, so the UI can render it as approximate.
+#[derive(Debug, Serialize)]
+pub struct TableRowCount {
+    pub count: i64,
+    pub estimated: bool,
 }
 
 #[derive(Debug, Serialize)]
@@ -0,0 +1,247 @@
+use crate::commands::{
+    expand_ssh_connection_params, find_connection_by_id, resolve_connection_params,
+};
+use crate::pool_manager::get_postgres_pool;
+use serde::Serialize;
+use sqlx::postgres::{PgListener, PgPool};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter, Runtime, State};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::AbortHandle;
+
+type Reply = oneshot::Sender<Result<(), String>>;
+
+/// A change to the channels a connection's listener is subscribed to
+enum ListenCommand {
+    Listen(String, Reply),
+    Unlisten(String, Reply),
+}
+
+/// The LISTEN task of one connection, which serves every channel subscribed on it
+struct ConnectionListener {
+    commands: mpsc::UnboundedSender<ListenCommand>,
+    task: AbortHandle,
+}
+
+type ListenerMap = Arc<Mutex<HashMap<String, ConnectionListener>>>;
+
+const LISTENER_STOPPED: &str = "The notification listener stopped";
+
+/// Background LISTEN tasks by connection id. Each holds one connection of the
+/// connection's pool while it listens on at least one channel.
+pub struct NotificationState {
+    listeners: ListenerMap,
+}
+
+impl Default for NotificationState {
+    fn default() -> Self {
+        Self {
+            listeners: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl NotificationState {
+    /// Stops every listener (on app exit)
+    pub fn stop_all(&self) {
+        for (_, listener) in self.listeners.lock().unwrap().drain() {
+            listener.task.abort();
+        }
+    }
+
+    /// Listens on `channel`, passing its notifications to `on_notify`. The first
+    /// channel of a connection starts its listener on a connection of `pool`; later
+    /// ones (and their notifications) go through the same listener, so `on_notify`
+    /// is only used when the listener is started. Listening twice does nothing.
+    pub async fn listen<F>(
+        &self,
+        connection_id: &str,
+        channel: &str,
+        pool: &PgPool,
+        on_notify: F,
+    ) -> Result<(), String>
+    where
+        F: Fn(PgNotifyEvent) + Send + 'static,
+    {
+        let listen = |reply| ListenCommand::Listen(channel.to_string(), reply);
+        let response = match self.send(connection_id, listen) {
+            Some(response) => response,
+            None => {
+                // The listener takes a connection of its own and reconnects if it drops
+                let listener = PgListener::connect_with(pool)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                let (commands, receiver) = mpsc::unbounded_channel();
+                let task = tokio::spawn(run_listener(
+                    connection_id.to_string(),
+                    listener,
+                    receiver,
+                    self.listeners.clone(),
+                    on_notify,
+                ));
+                let mut listeners = self.listeners.lock().unwrap();
+                match listeners.entry(connection_id.to_string()) {
+                    // A concurrent subscribe won the race; keep a single listener
+                    Entry::Occupied(_) => task.abort(),
+                    Entry::Vacant(entry) => {
+                        entry.insert(ConnectionListener {
+                            commands,
+                            task: task.abort_handle(),
+                        });
+                    }
+                }
+                drop(listeners);
+                self.send(connection_id, listen)
+                    .ok_or_else(|| LISTENER_STOPPED.to_string())?
+            }
+        };
+        response.await.map_err(|_| LISTENER_STOPPED.to_string())?
+    }
+
+    /// Stops listening on `channel`. The listener of a connection stops, returning its
+    /// connection to the pool, once it listens on no channel.
+    pub async fn unlisten(&self, connection_id: &str, channel: &str) -> Result<(), String> {
+        let unlisten = |reply| ListenCommand::Unlisten(channel.to_string(), reply);
+        match self.send(connection_id, unlisten) {
+            Some(response) => response.await.map_err(|_| LISTENER_STOPPED.to_string())?,
+            None => Err(format!("Not subscribed to '{}'", channel)),
+        }
+    }
+
+    /// Queues a command on the connection's listener, or returns `None` when it has
+    /// none. Sent under the map lock, so a listener that stops once idle can't miss it.
+    fn send(
+        &self,
+        connection_id: &str,
+        command: impl FnOnce(Reply) -> ListenCommand,
+    ) -> Option<oneshot::Receiver<Result<(), String>>> {
+        let (reply, response) = oneshot::channel();
+        let mut listeners = self.listeners.lock().unwrap();
+        let listener = listeners.get(connection_id)?;
+        if listener.commands.send(command(reply)).is_err() {
+            // The task ended before it could remove itself
+            listeners.remove(connection_id);
+            return None;
+        }
+        Some(response)
+    }
+}
+
+/// Runs a connection's listener: applies LISTEN/UNLISTEN commands and forwards
+/// notifications until the last channel is unsubscribed or the listener fails
+async fn run_listener<F>(
+    connection_id: String,
+    mut listener: PgListener,
+    mut commands: mpsc::UnboundedReceiver<ListenCommand>,
+    listeners: ListenerMap,
+    on_notify: F,
+) where
+    F: Fn(PgNotifyEvent),
+{
+    let mut channels = HashSet::new();
+    loop {
+        tokio::select! {
+            command = commands.recv() => {
+                match command {
+                    Some(ListenCommand::Listen(channel, reply)) => {
+                        let result = if channels.contains(&channel) {
+                            Ok(())
+                        } else {
+                            listener.listen(&channel).await.map_err(|e| e.to_string())
+                        };
+                        if result.is_ok() && channels.insert(channel.clone()) {
+                            println!("[Notify] Listening on '{}' for {}", channel, connection_id);
+                        }
+                        let _ = reply.send(result);
+                    }
+                    Some(ListenCommand::Unlisten(channel, reply)) => {
+                        let result = if channels.remove(&channel) {
+                            listener.unlisten(&channel).await.map_err(|e| e.to_string())
+                        } else {
+                            Err(format!("Not subscribed to '{}'", channel))
+                        };
+                        let _ = reply.send(result);
+                    }
+                    None => return,
+                }
+                // Idle after the last UNLISTEN (or a failed first LISTEN). Commands are
+                // queued under the map lock, so none can arrive once it is removed.
+                if channels.is_empty() {
+                    let mut listeners = listeners.lock().unwrap();
+                    if commands.is_empty() {
+                        remove_current_task(&mut listeners, &connection_id);
+                        return;
+                    }
+                }
+            }
+            notification = listener.recv() => match notification {
+                Ok(notification) => on_notify(PgNotifyEvent {
+                    connection_id: connection_id.clone(),
+                    channel: notification.channel().to_string(),
+                    payload: notification.payload().to_string(),
+                    process_id: notification.process_id(),
+                }),
+                // E.g. the pool was closed after the connection was edited
+                Err(e) => {
+                    eprintln!("[Notify] Stopped listening for {}: {}", connection_id, e);
+                    break;
+                }
+            },
+        }
+    }
+    remove_current_task(&mut listeners.lock().unwrap(), &connection_id);
+}
+
+/// Removes the connection's listener if it is the one running this task, and not one
+/// that replaced it
+fn remove_current_task(listeners: &mut HashMap<String, ConnectionListener>, connection_id: &str) {
+    if listeners.get(connection_id).map(|l| l.task.id()) == Some(tokio::task::id()) {
+        listeners.remove(connection_id);
+    }
+}
+
+/// Payload of `pg-notify`, sent for each notification on a subscribed channel
+#[derive(Clone, Debug, Serialize)]
+pub struct PgNotifyEvent {
+    pub connection_id: String,
+    pub channel: String,
+    pub payload: String,
+    /// Backend process that sent the notification
+    pub process_id: u32,
+}
+
+/// Starts forwarding notifications sent on `channel` as `pg-notify` events.
+/// Subscribing to a channel that is already listened on does nothing.
+#[tauri::command]
+pub async fn subscribe_notifications<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, NotificationState>,
+    connection_id: String,
+    channel: String,
+) -> Result<(), String> {
+    let saved_conn = find_connection_by_id(&app, &connection_id)?;
+    if saved_conn.params.driver != "postgres" {
+        return Err("Notifications are only supported on PostgreSQL connections".into());
+    }
+    let expanded_params = expand_ssh_connection_params(&app, &saved_conn.params).await?;
+    let params = resolve_connection_params(&expanded_params)?;
+    let pool = get_postgres_pool(&params).await?;
+
+    state
+        .listen(&connection_id, &channel, &pool, move |event| {
+            let _ = app.emit("pg-notify", event);
+        })
+        .await
+}
+
+/// Stops forwarding notifications sent on `channel`
+#[tauri::command]
+pub async fn unsubscribe_notifications(
+    state: State<'_, NotificationState>,
+    connection_id: String,
+    channel: String,
+) -> Result<(), String> {
+    state.unlisten(&connection_id, &channel).await
+}
@@ -0,0 +1,370 @@
+//! Parquet output for `export_query_to_file`, built with the `parquet` cargo feature.
+//! Rows go through DuckDB's bundled Parquet writer, using the arrow version DuckDB
+//! re-exports.
+
+use crate::drivers::common::{parse_naive_timestamp, parse_timestamp_tz};
+use chrono::NaiveDate;
+use duckdb::arrow::array::builder::{
+    BooleanBuilder, Date32Builder, Float64Builder, Int64Builder, StringBuilder,
+    TimestampMicrosecondBuilder, UInt64Builder,
+};
+use duckdb::arrow::array::{ArrayRef, RecordBatch};
+use duckdb::arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Rows buffered before they are written out as one `RecordBatch`
+const PARQUET_BATCH_ROWS: usize = 8192;
+
+/// Arrow type for a column, from the type names reported in `QueryResult.column_types`.
+/// DECIMAL/NUMERIC stay strings so no precision is lost; unknown types fall back to
+/// strings too.
+pub fn arrow_type(type_name: &str) -> DataType {
+    let name = type_name.to_uppercase();
+    match name.as_str() {
+        "BOOL" | "BOOLEAN" => DataType::Boolean,
+        "INT2" | "INT4" | "INT8" | "TINYINT" | "SMALLINT" | "MEDIUMINT" | "INT" | "INTEGER"
+        | "BIGINT" | "TINYINT UNSIGNED" | "SMALLINT UNSIGNED" | "MEDIUMINT UNSIGNED"
+        | "INT UNSIGNED" => DataType::Int64,
+        "BIGINT UNSIGNED" => DataType::UInt64,
+        "FLOAT4" | "FLOAT8" | "REAL" | "FLOAT" | "DOUBLE" => DataType::Float64,
+        "DATE" => DataType::Date32,
+        "TIMESTAMP" | "DATETIME" => DataType::Timestamp(TimeUnit::Microsecond, None),
+        "TIMESTAMPTZ" => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+        _ => DataType::Utf8,
+    }
+}
+
+/// DuckDB column type for an Arrow type produced by `arrow_type`
+fn duckdb_type(data_type: &DataType) -> &'static str {
+    match data_type {
+        DataType::Boolean => "BOOLEAN",
+        DataType::Int64 => "BIGINT",
+        DataType::UInt64 => "UBIGINT",
+        DataType::Float64 => "DOUBLE",
+        DataType::Date32 => "DATE",
+        DataType::Timestamp(_, None) => "TIMESTAMP",
+        DataType::Timestamp(_, Some(_)) => "TIMESTAMPTZ",
+        _ => "VARCHAR",
+    }
+}
+
+/// Buffers JSON rows and appends them in batches to an in-memory DuckDB table, which
+/// `finish` copies out as Parquet. NULL stays null; a value that does not convert to
+/// its column's type fails the export.
+pub struct ParquetRowWriter<W: Write + Send> {
+    out: W,
+    conn: duckdb::Connection,
+    schema: SchemaRef,
+    rows: Vec<Vec<serde_json::Value>>,
+}
+
+impl<W: Write + Send> ParquetRowWriter<W> {
+    pub fn new(out: W, columns: &[String], type_names: &[String]) -> Result<Self, String> {
+        let fields: Vec<Field> = columns
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let type_name = type_names.get(i).map_or("", String::as_str);
+                Field::new(name, arrow_type(type_name), true)
+            })
+            .collect();
+        let schema = Arc::new(Schema::new(fields));
+
+        let definitions: Vec<String> = schema
+            .fields()
+            .iter()
+            .map(|field| {
+                let name = field.name().replace('"', "\"\"");
+                format!("\"{}\" {}", name, duckdb_type(field.data_type()))
+            })
+            .collect();
+        let conn = duckdb::Connection::open_in_memory().map_err(|e| e.to_string())?;
+        conn.execute_batch(&format!("CREATE TABLE rows ({})", definitions.join(", ")))
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            out,
+            conn,
+            schema,
+            rows: Vec::with_capacity(PARQUET_BATCH_ROWS),
+        })
+    }
+
+    pub fn push(&mut self, row: Vec<serde_json::Value>) -> Result<(), String> {
+        self.rows.push(row);
+        if self.rows.len() >= PARQUET_BATCH_ROWS {
+            self.write_batch()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the buffered rows, then copies the table into `out` as a Parquet file
+    pub fn finish(mut self) -> Result<(), String> {
+        self.write_batch()?;
+
+        // DuckDB's COPY only writes to a path, so the file is staged in the temp dir
+        let staged = StagedFile(
+            std::env::temp_dir().join(format!("tabularis-{}.parquet", uuid::Uuid::new_v4())),
+        );
+        let target = staged.0.to_string_lossy().replace('\'', "''");
+        self.conn
+            .execute_batch(&format!("COPY rows TO '{}' (FORMAT PARQUET)", target))
+            .map_err(|e| e.to_string())?;
+        let mut file = File::open(&staged.0).map_err(|e| e.to_string())?;
+        io::copy(&mut file, &mut self.out).map_err(|e| e.to_string())?;
+        self.out.flush().map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn write_batch(&mut self) -> Result<(), String> {
+        if self.rows.is_empty() {
+            return Ok(());
+        }
+        let columns = self
+            .schema
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(i, field)| build_column(field, &self.rows, i))
+            .collect::<Result<Vec<ArrayRef>, String>>()?;
+        let batch =
+            RecordBatch::try_new(self.schema.clone(), columns).map_err(|e| e.to_string())?;
+        let mut appender = self.conn.appender("rows").map_err(|e| e.to_string())?;
+        appender
+            .append_record_batch(batch)
+            .map_err(|e| e.to_string())?;
+        appender.flush().map_err(|e| e.to_string())?;
+        self.rows.clear();
+        Ok(())
+    }
+}
+
+/// Removes the staged Parquet file once it has been copied, or on error
+struct StagedFile(PathBuf);
+
+impl Drop for StagedFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+fn build_column(
+    field: &Field,
+    rows: &[Vec<serde_json::Value>],
+    index: usize,
+) -> Result<ArrayRef, String> {
+    let values = rows
+        .iter()
+        .map(|row| row.get(index).unwrap_or(&serde_json::Value::Null));
+    Ok(match field.data_type() {
+        DataType::Boolean => {
+            let mut builder = BooleanBuilder::with_capacity(rows.len());
+            for v in values {
+                builder.append_option(convert(field, v, to_bool(v))?);
+            }
+            Arc::new(builder.finish())
+        }
+        DataType::Int64 => {
+            let mut builder = Int64Builder::with_capacity(rows.len());
+            for v in values {
+                builder.append_option(convert(field, v, parse_number(v, |n| n.as_i64()))?);
+            }
+            Arc::new(builder.finish())
+        }
+        DataType::UInt64 => {
+            let mut builder = UInt64Builder::with_capacity(rows.len());
+            for v in values {
+                builder.append_option(convert(field, v, parse_number(v, |n| n.as_u64()))?);
+            }
+            Arc::new(builder.finish())
+        }
+        DataType::Float64 => {
+            let mut builder = Float64Builder::with_capacity(rows.len());
+            for v in values {
+                builder.append_option(convert(field, v, parse_number(v, |n| n.as_f64()))?);
+            }
+            Arc::new(builder.finish())
+        }
+        DataType::Date32 => {
+            let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+            let mut builder = Date32Builder::with_capacity(rows.len());
+            for v in values {
+                let days = v
+                    .as_str()
+                    .and_then(|s| NaiveDate::parse_from_str(s, "%Y-%m-%d").ok())
+                    .map(|date| (date - epoch).num_days() as i32);
+                builder.append_option(convert(field, v, days)?);
+            }
+            Arc::new(builder.finish())
+        }
+        DataType::Timestamp(_, tz) => {
+            let mut builder = TimestampMicrosecondBuilder::with_capacity(rows.len());
+            for v in values {
+                // Zoned values are stored as UTC, naive ones as written
+                let micros = v.as_str().and_then(|s| {
+                    parse_naive_timestamp(s)
+                        .map(|dt| dt.and_utc().timestamp_micros())
+                        .or_else(|| parse_timestamp_tz(s).map(|dt| dt.timestamp_micros()))
+                });
+                builder.append_option(convert(field, v, micros)?);
+            }
+            Arc::new(builder.with_timezone_opt(tz.clone()).finish())
+        }
+        _ => {
+            let mut builder = StringBuilder::with_capacity(rows.len(), rows.len() * 16);
+            values.for_each(|v| match v {
+                serde_json::Value::Null => builder.append_null(),
+                serde_json::Value::String(s) => builder.append_value(s),
+                other => builder.append_value(other.to_string()),
+            });
+            Arc::new(builder.finish())
+        }
+    })
+}
+
+/// NULL converts to None; any other value must convert, or the export fails
+fn convert<T>(
+    field: &Field,
+    value: &serde_json::Value,
+    converted: Option<T>,
+) -> Result<Option<T>, String> {
+    match value {
+        serde_json::Value::Null => Ok(None),
+        _ => converted.map(Some).ok_or_else(|| {
+            format!(
+                "Cannot write {} to Parquet column \"{}\" of type {}",
+                value,
+                field.name(),
+                duckdb_type(field.data_type())
+            )
+        }),
+    }
+}
+
+fn to_bool(value: &serde_json::Value) -> Option<bool> {
+    match value {
+        serde_json::Value::Bool(b) => Some(*b),
+        serde_json::Value::Number(n) => n.as_i64().map(|v| v != 0),
+        _ => None,
+    }
+}
+
+/// Numbers as JSON numbers, or as strings (e.g. MySQL decimals over the wire)
+fn parse_number<T: std::str::FromStr>(
+    value: &serde_json::Value,
+    from_json: impl Fn(&serde_json::Number) -> Option<T>,
+) -> Option<T> {
+    match value {
+        serde_json::Value::Number(n) => from_json(n),
+        serde_json::Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_arrow_type_from_column_types() {
+        assert_eq!(arrow_type("INT4"), DataType::Int64);
+        assert_eq!(arrow_type("BIGINT UNSIGNED"), DataType::UInt64);
+        assert_eq!(arrow_type("float8"), DataType::Float64);
+        assert_eq!(arrow_type("BOOLEAN"), DataType::Boolean);
+        assert_eq!(arrow_type("DATE"), DataType::Date32);
+        assert_eq!(
+            arrow_type("TIMESTAMPTZ"),
+            DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()))
+        );
+        assert_eq!(arrow_type("NUMERIC"), DataType::Utf8);
+        assert_eq!(arrow_type("JSONB"), DataType::Utf8);
+    }
+
+    #[test]
+    fn test_rows_round_trip_with_nulls() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let columns = strings(&["id", "price", "born", "seen", "tags"]);
+        let types = strings(&["INT8", "FLOAT8", "DATE", "TIMESTAMPTZ", "JSONB"]);
+        let mut writer = ParquetRowWriter::new(file.reopen().unwrap(), &columns, &types).unwrap();
+        writer
+            .push(vec![
+                json!(1),
+                json!("9.5"),
+                json!("1970-01-02"),
+                json!("2024-01-01T02:00:00+02:00"),
+                json!(["a", "b"]),
+            ])
+            .unwrap();
+        writer
+            .push(vec![
+                json!(2),
+                json!(null),
+                json!(null),
+                json!(null),
+                json!(null),
+            ])
+            .unwrap();
+        writer.finish().unwrap();
+
+        let conn = duckdb::Connection::open_in_memory().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, price, born::VARCHAR, epoch_us(seen), tags \
+                 FROM read_parquet(?) ORDER BY id",
+            )
+            .unwrap();
+        type Row = (
+            i64,
+            Option<f64>,
+            Option<String>,
+            Option<i64>,
+            Option<String>,
+        );
+        let rows: Vec<Row> = stmt
+            .query_map([file.path().to_str().unwrap()], |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                ))
+            })
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                (
+                    1,
+                    Some(9.5),
+                    Some("1970-01-02".to_string()),
+                    Some(1_704_067_200_000_000),
+                    Some("[\"a\",\"b\"]".to_string()),
+                ),
+                (2, None, None, None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unconvertible_value_fails() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let columns = strings(&["id"]);
+        let types = strings(&["INT8"]);
+        let mut writer = ParquetRowWriter::new(file.reopen().unwrap(), &columns, &types).unwrap();
+        writer.push(vec![json!("abc")]).unwrap();
+
+        let err = writer.finish().unwrap_err();
+        assert!(err.contains("\"abc\""), "{}", err);
+        assert!(err.contains("\"id\""), "{}", err);
+    }
+}
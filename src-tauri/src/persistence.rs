@@ -1,32 +1,186 @@
 use crate::keychain_utils;
 use crate::models::SavedConnection;
+use fs2::FileExt;
+use serde::de::DeserializeOwned;
+use std::collections::HashSet;
 use std::fs;
-use std::path::Path;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
 
-pub fn load_connections(path: &Path) -> Result<Vec<SavedConnection>, String> {
-    if !path.exists() {
-        return Ok(Vec::new());
+/// How long to wait for another window or instance to release a data file
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// `path` with `suffix` appended, e.g. `connections.json.tmp`
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".tmp")
+}
+
+/// Advisory lock on a data file, held on `<file>.lock` and released on drop or
+/// when the process exits. Take it around every read-modify-write so concurrent
+/// instances don't drop each other's changes.
+pub struct FileLock {
+    file: fs::File,
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        FileExt::unlock(&self.file).ok();
     }
-    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
-    let mut connections: Vec<SavedConnection> = serde_json::from_str(&content)
-        .map_err(|_| "Failed to parse connections file".to_string())?;
+}
+
+/// Blocks the calling thread until the lock is free; async code uses
+/// `lock_file_async` instead.
+pub fn lock_file(path: &Path) -> Result<FileLock, String> {
+    lock_file_timeout(path, LOCK_TIMEOUT)
+}
+
+pub async fn lock_file_async(path: &Path) -> Result<FileLock, String> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || lock_file(&path))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+fn lock_file_timeout(path: &Path, timeout: Duration) -> Result<FileLock, String> {
+    let file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(with_suffix(path, ".lock"))
+        .map_err(|e| e.to_string())?;
+    let start = Instant::now();
+    loop {
+        match file.try_lock_exclusive() {
+            Ok(()) => return Ok(FileLock { file }),
+            Err(e) if e.kind() == fs2::lock_contended_error().kind() => {
+                if start.elapsed() >= timeout {
+                    return Err(format!(
+                        "{} is in use by another Tabularis window, please try again",
+                        path.display()
+                    ));
+                }
+                thread::sleep(Duration::from_millis(50));
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+/// Replaces `path` through a temp file in the same directory and a rename, so a
+/// crash mid-write leaves either the old or the new file, never a truncated one
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), String> {
+    write_atomic_with(path, contents, |tmp| fs::File::create(tmp))
+}
+
+/// `write_atomic` for a file only the user may read: on Unix the temp file has mode
+/// 0600 before anything is written to it, so the contents are never exposed
+pub fn write_atomic_private(path: &Path, contents: &[u8]) -> Result<(), String> {
+    write_atomic_with(path, contents, create_private)
+}
+
+fn write_atomic_with(
+    path: &Path,
+    contents: &[u8],
+    create: impl FnOnce(&Path) -> std::io::Result<fs::File>,
+) -> Result<(), String> {
+    let tmp = temp_path(path);
+    let mut file = create(&tmp).map_err(|e| e.to_string())?;
+    file.write_all(contents).map_err(|e| e.to_string())?;
+    file.sync_all().map_err(|e| e.to_string())?;
+    drop(file);
+    fs::rename(&tmp, path).map_err(|e| e.to_string())
+}
+
+#[cfg(unix)]
+fn create_private(path: &Path) -> std::io::Result<fs::File> {
+    use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+    let file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    // A temp file left behind by an interrupted write keeps its old mode
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    Ok(file)
+}
+
+#[cfg(not(unix))]
+fn create_private(path: &Path) -> std::io::Result<fs::File> {
+    fs::File::create(path)
+}
+
+/// Reads a JSON file written by `write_atomic`. If it is missing or corrupt but a
+/// complete temp file was left behind by an interrupted write, the temp file is
+/// moved into place and used instead. `None` means neither file exists.
+pub fn read_json_recovering<T: DeserializeOwned>(path: &Path) -> Result<Option<T>, String> {
+    let error = if path.exists() {
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        match serde_json::from_str(&content) {
+            Ok(value) => return Ok(Some(value)),
+            Err(e) => Some(e.to_string()),
+        }
+    } else {
+        None
+    };
+
+    let tmp = temp_path(path);
+    let recovered = fs::read_to_string(&tmp)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok());
+    if let Some(value) = recovered {
+        eprintln!(
+            "[Persistence] Recovered {} from an interrupted write",
+            path.display()
+        );
+        fs::rename(&tmp, path).map_err(|e| e.to_string())?;
+        return Ok(Some(value));
+    }
+
+    match error {
+        Some(e) => Err(e),
+        None => Ok(None),
+    }
+}
+
+pub fn load_connections(path: &Path) -> Result<Vec<SavedConnection>, String> {
+    let mut connections: Vec<SavedConnection> = match read_json_recovering(path) {
+        Ok(Some(connections)) => connections,
+        Ok(None) => return Ok(Vec::new()),
+        Err(_) => return Err("Failed to parse connections file".to_string()),
+    };
 
     // Populate passwords from keychain if needed
     for conn in &mut connections {
         if conn.params.save_in_keychain.unwrap_or(false) {
             match keychain_utils::get_db_password(&conn.id) {
-                Ok(pwd) => conn.params.password = Some(pwd),
+                Ok(Some(pwd)) => conn.params.password = Some(pwd),
+                Ok(None) => {}
                 Err(e) => eprintln!(
                     "[Keyring Error] Failed to get DB password for {}: {}",
                     conn.id, e
                 ),
             }
             if conn.params.ssh_enabled.unwrap_or(false) {
-                if let Ok(ssh_pwd) = keychain_utils::get_ssh_password(&conn.id) {
+                if let Ok(Some(ssh_pwd)) = keychain_utils::get_ssh_password(&conn.id) {
                     if !ssh_pwd.trim().is_empty() {
                         conn.params.ssh_password = Some(ssh_pwd);
                     }
                 }
+                if let Ok(Some(passphrase)) = keychain_utils::get_ssh_key_passphrase(&conn.id) {
+                    if !passphrase.trim().is_empty() {
+                        conn.params.ssh_key_passphrase = Some(passphrase);
+                    }
+                }
             }
         }
     }
@@ -47,12 +201,272 @@ pub fn save_connections(path: &Path, connections: &[SavedConnection]) -> Result<
         let mut c = conn.clone();
         if c.params.save_in_keychain.unwrap_or(false) {
             // Passwords are stored in keychain, remove from JSON
-            c.params.password = None;
-            c.params.ssh_password = None;
+            strip_secrets(&mut c);
         }
         to_save.push(c);
     }
 
     let json = serde_json::to_string_pretty(&to_save).map_err(|e| e.to_string())?;
+    write_atomic(path, json.as_bytes())
+}
+
+fn strip_secrets(conn: &mut SavedConnection) {
+    conn.params.password = None;
+    conn.params.ssh_password = None;
+    conn.params.ssh_key_passphrase = None;
+}
+
+/// Writes the connections to `path` for another machine, always without passwords
+/// or key passphrases (whether or not they are kept in the keychain)
+pub fn export_connections(path: &Path, connections: &[SavedConnection]) -> Result<(), String> {
+    let sanitized: Vec<SavedConnection> = connections
+        .iter()
+        .cloned()
+        .map(|mut c| {
+            strip_secrets(&mut c);
+            c
+        })
+        .collect();
+    let json = serde_json::to_string_pretty(&sanitized).map_err(|e| e.to_string())?;
     fs::write(path, json).map_err(|e| e.to_string())
 }
+
+/// Reads an export made by `export_connections` and prepares it for adding to
+/// `existing`: every connection gets a fresh id, and a name already in use gets a
+/// ` (2)`, ` (3)`, ... suffix
+pub fn import_connections(
+    path: &Path,
+    existing: &[SavedConnection],
+) -> Result<Vec<SavedConnection>, String> {
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let imported: Vec<SavedConnection> = serde_json::from_str(&content)
+        .map_err(|_| "Failed to parse connections export".to_string())?;
+
+    let mut names: HashSet<String> = existing.iter().map(|c| c.name.clone()).collect();
+    Ok(imported
+        .into_iter()
+        .map(|mut conn| {
+            strip_secrets(&mut conn);
+            conn.id = Uuid::new_v4().to_string();
+            conn.name = unique_name(&conn.name, &names);
+            names.insert(conn.name.clone());
+            conn
+        })
+        .collect())
+}
+
+fn unique_name(name: &str, taken: &HashSet<String>) -> String {
+    if !taken.contains(name) {
+        return name.to_string();
+    }
+    (2..)
+        .map(|n| format!("{} ({})", name, n))
+        .find(|candidate| !taken.contains(candidate))
+        .unwrap_or_default()
+}
+
+/// Trims each segment of a group path and drops empty ones, so `" Prod//EU/ "`
+/// becomes `Prod/EU`; an empty path means no group
+pub fn normalize_group(group: Option<&str>) -> Option<String> {
+    let path = group?
+        .split('/')
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join("/");
+    (!path.is_empty()).then_some(path)
+}
+
+/// Moves every connection in group `from`, including its subgroups, under `to`
+/// (`None` moves them to the top level). Returns the number of moved connections.
+pub fn rename_group(connections: &mut [SavedConnection], from: &str, to: Option<&str>) -> usize {
+    let Some(from) = normalize_group(Some(from)) else {
+        return 0;
+    };
+    let to = normalize_group(to);
+    let mut moved = 0;
+
+    for conn in connections.iter_mut() {
+        let Some(group) = conn.group.as_deref() else {
+            continue;
+        };
+        let rest = if group == from {
+            ""
+        } else if let Some(rest) = group.strip_prefix(&format!("{}/", from)) {
+            rest
+        } else {
+            continue;
+        };
+        let new_group = match &to {
+            Some(to) => format!("{}/{}", to, rest),
+            None => rest.to_string(),
+        };
+        conn.group = normalize_group(Some(&new_group));
+        moved += 1;
+    }
+
+    moved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ConnectionParams;
+    use tempfile::TempDir;
+
+    fn conn(id: &str, group: Option<&str>) -> SavedConnection {
+        SavedConnection {
+            id: id.to_string(),
+            name: id.to_string(),
+            params: ConnectionParams::default(),
+            group: group.map(str::to_string),
+        }
+    }
+
+    fn groups(connections: &[SavedConnection]) -> Vec<Option<&str>> {
+        connections.iter().map(|c| c.group.as_deref()).collect()
+    }
+
+    #[test]
+    fn test_normalize_group() {
+        assert_eq!(
+            normalize_group(Some(" Prod//EU/ ")).as_deref(),
+            Some("Prod/EU")
+        );
+        assert_eq!(normalize_group(Some(" / ")), None);
+        assert_eq!(normalize_group(None), None);
+    }
+
+    #[test]
+    fn test_rename_group_moves_subgroups() {
+        let mut connections = vec![
+            conn("a", Some("Prod")),
+            conn("b", Some("Prod/EU")),
+            conn("c", Some("Production")),
+            conn("d", None),
+        ];
+        assert_eq!(
+            rename_group(&mut connections, "Prod", Some("Archive/Prod")),
+            2
+        );
+        assert_eq!(
+            groups(&connections),
+            vec![
+                Some("Archive/Prod"),
+                Some("Archive/Prod/EU"),
+                Some("Production"),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rename_group_to_top_level() {
+        let mut connections = vec![conn("a", Some("Old")), conn("b", Some("Old/Sub"))];
+        assert_eq!(rename_group(&mut connections, "Old", None), 2);
+        assert_eq!(groups(&connections), vec![None, Some("Sub")]);
+    }
+
+    #[test]
+    fn test_export_and_import_connections() {
+        let mut prod = conn("1", Some("Prod"));
+        prod.name = "prod".to_string();
+        prod.params.password = Some("secret".to_string());
+        prod.params.ssh_key_passphrase = Some("phrase".to_string());
+        let mut dev = conn("2", None);
+        dev.name = "dev".to_string();
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        export_connections(file.path(), &[prod, dev.clone()]).unwrap();
+        let exported = fs::read_to_string(file.path()).unwrap();
+        assert!(!exported.contains("secret"));
+        assert!(!exported.contains("phrase"));
+
+        let mut existing = vec![dev.clone()];
+        existing[0].name = "prod".to_string();
+        let imported = import_connections(file.path(), &existing).unwrap();
+
+        let names: Vec<&str> = imported.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["prod (2)", "dev"]);
+        assert!(imported.iter().all(|c| c.id != "1" && c.id != "2"));
+        assert_ne!(imported[0].id, imported[1].id);
+        assert_eq!(imported[0].group.as_deref(), Some("Prod"));
+        assert_eq!(imported[0].params.password, None);
+    }
+
+    #[test]
+    fn test_unique_name() {
+        let taken: HashSet<String> = ["db", "db (2)"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(unique_name("db", &taken), "db (3)");
+        assert_eq!(unique_name("other", &taken), "other");
+    }
+
+    #[test]
+    fn test_saved_connection_without_group_deserializes() {
+        let json =
+            r#"[{"id": "1", "name": "local", "params": {"driver": "sqlite", "database": "a.db"}}]"#;
+        let connections: Vec<SavedConnection> = serde_json::from_str(json).unwrap();
+        assert_eq!(connections[0].group, None);
+        assert!(!serde_json::to_string(&connections)
+            .unwrap()
+            .contains("group"));
+    }
+
+    #[test]
+    fn test_write_atomic_leaves_no_temp_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("connections.json");
+        write_atomic(&path, b"[1]").unwrap();
+        write_atomic(&path, b"[2]").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[2]");
+        assert!(!temp_path(&path).exists());
+    }
+
+    #[test]
+    fn test_read_json_recovers_from_temp_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("connections.json");
+        assert_eq!(read_json_recovering::<Vec<u32>>(&path), Ok(None));
+
+        // Missing main file
+        fs::write(temp_path(&path), "[1]").unwrap();
+        assert_eq!(read_json_recovering(&path), Ok(Some(vec![1u32])));
+        assert!(!temp_path(&path).exists());
+
+        // Corrupt main file
+        fs::write(&path, "[1, 2").unwrap();
+        fs::write(temp_path(&path), "[1, 2]").unwrap();
+        assert_eq!(read_json_recovering(&path), Ok(Some(vec![1u32, 2])));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[1, 2]");
+    }
+
+    #[test]
+    fn test_read_json_corrupt_without_temp_file_errors() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("connections.json");
+        fs::write(&path, "not json").unwrap();
+        assert!(read_json_recovering::<Vec<u32>>(&path).is_err());
+    }
+
+    #[test]
+    fn test_lock_file_excludes_until_dropped() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("connections.json");
+
+        let lock = lock_file(&path).unwrap();
+        let err = lock_file_timeout(&path, Duration::from_millis(100)).err();
+        assert!(err.unwrap().contains("in use"));
+
+        drop(lock);
+        assert!(lock_file_timeout(&path, Duration::from_millis(100)).is_ok());
+    }
+
+    #[test]
+    fn test_lock_file_ignores_leftover_lock_file() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("connections.json");
+        fs::write(with_suffix(&path, ".lock"), "").unwrap();
+
+        assert!(lock_file_timeout(&path, Duration::from_millis(100)).is_ok());
+    }
+}
@@ -0,0 +1,163 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, Runtime};
+
+/// Entries kept per connection; the oldest are dropped first
+const MAX_HISTORY_ENTRIES: usize = 500;
+
+/// Serializes read-modify-write of the history files between concurrent queries
+static HISTORY_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QueryHistoryEntry {
+    pub query: String,
+    /// RFC 3339 timestamp of when the query finished
+    pub executed_at: String,
+    pub duration_ms: u64,
+    pub row_count: usize,
+    pub affected_rows: u64,
+    pub success: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+fn get_history_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let config_dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    let history_dir = config_dir.join("query_history");
+    if !history_dir.exists() {
+        fs::create_dir_all(&history_dir).map_err(|e| e.to_string())?;
+    }
+    Ok(history_dir)
+}
+
+/// One file per connection, e.g. `query_history/<connection id>.json`
+fn get_history_path<R: Runtime>(
+    app: &AppHandle<R>,
+    connection_id: &str,
+) -> Result<PathBuf, String> {
+    let name: String = connection_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    Ok(get_history_dir(app)?.join(format!("{}.json", name)))
+}
+
+/// Entries oldest first; a missing file is an empty history
+fn read_history(path: &Path) -> Result<Vec<QueryHistoryEntry>, String> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| e.to_string())
+}
+
+/// Appends `entry`, dropping the oldest entries beyond `MAX_HISTORY_ENTRIES`
+fn append_entry(path: &Path, entry: QueryHistoryEntry) -> Result<(), String> {
+    let _guard = HISTORY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut entries = read_history(path).unwrap_or_default();
+    entries.push(entry);
+    if entries.len() > MAX_HISTORY_ENTRIES {
+        entries.drain(..entries.len() - MAX_HISTORY_ENTRIES);
+    }
+    let content = serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())?;
+    fs::write(path, content).map_err(|e| e.to_string())
+}
+
+/// Records an executed query on a blocking thread, so the query result does not
+/// wait for the disk. Failures are reported on stderr and never affect the result.
+pub fn record_query<R: Runtime>(app: &AppHandle<R>, connection_id: &str, entry: QueryHistoryEntry) {
+    let app = app.clone();
+    let connection_id = connection_id.to_string();
+    tokio::task::spawn_blocking(move || {
+        let result =
+            get_history_path(&app, &connection_id).and_then(|path| append_entry(&path, entry));
+        if let Err(e) = result {
+            eprintln!("[Query History] Failed to record query: {}", e);
+        }
+    });
+}
+
+/// The connection's history, most recent first
+#[tauri::command]
+pub async fn get_query_history<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+) -> Result<Vec<QueryHistoryEntry>, String> {
+    tokio::task::spawn_blocking(move || {
+        let path = get_history_path(&app, &connection_id)?;
+        let mut entries = read_history(&path)?;
+        entries.reverse();
+        Ok(entries)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub async fn clear_query_history<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || {
+        let path = get_history_path(&app, &connection_id)?;
+        let _guard = HISTORY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        if path.exists() {
+            fs::remove_file(path).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry(query: &str) -> QueryHistoryEntry {
+        QueryHistoryEntry {
+            query: query.to_string(),
+            executed_at: "2024-01-01T00:00:00+00:00".to_string(),
+            duration_ms: 5,
+            row_count: 1,
+            affected_rows: 0,
+            success: true,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_append_entry_keeps_the_newest_entries() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("history.json");
+
+        for i in 0..MAX_HISTORY_ENTRIES + 2 {
+            append_entry(&path, entry(&format!("SELECT {}", i))).unwrap();
+        }
+
+        let entries = read_history(&path).unwrap();
+        assert_eq!(entries.len(), MAX_HISTORY_ENTRIES);
+        assert_eq!(entries[0].query, "SELECT 2");
+        assert_eq!(
+            entries.last().unwrap().query,
+            format!("SELECT {}", MAX_HISTORY_ENTRIES + 1)
+        );
+    }
+
+    #[test]
+    fn test_read_history_missing_file_is_empty() {
+        let dir = TempDir::new().unwrap();
+        assert!(read_history(&dir.path().join("none.json"))
+            .unwrap()
+            .is_empty());
+    }
+}
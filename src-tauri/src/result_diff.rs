@@ -0,0 +1,248 @@
+use crate::models::QueryResult;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+
+/// A cell whose value differs between the two results
+#[derive(Debug, Serialize, PartialEq)]
+pub struct CellChange {
+    pub column: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+/// A row present in both results, matched by its key, with at least one changed cell
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ChangedRow {
+    /// Values of the key columns
+    pub key: Vec<Value>,
+    /// The row as it is in the newer result
+    pub row: Vec<Value>,
+    pub changes: Vec<CellChange>,
+}
+
+/// Outcome of `diff_results`. Added and changed rows follow the newer result's
+/// row and column order, removed rows the older one's.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ResultDiff {
+    pub added: Vec<Vec<Value>>,
+    pub removed: Vec<Vec<Value>>,
+    pub changed: Vec<ChangedRow>,
+    /// Count of rows matched by key with every shared cell equal
+    pub unchanged: usize,
+}
+
+/// Positions of `names` among `columns`, or an error naming the side missing one
+fn column_positions(
+    columns: &[String],
+    names: &[String],
+    side: &str,
+) -> Result<Vec<usize>, String> {
+    names
+        .iter()
+        .map(|name| {
+            columns
+                .iter()
+                .position(|c| c == name)
+                .ok_or_else(|| format!("Column '{}' is not in the {} result", name, side))
+        })
+        .collect()
+}
+
+fn row_key(row: &[Value], positions: &[usize]) -> Vec<Value> {
+    positions
+        .iter()
+        .map(|&i| row.get(i).cloned().unwrap_or(Value::Null))
+        .collect()
+}
+
+/// Compares two results of the same query, matching rows on `key_columns`. Rows
+/// sharing a key are paired in order. Cells are compared on the columns both results
+/// have. Without key columns, rows match on all shared columns, so a changed row
+/// shows up as removed and added.
+#[tauri::command]
+pub fn diff_results(
+    prev: QueryResult,
+    next: QueryResult,
+    key_columns: Vec<String>,
+) -> Result<ResultDiff, String> {
+    let shared: Vec<String> = next
+        .columns
+        .iter()
+        .filter(|c| prev.columns.contains(c))
+        .cloned()
+        .collect();
+    let key_columns = if key_columns.is_empty() {
+        shared.clone()
+    } else {
+        key_columns
+    };
+    let prev_keys = column_positions(&prev.columns, &key_columns, "previous")?;
+    let next_keys = column_positions(&next.columns, &key_columns, "new")?;
+    let prev_shared = column_positions(&prev.columns, &shared, "previous")?;
+    let next_shared = column_positions(&next.columns, &shared, "new")?;
+
+    // Older rows still waiting for a match, by serialized key
+    let mut pending: HashMap<String, VecDeque<usize>> = HashMap::new();
+    for (i, row) in prev.rows.iter().enumerate() {
+        let key = serde_json::to_string(&row_key(row, &prev_keys)).map_err(|e| e.to_string())?;
+        pending.entry(key).or_default().push_back(i);
+    }
+
+    let mut matched = vec![false; prev.rows.len()];
+    let mut diff = ResultDiff {
+        added: Vec::new(),
+        removed: Vec::new(),
+        changed: Vec::new(),
+        unchanged: 0,
+    };
+    for row in &next.rows {
+        let key = row_key(row, &next_keys);
+        let serialized = serde_json::to_string(&key).map_err(|e| e.to_string())?;
+        let Some(old_index) = pending.get_mut(&serialized).and_then(VecDeque::pop_front) else {
+            diff.added.push(row.clone());
+            continue;
+        };
+        matched[old_index] = true;
+        let old_row = &prev.rows[old_index];
+        let changes: Vec<CellChange> = shared
+            .iter()
+            .zip(prev_shared.iter().zip(&next_shared))
+            .filter_map(|(column, (&old_i, &new_i))| {
+                let old = old_row.get(old_i).cloned().unwrap_or(Value::Null);
+                let new = row.get(new_i).cloned().unwrap_or(Value::Null);
+                (old != new).then(|| CellChange {
+                    column: column.clone(),
+                    old,
+                    new,
+                })
+            })
+            .collect();
+        if changes.is_empty() {
+            diff.unchanged += 1;
+        } else {
+            diff.changed.push(ChangedRow {
+                key,
+                row: row.clone(),
+                changes,
+            });
+        }
+    }
+    diff.removed = prev
+        .rows
+        .into_iter()
+        .zip(matched)
+        .filter(|(_, matched)| !matched)
+        .map(|(row, _)| row)
+        .collect();
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn result(columns: &[&str], rows: Vec<Vec<Value>>) -> QueryResult {
+        QueryResult {
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            column_types: Vec::new(),
+            rows,
+            affected_rows: 0,
+            truncated: false,
+            pagination: None,
+            has_more: false,
+            column_refs: None,
+            execution_ms: 0,
+            row_count: None,
+            auto_limit: None,
+        }
+    }
+
+    #[test]
+    fn test_diff_results_by_key() {
+        let prev = result(
+            &["id", "name", "qty"],
+            vec![
+                vec![json!(1), json!("apple"), json!(3)],
+                vec![json!(2), json!("pear"), json!(5)],
+                vec![json!(3), json!("plum"), json!(1)],
+            ],
+        );
+        let next = result(
+            &["id", "name", "qty"],
+            vec![
+                vec![json!(1), json!("apple"), json!(3)],
+                vec![json!(2), json!("pear"), json!(4)],
+                vec![json!(4), json!("fig"), json!(9)],
+            ],
+        );
+        let diff = diff_results(prev, next, vec!["id".to_string()]).unwrap();
+        assert_eq!(diff.added, vec![vec![json!(4), json!("fig"), json!(9)]]);
+        assert_eq!(diff.removed, vec![vec![json!(3), json!("plum"), json!(1)]]);
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(
+            diff.changed,
+            vec![ChangedRow {
+                key: vec![json!(2)],
+                row: vec![json!(2), json!("pear"), json!(4)],
+                changes: vec![CellChange {
+                    column: "qty".to_string(),
+                    old: json!(5),
+                    new: json!(4),
+                }],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_diff_results_compares_shared_columns_by_name() {
+        let prev = result(
+            &["name", "id", "dropped"],
+            vec![vec![json!("apple"), json!(1), json!(true)]],
+        );
+        let next = result(
+            &["id", "name", "added"],
+            vec![vec![json!(1), json!("Apple"), json!(false)]],
+        );
+        let diff = diff_results(prev, next, vec!["id".to_string()]).unwrap();
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(
+            diff.changed[0].changes,
+            vec![CellChange {
+                column: "name".to_string(),
+                old: json!("apple"),
+                new: json!("Apple"),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_diff_results_pairs_duplicate_keys_in_order() {
+        let prev = result(
+            &["k", "v"],
+            vec![vec![json!("a"), json!(1)], vec![json!("a"), json!(2)]],
+        );
+        let next = result(&["k", "v"], vec![vec![json!("a"), json!(1)]]);
+        let diff = diff_results(prev, next, vec!["k".to_string()]).unwrap();
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.removed, vec![vec![json!("a"), json!(2)]]);
+    }
+
+    #[test]
+    fn test_diff_results_without_key_matches_whole_rows() {
+        let prev = result(&["v"], vec![vec![json!(1)], vec![json!(2)]]);
+        let next = result(&["v"], vec![vec![json!(2)], vec![json!(3)]]);
+        let diff = diff_results(prev, next, Vec::new()).unwrap();
+        assert_eq!(diff.added, vec![vec![json!(3)]]);
+        assert_eq!(diff.removed, vec![vec![json!(1)]]);
+        assert!(diff.changed.is_empty());
+    }
+
+    #[test]
+    fn test_diff_results_unknown_key_column() {
+        let prev = result(&["id"], Vec::new());
+        let next = result(&["id"], Vec::new());
+        assert!(diff_results(prev, next, vec!["missing".to_string()]).is_err());
+    }
+}
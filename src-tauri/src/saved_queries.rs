@@ -1,6 +1,8 @@
+use crate::persistence;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tauri::{AppHandle, Manager, Runtime};
 use uuid::Uuid;
 
@@ -10,6 +12,13 @@ pub struct SavedQueryMeta {
     pub name: String,
     pub filename: String,
     pub connection_id: String,
+    #[serde(default)]
+    pub is_favorite: bool,
+    /// Manual position within the favorites or the other queries (lower first)
+    #[serde(default)]
+    pub sort_order: i32,
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -18,100 +27,223 @@ pub struct SavedQuery {
     pub name: String,
     pub sql: String,
     pub connection_id: String,
+    pub is_favorite: bool,
+    pub sort_order: i32,
+    pub tags: Vec<String>,
+}
+
+/// Trims tags and drops empty and repeated ones, keeping the first spelling
+fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim();
+        if !tag.is_empty() && !normalized.iter().any(|t| t == tag) {
+            normalized.push(tag.to_string());
+        }
+    }
+    normalized
+}
+
+/// Favorites first, then by `sort_order`, then by name (case-insensitive)
+fn sort_queries(queries: &mut [SavedQuery]) {
+    queries.sort_by(|a, b| {
+        b.is_favorite
+            .cmp(&a.is_favorite)
+            .then(a.sort_order.cmp(&b.sort_order))
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+}
+
+/// Where saved queries live inside the app config directory
+pub fn queries_dir_in(config_dir: &Path) -> PathBuf {
+    config_dir.join("saved_queries")
 }
 
 fn get_queries_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
     let config_dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
-    let queries_dir = config_dir.join("saved_queries");
+    let queries_dir = queries_dir_in(&config_dir);
     if !queries_dir.exists() {
         fs::create_dir_all(&queries_dir).map_err(|e| e.to_string())?;
     }
     Ok(queries_dir)
 }
 
-fn get_meta_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
-    let dir = get_queries_dir(app)?;
-    Ok(dir.join("meta.json"))
+fn read_meta(dir: &Path) -> Result<Vec<SavedQueryMeta>, String> {
+    Ok(persistence::read_json_recovering(&dir.join("meta.json"))?.unwrap_or_default())
 }
 
-fn read_meta<R: Runtime>(app: &AppHandle<R>) -> Result<Vec<SavedQueryMeta>, String> {
-    let path = get_meta_path(app)?;
-    if !path.exists() {
-        return Ok(Vec::new());
-    }
-    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
-    serde_json::from_str(&content).map_err(|e| e.to_string())
+/// Held around every read-modify-write of `meta.json`
+fn lock_meta(dir: &Path) -> Result<persistence::FileLock, String> {
+    persistence::lock_file(&dir.join("meta.json"))
+}
+
+async fn lock_meta_async(dir: &Path) -> Result<persistence::FileLock, String> {
+    persistence::lock_file_async(&dir.join("meta.json")).await
 }
 
-fn write_meta<R: Runtime>(app: &AppHandle<R>, meta: &Vec<SavedQueryMeta>) -> Result<(), String> {
-    let path = get_meta_path(app)?;
+fn write_meta(dir: &Path, meta: &[SavedQueryMeta]) -> Result<(), String> {
     let content = serde_json::to_string_pretty(meta).map_err(|e| e.to_string())?;
-    fs::write(path, content).map_err(|e| e.to_string())
+    persistence::write_atomic(&dir.join("meta.json"), content.as_bytes())
+}
+
+/// Optional query settings; on updates, omitted ones keep their current value
+#[derive(Debug, Default)]
+struct QuerySettings {
+    is_favorite: Option<bool>,
+    sort_order: Option<i32>,
+    tags: Option<Vec<String>>,
+}
+
+impl QuerySettings {
+    fn apply_to(self, meta: &mut SavedQueryMeta) {
+        if let Some(is_favorite) = self.is_favorite {
+            meta.is_favorite = is_favorite;
+        }
+        if let Some(sort_order) = self.sort_order {
+            meta.sort_order = sort_order;
+        }
+        if let Some(tags) = self.tags {
+            meta.tags = normalize_tags(tags);
+        }
+    }
+}
+
+fn to_saved_query(meta: &SavedQueryMeta, sql: String) -> SavedQuery {
+    SavedQuery {
+        id: meta.id.clone(),
+        name: meta.name.clone(),
+        sql,
+        connection_id: meta.connection_id.clone(),
+        is_favorite: meta.is_favorite,
+        sort_order: meta.sort_order,
+        tags: meta.tags.clone(),
+    }
+}
+
+/// Saves a new query in `dir`. A query with the same (trimmed) name on the same
+/// connection is an error unless `overwrite` is set, which updates it in place.
+fn duplicate_name_error(name: &str) -> String {
+    format!(
+        "A query named \"{}\" already exists for this connection",
+        name
+    )
+}
+
+fn save_query_in(
+    dir: &Path,
+    connection_id: String,
+    name: &str,
+    sql: String,
+    settings: QuerySettings,
+    overwrite: bool,
+) -> Result<SavedQuery, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Query name cannot be empty".into());
+    }
+
+    let _lock = lock_meta(dir)?;
+    let mut meta_list = read_meta(dir)?;
+    let existing = meta_list
+        .iter()
+        .position(|m| m.connection_id == connection_id && m.name == name);
+
+    let idx = match existing {
+        Some(_) if !overwrite => return Err(duplicate_name_error(name)),
+        Some(idx) => idx,
+        None => {
+            let id = Uuid::new_v4().to_string();
+            meta_list.push(SavedQueryMeta {
+                filename: format!("{}.sql", id),
+                id,
+                name: name.to_string(),
+                connection_id,
+                is_favorite: false,
+                sort_order: 0,
+                tags: Vec::new(),
+            });
+            meta_list.len() - 1
+        }
+    };
+
+    settings.apply_to(&mut meta_list[idx]);
+    fs::write(dir.join(&meta_list[idx].filename), &sql).map_err(|e| e.to_string())?;
+    write_meta(dir, &meta_list)?;
+
+    Ok(to_saved_query(&meta_list[idx], sql))
 }
 
 #[tauri::command]
 pub async fn get_saved_queries<R: Runtime>(
     app: AppHandle<R>,
     connection_id: String,
+    tag: Option<String>,
 ) -> Result<Vec<SavedQuery>, String> {
-    let meta_list = read_meta(&app)?;
     let dir = get_queries_dir(&app)?;
+    load_queries(&dir, |meta| {
+        let tag_matches = tag.as_ref().map_or(true, |tag| meta.tags.contains(tag));
+        meta.connection_id == connection_id && tag_matches
+    })
+}
+
+/// Every saved query in `dir`, in sidebar order
+pub fn load_all_queries(dir: &Path) -> Result<Vec<SavedQuery>, String> {
+    load_queries(dir, |_| true)
+}
 
+/// Reads the SQL of the queries accepted by `keep`; a missing file reads as empty
+fn load_queries(
+    dir: &Path,
+    keep: impl Fn(&SavedQueryMeta) -> bool,
+) -> Result<Vec<SavedQuery>, String> {
     let mut results = Vec::new();
 
-    for meta in meta_list {
-        if meta.connection_id == connection_id {
-            let file_path = dir.join(&meta.filename);
-            let sql = if file_path.exists() {
-                fs::read_to_string(file_path).unwrap_or_default()
-            } else {
-                String::new()
-            };
-
-            results.push(SavedQuery {
-                id: meta.id,
-                name: meta.name,
-                sql,
-                connection_id: meta.connection_id,
-            });
-        }
+    for meta in read_meta(dir)?.iter().filter(|meta| keep(meta)) {
+        let file_path = dir.join(&meta.filename);
+        let sql = if file_path.exists() {
+            fs::read_to_string(file_path).unwrap_or_default()
+        } else {
+            String::new()
+        };
+
+        results.push(to_saved_query(meta, sql));
     }
 
+    sort_queries(&mut results);
     Ok(results)
 }
 
+/// Saves a new query; see `save_query_in` for how name clashes are handled
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn save_query<R: Runtime>(
     app: AppHandle<R>,
     connection_id: String,
     name: String,
     sql: String,
+    is_favorite: Option<bool>,
+    sort_order: Option<i32>,
+    tags: Option<Vec<String>>,
+    overwrite: Option<bool>,
 ) -> Result<SavedQuery, String> {
-    let mut meta_list = read_meta(&app)?;
     let dir = get_queries_dir(&app)?;
-
-    let id = Uuid::new_v4().to_string();
-    let filename = format!("{}.sql", id);
-    let file_path = dir.join(&filename);
-
-    fs::write(file_path, &sql).map_err(|e| e.to_string())?;
-
-    let new_meta = SavedQueryMeta {
-        id: id.clone(),
-        name: name.clone(),
-        filename,
-        connection_id: connection_id.clone(),
+    let settings = QuerySettings {
+        is_favorite,
+        sort_order,
+        tags,
     };
-
-    meta_list.push(new_meta);
-    write_meta(&app, &meta_list)?;
-
-    Ok(SavedQuery {
-        id,
-        name,
-        sql,
-        connection_id,
+    tokio::task::spawn_blocking(move || {
+        save_query_in(
+            &dir,
+            connection_id,
+            &name,
+            sql,
+            settings,
+            overwrite.unwrap_or(false),
+        )
     })
+    .await
+    .map_err(|e| e.to_string())?
 }
 
 #[tauri::command]
@@ -120,35 +252,100 @@ pub async fn update_saved_query<R: Runtime>(
     id: String,
     name: String,
     sql: String,
+    is_favorite: Option<bool>,
+    sort_order: Option<i32>,
+    tags: Option<Vec<String>>,
 ) -> Result<SavedQuery, String> {
-    let mut meta_list = read_meta(&app)?;
     let dir = get_queries_dir(&app)?;
+    let settings = QuerySettings {
+        is_favorite,
+        sort_order,
+        tags,
+    };
+    tokio::task::spawn_blocking(move || update_query_in(&dir, &id, &name, sql, settings))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Renames and rewrites a saved query. Like `save_query`, refuses a name another
+/// query of the same connection already has.
+fn update_query_in(
+    dir: &Path,
+    id: &str,
+    name: &str,
+    sql: String,
+    settings: QuerySettings,
+) -> Result<SavedQuery, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Query name cannot be empty".into());
+    }
+
+    let _lock = lock_meta(dir)?;
+    let mut meta_list = read_meta(dir)?;
 
     let idx = meta_list
         .iter()
         .position(|m| m.id == id)
         .ok_or("Query not found")?;
+    let connection_id = &meta_list[idx].connection_id;
+    if meta_list
+        .iter()
+        .any(|m| m.id != id && &m.connection_id == connection_id && m.name == name)
+    {
+        return Err(duplicate_name_error(name));
+    }
 
-    // Update metadata name
-    meta_list[idx].name = name.clone();
-    write_meta(&app, &meta_list)?;
+    // Update metadata; omitted favorite/order/tag settings are kept
+    meta_list[idx].name = name.to_string();
+    settings.apply_to(&mut meta_list[idx]);
+    write_meta(dir, &meta_list)?;
 
     // Update SQL file
     let file_path = dir.join(&meta_list[idx].filename);
     fs::write(file_path, &sql).map_err(|e| e.to_string())?;
 
-    Ok(SavedQuery {
-        id: meta_list[idx].id.clone(),
-        name,
-        sql,
-        connection_id: meta_list[idx].connection_id.clone(),
-    })
+    Ok(to_saved_query(&meta_list[idx], sql))
+}
+
+/// Distinct tags used by the connection's saved queries, sorted
+#[tauri::command]
+pub async fn list_tags<R: Runtime>(
+    app: AppHandle<R>,
+    connection_id: String,
+) -> Result<Vec<String>, String> {
+    let meta_list = read_meta(&get_queries_dir(&app)?)?;
+    let tags: BTreeSet<String> = meta_list
+        .into_iter()
+        .filter(|m| m.connection_id == connection_id)
+        .flat_map(|m| m.tags)
+        .collect();
+    Ok(tags.into_iter().collect())
+}
+
+/// Flips the favorite flag of a saved query and returns the new value
+#[tauri::command]
+pub async fn toggle_favorite<R: Runtime>(app: AppHandle<R>, id: String) -> Result<bool, String> {
+    let dir = get_queries_dir(&app)?;
+    let _lock = lock_meta_async(&dir).await?;
+    let mut meta_list = read_meta(&dir)?;
+
+    let meta = meta_list
+        .iter_mut()
+        .find(|m| m.id == id)
+        .ok_or("Query not found")?;
+    meta.is_favorite = !meta.is_favorite;
+    let is_favorite = meta.is_favorite;
+
+    write_meta(&dir, &meta_list)?;
+    Ok(is_favorite)
 }
 
 #[tauri::command]
 pub async fn delete_saved_query<R: Runtime>(app: AppHandle<R>, id: String) -> Result<(), String> {
-    let mut meta_list = read_meta(&app)?;
     let dir = get_queries_dir(&app)?;
+    let _lock = lock_meta_async(&dir).await?;
+    let mut meta_list = read_meta(&dir)?;
 
     let idx = meta_list
         .iter()
@@ -156,7 +353,7 @@ pub async fn delete_saved_query<R: Runtime>(app: AppHandle<R>, id: String) -> Re
         .ok_or("Query not found")?;
     let meta = meta_list.remove(idx);
 
-    write_meta(&app, &meta_list)?;
+    write_meta(&dir, &meta_list)?;
 
     let file_path = dir.join(&meta.filename);
     if file_path.exists() {
@@ -165,3 +362,176 @@ pub async fn delete_saved_query<R: Runtime>(app: AppHandle<R>, id: String) -> Re
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn query(name: &str, is_favorite: bool, sort_order: i32) -> SavedQuery {
+        SavedQuery {
+            id: name.to_string(),
+            name: name.to_string(),
+            sql: String::new(),
+            connection_id: "c".to_string(),
+            is_favorite,
+            sort_order,
+            tags: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_sort_queries() {
+        let mut queries = vec![
+            query("beta", false, 0),
+            query("Alpha", false, 0),
+            query("late favorite", true, 5),
+            query("first", false, -1),
+            query("early favorite", true, 1),
+        ];
+        sort_queries(&mut queries);
+        let names: Vec<&str> = queries.iter().map(|q| q.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["early favorite", "late favorite", "first", "Alpha", "beta"]
+        );
+    }
+
+    #[test]
+    fn test_meta_without_favorite_fields_loads() {
+        let json = r#"[{"id": "1", "name": "q", "filename": "1.sql", "connection_id": "c"}]"#;
+        let meta: Vec<SavedQueryMeta> = serde_json::from_str(json).unwrap();
+        assert!(!meta[0].is_favorite);
+        assert_eq!(meta[0].sort_order, 0);
+        assert!(meta[0].tags.is_empty());
+    }
+
+    #[test]
+    fn test_normalize_tags() {
+        let tags = vec![
+            " billing ".to_string(),
+            "".to_string(),
+            "reports".to_string(),
+            "billing".to_string(),
+        ];
+        assert_eq!(normalize_tags(tags), vec!["billing", "reports"]);
+    }
+
+    #[test]
+    fn test_save_query_duplicate_name() {
+        let dir = TempDir::new().unwrap();
+        let first = save_query_in(
+            dir.path(),
+            "c".into(),
+            "report",
+            "SELECT 1".into(),
+            QuerySettings::default(),
+            false,
+        )
+        .unwrap();
+
+        let err = save_query_in(
+            dir.path(),
+            "c".into(),
+            "  report ",
+            "SELECT 2".into(),
+            QuerySettings::default(),
+            false,
+        )
+        .unwrap_err();
+        assert!(err.contains("already exists"));
+
+        let second = save_query_in(
+            dir.path(),
+            "c".into(),
+            "report",
+            "SELECT 2".into(),
+            QuerySettings::default(),
+            true,
+        )
+        .unwrap();
+        assert_eq!(second.id, first.id);
+
+        let meta = read_meta(dir.path()).unwrap();
+        assert_eq!(meta.len(), 1);
+        let sql = fs::read_to_string(dir.path().join(&meta[0].filename)).unwrap();
+        assert_eq!(sql, "SELECT 2");
+    }
+
+    #[test]
+    fn test_save_query_same_name_other_connection() {
+        let dir = TempDir::new().unwrap();
+        for connection_id in ["a", "b"] {
+            save_query_in(
+                dir.path(),
+                connection_id.into(),
+                "report",
+                "SELECT 1".into(),
+                QuerySettings::default(),
+                false,
+            )
+            .unwrap();
+        }
+        assert_eq!(read_meta(dir.path()).unwrap().len(), 2);
+
+        let all = load_all_queries(dir.path()).unwrap();
+        assert_eq!(all.len(), 2);
+        assert!(all.iter().all(|q| q.sql == "SELECT 1"));
+    }
+
+    #[test]
+    fn test_update_query_rename_duplicate_name() {
+        let dir = TempDir::new().unwrap();
+        let save = |connection_id: &str, name: &str| {
+            save_query_in(
+                dir.path(),
+                connection_id.into(),
+                name,
+                "SELECT 1".into(),
+                QuerySettings::default(),
+                false,
+            )
+            .unwrap()
+        };
+        save("c", "report");
+        let draft = save("c", "draft");
+        save("other", "final");
+
+        let err = update_query_in(
+            dir.path(),
+            &draft.id,
+            " report ",
+            "SELECT 2".into(),
+            QuerySettings::default(),
+        )
+        .unwrap_err();
+        assert!(err.contains("already exists"));
+
+        // Keeping its own name, or taking one used on another connection, is fine
+        for name in ["draft", "final"] {
+            let updated = update_query_in(
+                dir.path(),
+                &draft.id,
+                name,
+                "SELECT 2".into(),
+                QuerySettings::default(),
+            )
+            .unwrap();
+            assert_eq!(updated.name, name);
+        }
+    }
+
+    #[test]
+    fn test_save_query_rejects_empty_name() {
+        let dir = TempDir::new().unwrap();
+        let result = save_query_in(
+            dir.path(),
+            "c".into(),
+            "   ",
+            "SELECT 1".into(),
+            QuerySettings::default(),
+            false,
+        );
+        assert!(result.is_err());
+    }
+}
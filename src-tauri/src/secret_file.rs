@@ -0,0 +1,328 @@
+//! Encrypted file store for secrets, used instead of the OS keyring where none is
+//! available (e.g. headless Linux without a Secret Service).
+//!
+//! Each secret is sealed with AES-256-GCM under a key derived from the machine id
+//! and the user name, with the account name as associated data. The file cannot be
+//! decrypted after being copied to another machine, but it does not protect against
+//! other processes running as the same user on this one; the OS keyring remains the
+//! default for that reason.
+
+use crate::paths;
+use crate::persistence;
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+const FILE_VERSION: u32 = 1;
+const NONCE_LEN: usize = 12;
+const KEY_INFO: &str = "tabularis secrets v1";
+
+#[derive(Serialize, Deserialize)]
+struct SecretFileData {
+    version: u32,
+    /// Random HKDF salt, created with the file
+    salt: String,
+    /// Account name to base64 `nonce || ciphertext`
+    entries: BTreeMap<String, String>,
+}
+
+pub struct SecretFile {
+    path: PathBuf,
+    machine_id: String,
+}
+
+pub fn default_path() -> PathBuf {
+    paths::get_app_config_dir().join("secrets.enc")
+}
+
+impl SecretFile {
+    pub fn new(path: PathBuf, machine_id: String) -> Self {
+        Self { path, machine_id }
+    }
+
+    /// The store in the app config directory, keyed to this machine
+    pub fn open_default() -> Result<Self, String> {
+        Ok(Self::new(default_path(), machine_id()?))
+    }
+
+    pub fn get(&self, account: &str) -> Result<Option<String>, String> {
+        let Some(data) = self.read()? else {
+            return Ok(None);
+        };
+        let Some(sealed) = data.entries.get(account) else {
+            return Ok(None);
+        };
+        let key = self.derive_key(&data.salt)?;
+        open(&key, account, sealed).map(Some)
+    }
+
+    pub fn set(&self, account: &str, secret: &str) -> Result<(), String> {
+        self.update(|key, entries| {
+            entries.insert(account.to_string(), seal(key, account, secret)?);
+            Ok(())
+        })
+    }
+
+    pub fn delete(&self, account: &str) -> Result<(), String> {
+        if self.read()?.is_none() {
+            return Ok(());
+        }
+        self.update(|_, entries| {
+            entries.remove(account);
+            Ok(())
+        })
+    }
+
+    /// Account names with a stored secret
+    pub fn accounts(&self) -> Result<Vec<String>, String> {
+        Ok(self
+            .read()?
+            .map(|data| data.entries.into_keys().collect())
+            .unwrap_or_default())
+    }
+
+    fn read(&self) -> Result<Option<SecretFileData>, String> {
+        let data: Option<SecretFileData> = persistence::read_json_recovering(&self.path)
+            .map_err(|e| format!("Failed to read secrets file {}: {}", self.path.display(), e))?;
+        match data {
+            Some(data) if data.version != FILE_VERSION => Err(format!(
+                "Secrets file {} has unsupported version {}",
+                self.path.display(),
+                data.version
+            )),
+            other => Ok(other),
+        }
+    }
+
+    fn update(
+        &self,
+        change: impl FnOnce(&[u8; 32], &mut BTreeMap<String, String>) -> Result<(), String>,
+    ) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let _lock = persistence::lock_file(&self.path)?;
+        let mut data = match self.read()? {
+            Some(data) => data,
+            None => {
+                let mut salt = [0u8; 32];
+                OsRng.fill_bytes(&mut salt);
+                SecretFileData {
+                    version: FILE_VERSION,
+                    salt: STANDARD.encode(salt),
+                    entries: BTreeMap::new(),
+                }
+            }
+        };
+        let key = self.derive_key(&data.salt)?;
+        change(&key, &mut data.entries)?;
+
+        let json = serde_json::to_string_pretty(&data).map_err(|e| e.to_string())?;
+        persistence::write_atomic_private(&self.path, json.as_bytes())
+    }
+
+    fn derive_key(&self, salt: &str) -> Result<[u8; 32], String> {
+        let salt = STANDARD
+            .decode(salt)
+            .map_err(|e| format!("Secrets file has an invalid salt: {}", e))?;
+        let user = std::env::var("USER")
+            .or_else(|_| std::env::var("USERNAME"))
+            .unwrap_or_default();
+        let hkdf = Hkdf::<Sha256>::new(Some(&salt), self.machine_id.as_bytes());
+        let mut key = [0u8; 32];
+        hkdf.expand(format!("{}:{}", KEY_INFO, user).as_bytes(), &mut key)
+            .map_err(|e| e.to_string())?;
+        Ok(key)
+    }
+}
+
+fn seal(key: &[u8; 32], account: &str, secret: &str) -> Result<String, String> {
+    let cipher = Aes256Gcm::new(key.into());
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let payload = Payload {
+        msg: secret.as_bytes(),
+        aad: account.as_bytes(),
+    };
+    let ciphertext = cipher.encrypt(&nonce, payload).map_err(|e| e.to_string())?;
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    Ok(STANDARD.encode(sealed))
+}
+
+fn open(key: &[u8; 32], account: &str, sealed: &str) -> Result<String, String> {
+    let undecryptable = || {
+        format!(
+            "Could not decrypt the secret for {}: the secrets file was created on another machine or by another user, or is corrupted",
+            account
+        )
+    };
+    let bytes = STANDARD.decode(sealed).map_err(|_| undecryptable())?;
+    if bytes.len() < NONCE_LEN {
+        return Err(undecryptable());
+    }
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new(key.into());
+    let payload = Payload {
+        msg: ciphertext,
+        aad: account.as_bytes(),
+    };
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), payload)
+        .map_err(|_| undecryptable())?;
+    String::from_utf8(plaintext).map_err(|_| undecryptable())
+}
+
+#[cfg(target_os = "linux")]
+fn machine_id() -> Result<String, String> {
+    ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+        .iter()
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .map(|id| id.trim().to_string())
+        .find(|id| !id.is_empty())
+        .ok_or_else(|| {
+            "No machine id found in /etc/machine-id or /var/lib/dbus/machine-id; the encrypted secrets file needs one".to_string()
+        })
+}
+
+#[cfg(target_os = "macos")]
+fn machine_id() -> Result<String, String> {
+    let output = std::process::Command::new("ioreg")
+        .args(["-rd1", "-c", "IOPlatformExpertDevice"])
+        .output()
+        .map_err(|e| format!("Failed to read the machine id: {}", e))?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find(|line| line.contains("IOPlatformUUID"))
+        .and_then(|line| line.split('"').nth(3))
+        .map(str::to_string)
+        .ok_or_else(|| "No IOPlatformUUID found; the encrypted secrets file needs one".to_string())
+}
+
+#[cfg(target_os = "windows")]
+fn machine_id() -> Result<String, String> {
+    let output = std::process::Command::new("reg")
+        .args([
+            "query",
+            r"HKLM\SOFTWARE\Microsoft\Cryptography",
+            "/v",
+            "MachineGuid",
+        ])
+        .output()
+        .map_err(|e| format!("Failed to read the machine id: {}", e))?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find(|line| line.contains("MachineGuid"))
+        .and_then(|line| line.split_whitespace().last())
+        .map(str::to_string)
+        .ok_or_else(|| "No MachineGuid found; the encrypted secrets file needs one".to_string())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn machine_id() -> Result<String, String> {
+    Err("The encrypted secrets file is not supported on this platform".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn store(dir: &Path, machine_id: &str) -> SecretFile {
+        SecretFile::new(dir.join("secrets.enc"), machine_id.to_string())
+    }
+
+    #[test]
+    fn test_round_trip_and_delete() {
+        let dir = tempfile::tempdir().unwrap();
+        let secrets = store(dir.path(), "machine-a");
+        assert_eq!(secrets.get("conn:db").unwrap(), None);
+
+        secrets.set("conn:db", "hunter2").unwrap();
+        secrets.set("ai_key:openai", "sk-test").unwrap();
+        assert_eq!(secrets.get("conn:db").unwrap().as_deref(), Some("hunter2"));
+        assert_eq!(
+            secrets.accounts().unwrap(),
+            vec!["ai_key:openai", "conn:db"]
+        );
+
+        secrets.delete("conn:db").unwrap();
+        assert_eq!(secrets.get("conn:db").unwrap(), None);
+        assert_eq!(
+            secrets.get("ai_key:openai").unwrap().as_deref(),
+            Some("sk-test")
+        );
+    }
+
+    #[test]
+    fn test_secrets_are_not_stored_in_plain_text() {
+        let dir = tempfile::tempdir().unwrap();
+        store(dir.path(), "machine-a")
+            .set("conn:db", "hunter2")
+            .unwrap();
+        let content = fs::read_to_string(dir.path().join("secrets.enc")).unwrap();
+        assert!(content.contains("conn:db"));
+        assert!(!content.contains("hunter2"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_file_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        // A world-readable temp file left by an interrupted write is not reused as is
+        let path = dir.path().join("secrets.enc");
+        fs::write(dir.path().join("secrets.enc.tmp"), "").unwrap();
+        fs::set_permissions(
+            dir.path().join("secrets.enc.tmp"),
+            fs::Permissions::from_mode(0o644),
+        )
+        .unwrap();
+
+        store(dir.path(), "machine-a")
+            .set("conn:db", "hunter2")
+            .unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[test]
+    fn test_other_machine_cannot_decrypt() {
+        let dir = tempfile::tempdir().unwrap();
+        store(dir.path(), "machine-a")
+            .set("conn:db", "hunter2")
+            .unwrap();
+        let err = store(dir.path(), "machine-b").get("conn:db").unwrap_err();
+        assert!(err.contains("another machine"));
+    }
+
+    #[test]
+    fn test_value_is_bound_to_its_account() {
+        let dir = tempfile::tempdir().unwrap();
+        let secrets = store(dir.path(), "machine-a");
+        secrets.set("a:db", "one").unwrap();
+
+        // Moving a sealed value to another account must not decrypt
+        let path = dir.path().join("secrets.enc");
+        let mut data: SecretFileData =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let sealed = data.entries["a:db"].clone();
+        data.entries.insert("b:db".to_string(), sealed);
+        fs::write(&path, serde_json::to_string(&data).unwrap()).unwrap();
+
+        assert!(secrets.get("b:db").is_err());
+    }
+
+    #[test]
+    fn test_delete_without_file_is_a_no_op() {
+        let dir = tempfile::tempdir().unwrap();
+        store(dir.path(), "machine-a").delete("conn:db").unwrap();
+        assert!(!dir.path().join("secrets.enc").exists());
+    }
+}
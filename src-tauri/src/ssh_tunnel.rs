@@ -1,10 +1,10 @@
 use async_trait::async_trait;
 use russh::client;
 use russh_keys::key;
-use std::collections::HashMap;
-use std::io::{BufRead, BufReader};
-use std::net::{TcpListener, TcpStream};
-use std::path::Path;
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, ErrorKind, Read};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
 use std::process::{Child, Command, Stdio};
 use std::sync::OnceLock;
 use std::sync::{
@@ -15,16 +15,76 @@ use std::thread;
 use std::time::{Duration, Instant};
 use tokio::io::copy_bidirectional;
 use tokio::runtime::Runtime;
-use tokio::sync::Mutex as TokioMutex;
+use tokio::sync::RwLock as TokioRwLock;
+
+/// Keepalive interval used when the connection doesn't configure one
+pub const DEFAULT_KEEPALIVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Unanswered keepalives before the SSH session is considered dead
+const KEEPALIVE_MAX: usize = 3;
+
+/// Attempts to re-open a dropped russh session before the tunnel gives up
+const MAX_RECONNECT_ATTEMPTS: u32 = 3;
+
+/// How long opening a forwarding channel may take before the connection is dropped
+const CHANNEL_OPEN_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Environment variable carrying a key passphrase to the askpass helper
+const ASKPASS_PASSPHRASE_ENV: &str = "TABULARIS_SSH_ASKPASS_PASSPHRASE";
+
+/// Lines of system ssh stderr kept to explain why a tunnel died
+const STDERR_TAIL_LINES: usize = 20;
+
+/// How long a tunnel probe waits for the forwarded connection to be closed
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
 
 #[derive(Clone)]
 enum TunnelBackend {
-    Russh(Arc<AtomicBool>),
-    SystemSsh(Arc<Mutex<Child>>),
+    Russh {
+        running: Arc<AtomicBool>,
+        healthy: Arc<AtomicBool>,
+        /// Why the tunnel thread gave up, once it has
+        error: Arc<Mutex<Option<String>>>,
+    },
+    SystemSsh {
+        child: Arc<Mutex<Child>>,
+        /// The last stderr lines, read for as long as the process runs
+        stderr: Arc<Mutex<VecDeque<String>>>,
+    },
+}
+
+/// Captured stderr without ssh's `-v` debug output, which would bury the
+/// actual error (e.g. "Permission denied (publickey)")
+fn stderr_tail(lines: &Mutex<VecDeque<String>>) -> String {
+    lines
+        .lock()
+        .map(|lines| {
+            lines
+                .iter()
+                .filter(|line| !line.starts_with("debug"))
+                .cloned()
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .unwrap_or_default()
 }
 
 #[derive(Clone)]
-struct RusshClientHandler;
+struct RusshClientHandler {
+    /// Host and port to look up in ~/.ssh/known_hosts; any key is accepted without
+    verify: Option<(String, u16)>,
+    /// Why the server key was rejected, reported instead of russh's generic error
+    rejection: Arc<Mutex<Option<String>>>,
+}
+
+impl RusshClientHandler {
+    fn new(verify: Option<(String, u16)>) -> Self {
+        Self {
+            verify,
+            rejection: Arc::new(Mutex::new(None)),
+        }
+    }
+}
 
 #[async_trait]
 impl client::Handler for RusshClientHandler {
@@ -32,9 +92,65 @@ impl client::Handler for RusshClientHandler {
 
     async fn check_server_key(
         &mut self,
-        _server_public_key: &key::PublicKey,
+        server_public_key: &key::PublicKey,
     ) -> Result<bool, Self::Error> {
-        Ok(true)
+        let Some((host, port)) = &self.verify else {
+            return Ok(true);
+        };
+        match verify_known_host(host, *port, server_public_key, &known_hosts_path()) {
+            Ok(()) => Ok(true),
+            Err(e) => {
+                eprintln!("[SSH Tunnel Error] {}", e);
+                if let Ok(mut rejection) = self.rejection.lock() {
+                    *rejection = Some(e);
+                }
+                Ok(false)
+            }
+        }
+    }
+}
+
+/// The known_hosts file server keys are checked against when verification is on
+pub fn known_hosts_path() -> PathBuf {
+    directories::BaseDirs::new()
+        .map(|dirs| dirs.home_dir().to_path_buf())
+        .unwrap_or_default()
+        .join(".ssh")
+        .join("known_hosts")
+}
+
+/// Checks a server key against a known_hosts file, with an error that says what to
+/// do: add an unknown host by connecting once with `ssh`, or investigate a changed
+/// key before trusting it
+fn verify_known_host(
+    host: &str,
+    port: u16,
+    server_key: &key::PublicKey,
+    known_hosts: &Path,
+) -> Result<(), String> {
+    let fingerprint = server_key.fingerprint();
+    match russh_keys::check_known_hosts_path(host, port, server_key, known_hosts) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(format!(
+            "Host key verification failed: {}:{} is not in {} (server key SHA256:{}). Connect once with ssh to verify and add it.",
+            host,
+            port,
+            known_hosts.display(),
+            fingerprint
+        )),
+        Err(russh_keys::Error::KeyChanged { line }) => Err(format!(
+            "Host key verification failed: the key of {}:{} (SHA256:{}) does not match the one on line {} of {}. The server key may have changed, or someone may be intercepting the connection.",
+            host,
+            port,
+            fingerprint,
+            line,
+            known_hosts.display()
+        )),
+        Err(e) => Err(format!(
+            "Host key verification failed: could not read {}: {}",
+            known_hosts.display(),
+            e
+        )),
     }
 }
 
@@ -44,67 +160,329 @@ pub struct SshTunnel {
     backend: TunnelBackend,
 }
 
+/// The SSH server a tunnel goes through and how to log in to it
+#[derive(Debug, Clone, Default)]
+pub struct SshEndpoint {
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    pub password: Option<String>,
+    pub key_file: Option<String>,
+    pub key_passphrase: Option<String>,
+    /// Hops on the way to the server, each `[user@]host[:port]` as in ssh's `-J`
+    pub jump_hosts: Vec<String>,
+}
+
+/// Tunnel settings that don't identify the SSH server itself
+#[derive(Debug, Clone)]
+pub struct TunnelOptions {
+    /// Bind this local port instead of a random one
+    pub preferred_local_port: Option<u16>,
+    /// Use a random port when the preferred one is taken (otherwise fail)
+    pub allow_port_fallback: bool,
+    /// Interval between SSH keepalive messages
+    pub keepalive_interval: Duration,
+    /// Check server keys against ~/.ssh/known_hosts instead of accepting any key.
+    /// Off by default for compatibility with existing connections.
+    pub verify_host_key: bool,
+}
+
+impl Default for TunnelOptions {
+    fn default() -> Self {
+        Self {
+            preferred_local_port: None,
+            allow_port_fallback: false,
+            keepalive_interval: DEFAULT_KEEPALIVE_INTERVAL,
+            verify_host_key: false,
+        }
+    }
+}
+
+/// An SSH jump host (ProxyJump) the tunnel passes through on its way to the SSH
+/// server, written as `[user@]host[:port]` like ssh's `-J`
+#[derive(Debug, Clone, PartialEq)]
+pub struct JumpHost {
+    pub user: Option<String>,
+    pub host: String,
+    pub port: u16,
+}
+
+impl JumpHost {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let invalid = || {
+            format!(
+                "Invalid SSH jump host '{}', expected [user@]host[:port]",
+                spec
+            )
+        };
+        let spec = spec.trim();
+        let (user, address) = match spec.rsplit_once('@') {
+            Some((user, address)) if !user.is_empty() => (Some(user.to_string()), address),
+            Some(_) => return Err(invalid()),
+            None => (None, spec),
+        };
+
+        // IPv6 addresses are bracketed when a port follows: [::1]:2222
+        let (host, port) = if let Some(rest) = address.strip_prefix('[') {
+            let (host, after) = rest.split_once(']').ok_or_else(invalid)?;
+            match after.strip_prefix(':') {
+                Some(port) => (host, Some(port)),
+                None if after.is_empty() => (host, None),
+                None => return Err(invalid()),
+            }
+        } else {
+            match address.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (address, None),
+            }
+        };
+        if host.is_empty() {
+            return Err(invalid());
+        }
+        let port = match port {
+            Some(port) => port.parse().map_err(|_| invalid())?,
+            None => 22,
+        };
+
+        Ok(Self {
+            user,
+            host: host.to_string(),
+            port,
+        })
+    }
+}
+
+/// Back to `-J` syntax for system ssh
+impl std::fmt::Display for JumpHost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(user) = &self.user {
+            write!(f, "{}@", user)?;
+        }
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+/// A russh session, plus the sessions to the jump hosts it is tunnelled through;
+/// those must stay open for as long as it does
+struct RusshConnection {
+    handle: client::Handle<RusshClientHandler>,
+    _jumps: Vec<client::Handle<RusshClientHandler>>,
+}
+
+/// Everything needed to (re)open the russh session behind a tunnel
+struct RusshSession {
+    host: String,
+    port: u16,
+    user: String,
+    password: Option<String>,
+    key_file: Option<String>,
+    key_passphrase: Option<String>,
+    keepalive_interval: Duration,
+    verify_host_key: bool,
+    /// Jump hosts in the order they are passed through. They are authenticated
+    /// with the same key or password as the SSH server itself.
+    jump_hosts: Vec<JumpHost>,
+}
+
+impl RusshSession {
+    async fn connect(&self) -> Result<RusshConnection, String> {
+        let mut jumps: Vec<client::Handle<RusshClientHandler>> = Vec::new();
+        for jump in &self.jump_hosts {
+            println!(
+                "[SSH Tunnel] Connecting to jump host {}:{}",
+                jump.host, jump.port
+            );
+            let mut handle = self.open(jumps.last(), &jump.host, jump.port).await?;
+            let user = jump.user.as_deref().unwrap_or(&self.user);
+            self.authenticate(&mut handle, user)
+                .await
+                .map_err(|e| format!("Jump host {}: {}", jump.host, e))?;
+            jumps.push(handle);
+        }
+
+        let mut handle = self.open(jumps.last(), &self.host, self.port).await?;
+        self.authenticate(&mut handle, &self.user).await?;
+        Ok(RusshConnection {
+            handle,
+            _jumps: jumps,
+        })
+    }
+
+    /// Opens an SSH session to `host`, directly or through the previous jump host
+    async fn open(
+        &self,
+        via: Option<&client::Handle<RusshClientHandler>>,
+        host: &str,
+        port: u16,
+    ) -> Result<client::Handle<RusshClientHandler>, String> {
+        let config = Arc::new(client::Config {
+            keepalive_interval: Some(self.keepalive_interval),
+            keepalive_max: KEEPALIVE_MAX,
+            ..Default::default()
+        });
+
+        let handler =
+            RusshClientHandler::new(self.verify_host_key.then(|| (host.to_string(), port)));
+        let rejection = handler.rejection.clone();
+
+        let connected = match via {
+            Some(jump) => {
+                let channel = jump
+                    .channel_open_direct_tcpip(host, u32::from(port), "127.0.0.1", 0)
+                    .await
+                    .map_err(|e| {
+                        format!("Failed to reach {}:{} through jump host: {}", host, port, e)
+                    })?;
+                client::connect_stream(config, channel.into_stream(), handler).await
+            }
+            None => client::connect(config, format!("{}:{}", host, port), handler).await,
+        };
+        connected.map_err(|e| match rejection.lock().ok().and_then(|r| r.clone()) {
+            Some(rejection) => rejection,
+            None => format!("Failed to connect to SSH server {}: {}", host, e),
+        })
+    }
+
+    async fn authenticate(
+        &self,
+        handle: &mut client::Handle<RusshClientHandler>,
+        user: &str,
+    ) -> Result<(), String> {
+        let authenticated =
+            if let Some(key_path) = self.key_file.as_deref().filter(|p| !p.trim().is_empty()) {
+                println!("[SSH Tunnel] Authenticating with key file: {}", key_path);
+                let passphrase = self
+                    .key_passphrase
+                    .as_deref()
+                    .filter(|p| !p.trim().is_empty());
+                let key = russh_keys::load_secret_key(Path::new(key_path), passphrase)
+                    .map_err(|e| format!("SSH key auth failed: {}", e))?;
+                handle
+                    .authenticate_publickey(user, Arc::new(key))
+                    .await
+                    .map_err(|e| format!("SSH key auth failed: {}", e))?
+            } else if let Some(pwd) = self.password.as_deref().filter(|p| !p.trim().is_empty()) {
+                println!("[SSH Tunnel] Authenticating with password");
+                handle
+                    .authenticate_password(user, pwd)
+                    .await
+                    .map_err(|e| format!("SSH password auth failed: {}", e))?
+            } else {
+                return Err("No SSH credentials provided for russh".to_string());
+            };
+
+        if !authenticated {
+            return Err("SSH authentication failed".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Re-opens a dropped session, waiting 1s, 2s, 4s... between attempts
+    async fn reconnect(&self) -> Result<RusshConnection, String> {
+        let mut last_err = String::new();
+        for attempt in 1..=MAX_RECONNECT_ATTEMPTS {
+            tokio::time::sleep(Duration::from_secs(1 << (attempt - 1))).await;
+            println!(
+                "[SSH Tunnel] Reconnecting to {}:{} (attempt {}/{})",
+                self.host, self.port, attempt, MAX_RECONNECT_ATTEMPTS
+            );
+            match self.connect().await {
+                Ok(handle) => return Ok(handle),
+                Err(e) => {
+                    eprintln!("[SSH Tunnel Error] Reconnect failed: {}", e);
+                    last_err = e;
+                }
+            }
+        }
+        Err(last_err)
+    }
+}
+
 pub static TUNNELS: OnceLock<Mutex<HashMap<String, SshTunnel>>> = OnceLock::new();
 
 pub fn get_tunnels() -> &'static Mutex<HashMap<String, SshTunnel>> {
     TUNNELS.get_or_init(|| Mutex::new(HashMap::new()))
 }
 
+/// Stops and forgets a single tunnel. Returns false when no tunnel has that key.
+pub fn stop_tunnel(key: &str) -> bool {
+    let mut tunnels = get_tunnels().lock().unwrap();
+    match tunnels.remove(key) {
+        Some(tunnel) => {
+            println!("[SSH Tunnel] Stopping tunnel {}", key);
+            tunnel.stop();
+            true
+        }
+        None => false,
+    }
+}
+
+/// Stops every active tunnel (used on app shutdown so no ssh child processes linger)
+pub fn stop_all_tunnels() {
+    let mut tunnels = get_tunnels().lock().unwrap();
+    for (key, tunnel) in tunnels.drain() {
+        println!("[SSH Tunnel] Stopping tunnel {}", key);
+        tunnel.stop();
+    }
+}
+
 impl SshTunnel {
     pub fn new(
-        ssh_host: &str,
-        ssh_port: u16,
-        ssh_user: &str,
-        ssh_password: Option<&str>,
-        ssh_key_file: Option<&str>,
-        ssh_key_passphrase: Option<&str>,
+        ssh: &SshEndpoint,
         remote_host: &str,
         remote_port: u16,
+        options: &TunnelOptions,
     ) -> Result<Self, String> {
-        let ssh_password = ssh_password.filter(|p| !p.trim().is_empty());
+        let jump_hosts = ssh
+            .jump_hosts
+            .iter()
+            .filter(|spec| !spec.trim().is_empty())
+            .map(|spec| JumpHost::parse(spec))
+            .collect::<Result<Vec<_>, _>>()?;
         let use_system_ssh = system_ssh_available();
         println!(
             "[SSH Tunnel] New Request: Host={}, Port={}, User={}, SystemAvailable={}",
-            ssh_host, ssh_port, ssh_user, use_system_ssh
+            ssh.host, ssh.port, ssh.user, use_system_ssh
         );
 
-        let local_port = {
-            let listener = TcpListener::bind("127.0.0.1:0").map_err(|e| {
-                let err = format!("Failed to find free local port: {}", e);
-                eprintln!("[SSH Tunnel Error] {}", err);
-                err
+        let local_port = pick_local_port(options.preferred_local_port, options.allow_port_fallback)
+            .map_err(|e| {
+                eprintln!("[SSH Tunnel Error] {}", e);
+                e
             })?;
-            listener.local_addr().unwrap().port()
-        };
         println!("[SSH Tunnel] Assigned Local Port: {}", local_port);
 
         if use_system_ssh {
             Self::new_system_ssh(
-                ssh_host,
-                ssh_port,
-                ssh_user,
-                ssh_key_file,
+                ssh,
+                &jump_hosts,
                 remote_host,
                 remote_port,
                 local_port,
+                options,
             )
             .map_err(|e| {
                 eprintln!("[SSH Tunnel Error] System SSH failed: {}", e);
                 e
             })
         } else {
-            Self::new_russh(
-                ssh_host,
-                ssh_port,
-                ssh_user,
-                ssh_password,
-                ssh_key_file,
-                ssh_key_passphrase,
-                remote_host,
-                remote_port,
-                local_port,
-            )
-            .map_err(|e| {
+            let session = RusshSession {
+                host: ssh.host.clone(),
+                port: ssh.port,
+                user: ssh.user.clone(),
+                password: ssh.password.clone().filter(|p| !p.trim().is_empty()),
+                key_file: ssh.key_file.clone(),
+                key_passphrase: ssh.key_passphrase.clone(),
+                keepalive_interval: options.keepalive_interval,
+                verify_host_key: options.verify_host_key,
+                jump_hosts,
+            };
+            Self::new_russh(session, remote_host, remote_port, local_port).map_err(|e| {
                 eprintln!("[SSH Tunnel Error] Russh failed: {}", e);
                 e
             })
@@ -112,13 +490,12 @@ impl SshTunnel {
     }
 
     fn new_system_ssh(
-        ssh_host: &str,
-        ssh_port: u16,
-        ssh_user: &str,
-        ssh_key_file: Option<&str>,
+        ssh: &SshEndpoint,
+        jump_hosts: &[JumpHost],
         remote_host: &str,
         remote_port: u16,
         local_port: u16,
+        options: &TunnelOptions,
     ) -> Result<Self, String> {
         let mut args = Vec::new();
 
@@ -133,34 +510,56 @@ impl SshTunnel {
             local_port, remote_host, remote_port
         ));
 
-        let destination = if !ssh_user.trim().is_empty() {
-            format!("{}@{}", ssh_user, ssh_host)
+        let destination = if !ssh.user.trim().is_empty() {
+            format!("{}@{}", ssh.user, ssh.host)
         } else {
-            ssh_host.to_string()
+            ssh.host.clone()
         };
 
-        if ssh_port != 22 {
+        if ssh.port != 22 {
             args.push("-p".to_string());
-            args.push(ssh_port.to_string());
+            args.push(ssh.port.to_string());
         }
 
-        if let Some(key) = ssh_key_file {
+        if let Some(key) = &ssh.key_file {
             if !key.trim().is_empty() {
                 args.push("-i".to_string());
                 args.push(key.to_string());
             }
         }
 
+        if !jump_hosts.is_empty() {
+            args.push("-J".to_string());
+            args.push(
+                jump_hosts
+                    .iter()
+                    .map(JumpHost::to_string)
+                    .collect::<Vec<_>>()
+                    .join(","),
+            );
+        }
+
+        // With verification on, ssh's own known_hosts checking (and the user's
+        // ssh_config) applies; BatchMode makes an unknown host fail instead of prompting
+        if !options.verify_host_key {
+            args.push("-o".to_string());
+            args.push("StrictHostKeyChecking=no".to_string());
+        }
         args.push("-o".to_string());
-        args.push("StrictHostKeyChecking=no".to_string());
+        args.push(format!(
+            "ServerAliveInterval={}",
+            options.keepalive_interval.as_secs().max(1)
+        ));
         args.push("-o".to_string());
-        args.push("BatchMode=yes".to_string());
+        args.push(format!("ServerAliveCountMax={}", KEEPALIVE_MAX));
 
         args.push(destination);
 
         println!("[SSH Tunnel] Executing: ssh {:?}", args);
 
-        let mut child = Command::new("ssh")
+        let mut command = Command::new("ssh");
+        configure_auth_prompts(&mut command, ssh.key_passphrase.as_deref());
+        let mut child = command
             .args(args)
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
@@ -175,7 +574,7 @@ impl SshTunnel {
             })?;
 
         let stdout_log = Arc::new(Mutex::new(Vec::new()));
-        let stderr_log = Arc::new(Mutex::new(Vec::new()));
+        let stderr_log = Arc::new(Mutex::new(VecDeque::with_capacity(STDERR_TAIL_LINES)));
 
         // Spawn threads to capture and log stdout/stderr in real-time
         if let Some(stdout) = child.stdout.take() {
@@ -205,7 +604,10 @@ impl SshTunnel {
                         eprintln!("[SSH System Err] {}", l);
 
                         if let Ok(mut g) = log.lock() {
-                            g.push(l);
+                            if g.len() == STDERR_TAIL_LINES {
+                                g.pop_front();
+                            }
+                            g.push_back(l);
                         }
                     }
                 }
@@ -226,7 +628,7 @@ impl SshTunnel {
                 if let Ok(Some(status)) = c.try_wait() {
                     // Collect captured logs
                     let stdout_content = stdout_log.lock().unwrap().join("\n");
-                    let stderr_content = stderr_log.lock().unwrap().join("\n");
+                    let stderr_content = stderr_tail(&stderr_log);
 
                     let err_msg = format!(
                         "SSH process exited prematurely with status: {}.\nStderr: {}\nStdout: {}",
@@ -264,22 +666,23 @@ impl SshTunnel {
 
         Ok(Self {
             local_port,
-            backend: TunnelBackend::SystemSsh(child_arc),
+            backend: TunnelBackend::SystemSsh {
+                child: child_arc,
+                stderr: stderr_log,
+            },
         })
     }
 
     fn new_russh(
-        ssh_host: &str,
-        ssh_port: u16,
-        ssh_user: &str,
-        ssh_password: Option<&str>,
-        ssh_key_file: Option<&str>,
-        ssh_key_passphrase: Option<&str>,
+        session: RusshSession,
         remote_host: &str,
         remote_port: u16,
         local_port: u16,
     ) -> Result<Self, String> {
-        println!("[SSH Tunnel] Russh connecting to {}:{}", ssh_host, ssh_port);
+        println!(
+            "[SSH Tunnel] Russh connecting to {}:{}",
+            session.host, session.port
+        );
         let listener = TcpListener::bind(format!("127.0.0.1:{}", local_port)).map_err(|e| {
             let err = format!("Failed to bind local port {}: {}", local_port, e);
             eprintln!("[SSH Tunnel Error] {}", err);
@@ -293,12 +696,11 @@ impl SshTunnel {
         }
 
         let running = Arc::new(AtomicBool::new(true));
+        let healthy = Arc::new(AtomicBool::new(true));
+        let error = Arc::new(Mutex::new(None));
         let running_clone = running.clone();
-        let ssh_host = ssh_host.to_string();
-        let ssh_user = ssh_user.to_string();
-        let ssh_password = ssh_password.map(|p| p.to_string());
-        let ssh_key_file = ssh_key_file.map(|p| p.to_string());
-        let ssh_key_passphrase = ssh_key_passphrase.map(|p| p.to_string());
+        let healthy_clone = healthy.clone();
+        let error_clone = error.clone();
         let remote_host = remote_host.to_string();
 
         let (ready_tx, ready_rx) = mpsc::channel();
@@ -316,54 +718,44 @@ impl SshTunnel {
 
             let ready_tx_inner = ready_tx.clone();
             let result = runtime.block_on(async move {
-                let config = Arc::new(client::Config::default());
-                let addr = format!("{}:{}", ssh_host, ssh_port);
-
-                let mut handle = client::connect(config, addr, RusshClientHandler)
-                    .await
-                    .map_err(|e| format!("Failed to connect to SSH server: {}", e))?;
-
-                let authenticated = if let Some(key_path) =
-                    ssh_key_file.as_deref().filter(|p| !p.trim().is_empty())
-                {
-                    println!("[SSH Tunnel] Authenticating with key file: {}", key_path);
-                    let passphrase = ssh_key_passphrase
-                        .as_deref()
-                        .filter(|p| !p.trim().is_empty());
-                    let key = russh_keys::load_secret_key(Path::new(key_path), passphrase)
-                        .map_err(|e| format!("SSH key auth failed: {}", e))?;
-                    handle
-                        .authenticate_publickey(&ssh_user, Arc::new(key))
-                        .await
-                        .map_err(|e| format!("SSH key auth failed: {}", e))?
-                } else if let Some(pwd) = ssh_password.as_deref().filter(|p| !p.trim().is_empty()) {
-                    println!("[SSH Tunnel] Authenticating with password");
-                    handle
-                        .authenticate_password(&ssh_user, pwd)
-                        .await
-                        .map_err(|e| format!("SSH password auth failed: {}", e))?
-                } else {
-                    let err = "No SSH credentials provided for russh".to_string();
-                    eprintln!("[SSH Tunnel Error] {}", err);
-                    let _ = ready_tx_inner.send(Err(err.clone()));
-                    return Err(err);
-                };
-
-                if !authenticated {
-                    let err = "SSH authentication failed".to_string();
-                    eprintln!("[SSH Tunnel Error] {}", err);
-                    let _ = ready_tx_inner.send(Err(err.clone()));
-                    return Err(err);
-                }
+                let connection = session.connect().await?;
 
                 let listener = tokio::net::TcpListener::from_std(listener)
                     .map_err(|e| format!("Failed to configure async listener: {}", e))?;
 
-                let handle = Arc::new(TokioMutex::new(handle));
+                // russh opens channels through `&Handle`, so forwarded connections
+                // share a read lock and open their channels concurrently; only a
+                // reconnect, which swaps the session, takes the write lock
+                let handle = Arc::new(TokioRwLock::new(connection));
 
                 let _ = ready_tx_inner.send(Ok(()));
 
                 while running_clone.load(Ordering::Relaxed) {
+                    // Missed keepalives close the session; bring it back before
+                    // accepting more connections, or give up and mark the tunnel down
+                    let closed = handle.read().await.handle.is_closed();
+                    if closed {
+                        healthy_clone.store(false, Ordering::Relaxed);
+                        eprintln!(
+                            "[SSH Tunnel Error] SSH session to {}:{} was closed",
+                            session.host, session.port
+                        );
+                        match session.reconnect().await {
+                            Ok(new_connection) => {
+                                *handle.write().await = new_connection;
+                                healthy_clone.store(true, Ordering::Relaxed);
+                                println!("[SSH Tunnel] Reconnected to {}", session.host);
+                            }
+                            Err(e) => {
+                                running_clone.store(false, Ordering::Relaxed);
+                                return Err(format!(
+                                    "SSH tunnel down after {} reconnect attempts: {}",
+                                    MAX_RECONNECT_ATTEMPTS, e
+                                ));
+                            }
+                        }
+                    }
+
                     let accept =
                         tokio::time::timeout(Duration::from_millis(200), listener.accept()).await;
 
@@ -382,23 +774,34 @@ impl SshTunnel {
                     let handle = handle.clone();
                     let r_host = remote_host.clone();
                     tokio::spawn(async move {
-                        let handle = handle.lock().await;
-                        let channel = match handle
-                            .channel_open_direct_tcpip(
+                        // The lock is only held while the channel opens, never
+                        // while data is forwarded
+                        let channel = {
+                            let connection = handle.read().await;
+                            let open = connection.handle.channel_open_direct_tcpip(
                                 r_host,
                                 u32::from(remote_port),
                                 "127.0.0.1",
                                 0,
-                            )
-                            .await
-                        {
-                            Ok(c) => c,
-                            Err(e) => {
-                                eprintln!("[SSH Tunnel Error] Failed to open SSH channel: {}", e);
-                                return;
+                            );
+                            match tokio::time::timeout(CHANNEL_OPEN_TIMEOUT, open).await {
+                                Ok(Ok(c)) => c,
+                                Ok(Err(e)) => {
+                                    eprintln!(
+                                        "[SSH Tunnel Error] Failed to open SSH channel: {}",
+                                        e
+                                    );
+                                    return;
+                                }
+                                Err(_) => {
+                                    eprintln!(
+                                        "[SSH Tunnel Error] Timed out opening SSH channel after {}s",
+                                        CHANNEL_OPEN_TIMEOUT.as_secs()
+                                    );
+                                    return;
+                                }
                             }
                         };
-                        drop(handle);
 
                         let mut stream = stream;
                         let mut channel_stream = channel.into_stream();
@@ -412,6 +815,10 @@ impl SshTunnel {
             });
 
             if let Err(err) = result {
+                eprintln!("[SSH Tunnel Error] {}", err);
+                if let Ok(mut e) = error_clone.lock() {
+                    *e = Some(err.clone());
+                }
                 let _ = ready_tx.send(Err(err));
             }
         });
@@ -419,7 +826,11 @@ impl SshTunnel {
         match ready_rx.recv_timeout(Duration::from_secs(10)) {
             Ok(Ok(())) => Ok(Self {
                 local_port,
-                backend: TunnelBackend::Russh(running),
+                backend: TunnelBackend::Russh {
+                    running,
+                    healthy,
+                    error,
+                },
             }),
             Ok(Err(err)) => Err(err),
             Err(_) => Err("Timed out waiting for Russh tunnel to initialize".to_string()),
@@ -427,17 +838,174 @@ impl SshTunnel {
     }
 
     pub fn stop(&self) {
+        if let Some(reason) = self.failure() {
+            println!("[SSH Tunnel] Stopping a tunnel that had failed: {}", reason);
+        }
         match &self.backend {
-            TunnelBackend::Russh(running) => {
+            TunnelBackend::Russh { running, .. } => {
                 running.store(false, Ordering::Relaxed);
             }
-            TunnelBackend::SystemSsh(child) => {
+            TunnelBackend::SystemSsh { child, .. } => {
                 if let Ok(mut c) = child.lock() {
                     let _ = c.kill();
                 }
             }
         }
     }
+
+    /// `system-ssh` or `russh`
+    pub fn backend_name(&self) -> &'static str {
+        match &self.backend {
+            TunnelBackend::Russh { .. } => "russh",
+            TunnelBackend::SystemSsh { .. } => "system-ssh",
+        }
+    }
+
+    /// Why the tunnel stopped forwarding, while it is unhealthy: the ssh exit
+    /// status with its last stderr lines, or the russh session error
+    pub fn failure(&self) -> Option<String> {
+        match &self.backend {
+            TunnelBackend::Russh { healthy, error, .. } => {
+                if healthy.load(Ordering::Relaxed) {
+                    return None;
+                }
+                let error = error.lock().ok().and_then(|e| e.clone());
+                Some(error.unwrap_or_else(|| "SSH session was closed, reconnecting".to_string()))
+            }
+            TunnelBackend::SystemSsh { child, stderr } => {
+                let status = child.lock().ok()?.try_wait().ok()??;
+                let tail = stderr_tail(stderr);
+                Some(if tail.is_empty() {
+                    format!("ssh exited with {}", status)
+                } else {
+                    format!("ssh exited with {}: {}", status, tail)
+                })
+            }
+        }
+    }
+
+    /// False once the tunnel can no longer forward traffic: the ssh process exited,
+    /// or the russh session dropped (stays false until a reconnect succeeds)
+    pub fn is_healthy(&self) -> bool {
+        match &self.backend {
+            TunnelBackend::Russh { healthy, .. } => healthy.load(Ordering::Relaxed),
+            TunnelBackend::SystemSsh { child, .. } => child
+                .lock()
+                .map(|mut c| matches!(c.try_wait(), Ok(None)))
+                .unwrap_or(false),
+        }
+    }
+}
+
+/// Picks the local port for a tunnel: the preferred port if it is free, otherwise
+/// a random one when `allow_fallback` is set (or an error when it isn't)
+fn pick_local_port(preferred: Option<u16>, allow_fallback: bool) -> Result<u16, String> {
+    if let Some(port) = preferred {
+        match TcpListener::bind(("127.0.0.1", port)) {
+            Ok(_) => return Ok(port),
+            Err(e) if allow_fallback => {
+                println!(
+                    "[SSH Tunnel] Preferred local port {} unavailable ({}), using a random port",
+                    port, e
+                );
+            }
+            Err(e) => {
+                return Err(format!(
+                    "Preferred local port {} is not available: {}",
+                    port, e
+                ));
+            }
+        }
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .map_err(|e| format!("Failed to find free local port: {}", e))?;
+    listener
+        .local_addr()
+        .map(|addr| addr.port())
+        .map_err(|e| format!("Failed to find free local port: {}", e))
+}
+
+/// Lets system ssh use a passphrase-protected key without a terminal: ssh runs this
+/// executable as its SSH_ASKPASS program, which answers the passphrase prompt (see
+/// `answer_askpass_request`). Needs OpenSSH 8.4+ for SSH_ASKPASS_REQUIRE. The
+/// passphrase is visible in the ssh process environment to the same OS user.
+/// Without a passphrase, ssh runs in BatchMode and encrypted keys must be loaded in
+/// the agent with `ssh-add`.
+fn configure_auth_prompts(command: &mut Command, passphrase: Option<&str>) {
+    let askpass = passphrase
+        .filter(|p| !p.is_empty())
+        .and_then(|p| std::env::current_exe().ok().map(|exe| (exe, p)));
+    match askpass {
+        Some((exe, passphrase)) => {
+            command
+                .env("SSH_ASKPASS", exe)
+                .env("SSH_ASKPASS_REQUIRE", "force")
+                .env(ASKPASS_PASSPHRASE_ENV, passphrase)
+                .stdin(Stdio::null())
+                .args(["-o", "NumberOfPasswordPrompts=1"]);
+        }
+        None => {
+            command.args(["-o", "BatchMode=yes"]);
+        }
+    }
+}
+
+/// The reply to an ssh askpass prompt: the passphrase for key passphrase prompts,
+/// nothing (cancel) for anything else, such as host key confirmations
+fn askpass_reply<'a>(prompt: &str, passphrase: &'a str) -> Option<&'a str> {
+    prompt
+        .to_lowercase()
+        .contains("passphrase")
+        .then_some(passphrase)
+}
+
+/// When this process was started by ssh as its askpass program, prints the answer
+/// and exits; otherwise returns. Called first thing in `run`.
+pub fn answer_askpass_request() {
+    let Ok(passphrase) = std::env::var(ASKPASS_PASSPHRASE_ENV) else {
+        return;
+    };
+    let prompt = std::env::args().nth(1).unwrap_or_default();
+    match askpass_reply(&prompt, &passphrase) {
+        Some(reply) => {
+            println!("{}", reply);
+            std::process::exit(0);
+        }
+        None => std::process::exit(1),
+    }
+}
+
+/// Checks that a tunnel's local port accepts connections. With `probe`, also
+/// checks that a connection forwarded through it stays open: the SSH server closes
+/// it straight away when it can't reach the remote host. Servers that speak first
+/// (MySQL) answer with data, those that wait for the client (Postgres) let the
+/// read time out; both count as reachable.
+pub fn check_tunnel(local_port: u16, probe: bool) -> Result<(), String> {
+    let addr = SocketAddr::from(([127, 0, 0, 1], local_port));
+    let mut stream = TcpStream::connect_timeout(&addr, PROBE_TIMEOUT).map_err(|e| {
+        format!(
+            "Local tunnel port {} is not accepting connections: {}",
+            local_port, e
+        )
+    })?;
+    if !probe {
+        return Ok(());
+    }
+
+    stream
+        .set_read_timeout(Some(PROBE_TIMEOUT))
+        .map_err(|e| e.to_string())?;
+    let mut buf = [0u8; 1];
+    match stream.read(&mut buf) {
+        Ok(0) => Err(
+            "The SSH server closed the forwarded connection: the remote host is not reachable from the SSH server"
+                .to_string(),
+        ),
+        Ok(_) => Ok(()),
+        Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => Ok(()),
+        Err(e) => Err(format!("Forwarded connection failed: {}", e)),
+    }
 }
 
 /// Test an SSH connection without creating a tunnel
@@ -448,6 +1016,7 @@ pub fn test_ssh_connection(
     ssh_password: Option<&str>,
     ssh_key_file: Option<&str>,
     ssh_key_passphrase: Option<&str>,
+    verify_host_key: bool,
 ) -> Result<String, String> {
     let ssh_password = ssh_password.filter(|p| !p.trim().is_empty());
     let use_system_ssh = system_ssh_available();
@@ -457,7 +1026,14 @@ pub fn test_ssh_connection(
     );
 
     if use_system_ssh {
-        test_ssh_connection_system(ssh_host, ssh_port, ssh_user, ssh_key_file)
+        test_ssh_connection_system(
+            ssh_host,
+            ssh_port,
+            ssh_user,
+            ssh_key_file,
+            ssh_key_passphrase,
+            verify_host_key,
+        )
     } else {
         test_ssh_connection_russh(
             ssh_host,
@@ -466,6 +1042,7 @@ pub fn test_ssh_connection(
             ssh_password,
             ssh_key_file,
             ssh_key_passphrase,
+            verify_host_key,
         )
     }
 }
@@ -476,6 +1053,8 @@ fn test_ssh_connection_system(
     ssh_port: u16,
     ssh_user: &str,
     ssh_key_file: Option<&str>,
+    ssh_key_passphrase: Option<&str>,
+    verify_host_key: bool,
 ) -> Result<String, String> {
     println!("[SSH Test] Using system SSH (supports ~/.ssh/config)");
 
@@ -483,14 +1062,13 @@ fn test_ssh_connection_system(
     let port_string = ssh_port.to_string();
     let destination = format!("{}@{}", ssh_user, ssh_host);
 
-    let mut args = vec![
-        "-o",
-        "BatchMode=yes",
-        "-o",
-        "ConnectTimeout=10",
-        "-o",
-        "StrictHostKeyChecking=accept-new",
-    ];
+    // With verification on, an unknown or changed host key fails the test
+    let host_key_checking = if verify_host_key {
+        "StrictHostKeyChecking=yes"
+    } else {
+        "StrictHostKeyChecking=accept-new"
+    };
+    let mut args = vec!["-o", "ConnectTimeout=10", "-o", host_key_checking];
 
     if ssh_port != 22 {
         args.push("-p");
@@ -509,7 +1087,9 @@ fn test_ssh_connection_system(
 
     println!("[SSH Test] Executing: ssh {:?}", args);
 
-    let output = Command::new("ssh").args(&args).output().map_err(|e| {
+    let mut command = Command::new("ssh");
+    configure_auth_prompts(&mut command, ssh_key_passphrase);
+    let output = command.args(&args).output().map_err(|e| {
         format!(
             "Failed to execute ssh command: {}. Ensure 'ssh' is in PATH.",
             e
@@ -538,6 +1118,7 @@ fn test_ssh_connection_russh(
     ssh_password: Option<&str>,
     ssh_key_file: Option<&str>,
     ssh_key_passphrase: Option<&str>,
+    verify_host_key: bool,
 ) -> Result<String, String> {
     println!("[SSH Test] Using russh for authentication");
 
@@ -546,13 +1127,18 @@ fn test_ssh_connection_russh(
     let result = runtime.block_on(async {
         let config = Arc::new(client::Config::default());
         let addr = format!("{}:{}", ssh_host, ssh_port);
-        let mut handle = client::connect(config, addr, RusshClientHandler)
-            .await
-            .map_err(|e| {
-                format!(
-                    "Failed to connect to SSH server {}:{}: {}",
-                    ssh_host, ssh_port, e
-                )
+        let handler =
+            RusshClientHandler::new(verify_host_key.then(|| (ssh_host.to_string(), ssh_port)));
+        let rejection = handler.rejection.clone();
+        let mut handle =
+            client::connect(config, addr, handler).await.map_err(|e| {
+                match rejection.lock().ok().and_then(|r| r.clone()) {
+                    Some(rejection) => rejection,
+                    None => format!(
+                        "Failed to connect to SSH server {}:{}: {}",
+                        ssh_host, ssh_port, e
+                    ),
+                }
             })?;
 
         let authenticated = if let Some(key_path) = ssh_key_file.filter(|p| !p.trim().is_empty()) {
@@ -600,3 +1186,153 @@ fn system_ssh_available() -> bool {
         .status()
         .is_ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_askpass_only_answers_passphrase_prompts() {
+        assert_eq!(
+            askpass_reply(
+                "Enter passphrase for key '/home/me/.ssh/id_ed25519': ",
+                "s3cret"
+            ),
+            Some("s3cret")
+        );
+        assert_eq!(
+            askpass_reply(
+                "Are you sure you want to continue connecting (yes/no/[fingerprint])? ",
+                "s3cret"
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_verify_known_host() {
+        let dir = tempfile::tempdir().unwrap();
+        let known_hosts = dir.path().join("known_hosts");
+        let server_key = russh_keys::key::KeyPair::generate_ed25519()
+            .unwrap()
+            .clone_public_key()
+            .unwrap();
+        let other_key = russh_keys::key::KeyPair::generate_ed25519()
+            .unwrap()
+            .clone_public_key()
+            .unwrap();
+
+        let err = verify_known_host("db.example.com", 22, &server_key, &known_hosts).unwrap_err();
+        assert!(err.contains("is not in"));
+
+        russh_keys::learn_known_hosts_path("db.example.com", 22, &server_key, &known_hosts)
+            .unwrap();
+        assert!(verify_known_host("db.example.com", 22, &server_key, &known_hosts).is_ok());
+
+        let err = verify_known_host("db.example.com", 22, &other_key, &known_hosts).unwrap_err();
+        assert!(err.contains("does not match"));
+
+        // Keys are recorded per port
+        assert!(verify_known_host("db.example.com", 2222, &server_key, &known_hosts).is_err());
+    }
+
+    #[test]
+    fn test_jump_host_parse() {
+        assert_eq!(
+            JumpHost::parse("bastion").unwrap(),
+            JumpHost {
+                user: None,
+                host: "bastion".into(),
+                port: 22
+            }
+        );
+        assert_eq!(
+            JumpHost::parse("ops@bastion.example.com:2222").unwrap(),
+            JumpHost {
+                user: Some("ops".into()),
+                host: "bastion.example.com".into(),
+                port: 2222
+            }
+        );
+        assert_eq!(
+            JumpHost::parse("[2001:db8::1]:2200").unwrap(),
+            JumpHost {
+                user: None,
+                host: "2001:db8::1".into(),
+                port: 2200
+            }
+        );
+        assert!(JumpHost::parse("bastion:ssh").is_err());
+        assert!(JumpHost::parse("@bastion").is_err());
+        assert!(JumpHost::parse("ops@").is_err());
+    }
+
+    #[test]
+    fn test_jump_host_display_round_trips() {
+        for spec in ["bastion", "ops@bastion:2222", "[2001:db8::1]:2200"] {
+            let jump = JumpHost::parse(spec).unwrap();
+            assert_eq!(JumpHost::parse(&jump.to_string()).unwrap(), jump);
+        }
+    }
+
+    #[test]
+    fn test_pick_local_port_prefers_free_port() {
+        let free_port = pick_local_port(None, false).unwrap();
+        assert_eq!(pick_local_port(Some(free_port), false), Ok(free_port));
+    }
+
+    #[test]
+    fn test_pick_local_port_when_preferred_is_taken() {
+        let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = taken.local_addr().unwrap().port();
+
+        assert!(pick_local_port(Some(port), false).is_err());
+
+        let fallback = pick_local_port(Some(port), true).unwrap();
+        assert_ne!(fallback, port);
+    }
+
+    #[test]
+    fn test_stderr_tail_skips_debug_output() {
+        let lines = Mutex::new(VecDeque::from(vec![
+            "debug1: Authentications that can continue: publickey".to_string(),
+            "user@host: Permission denied (publickey).".to_string(),
+        ]));
+        assert_eq!(
+            stderr_tail(&lines),
+            "user@host: Permission denied (publickey)."
+        );
+    }
+
+    #[test]
+    fn test_check_tunnel_without_listener() {
+        let port = pick_local_port(None, false).unwrap();
+        assert!(check_tunnel(port, false).is_err());
+    }
+
+    #[test]
+    fn test_check_tunnel_probe_sees_closed_forward() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        // Accepts and immediately closes, like a forward to an unreachable host
+        let server = thread::spawn(move || drop(listener.accept().unwrap()));
+
+        let err = check_tunnel(port, true).unwrap_err();
+        assert!(err.contains("not reachable"));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_check_tunnel_probe_accepts_greeting() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            std::io::Write::write_all(&mut stream, b"J").unwrap();
+            thread::sleep(Duration::from_millis(200));
+        });
+
+        assert_eq!(check_tunnel(port, true), Ok(()));
+        server.join().unwrap();
+    }
+}
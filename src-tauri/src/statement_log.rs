@@ -0,0 +1,159 @@
+use crate::models::ConnectionParams;
+use crate::paths;
+use crate::pool_manager;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Column name fragments whose bound values must never reach the log file
+const SENSITIVE_COLUMN_PATTERNS: [&str; 3] = ["password", "secret", "token"];
+
+const REDACTED: &str = "<redacted>";
+
+pub fn is_sensitive_column(column: &str) -> bool {
+    let lower = column.to_lowercase();
+    SENSITIVE_COLUMN_PATTERNS
+        .iter()
+        .any(|pattern| lower.contains(pattern))
+}
+
+fn format_binding(column: &str, value: &serde_json::Value) -> String {
+    if is_sensitive_column(column) {
+        return format!("{} = {}", column, REDACTED);
+    }
+    format!("{} = {}", column, value)
+}
+
+/// Formats a single log line: timestamp, statement and (redacted) bound values
+pub fn format_entry(sql: &str, bindings: &[(String, serde_json::Value)]) -> String {
+    let statement = sql.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut line = format!("[{}] {}", chrono::Local::now().to_rfc3339(), statement);
+
+    if !bindings.is_empty() {
+        let values: Vec<String> = bindings
+            .iter()
+            .map(|(column, value)| format_binding(column, value))
+            .collect();
+        line.push_str(&format!(" -- params: {}", values.join(", ")));
+    }
+
+    line
+}
+
+/// One log file per connection target, e.g. `statement_logs/postgres-mydb-1a2b3c4d5e6f.log`.
+/// The digest tells apart databases of the same name on other hosts or users.
+fn get_log_path(params: &ConnectionParams) -> PathBuf {
+    let target = format!(
+        "{}-{}-{}",
+        params.driver,
+        params.database,
+        pool_manager::connection_key_digest(params)
+    );
+    let target: String = target
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    paths::get_app_config_dir()
+        .join("statement_logs")
+        .join(format!("{}.log", target))
+}
+
+/// Appends the statement to the connection log when `log_statements` is enabled.
+/// Logging failures are reported on stderr and never abort the statement itself.
+pub fn log_statement(
+    params: &ConnectionParams,
+    sql: &str,
+    bindings: &[(String, serde_json::Value)],
+) {
+    if !params.log_statements.unwrap_or(false) {
+        return;
+    }
+
+    let path = get_log_path(params);
+    if let Some(dir) = path.parent() {
+        if !dir.exists() {
+            if let Err(e) = fs::create_dir_all(dir) {
+                eprintln!("[Statement Log] Failed to create log directory: {}", e);
+                return;
+            }
+        }
+    }
+
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{}", format_entry(sql, bindings)));
+
+    if let Err(e) = result {
+        eprintln!(
+            "[Statement Log] Failed to write to {}: {}",
+            path.display(),
+            e
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_is_sensitive_column() {
+        assert!(is_sensitive_column("password"));
+        assert!(is_sensitive_column("user_password_hash"));
+        assert!(is_sensitive_column("API_SECRET"));
+        assert!(is_sensitive_column("refreshToken"));
+        assert!(!is_sensitive_column("email"));
+        assert!(!is_sensitive_column("id"));
+    }
+
+    #[test]
+    fn test_format_entry_redacts_sensitive_values() {
+        let bindings = vec![
+            ("email".to_string(), json!("a@b.c")),
+            ("password".to_string(), json!("hunter2")),
+            ("id".to_string(), json!(42)),
+        ];
+        let entry = format_entry("UPDATE users\n  SET email = ?", &bindings);
+
+        assert!(entry.contains("UPDATE users SET email = ?"));
+        assert!(entry.contains("email = \"a@b.c\""));
+        assert!(entry.contains("password = <redacted>"));
+        assert!(entry.contains("id = 42"));
+        assert!(!entry.contains("hunter2"));
+    }
+
+    #[test]
+    fn test_log_path_depends_on_host() {
+        let params = |host: &str| ConnectionParams {
+            driver: "postgres".to_string(),
+            host: Some(host.to_string()),
+            database: "app".to_string(),
+            ..Default::default()
+        };
+        let staging = get_log_path(&params("staging.internal"));
+        let prod = get_log_path(&params("prod.internal"));
+        assert_ne!(staging, prod);
+        assert!(prod
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("postgres-app-"));
+    }
+
+    #[test]
+    fn test_format_entry_without_bindings() {
+        let entry = format_entry("SELECT 1", &[]);
+        assert!(entry.ends_with("] SELECT 1"));
+        assert!(!entry.contains("params"));
+    }
+}
@@ -1,6 +1,10 @@
+use std::collections::HashMap;
 use std::time::Duration;
+use tabularis_lib::drivers::common::split_statements;
 use tabularis_lib::drivers::{mysql, postgres};
-use tabularis_lib::models::ConnectionParams;
+use tabularis_lib::models::{ColumnDefinition, ConnectionParams};
+use tabularis_lib::notifications::NotificationState;
+use tabularis_lib::pool_manager;
 use tokio::time::sleep;
 
 // Helper to construct connection params
@@ -12,6 +16,7 @@ fn get_mysql_params() -> ConnectionParams {
         username: Some("root".to_string()),
         password: Some("password".to_string()),
         database: "testdb".to_string(),
+        schema: None,
         ssh_enabled: None,
         ssh_connection_id: None,
         ssh_host: None,
@@ -20,7 +25,27 @@ fn get_mysql_params() -> ConnectionParams {
         ssh_password: None,
         ssh_key_file: None,
         ssh_key_passphrase: None,
+        ssh_jump_hosts: None,
+        ssh_verify_host_key: None,
+        ssh_local_port: None,
+        ssh_local_port_fallback: None,
+        ssh_keepalive_secs: None,
         save_in_keychain: None,
+        read_only: None,
+        log_statements: None,
+        connection_string: None,
+        extra_params: None,
+        connect_timeout_secs: None,
+        sqlite_busy_timeout_ms: None,
+        sqlite_wal: None,
+        attach: None,
+        timezone: None,
+        max_connections: None,
+        min_connections: None,
+        idle_timeout_secs: None,
+        app_name: None,
+        statement_timeout_secs: None,
+        default_row_limit: None,
     }
 }
 
@@ -32,6 +57,7 @@ fn get_postgres_params() -> ConnectionParams {
         username: Some("postgres".to_string()),
         password: Some("password".to_string()),
         database: "testdb".to_string(),
+        schema: None,
         ssh_enabled: None,
         ssh_connection_id: None,
         ssh_host: None,
@@ -40,7 +66,27 @@ fn get_postgres_params() -> ConnectionParams {
         ssh_password: None,
         ssh_key_file: None,
         ssh_key_passphrase: None,
+        ssh_jump_hosts: None,
+        ssh_verify_host_key: None,
+        ssh_local_port: None,
+        ssh_local_port_fallback: None,
+        ssh_keepalive_secs: None,
         save_in_keychain: None,
+        read_only: None,
+        log_statements: None,
+        connection_string: None,
+        extra_params: None,
+        connect_timeout_secs: None,
+        sqlite_busy_timeout_ms: None,
+        sqlite_wal: None,
+        attach: None,
+        timezone: None,
+        max_connections: None,
+        min_connections: None,
+        idle_timeout_secs: None,
+        app_name: None,
+        statement_timeout_secs: None,
+        default_row_limit: None,
     }
 }
 
@@ -70,7 +116,7 @@ async fn test_mysql_integration_flow() {
         name VARCHAR(100),
         email VARCHAR(100)
     )";
-    let res = mysql::execute_query(&params, create_sql, None, 1).await;
+    let res = mysql::execute_query(&params, create_sql, None, 1, None, None).await;
     assert!(
         res.is_ok(),
         "Failed to create table in MySQL: {:?}",
@@ -78,17 +124,17 @@ async fn test_mysql_integration_flow() {
     );
 
     // 3. Clean table (idempotency)
-    let _ = mysql::execute_query(&params, "TRUNCATE TABLE test_users", None, 1).await;
+    let _ = mysql::execute_query(&params, "TRUNCATE TABLE test_users", None, 1, None, None).await;
 
     // 4. Insert Data
     let insert_sql =
         "INSERT INTO test_users (name, email) VALUES ('Mario Rossi', 'mario@test.com')";
-    let res = mysql::execute_query(&params, insert_sql, None, 1).await;
+    let res = mysql::execute_query(&params, insert_sql, None, 1, None, None).await;
     assert!(res.is_ok(), "Failed to insert data in MySQL");
 
     // 5. Select Data
     let select_sql = "SELECT * FROM test_users WHERE email = 'mario@test.com'";
-    let res = mysql::execute_query(&params, select_sql, None, 1).await;
+    let res = mysql::execute_query(&params, select_sql, None, 1, None, None).await;
     match res {
         Ok(data) => {
             assert_eq!(data.rows.len(), 1, "Expected 1 row");
@@ -108,7 +154,7 @@ async fn test_mysql_integration_flow() {
     }
 
     // 6. Cleanup
-    let _ = mysql::execute_query(&params, "DROP TABLE test_users", None, 1).await;
+    let _ = mysql::execute_query(&params, "DROP TABLE test_users", None, 1, None, None).await;
 }
 
 #[tokio::test]
@@ -137,7 +183,7 @@ async fn test_postgres_integration_flow() {
         name TEXT,
         email TEXT
     )";
-    let res = postgres::execute_query(&params, create_sql, None, 1).await;
+    let res = postgres::execute_query(&params, create_sql, None, 1, None, None).await;
     assert!(
         res.is_ok(),
         "Failed to create table in Postgres: {:?}",
@@ -145,17 +191,18 @@ async fn test_postgres_integration_flow() {
     );
 
     // 3. Clean table
-    let _ = postgres::execute_query(&params, "TRUNCATE TABLE test_users", None, 1).await;
+    let _ =
+        postgres::execute_query(&params, "TRUNCATE TABLE test_users", None, 1, None, None).await;
 
     // 4. Insert Data
     let insert_sql =
         "INSERT INTO test_users (name, email) VALUES ('Luigi Verdi', 'luigi@test.com')";
-    let res = postgres::execute_query(&params, insert_sql, None, 1).await;
+    let res = postgres::execute_query(&params, insert_sql, None, 1, None, None).await;
     assert!(res.is_ok(), "Failed to insert data in Postgres");
 
     // 5. Select Data
     let select_sql = "SELECT * FROM test_users WHERE email = 'luigi@test.com'";
-    let res = postgres::execute_query(&params, select_sql, None, 1).await;
+    let res = postgres::execute_query(&params, select_sql, None, 1, None, None).await;
     match res {
         Ok(data) => {
             assert_eq!(data.rows.len(), 1, "Expected 1 row");
@@ -171,5 +218,1213 @@ async fn test_postgres_integration_flow() {
     }
 
     // 6. Cleanup
-    let _ = postgres::execute_query(&params, "DROP TABLE test_users", None, 1).await;
+    let _ = postgres::execute_query(&params, "DROP TABLE test_users", None, 1, None, None).await;
+}
+
+#[tokio::test]
+#[ignore] // Ignored by default
+async fn test_postgres_numeric_precision() {
+    let params = get_postgres_params();
+
+    if postgres::get_tables(&params).await.is_err() {
+        eprintln!("SKIPPING Postgres Test: Could not connect to Docker container on port 54320");
+        return;
+    }
+
+    let create_sql =
+        "CREATE TABLE IF NOT EXISTS test_amounts (id SERIAL PRIMARY KEY, amount NUMERIC(18,4))";
+    let res = postgres::execute_query(&params, create_sql, None, 1, None, None).await;
+    assert!(res.is_ok(), "Failed to create table: {:?}", res.err());
+    let _ =
+        postgres::execute_query(&params, "TRUNCATE TABLE test_amounts", None, 1, None, None).await;
+
+    let insert_sql = "INSERT INTO test_amounts (amount) VALUES (99999999999999.9999)";
+    let res = postgres::execute_query(&params, insert_sql, None, 1, None, None).await;
+    assert!(res.is_ok(), "Failed to insert data in Postgres");
+
+    let res = postgres::execute_query(
+        &params,
+        "SELECT amount FROM test_amounts",
+        None,
+        1,
+        None,
+        None,
+    )
+    .await
+    .expect("Select failed");
+    assert_eq!(res.rows[0][0].as_str(), Some("99999999999999.9999"));
+
+    let _ = postgres::execute_query(&params, "DROP TABLE test_amounts", None, 1, None, None).await;
+}
+
+fn upsert_data(id: i64, name: &str) -> HashMap<String, serde_json::Value> {
+    [
+        ("id".to_string(), serde_json::Value::from(id)),
+        ("name".to_string(), serde_json::Value::from(name)),
+    ]
+    .into_iter()
+    .collect()
+}
+
+#[tokio::test]
+#[ignore] // Ignored by default
+async fn test_mysql_upsert_record() {
+    let params = get_mysql_params();
+
+    if mysql::get_tables(&params).await.is_err() {
+        eprintln!("SKIPPING MySQL Test: Could not connect to Docker container on port 33060");
+        return;
+    }
+
+    let create_sql =
+        "CREATE TABLE IF NOT EXISTS test_upsert (id INT PRIMARY KEY, name VARCHAR(100))";
+    let res = mysql::execute_query(&params, create_sql, None, 1, None, None).await;
+    assert!(res.is_ok(), "Failed to create table: {:?}", res.err());
+    let _ = mysql::execute_query(&params, "TRUNCATE TABLE test_upsert", None, 1, None, None).await;
+
+    let conflict = vec!["id".to_string()];
+    let inserted = mysql::upsert_record(&params, "test_upsert", &conflict, upsert_data(1, "first"))
+        .await
+        .expect("First upsert failed");
+    assert_eq!(inserted, 1);
+    let updated = mysql::upsert_record(&params, "test_upsert", &conflict, upsert_data(1, "second"))
+        .await
+        .expect("Second upsert failed");
+    assert_eq!(updated, 2);
+
+    let res = mysql::execute_query(&params, "SELECT name FROM test_upsert", None, 1, None, None)
+        .await
+        .expect("Select failed");
+    assert_eq!(res.rows.len(), 1);
+    assert_eq!(res.rows[0][0].as_str(), Some("second"));
+
+    let _ = mysql::execute_query(&params, "DROP TABLE test_upsert", None, 1, None, None).await;
+}
+
+#[tokio::test]
+#[ignore] // Ignored by default
+async fn test_postgres_upsert_record() {
+    let params = get_postgres_params();
+
+    if postgres::get_tables(&params).await.is_err() {
+        eprintln!("SKIPPING Postgres Test: Could not connect to Docker container on port 54320");
+        return;
+    }
+
+    let create_sql = "CREATE TABLE IF NOT EXISTS test_upsert (id INT PRIMARY KEY, name TEXT)";
+    let res = postgres::execute_query(&params, create_sql, None, 1, None, None).await;
+    assert!(res.is_ok(), "Failed to create table: {:?}", res.err());
+    let _ =
+        postgres::execute_query(&params, "TRUNCATE TABLE test_upsert", None, 1, None, None).await;
+
+    let conflict = vec!["id".to_string()];
+    for name in ["first", "second"] {
+        let affected =
+            postgres::upsert_record(&params, "test_upsert", &conflict, upsert_data(1, name))
+                .await
+                .expect("Upsert failed");
+        assert_eq!(affected, 1);
+    }
+
+    let res = postgres::execute_query(&params, "SELECT name FROM test_upsert", None, 1, None, None)
+        .await
+        .expect("Select failed");
+    assert_eq!(res.rows.len(), 1);
+    assert_eq!(res.rows[0][0].as_str(), Some("second"));
+
+    let _ = postgres::execute_query(&params, "DROP TABLE test_upsert", None, 1, None, None).await;
+}
+
+#[tokio::test]
+#[ignore] // Ignored by default
+async fn test_postgres_uuid_primary_key() {
+    let params = get_postgres_params();
+
+    if postgres::get_tables(&params).await.is_err() {
+        eprintln!("SKIPPING Postgres Test: Could not connect to Docker container on port 54320");
+        return;
+    }
+
+    let create_sql = "CREATE TABLE IF NOT EXISTS test_uuid (id UUID PRIMARY KEY, name TEXT)";
+    let res = postgres::execute_query(&params, create_sql, None, 1, None, None).await;
+    assert!(res.is_ok(), "Failed to create table: {:?}", res.err());
+    let _ = postgres::execute_query(&params, "TRUNCATE TABLE test_uuid", None, 1, None, None).await;
+
+    let id = "0b6f3c2e-7d1a-4e55-9a8b-3f2c1d0e9a77";
+    let insert_sql = format!("INSERT INTO test_uuid VALUES ('{}', 'before')", id);
+    postgres::execute_query(&params, &insert_sql, None, 1, None, None)
+        .await
+        .expect("Insert failed");
+
+    let res = postgres::execute_query(&params, "SELECT id FROM test_uuid", None, 1, None, None)
+        .await
+        .expect("Select failed");
+    assert_eq!(res.rows[0][0].as_str(), Some(id));
+
+    let pk = HashMap::from([("id".to_string(), serde_json::Value::from(id))]);
+    let updated = postgres::update_record(
+        &params,
+        "test_uuid",
+        &pk,
+        "name",
+        serde_json::Value::from("after"),
+    )
+    .await
+    .expect("Update failed");
+    assert_eq!(updated, 1);
+
+    let deleted = postgres::delete_record(&params, "test_uuid", &pk)
+        .await
+        .expect("Delete failed");
+    assert_eq!(deleted, 1);
+
+    let _ = postgres::execute_query(&params, "DROP TABLE test_uuid", None, 1, None, None).await;
+}
+
+#[tokio::test]
+#[ignore] // Ignored by default
+async fn test_postgres_jsonb_roundtrip() {
+    let params = get_postgres_params();
+
+    if postgres::get_tables(&params).await.is_err() {
+        eprintln!("SKIPPING Postgres Test: Could not connect to Docker container on port 54320");
+        return;
+    }
+
+    let create_sql = "CREATE TABLE IF NOT EXISTS test_jsonb (id INT PRIMARY KEY, data JSONB)";
+    let res = postgres::execute_query(&params, create_sql, None, 1, None, None).await;
+    assert!(res.is_ok(), "Failed to create table: {:?}", res.err());
+    let _ =
+        postgres::execute_query(&params, "TRUNCATE TABLE test_jsonb", None, 1, None, None).await;
+
+    let data = HashMap::from([
+        ("id".to_string(), serde_json::Value::from(1)),
+        ("data".to_string(), serde_json::json!({"a": [1, 2]})),
+    ]);
+    postgres::insert_record(&params, "test_jsonb", data)
+        .await
+        .expect("Insert failed");
+
+    let res = postgres::execute_query(
+        &params,
+        "SELECT jsonb_typeof(data), data FROM test_jsonb",
+        None,
+        1,
+        None,
+        None,
+    )
+    .await
+    .expect("Select failed");
+    assert_eq!(res.rows[0][0].as_str(), Some("object"));
+    assert_eq!(res.rows[0][1], serde_json::json!({"a": [1, 2]}));
+
+    // Edited cells arrive as JSON text and are stored as documents, not strings
+    let pk = HashMap::from([("id".to_string(), serde_json::Value::from(1))]);
+    postgres::update_record(
+        &params,
+        "test_jsonb",
+        &pk,
+        "data",
+        serde_json::Value::from(r#"{"b": true}"#),
+    )
+    .await
+    .expect("Update failed");
+
+    let res = postgres::execute_query(
+        &params,
+        "SELECT jsonb_typeof(data) FROM test_jsonb",
+        None,
+        1,
+        None,
+        None,
+    )
+    .await
+    .expect("Select failed");
+    assert_eq!(res.rows[0][0].as_str(), Some("object"));
+
+    let _ = postgres::execute_query(&params, "DROP TABLE test_jsonb", None, 1, None, None).await;
+}
+
+#[tokio::test]
+#[ignore] // Ignored by default
+async fn test_postgres_array_columns() {
+    let params = get_postgres_params();
+
+    if postgres::get_tables(&params).await.is_err() {
+        eprintln!("SKIPPING Postgres Test: Could not connect to Docker container on port 54320");
+        return;
+    }
+
+    let create_sql =
+        "CREATE TABLE IF NOT EXISTS test_arrays (id INT PRIMARY KEY, tags TEXT[], scores INT[], big BIGINT[])";
+    let res = postgres::execute_query(&params, create_sql, None, 1, None, None).await;
+    assert!(res.is_ok(), "Failed to create table: {:?}", res.err());
+    let _ =
+        postgres::execute_query(&params, "TRUNCATE TABLE test_arrays", None, 1, None, None).await;
+
+    let data = HashMap::from([
+        ("id".to_string(), serde_json::Value::from(1)),
+        ("tags".to_string(), serde_json::json!(["a", null, "b"])),
+        ("scores".to_string(), serde_json::json!([1, 2, 3])),
+        ("big".to_string(), serde_json::json!([9007199254740993i64])),
+    ]);
+    postgres::insert_record(&params, "test_arrays", data)
+        .await
+        .expect("Insert failed");
+
+    let pk = HashMap::from([("id".to_string(), serde_json::Value::from(1))]);
+    postgres::update_record(
+        &params,
+        "test_arrays",
+        &pk,
+        "scores",
+        serde_json::json!([4, 5]),
+    )
+    .await
+    .expect("Update failed");
+
+    let res = postgres::execute_query(
+        &params,
+        "SELECT tags, scores, big, ARRAY[now()] FROM test_arrays",
+        None,
+        1,
+        None,
+        None,
+    )
+    .await
+    .expect("Select failed");
+    assert_eq!(res.rows[0][0], serde_json::json!(["a", null, "b"]));
+    assert_eq!(res.rows[0][1], serde_json::json!([4, 5]));
+    assert_eq!(res.rows[0][2], serde_json::json!([9007199254740993i64]));
+    // Unsupported element types come back as NULL instead of failing the query
+    assert_eq!(res.rows[0][3], serde_json::Value::Null);
+
+    let _ = postgres::execute_query(&params, "DROP TABLE test_arrays", None, 1, None, None).await;
+}
+
+#[tokio::test]
+#[ignore] // Ignored by default
+async fn test_postgres_timestamptz_roundtrip() {
+    let params = ConnectionParams {
+        timezone: Some("Asia/Tokyo".to_string()),
+        ..get_postgres_params()
+    };
+
+    if postgres::get_tables(&params).await.is_err() {
+        eprintln!("SKIPPING Postgres Test: Could not connect to Docker container on port 54320");
+        return;
+    }
+
+    let create_sql = "CREATE TABLE IF NOT EXISTS test_timestamps (id INT PRIMARY KEY, at TIMESTAMPTZ, local_at TIMESTAMP)";
+    let res = postgres::execute_query(&params, create_sql, None, 1, None, None).await;
+    assert!(res.is_ok(), "Failed to create table: {:?}", res.err());
+    let _ = postgres::execute_query(
+        &params,
+        "TRUNCATE TABLE test_timestamps",
+        None,
+        1,
+        None,
+        None,
+    )
+    .await;
+
+    let data = HashMap::from([
+        ("id".to_string(), serde_json::Value::from(1)),
+        (
+            "at".to_string(),
+            serde_json::Value::from("2024-03-01T12:00:00+02:00"),
+        ),
+        (
+            "local_at".to_string(),
+            serde_json::Value::from("2024-03-01 12:00:00"),
+        ),
+    ]);
+    postgres::insert_record(&params, "test_timestamps", data)
+        .await
+        .expect("Insert failed");
+
+    let res = postgres::execute_query(
+        &params,
+        "SELECT at, local_at, at::text FROM test_timestamps",
+        None,
+        1,
+        None,
+        None,
+    )
+    .await
+    .expect("Select failed");
+    // Same instant, with an explicit offset instead of a bare UTC-shifted time
+    let at = chrono::DateTime::parse_from_rfc3339(res.rows[0][0].as_str().unwrap()).unwrap();
+    assert_eq!(
+        at,
+        chrono::DateTime::parse_from_rfc3339("2024-03-01T12:00:00+02:00").unwrap()
+    );
+    assert_eq!(res.rows[0][1].as_str(), Some("2024-03-01 12:00:00"));
+    // The session TimeZone applies to server-side formatting
+    assert_eq!(res.rows[0][2].as_str(), Some("2024-03-01 19:00:00+09"));
+
+    // The returned value can be written back unchanged
+    let pk = HashMap::from([("id".to_string(), serde_json::Value::from(1))]);
+    postgres::update_record(
+        &params,
+        "test_timestamps",
+        &pk,
+        "at",
+        res.rows[0][0].clone(),
+    )
+    .await
+    .expect("Update failed");
+
+    let _ =
+        postgres::execute_query(&params, "DROP TABLE test_timestamps", None, 1, None, None).await;
+}
+
+fn status_column() -> ColumnDefinition {
+    ColumnDefinition {
+        name: "status".to_string(),
+        data_type: "VARCHAR(20)".to_string(),
+        is_nullable: false,
+        default_value: Some("'new'".to_string()),
+    }
+}
+
+#[tokio::test]
+#[ignore] // Ignored by default
+async fn test_mysql_alter_columns() {
+    let params = get_mysql_params();
+
+    if mysql::get_tables(&params).await.is_err() {
+        eprintln!("SKIPPING MySQL Test: Could not connect to Docker container on port 33060");
+        return;
+    }
+
+    let _ = mysql::execute_query(
+        &params,
+        "DROP TABLE IF EXISTS test_alter",
+        None,
+        1,
+        None,
+        None,
+    )
+    .await;
+    mysql::execute_query(
+        &params,
+        "CREATE TABLE test_alter (id INT PRIMARY KEY)",
+        None,
+        1,
+        None,
+        None,
+    )
+    .await
+    .expect("Failed to create table");
+
+    mysql::add_column(&params, "test_alter", &status_column())
+        .await
+        .expect("Add column failed");
+    mysql::rename_column(&params, "test_alter", "status", "state")
+        .await
+        .expect("Rename column failed");
+
+    let columns = mysql::get_columns(&params, "test_alter").await.unwrap();
+    let state = columns
+        .iter()
+        .find(|c| c.name == "state")
+        .expect("Renamed column missing");
+    // CHANGE COLUMN keeps the original definition
+    assert!(!state.is_nullable);
+
+    mysql::drop_column(&params, "test_alter", "state")
+        .await
+        .expect("Drop column failed");
+    assert_eq!(
+        mysql::get_columns(&params, "test_alter")
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+
+    let _ = mysql::execute_query(&params, "DROP TABLE test_alter", None, 1, None, None).await;
+}
+
+#[tokio::test]
+#[ignore] // Ignored by default
+async fn test_postgres_alter_columns() {
+    let params = get_postgres_params();
+
+    if postgres::get_tables(&params).await.is_err() {
+        eprintln!("SKIPPING Postgres Test: Could not connect to Docker container on port 54320");
+        return;
+    }
+
+    let _ = postgres::execute_query(
+        &params,
+        "DROP TABLE IF EXISTS test_alter",
+        None,
+        1,
+        None,
+        None,
+    )
+    .await;
+    postgres::execute_query(
+        &params,
+        "CREATE TABLE test_alter (id INT PRIMARY KEY)",
+        None,
+        1,
+        None,
+        None,
+    )
+    .await
+    .expect("Failed to create table");
+
+    postgres::add_column(&params, "test_alter", &status_column())
+        .await
+        .expect("Add column failed");
+    postgres::rename_column(&params, "test_alter", "status", "state")
+        .await
+        .expect("Rename column failed");
+
+    let columns = postgres::get_columns(&params, "test_alter").await.unwrap();
+    assert!(columns.iter().any(|c| c.name == "state" && !c.is_nullable));
+
+    postgres::drop_column(&params, "test_alter", "state")
+        .await
+        .expect("Drop column failed");
+    assert_eq!(
+        postgres::get_columns(&params, "test_alter")
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+
+    let _ = postgres::execute_query(&params, "DROP TABLE test_alter", None, 1, None, None).await;
+}
+
+#[tokio::test]
+#[ignore] // Ignored by default
+async fn test_mysql_update_record_null_and_empty_string() {
+    let params = get_mysql_params();
+
+    if mysql::get_tables(&params).await.is_err() {
+        eprintln!("SKIPPING MySQL Test: Could not connect to Docker container on port 33060");
+        return;
+    }
+
+    let _ = mysql::execute_query(
+        &params,
+        "DROP TABLE IF EXISTS test_nulls",
+        None,
+        1,
+        None,
+        None,
+    )
+    .await;
+    mysql::execute_query(
+        &params,
+        "CREATE TABLE test_nulls (id INT PRIMARY KEY, body VARCHAR(100) NULL)",
+        None,
+        1,
+        None,
+        None,
+    )
+    .await
+    .expect("Failed to create table");
+    mysql::execute_query(
+        &params,
+        "INSERT INTO test_nulls (id, body) VALUES (1, NULL)",
+        None,
+        1,
+        None,
+        None,
+    )
+    .await
+    .expect("Insert failed");
+
+    let pk = HashMap::from([("id".to_string(), serde_json::Value::from(1))]);
+    let select = "SELECT body FROM test_nulls WHERE id = 1";
+
+    mysql::update_record(
+        &params,
+        "test_nulls",
+        &pk,
+        "body",
+        serde_json::Value::from(""),
+    )
+    .await
+    .expect("NULL to empty string failed");
+    let res = mysql::execute_query(&params, select, None, 1, None, None)
+        .await
+        .unwrap();
+    assert_eq!(res.rows[0][0].as_str(), Some(""));
+
+    mysql::update_record(&params, "test_nulls", &pk, "body", serde_json::Value::Null)
+        .await
+        .expect("Empty string to NULL failed");
+    let res = mysql::execute_query(&params, select, None, 1, None, None)
+        .await
+        .unwrap();
+    assert!(res.rows[0][0].is_null());
+
+    let _ = mysql::execute_query(&params, "DROP TABLE test_nulls", None, 1, None, None).await;
+}
+
+#[tokio::test]
+#[ignore] // Ignored by default
+async fn test_postgres_update_record_null_and_empty_string() {
+    let params = get_postgres_params();
+
+    if postgres::get_tables(&params).await.is_err() {
+        eprintln!("SKIPPING Postgres Test: Could not connect to Docker container on port 54320");
+        return;
+    }
+
+    let _ = postgres::execute_query(
+        &params,
+        "DROP TABLE IF EXISTS test_nulls",
+        None,
+        1,
+        None,
+        None,
+    )
+    .await;
+    postgres::execute_query(
+        &params,
+        "CREATE TABLE test_nulls (id INT PRIMARY KEY, body TEXT NULL)",
+        None,
+        1,
+        None,
+        None,
+    )
+    .await
+    .expect("Failed to create table");
+    postgres::execute_query(
+        &params,
+        "INSERT INTO test_nulls (id, body) VALUES (1, NULL)",
+        None,
+        1,
+        None,
+        None,
+    )
+    .await
+    .expect("Insert failed");
+
+    let pk = HashMap::from([("id".to_string(), serde_json::Value::from(1))]);
+    let select = "SELECT body FROM test_nulls WHERE id = 1";
+
+    postgres::update_record(
+        &params,
+        "test_nulls",
+        &pk,
+        "body",
+        serde_json::Value::from(""),
+    )
+    .await
+    .expect("NULL to empty string failed");
+    let res = postgres::execute_query(&params, select, None, 1, None, None)
+        .await
+        .unwrap();
+    assert_eq!(res.rows[0][0].as_str(), Some(""));
+
+    postgres::update_record(&params, "test_nulls", &pk, "body", serde_json::Value::Null)
+        .await
+        .expect("Empty string to NULL failed");
+    let res = postgres::execute_query(&params, select, None, 1, None, None)
+        .await
+        .unwrap();
+    assert!(res.rows[0][0].is_null());
+
+    let _ = postgres::execute_query(&params, "DROP TABLE test_nulls", None, 1, None, None).await;
+}
+
+#[tokio::test]
+#[ignore] // Ignored by default
+async fn test_mysql_unsigned_bigint_primary_key() {
+    let params = get_mysql_params();
+
+    if mysql::get_tables(&params).await.is_err() {
+        eprintln!("SKIPPING MySQL Test: Could not connect to Docker container on port 33060");
+        return;
+    }
+
+    let _ = mysql::execute_query(
+        &params,
+        "DROP TABLE IF EXISTS test_unsigned",
+        None,
+        1,
+        None,
+        None,
+    )
+    .await;
+    mysql::execute_query(
+        &params,
+        "CREATE TABLE test_unsigned (id BIGINT UNSIGNED PRIMARY KEY, name VARCHAR(100))",
+        None,
+        1,
+        None,
+        None,
+    )
+    .await
+    .expect("Failed to create table");
+
+    // Above i64::MAX, so it only survives the round trip as a u64
+    let big_id = serde_json::Value::from(u64::MAX - 1);
+    let mut data = HashMap::new();
+    data.insert("id".to_string(), big_id.clone());
+    data.insert("name".to_string(), serde_json::Value::from("big"));
+    mysql::insert_record(&params, "test_unsigned", data)
+        .await
+        .expect("Insert failed");
+
+    let res = mysql::execute_query(&params, "SELECT id FROM test_unsigned", None, 1, None, None)
+        .await
+        .expect("Select failed");
+    assert_eq!(res.rows[0][0], big_id);
+
+    let pk = HashMap::from([("id".to_string(), big_id)]);
+    let updated = mysql::update_record(
+        &params,
+        "test_unsigned",
+        &pk,
+        "name",
+        serde_json::Value::from("bigger"),
+    )
+    .await
+    .expect("Update failed");
+    assert_eq!(updated, 1);
+    let deleted = mysql::delete_record(&params, "test_unsigned", &pk)
+        .await
+        .expect("Delete failed");
+    assert_eq!(deleted, 1);
+
+    let _ = mysql::execute_query(&params, "DROP TABLE test_unsigned", None, 1, None, None).await;
+}
+
+#[tokio::test]
+#[ignore] // Ignored by default
+async fn test_mysql_record_operations_with_backticks_in_identifiers() {
+    let params = get_mysql_params();
+
+    if mysql::get_tables(&params).await.is_err() {
+        eprintln!("SKIPPING MySQL Test: Could not connect to Docker container on port 33060");
+        return;
+    }
+
+    let _ = mysql::execute_query(
+        &params,
+        "DROP TABLE IF EXISTS `foo``bar`",
+        None,
+        1,
+        None,
+        None,
+    )
+    .await;
+    mysql::execute_query(
+        &params,
+        "CREATE TABLE `foo``bar` (`id``x` INT PRIMARY KEY, `a\"b` VARCHAR(100))",
+        None,
+        1,
+        None,
+        None,
+    )
+    .await
+    .expect("Failed to create table");
+
+    let table = "foo`bar";
+    let data = HashMap::from([
+        ("id`x".to_string(), serde_json::json!(1)),
+        ("a\"b".to_string(), serde_json::json!("before")),
+    ]);
+    mysql::insert_record(&params, table, data)
+        .await
+        .expect("Insert failed");
+
+    let pk = HashMap::from([("id`x".to_string(), serde_json::json!(1))]);
+    let updated = mysql::update_record(&params, table, &pk, "a\"b", serde_json::json!("after"))
+        .await
+        .expect("Update failed");
+    assert_eq!(updated, 1);
+    let deleted = mysql::delete_record(&params, table, &pk)
+        .await
+        .expect("Delete failed");
+    assert_eq!(deleted, 1);
+
+    let _ = mysql::execute_query(&params, "DROP TABLE `foo``bar`", None, 1, None, None).await;
+}
+
+#[tokio::test]
+#[ignore] // Ignored by default
+async fn test_postgres_record_operations_with_quotes_in_identifiers() {
+    let params = get_postgres_params();
+
+    if postgres::get_tables(&params).await.is_err() {
+        eprintln!("SKIPPING Postgres Test: Could not connect to Docker container on port 54320");
+        return;
+    }
+
+    let _ = postgres::execute_query(
+        &params,
+        "DROP TABLE IF EXISTS \"a\"\"b\"",
+        None,
+        1,
+        None,
+        None,
+    )
+    .await;
+    postgres::execute_query(
+        &params,
+        "CREATE TABLE \"a\"\"b\" (\"id\"\"x\" INT PRIMARY KEY, \"foo`bar\" TEXT)",
+        None,
+        1,
+        None,
+        None,
+    )
+    .await
+    .expect("Failed to create table");
+
+    let table = "a\"b";
+    let data = HashMap::from([
+        ("id\"x".to_string(), serde_json::json!(1)),
+        ("foo`bar".to_string(), serde_json::json!("before")),
+    ]);
+    postgres::insert_record(&params, table, data)
+        .await
+        .expect("Insert failed");
+
+    let pk = HashMap::from([("id\"x".to_string(), serde_json::json!(1))]);
+    let updated =
+        postgres::update_record(&params, table, &pk, "foo`bar", serde_json::json!("after"))
+            .await
+            .expect("Update failed");
+    assert_eq!(updated, 1);
+    let ddl = postgres::get_table_ddl(&params, table).await.unwrap();
+    assert!(ddl.contains("\"id\"\"x\" "));
+    let deleted = postgres::delete_record(&params, table, &pk)
+        .await
+        .expect("Delete failed");
+    assert_eq!(deleted, 1);
+
+    let _ = postgres::execute_query(&params, "DROP TABLE \"a\"\"b\"", None, 1, None, None).await;
+}
+
+#[tokio::test]
+#[ignore] // Ignored by default; runs against MySQL or MariaDB on port 33060
+async fn test_mysql_catalog_on_either_flavor() {
+    let params = get_mysql_params();
+
+    let Ok(version) = mysql::server_version(&params).await else {
+        eprintln!("SKIPPING MySQL Test: Could not connect to Docker container on port 33060");
+        return;
+    };
+    println!("Testing against {:?}", version);
+
+    let _ = mysql::execute_query(
+        &params,
+        "DROP TABLE IF EXISTS test_flavor",
+        None,
+        1,
+        None,
+        None,
+    )
+    .await;
+    mysql::execute_query(
+        &params,
+        "CREATE TABLE test_flavor (id INT AUTO_INCREMENT PRIMARY KEY, \
+         updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP, \
+         name VARCHAR(50) NOT NULL)",
+        None,
+        1,
+        None,
+        None,
+    )
+    .await
+    .expect("Failed to create table");
+
+    let columns = mysql::get_columns(&params, "test_flavor").await.unwrap();
+    let flags: Vec<_> = columns
+        .iter()
+        .map(|c| (c.name.as_str(), c.is_pk, c.is_auto_increment))
+        .collect();
+    assert_eq!(
+        flags,
+        vec![
+            ("id", true, true),
+            ("updated_at", false, false),
+            ("name", false, false)
+        ]
+    );
+
+    mysql::rename_column(&params, "test_flavor", "name", "title")
+        .await
+        .expect("Rename failed");
+    let columns = mysql::get_columns(&params, "test_flavor").await.unwrap();
+    assert_eq!(columns[2].name, "title");
+    assert!(!columns[2].is_nullable);
+
+    let _ = mysql::execute_query(&params, "DROP TABLE test_flavor", None, 1, None, None).await;
+}
+
+#[tokio::test]
+#[ignore] // Ignored by default
+async fn test_postgres_table_ddl_recreates_table() {
+    let params = get_postgres_params();
+
+    if postgres::get_tables(&params).await.is_err() {
+        eprintln!("SKIPPING Postgres Test: Could not connect to Docker container on port 54320");
+        return;
+    }
+
+    let setup = [
+        "DROP TABLE IF EXISTS ddl_items",
+        "DROP TABLE IF EXISTS ddl_parents",
+        "CREATE TABLE ddl_parents (id integer PRIMARY KEY)",
+        "CREATE TABLE ddl_items (\
+         id serial PRIMARY KEY, \
+         parent_id integer REFERENCES ddl_parents (id) ON DELETE CASCADE, \
+         sku varchar(20) NOT NULL UNIQUE, \
+         price numeric(10,2) DEFAULT 0 CHECK (price >= 0), \
+         tags text[])",
+        "CREATE INDEX ddl_items_tags_idx ON ddl_items (tags)",
+    ];
+    postgres::execute_script(&params, &setup)
+        .await
+        .expect("Failed to create tables");
+
+    let ddl = postgres::get_table_ddl(&params, "ddl_items").await.unwrap();
+    assert!(ddl.contains("\"id\" serial NOT NULL"), "{}", ddl);
+    assert!(ddl.contains("\"sku\" character varying(20) NOT NULL"));
+    assert!(ddl.contains("\"price\" numeric(10,2) DEFAULT 0"));
+    assert!(ddl.contains("\"tags\" text[]"));
+    assert!(ddl.contains("PRIMARY KEY (id)"));
+    assert!(ddl.contains("UNIQUE (sku)"));
+    assert!(ddl.contains("CHECK ((price >= (0)::numeric))"));
+    assert!(ddl.contains("REFERENCES ddl_parents(id) ON DELETE CASCADE"));
+    assert!(ddl.contains("CREATE INDEX ddl_items_tags_idx ON public.ddl_items"));
+
+    // Replaying the DDL recreates the same table
+    let mut replay = vec!["DROP TABLE ddl_items"];
+    replay.extend(split_statements(&ddl));
+    postgres::execute_script(&params, &replay)
+        .await
+        .expect("Failed to replay DDL");
+    assert_eq!(
+        postgres::get_table_ddl(&params, "ddl_items").await.unwrap(),
+        ddl
+    );
+
+    let cleanup = ["DROP TABLE ddl_items", "DROP TABLE ddl_parents"];
+    let _ = postgres::execute_script(&params, &cleanup).await;
+}
+
+#[tokio::test]
+#[ignore] // Ignored by default
+async fn test_postgres_routines() {
+    let params = get_postgres_params();
+
+    if postgres::get_tables(&params).await.is_err() {
+        eprintln!("SKIPPING Postgres Test: Could not connect to Docker container on port 54320");
+        return;
+    }
+
+    let setup = [
+        "DROP FUNCTION IF EXISTS routine_double(integer)",
+        "DROP PROCEDURE IF EXISTS routine_noop()",
+        "CREATE FUNCTION routine_double(x integer) RETURNS integer LANGUAGE sql AS $$ SELECT x * 2 $$",
+        "CREATE PROCEDURE routine_noop() LANGUAGE plpgsql AS $$ BEGIN END $$",
+    ];
+    postgres::execute_script(&params, &setup)
+        .await
+        .expect("Failed to create routines");
+
+    let routines = postgres::get_routines(&params, None).await.unwrap();
+    let double = routines
+        .iter()
+        .find(|r| r.name == "routine_double")
+        .expect("function listed");
+    assert_eq!(double.routine_type, "FUNCTION");
+    assert_eq!(double.return_type.as_deref(), Some("integer"));
+    let noop = routines
+        .iter()
+        .find(|r| r.name == "routine_noop")
+        .expect("procedure listed");
+    assert_eq!(noop.routine_type, "PROCEDURE");
+    assert_eq!(noop.return_type, None);
+
+    let definition = postgres::get_routine_definition(&params, None, "routine_double")
+        .await
+        .unwrap();
+    assert!(definition.contains("CREATE OR REPLACE FUNCTION public.routine_double(x integer)"));
+    assert!(
+        postgres::get_routine_definition(&params, None, "routine_missing")
+            .await
+            .is_err()
+    );
+
+    let cleanup = [
+        "DROP FUNCTION routine_double(integer)",
+        "DROP PROCEDURE routine_noop()",
+    ];
+    let _ = postgres::execute_script(&params, &cleanup).await;
+}
+
+#[tokio::test]
+#[ignore] // Ignored by default
+async fn test_mysql_call_procedure_with_out_parameters() {
+    let params = get_mysql_params();
+
+    if mysql::get_tables(&params).await.is_err() {
+        eprintln!("SKIPPING MySQL Test: Could not connect to Docker container on port 33060");
+        return;
+    }
+
+    // CREATE PROCEDURE is not allowed as a prepared statement, so it goes as plain text
+    let pool = pool_manager::get_mysql_pool(&params).await.unwrap();
+    sqlx::raw_sql(
+        "DROP PROCEDURE IF EXISTS test_scale; \
+         CREATE PROCEDURE test_scale(IN factor INT, INOUT total INT, OUT label VARCHAR(20)) \
+         BEGIN SET total = total * factor; SET label = 'scaled'; SELECT factor AS f; END",
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create procedure");
+
+    let called = mysql::call_procedure(
+        &params,
+        "test_scale",
+        &[serde_json::json!(3), serde_json::json!(5)],
+    )
+    .await
+    .unwrap();
+    assert_eq!(called.result.columns, vec!["f"]);
+    assert_eq!(called.result.rows, vec![vec![serde_json::json!(3)]]);
+    assert_eq!(called.out_params["total"], serde_json::json!(15));
+    assert_eq!(called.out_params["label"], serde_json::json!("scaled"));
+
+    let err = mysql::call_procedure(&params, "test_scale", &[serde_json::json!(3)])
+        .await
+        .unwrap_err();
+    assert!(err.contains("takes 2 arguments"));
+
+    let _ = sqlx::raw_sql("DROP PROCEDURE test_scale")
+        .execute(&pool)
+        .await;
+}
+
+#[tokio::test]
+#[ignore] // Ignored by default
+async fn test_mysql_call_returns_every_result_set() {
+    let params = get_mysql_params();
+
+    if mysql::get_tables(&params).await.is_err() {
+        eprintln!("SKIPPING MySQL Test: Could not connect to Docker container on port 33060");
+        return;
+    }
+
+    let pool = pool_manager::get_mysql_pool(&params).await.unwrap();
+    sqlx::raw_sql(
+        "DROP PROCEDURE IF EXISTS test_two_sets; \
+         CREATE PROCEDURE test_two_sets() \
+         BEGIN SELECT 1 AS a; SELECT 'x' AS b UNION ALL SELECT 'y'; END",
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create procedure");
+
+    let sets = mysql::execute_query_sets(&params, "CALL test_two_sets()")
+        .await
+        .unwrap();
+    let with_rows: Vec<_> = sets.iter().filter(|s| !s.columns.is_empty()).collect();
+    assert_eq!(with_rows.len(), 2);
+    assert_eq!(with_rows[0].columns, vec!["a"]);
+    assert_eq!(with_rows[0].rows, vec![vec![serde_json::json!(1)]]);
+    assert_eq!(with_rows[1].columns, vec!["b"]);
+    assert_eq!(
+        with_rows[1].rows,
+        vec![vec![serde_json::json!("x")], vec![serde_json::json!("y")]]
+    );
+
+    // Callers expecting one result get the first set
+    let first = mysql::execute_query(&params, "CALL test_two_sets()", None, 1, None, None)
+        .await
+        .unwrap();
+    assert_eq!(first.columns, vec!["a"]);
+    assert_eq!(first.row_count, Some(1));
+
+    // Stacked statements come back one set each
+    let sets = mysql::execute_query_sets(&params, "SELECT 1 AS one; SELECT 2 AS two")
+        .await
+        .unwrap();
+    assert_eq!(sets.len(), 2);
+    assert_eq!(sets[1].columns, vec!["two"]);
+
+    let _ = sqlx::raw_sql("DROP PROCEDURE test_two_sets")
+        .execute(&pool)
+        .await;
+}
+
+#[tokio::test]
+#[ignore] // Ignored by default
+async fn test_mysql_call_is_limited_like_other_queries() {
+    let mut params = get_mysql_params();
+
+    if mysql::get_tables(&params).await.is_err() {
+        eprintln!("SKIPPING MySQL Test: Could not connect to Docker container on port 33060");
+        return;
+    }
+
+    let pool = pool_manager::get_mysql_pool(&params).await.unwrap();
+    sqlx::raw_sql(
+        "DROP PROCEDURE IF EXISTS test_five_rows; \
+         CREATE PROCEDURE test_five_rows() \
+         BEGIN SELECT 1 AS n UNION ALL SELECT 2 UNION ALL SELECT 3 \
+         UNION ALL SELECT 4 UNION ALL SELECT 5; END; \
+         DROP PROCEDURE IF EXISTS test_slow; \
+         CREATE PROCEDURE test_slow() BEGIN DO SLEEP(5); SELECT 1 AS a; END",
+    )
+    .execute(&pool)
+    .await
+    .expect("Failed to create procedures");
+
+    // The page window is applied while reading
+    let page = mysql::execute_query(&params, "CALL test_five_rows()", Some(2), 2, None, None)
+        .await
+        .unwrap();
+    assert_eq!(
+        page.rows,
+        vec![vec![serde_json::json!(3)], vec![serde_json::json!(4)]]
+    );
+    assert!(page.has_more);
+
+    params.default_row_limit = Some(3);
+    let capped = mysql::execute_query(&params, "CALL test_five_rows()", None, 1, None, None)
+        .await
+        .unwrap();
+    assert_eq!(capped.row_count, Some(3));
+    assert!(capped.truncated);
+    assert_eq!(capped.auto_limit, Some(3));
+
+    let sets = mysql::execute_query_sets(&params, "CALL test_five_rows()")
+        .await
+        .unwrap();
+    assert_eq!(sets[0].rows.len(), 3);
+    assert!(sets[0].truncated);
+
+    // Statements run by a procedure are stopped by the statement timeout too
+    params.default_row_limit = None;
+    params.statement_timeout_secs = Some(1);
+    let started = std::time::Instant::now();
+    let err = mysql::execute_query(&params, "CALL test_slow()", None, 1, None, None)
+        .await
+        .unwrap_err();
+    assert!(err.starts_with("Statement timeout"), "{}", err);
+    assert!(started.elapsed() < Duration::from_secs(4));
+
+    let _ = sqlx::raw_sql("DROP PROCEDURE test_five_rows; DROP PROCEDURE test_slow")
+        .execute(&pool)
+        .await;
+}
+
+#[tokio::test]
+#[ignore] // Ignored by default
+async fn test_postgres_call_procedure_and_function() {
+    let params = get_postgres_params();
+
+    if postgres::get_tables(&params).await.is_err() {
+        eprintln!("SKIPPING Postgres Test: Could not connect to Docker container on port 54320");
+        return;
+    }
+
+    let setup = [
+        "DROP FUNCTION IF EXISTS test_series(integer)",
+        "DROP PROCEDURE IF EXISTS test_double(integer)",
+        "CREATE FUNCTION test_series(n integer) RETURNS SETOF integer LANGUAGE sql \
+         AS $$ SELECT generate_series(1, n) $$",
+        "CREATE PROCEDURE test_double(INOUT x integer) LANGUAGE plpgsql \
+         AS $$ BEGIN x := x * 2; END $$",
+    ];
+    postgres::execute_script(&params, &setup)
+        .await
+        .expect("Failed to create routines");
+
+    // The integer parameter is bound as int8 and cast back, so the call resolves
+    let series = postgres::call_procedure(&params, "test_series", &[serde_json::json!(3)])
+        .await
+        .unwrap();
+    assert_eq!(series.result.rows.len(), 3);
+
+    let doubled = postgres::call_procedure(&params, "test_double", &[serde_json::json!(21)])
+        .await
+        .unwrap();
+    assert_eq!(doubled.result.columns, vec!["x"]);
+    assert_eq!(doubled.result.rows, vec![vec![serde_json::json!(42)]]);
+
+    let cleanup = [
+        "DROP FUNCTION test_series(integer)",
+        "DROP PROCEDURE test_double(integer)",
+    ];
+    let _ = postgres::execute_script(&params, &cleanup).await;
+}
+
+#[tokio::test]
+#[ignore] // Ignored by default
+async fn test_postgres_notifications_share_one_connection() {
+    let params = get_postgres_params();
+
+    if postgres::get_tables(&params).await.is_err() {
+        eprintln!("SKIPPING Postgres Test: Could not connect to Docker container on port 54320");
+        return;
+    }
+
+    let pool = pool_manager::get_postgres_pool(&params).await.unwrap();
+    let in_use = || pool.size() - pool.num_idle() as u32;
+    let busy = in_use();
+    let state = NotificationState::default();
+    let (events, mut received) = tokio::sync::mpsc::unbounded_channel();
+
+    for channel in ["test_notify_a", "test_notify_b"] {
+        let events = events.clone();
+        state
+            .listen("pg", channel, &pool, move |event| {
+                let _ = events.send(event);
+            })
+            .await
+            .unwrap();
+    }
+    // Both channels are served by the one listener connection
+    assert_eq!(in_use(), busy + 1);
+
+    for notify in ["NOTIFY test_notify_a, 'one'", "NOTIFY test_notify_b, 'two'"] {
+        postgres::execute_query(&params, notify, None, 1, None, None)
+            .await
+            .unwrap();
+    }
+    let mut payloads = Vec::new();
+    for _ in 0..2 {
+        let event = tokio::time::timeout(Duration::from_secs(5), received.recv())
+            .await
+            .expect("No notification received")
+            .unwrap();
+        payloads.push((event.channel, event.payload));
+    }
+    payloads.sort();
+    assert_eq!(
+        payloads,
+        vec![
+            ("test_notify_a".to_string(), "one".to_string()),
+            ("test_notify_b".to_string(), "two".to_string()),
+        ]
+    );
+
+    state.unlisten("pg", "test_notify_a").await.unwrap();
+    state.unlisten("pg", "test_notify_b").await.unwrap();
+    assert!(state.unlisten("pg", "test_notify_b").await.is_err());
+
+    // Once idle, the listener returns its connection to the pool
+    for _ in 0..10 {
+        if in_use() == busy {
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(in_use(), busy);
 }
@@ -2,6 +2,7 @@ import { useState } from "react";
 import { useNavigate, useLocation } from "react-router-dom";
 import { useTranslation } from "react-i18next";
 import { invoke } from "@tauri-apps/api/core";
+import clsx from "clsx";
 import {
   Database,
   Terminal,
@@ -23,6 +24,7 @@ import {
   Download,
   Upload,
   ChevronDown,
+  Eye,
 } from "lucide-react";
 import { ask, message, open } from "@tauri-apps/plugin-dialog";
 import { useDatabase } from "../../hooks/useDatabase";
@@ -61,6 +63,7 @@ export const Sidebar = () => {
     activeTable,
     setActiveTable,
     tables,
+    views,
     isLoadingTables,
     refreshTables,
     activeConnectionName,
@@ -99,6 +102,7 @@ export const Sidebar = () => {
 
   const [queriesOpen, setQueriesOpen] = useState(false);
   const [tablesOpen, setTablesOpen] = useState(true);
+  const [viewsOpen, setViewsOpen] = useState(false);
   const [queryModal, setQueryModal] = useState<{
     isOpen: boolean;
     query?: SavedQuery;
@@ -509,6 +513,39 @@ export const Sidebar = () => {
                       </div>
                     )}
                   </Accordion>
+
+                  {/* Views */}
+                  <Accordion
+                    title={`${t("sidebar.views")} (${views.length})`}
+                    isOpen={viewsOpen}
+                    onToggle={() => setViewsOpen(!viewsOpen)}
+                  >
+                    {views.length === 0 ? (
+                      <div className="text-center p-2 text-xs text-muted italic">
+                        {t("sidebar.noViews")}
+                      </div>
+                    ) : (
+                      <div>
+                        {views.map((view) => (
+                          <div
+                            key={view.name}
+                            onClick={() => handleTableClick(view.name)}
+                            onDoubleClick={() => handleOpenTable(view.name)}
+                            className={clsx(
+                              "flex items-center gap-2 px-3 py-1.5 text-sm cursor-pointer group select-none transition-colors border-l-2",
+                              activeTable === view.name
+                                ? "bg-blue-900/40 text-blue-200 border-blue-500"
+                                : "text-secondary hover:bg-surface-secondary border-transparent hover:text-primary",
+                            )}
+                            title={view.definition ?? view.name}
+                          >
+                            <Eye size={14} className="text-purple-400 shrink-0" />
+                            <span className="truncate">{view.name}</span>
+                          </div>
+                        ))}
+                      </div>
+                    )}
+                  </Accordion>
                 </>
               )}
             </div>
@@ -37,6 +37,12 @@ interface ConnectionParams {
   save_in_keychain?: boolean;
 }
 
+interface ConnectionTestInfo {
+  server_version: string;
+  database?: string | null;
+  latency_ms: number;
+}
+
 interface SavedConnection {
   id: string;
   name: string;
@@ -227,7 +233,7 @@ export const NewConnectionModal = ({
     setMessage("");
     setTestResult(null);
     try {
-      const result = await invoke<string>("test_connection", {
+      const result = await invoke<ConnectionTestInfo>("test_connection", {
         params: {
           driver,
           ...formData,
@@ -235,7 +241,9 @@ export const NewConnectionModal = ({
         },
       });
       setStatus("success");
-      setMessage(result);
+      setMessage(
+        `${result.server_version}${result.database ? ` · ${result.database}` : ""} (${result.latency_ms} ms)`,
+      );
       setTestResult("success");
       
       // Clear the success indicator and message after 3 seconds
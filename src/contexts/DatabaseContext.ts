@@ -4,6 +4,11 @@ export interface TableInfo {
   name: string;
 }
 
+export interface ViewInfo {
+  name: string;
+  definition?: string | null;
+}
+
 export interface SavedConnection {
   id: string;
   name: string;
@@ -12,6 +17,7 @@ export interface SavedConnection {
     host?: string;
     database: string;
   };
+  group?: string;
 }
 
 export interface DatabaseContextType {
@@ -21,6 +27,7 @@ export interface DatabaseContextType {
   activeConnectionName: string | null;
   activeDatabaseName: string | null;
   tables: TableInfo[];
+  views: ViewInfo[];
   isLoadingTables: boolean;
   connect: (connectionId: string) => Promise<void>;
   disconnect: () => void;
@@ -32,12 +32,17 @@ describe('DatabaseProvider', () => {
     { name: 'comments' },
   ];
 
+  const mockViews = [
+    { name: 'active_users', definition: 'SELECT * FROM users WHERE active = 1' },
+  ];
+
   beforeEach(() => {
     vi.resetAllMocks();
     // Default mock implementation that handles all invoke calls
     vi.mocked(invoke).mockImplementation((cmd: string) => {
       if (cmd === 'get_connections') return Promise.resolve(mockConnections);
       if (cmd === 'get_tables') return Promise.resolve(mockTables);
+      if (cmd === 'get_views') return Promise.resolve(mockViews);
       if (cmd === 'set_window_title') return Promise.resolve(undefined);
       return Promise.reject(new Error(`Unexpected command: ${cmd}`));
     });
@@ -76,11 +81,13 @@ describe('DatabaseProvider', () => {
       expect(result.current.activeConnectionName).toBe('Local MySQL');
       expect(result.current.activeDatabaseName).toBe('testdb');
       expect(result.current.tables).toHaveLength(3);
+      expect(result.current.views).toEqual(mockViews);
       expect(result.current.isLoadingTables).toBe(false);
     });
 
     expect(invoke).toHaveBeenCalledWith('get_connections');
     expect(invoke).toHaveBeenCalledWith('get_tables', { connectionId: 'conn-123' });
+    expect(invoke).toHaveBeenCalledWith('get_views', { connectionId: 'conn-123' });
   });
 
   it('should handle connection failure', async () => {
@@ -100,6 +107,37 @@ describe('DatabaseProvider', () => {
     });
   });
 
+  it('should keep the connection when views cannot be listed', async () => {
+    vi.mocked(invoke).mockImplementation((cmd: string) => {
+      if (cmd === 'get_connections') return Promise.resolve(mockConnections);
+      if (cmd === 'get_tables') return Promise.resolve(mockTables);
+      if (cmd === 'get_views') return Promise.reject(new Error('permission denied'));
+      if (cmd === 'set_window_title') return Promise.resolve(undefined);
+      return Promise.reject(new Error(`Unexpected command: ${cmd}`));
+    });
+    vi.spyOn(console, 'error').mockImplementation(() => {});
+
+    const wrapper = ({ children }: { children: React.ReactNode }) =>
+      React.createElement(DatabaseProvider, null, children);
+
+    const { result } = renderHook(() => useDatabase(), { wrapper });
+
+    await act(async () => {
+      await result.current.connect('conn-123');
+    });
+
+    expect(result.current.activeConnectionId).toBe('conn-123');
+    expect(result.current.tables).toHaveLength(3);
+    expect(result.current.views).toHaveLength(0);
+
+    await act(async () => {
+      await result.current.refreshTables();
+    });
+
+    expect(result.current.activeConnectionId).toBe('conn-123');
+    expect(result.current.tables).toHaveLength(3);
+  });
+
   it('should disconnect and reset state', async () => {
     const wrapper = ({ children }: { children: React.ReactNode }) =>
       React.createElement(DatabaseProvider, null, children);
@@ -120,6 +158,7 @@ describe('DatabaseProvider', () => {
     expect(result.current.activeDriver).toBeNull();
     expect(result.current.activeTable).toBeNull();
     expect(result.current.tables).toHaveLength(0);
+    expect(result.current.views).toHaveLength(0);
   });
 
   it('should refresh tables', async () => {
@@ -139,6 +178,7 @@ describe('DatabaseProvider', () => {
     vi.mocked(invoke).mockImplementation((cmd: string) => {
       if (cmd === 'get_connections') return Promise.resolve(mockConnections);
       if (cmd === 'get_tables') return Promise.resolve(updatedTables);
+      if (cmd === 'get_views') return Promise.resolve(mockViews);
       if (cmd === 'set_window_title') return Promise.resolve(undefined);
       return Promise.reject(new Error(`Unexpected command: ${cmd}`));
     });
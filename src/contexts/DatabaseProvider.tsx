@@ -1,6 +1,6 @@
 import { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import { DatabaseContext, type TableInfo, type SavedConnection } from './DatabaseContext';
+import { DatabaseContext, type TableInfo, type ViewInfo, type SavedConnection } from './DatabaseContext';
 import type { ReactNode } from 'react';
 import { clearAutocompleteCache } from '../utils/autocomplete';
 
@@ -11,6 +11,7 @@ export const DatabaseProvider = ({ children }: { children: ReactNode }) => {
   const [activeConnectionName, setActiveConnectionName] = useState<string | null>(null);
   const [activeDatabaseName, setActiveDatabaseName] = useState<string | null>(null);
   const [tables, setTables] = useState<TableInfo[]>([]);
+  const [views, setViews] = useState<ViewInfo[]>([]);
   const [isLoadingTables, setIsLoadingTables] = useState(false);
 
   // Sync Window Title with active connection
@@ -30,12 +31,24 @@ export const DatabaseProvider = ({ children }: { children: ReactNode }) => {
     updateTitle();
   }, [activeConnectionName, activeDatabaseName]);
 
+  // Views are optional: when they can't be listed (e.g. no catalog privileges) the
+  // list stays empty, without failing the tables or the connection
+  const loadViews = async (connectionId: string) => {
+      try {
+          setViews(await invoke<ViewInfo[]>('get_views', { connectionId }));
+      } catch (e) {
+          console.error('Failed to fetch views:', e);
+          setViews([]);
+      }
+  };
+
   const refreshTables = async () => {
       if (!activeConnectionId) return;
       setIsLoadingTables(true);
       try {
           const result = await invoke<TableInfo[]>('get_tables', { connectionId: activeConnectionId });
           setTables(result);
+          await loadViews(activeConnectionId);
       } catch (e) {
           console.error('Failed to refresh tables:', e);
       } finally {
@@ -47,6 +60,7 @@ export const DatabaseProvider = ({ children }: { children: ReactNode }) => {
     setActiveConnectionId(connectionId);
     setIsLoadingTables(true);
     setTables([]);
+    setViews([]);
     setActiveDriver(null);
     setActiveTable(null);
     setActiveConnectionName(null);
@@ -65,6 +79,9 @@ export const DatabaseProvider = ({ children }: { children: ReactNode }) => {
       // 2. Get tables
       const result = await invoke<TableInfo[]>('get_tables', { connectionId });
       setTables(result);
+
+      // 3. Get views
+      await loadViews(connectionId);
     } catch (error) {
       console.error('Failed to fetch tables:', error);
       setActiveConnectionId(null);
@@ -89,6 +106,7 @@ export const DatabaseProvider = ({ children }: { children: ReactNode }) => {
     setActiveConnectionName(null);
     setActiveDatabaseName(null);
     setTables([]);
+    setViews([]);
   };
 
   return (
@@ -99,6 +117,7 @@ export const DatabaseProvider = ({ children }: { children: ReactNode }) => {
       activeConnectionName,
       activeDatabaseName,
       tables, 
+      views,
       isLoadingTables, 
       connect, 
       disconnect,
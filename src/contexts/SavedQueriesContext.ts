@@ -5,6 +5,9 @@ export interface SavedQuery {
   name: string;
   sql: string;
   connection_id: string;
+  is_favorite: boolean;
+  sort_order: number;
+  tags: string[];
 }
 
 export interface SavedQueriesContextType {
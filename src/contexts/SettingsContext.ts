@@ -13,6 +13,7 @@ export interface Settings {
   aiModel: string | null;
   aiCustomModels?: Record<string, string[]>;
   aiOllamaPort?: number;
+  secretStore?: "keyring" | "file";
 }
 
 export interface SettingsContextType {
@@ -23,6 +23,7 @@ describe('useDatabase', () => {
       activeConnectionName: 'Local MySQL',
       activeDatabaseName: 'mydb',
       tables: [{ name: 'users' }, { name: 'posts' }],
+      views: [{ name: 'active_users' }],
       isLoadingTables: false,
       connect: vi.fn(),
       disconnect: vi.fn(),
@@ -52,6 +53,7 @@ describe('useDatabase', () => {
       activeConnectionName: null,
       activeDatabaseName: null,
       tables: [],
+      views: [],
       isLoadingTables: false,
       connect: vi.fn(),
       disconnect: vi.fn(),
@@ -77,6 +79,7 @@ describe('useDatabase', () => {
       activeConnectionName: 'Prod Postgres',
       activeDatabaseName: 'production',
       tables: [],
+      views: [],
       isLoadingTables: true,
       connect: vi.fn(),
       disconnect: vi.fn(),
@@ -69,7 +69,10 @@ interface EditorState {
 }
 
 interface ExportProgress {
-    rows_processed: number;
+    connection_id: string;
+    rows_written: number;
+    total_rows: number | null;
+    percentage: number | null;
 }
 
 export const Editor = () => {
@@ -115,8 +118,8 @@ export const Editor = () => {
   });
 
   useEffect(() => {
-    const unlisten = listen<ExportProgress>("export_progress", (event) => {
-       setExportState(prev => ({ ...prev, rowsProcessed: event.payload.rows_processed }));
+    const unlisten = listen<ExportProgress>("export-progress", (event) => {
+       setExportState(prev => ({ ...prev, rowsProcessed: event.payload.rows_written }));
     });
     return () => {
         unlisten.then(f => f());
@@ -275,14 +278,14 @@ export const Editor = () => {
   );
 
   const stopQuery = useCallback(async () => {
-    if (!activeConnectionId) return;
+    if (!activeTabId) return;
     try {
-      await invoke("cancel_query", { connectionId: activeConnectionId });
+      await invoke("cancel_query", { queryId: activeTabId });
       updateActiveTab({ isLoading: false });
     } catch (e) {
       console.error("Failed to stop:", e);
     }
-  }, [activeConnectionId, updateActiveTab]);
+  }, [activeTabId, updateActiveTab]);
 
   const runQuery = useCallback(
     async (
@@ -380,6 +383,7 @@ export const Editor = () => {
 
         const res = await invoke<QueryResult>("execute_query", {
           connectionId: activeConnectionId,
+          queryId: targetTabId,
           query: textToRun,
           limit: pageSize,
           page: pageNum,